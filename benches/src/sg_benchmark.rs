@@ -1,4 +1,4 @@
-use ast_grep_config::{from_yaml_string, RuleConfig};
+use ast_grep_config::{from_yaml_string, CombinedScan, RuleConfig};
 use ast_grep_core::{AstGrep, Language, Matcher, Pattern, StrDoc};
 use ast_grep_language::SupportLang;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
@@ -50,6 +50,31 @@ fn rule_bench(c: &mut Criterion) {
   });
 }
 
+fn find_all_with_env_bench(c: &mut Criterion) {
+  let lang = SupportLang::TypeScript;
+  let pattern = Pattern::str(black_box("foo($A, $B)"), lang);
+  let source = (0..1000)
+    .map(|i| format!("foo(a{i}, b{i});\n"))
+    .collect::<String>();
+  let sg = lang.ast_grep(source);
+  c.bench_function("find_all + get_env", |b| {
+    b.iter(|| {
+      sg.root()
+        .find_all(&pattern)
+        .map(|nm| nm.get_env().clone())
+        .for_each(drop)
+    })
+  });
+  c.bench_function("find_all_with_env", |b| {
+    b.iter(|| {
+      sg.root()
+        .find_all_with_env(&pattern)
+        .map(|(_, env)| env)
+        .for_each(drop)
+    })
+  });
+}
+
 fn build_pattern_bench(c: &mut Criterion) {
   let lang = SupportLang::TypeScript;
   c.bench_function("Build Normal Pattern", |b| {
@@ -57,5 +82,28 @@ fn build_pattern_bench(c: &mut Criterion) {
   });
 }
 
-criterion_group!(benches, find_all_bench, rule_bench, build_pattern_bench);
+fn combined_scan_bench(c: &mut Criterion) {
+  let yamls = (0..50)
+    .map(|i| {
+      format!("id: rule-{i}\nlanguage: TypeScript\nrule: {{pattern: '$A.method{i}($$$ARGS)'}}")
+    })
+    .collect::<Vec<_>>()
+    .join("\n---\n");
+  let rules = from_yaml_string(&yamls, &Default::default()).unwrap();
+  let checker_sg = get_sg("fixtures/checker.ts.fixture");
+  let scan = CombinedScan::new(rules.iter().collect());
+  c.bench_function("50 rules sequential", |b| b.iter(|| scan.find(&checker_sg)));
+  c.bench_function("50 rules parallel", |b| {
+    b.iter(|| scan.find_par(&checker_sg))
+  });
+}
+
+criterion_group!(
+  benches,
+  find_all_bench,
+  rule_bench,
+  find_all_with_env_bench,
+  build_pattern_bench,
+  combined_scan_bench
+);
 criterion_main!(benches);
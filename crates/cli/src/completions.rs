@@ -83,4 +83,17 @@ mod test {
     let output = String::from_utf8(output).expect("should be valid");
     assert!(output.contains("ast_grep"));
   }
+
+  #[test]
+  fn test_generate_all_shells() {
+    for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+      let mut output = vec![];
+      let arg = CompletionsArg { shell: Some(shell) };
+      run_shell_completion_impl::<App, _>(arg, &mut output).expect("should succeed");
+      let output = String::from_utf8(output).expect("should be valid");
+      // subcommands and options come from the clap definition
+      assert!(output.contains("scan"), "{shell} misses subcommand");
+      assert!(output.contains("rewrite"), "{shell} misses option");
+    }
+  }
 }
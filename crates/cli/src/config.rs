@@ -3,8 +3,9 @@ use crate::utils::{ErrorContext as EC, RuleOverwrite, RuleTrace};
 
 use anyhow::{Context, Result};
 use ast_grep_config::{
-  from_str, from_yaml_string, DeserializeEnv, GlobalRules, RuleCollection, RuleConfig,
+  from_str, from_yaml_string_with_cache, DeserializeEnv, GlobalRules, RuleCollection, RuleConfig,
 };
+use ast_grep_core::matcher::PatternCache;
 use ast_grep_language::config_file_type;
 use ignore::WalkBuilder;
 use serde::{Deserialize, Serialize};
@@ -12,6 +13,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::read_to_string;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -56,6 +58,8 @@ pub struct AstGrepConfig {
 #[derive(Clone)]
 pub struct ProjectConfig {
   pub project_dir: PathBuf,
+  /// the sgconfig.yml file, None if rules are specified without a project
+  pub config_path: Option<PathBuf>,
   /// YAML rule directories
   pub rule_dirs: Vec<PathBuf>,
   /// test configurations
@@ -74,11 +78,14 @@ impl ProjectConfig {
     };
     let config_str = read_to_string(&config_path).context(EC::ReadConfiguration)?;
     let sg_config: AstGrepConfig = from_str(&config_str).context(EC::ParseConfiguration)?;
-    let project_dir = config_path
-      .parent()
-      .expect("config file must have parent directory")
-      .to_path_buf();
-    Ok(Some((project_dir, sg_config)))
+    Ok(Some((config_path, sg_config)))
+  }
+
+  /// The config file and rule/util directories, whose changes affect the loaded rules.
+  pub fn rule_paths(&self) -> Vec<PathBuf> {
+    let dirs = self.rule_dirs.iter().chain(self.util_dirs.iter().flatten());
+    let dirs = dirs.map(|dir| self.project_dir.join(dir));
+    self.config_path.iter().cloned().chain(dirs).collect()
   }
 
   pub fn find_rules(
@@ -92,11 +99,16 @@ impl ProjectConfig {
   /// The inner Result is for configuration not found, or ProjectNotExist
   /// The outer Result is for definitely wrong config.
   pub fn setup(config_path: Option<PathBuf>) -> Result<Result<Self>> {
-    let Some((project_dir, mut sg_config)) = Self::discover_project(config_path)? else {
+    let Some((config_path, mut sg_config)) = Self::discover_project(config_path)? else {
       return Ok(Err(anyhow::anyhow!(EC::ProjectNotExist)));
     };
+    let project_dir = config_path
+      .parent()
+      .expect("config file must have parent directory")
+      .to_path_buf();
     let config = ProjectConfig {
       project_dir,
+      config_path: Some(config_path),
       rule_dirs: sg_config.rule_dirs.drain(..).collect(),
       test_configs: sg_config.test_configs.take(),
       util_dirs: sg_config.util_dirs.take(),
@@ -170,6 +182,8 @@ fn read_directory_yaml(
     rule_dirs,
     ..
   } = config;
+  // rules in one collection often share patterns, compile each only once
+  let pattern_cache = Arc::default();
   for dir in rule_dirs {
     let dir_path = project_dir.join(dir);
     let walker = WalkBuilder::new(&dir_path)
@@ -186,11 +200,12 @@ fn read_directory_yaml(
         continue;
       }
       let path = config_file.path();
-      let new_configs = read_rule_file(path, Some(&global_rules))?;
+      let new_configs = read_rule_file_with_cache(path, &global_rules, &pattern_cache)?;
       configs.extend(new_configs);
     }
   }
   let total_rule_count = configs.len();
+  warn_vacuous_rules(&configs);
 
   let configs = rule_overwrite.process_configs(configs)?;
   let collection = RuleCollection::try_new(configs).context(EC::GlobPattern)?;
@@ -203,6 +218,17 @@ fn read_directory_yaml(
   Ok((collection, trace))
 }
 
+/// report rules that trivially match every node or no node at all
+fn warn_vacuous_rules(configs: &[RuleConfig<SgLang>]) {
+  for config in configs {
+    if let Some(reason) = config.matcher.is_always_false() {
+      eprintln!("⚠️  Rule `{}` will never match: {reason}.", config.id);
+    } else if let Some(reason) = config.matcher.is_always_true() {
+      eprintln!("⚠️  Rule `{}` will match every node: {reason}.", config.id);
+    }
+  }
+}
+
 pub fn with_rule_stats(
   configs: Vec<RuleConfig<SgLang>>,
 ) -> Result<(RuleCollection<SgLang>, RuleTrace)> {
@@ -220,24 +246,36 @@ pub fn with_rule_stats(
 pub fn read_rule_file(
   path: &Path,
   global_rules: Option<&GlobalRules<SgLang>>,
+) -> Result<Vec<RuleConfig<SgLang>>> {
+  let globals = global_rules.cloned().unwrap_or_default();
+  read_rule_file_with_cache(path, &globals, &Default::default())
+}
+
+fn read_rule_file_with_cache(
+  path: &Path,
+  global_rules: &GlobalRules<SgLang>,
+  pattern_cache: &Arc<PatternCache<SgLang>>,
 ) -> Result<Vec<RuleConfig<SgLang>>> {
   let yaml = read_to_string(path).with_context(|| EC::ReadRule(path.to_path_buf()))?;
-  let parsed = if let Some(globals) = global_rules {
-    from_yaml_string(&yaml, globals)
-  } else {
-    from_yaml_string(&yaml, &Default::default())
-  };
-  parsed.with_context(|| EC::ParseRule(path.to_path_buf()))
+  from_yaml_string_with_cache(&yaml, global_rules, pattern_cache)
+    .with_context(|| EC::ParseRule(path.to_path_buf()))
 }
 
 const CONFIG_FILE: &str = "sgconfig.yml";
 
 /// return None if config file does not exist
+/// If `config_path` is a directory, search the config file from it instead of the cwd.
 fn find_config_path_with_default(config_path: Option<PathBuf>) -> Result<Option<PathBuf>> {
-  if config_path.is_some() {
-    return Ok(config_path);
+  match config_path {
+    Some(dir) if dir.is_dir() => find_config_path_from(&dir),
+    Some(file) => Ok(Some(file)),
+    None => find_config_path_from(&std::env::current_dir()?),
   }
-  let mut path = std::env::current_dir()?;
+}
+
+/// search the config file from `base` upwards, return None if not found
+fn find_config_path_from(base: &Path) -> Result<Option<PathBuf>> {
+  let mut path = base.to_path_buf();
   loop {
     let maybe_config = path.join(CONFIG_FILE);
     if maybe_config.exists() {
@@ -250,3 +288,24 @@ fn find_config_path_with_default(config_path: Option<PathBuf>) -> Result<Option<
     }
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use tempfile::TempDir;
+
+  #[test]
+  fn test_find_config_from_dir() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join(CONFIG_FILE), "ruleDirs: [rules]").unwrap();
+    let nested = dir.path().join("src/deep");
+    std::fs::create_dir_all(&nested).unwrap();
+    let found = find_config_path_with_default(Some(nested)).unwrap();
+    assert_eq!(found, Some(dir.path().join(CONFIG_FILE)));
+    let project = ProjectConfig::setup(Some(dir.path().to_path_buf()))
+      .unwrap()
+      .unwrap();
+    assert_eq!(project.project_dir, dir.path());
+    assert_eq!(project.rule_dirs, [PathBuf::from("rules")]);
+  }
+}
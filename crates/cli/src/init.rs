@@ -0,0 +1,193 @@
+use crate::config::AstGrepConfig;
+use crate::lang::SgLang;
+use crate::utils::ErrorContext as EC;
+
+use anyhow::Result;
+use ast_grep_language::SupportLang;
+use clap::Parser;
+
+use std::fs::{self, File};
+use std::path::Path;
+
+#[derive(Parser)]
+pub struct InitArg {
+  /// The language of the sample rule. Defaults to TypeScript.
+  ///
+  /// Sample rules are available for JavaScript, TypeScript, Tsx, Python and Rust.
+  #[arg(short, long, default_value = "ts")]
+  lang: SgLang,
+  /// The name of the rule directory to create.
+  #[arg(short, long, default_value = "rules")]
+  name: String,
+  /// Overwrite the sample rule file if it already exists.
+  #[arg(short, long)]
+  force: bool,
+}
+
+/// A rule that flags debug printing, with a fix to demonstrate rewriting.
+struct SampleRule {
+  id: &'static str,
+  message: &'static str,
+  pattern: &'static str,
+  fix: &'static str,
+}
+
+fn sample_rule(lang: SgLang) -> Option<SampleRule> {
+  use SupportLang as S;
+  let SgLang::Builtin(lang) = lang else {
+    return None;
+  };
+  let rule = match lang {
+    S::JavaScript | S::TypeScript | S::Tsx => SampleRule {
+      id: "no-console-log",
+      message: "Use logger instead of console.log.",
+      pattern: "console.log($$$ARGS)",
+      fix: "logger.log($$$ARGS)",
+    },
+    S::Python => SampleRule {
+      id: "no-print",
+      message: "Use logging instead of print.",
+      pattern: "print($$$ARGS)",
+      fix: "logging.info($$$ARGS)",
+    },
+    S::Rust => SampleRule {
+      id: "no-println",
+      message: "Use log macros instead of println.",
+      pattern: "println!($$$ARGS)",
+      fix: "log::info!($$$ARGS)",
+    },
+    _ => return None,
+  };
+  Some(rule)
+}
+
+fn sample_rule_yaml(lang: SgLang, rule: SampleRule) -> String {
+  let SampleRule {
+    id,
+    message,
+    pattern,
+    fix,
+  } = rule;
+  format!(
+    r#"# yaml-language-server: $schema=https://raw.githubusercontent.com/ast-grep/ast-grep/main/schemas/rule.json
+
+id: {id}
+message: {message}
+severity: warning # error, warning, info, hint
+language: {lang}
+rule:
+  pattern: {pattern}
+fix: {fix}
+"#
+  )
+}
+
+pub fn run_init(arg: InitArg) -> Result<()> {
+  let current_dir = std::env::current_dir()?;
+  init_project(arg, &current_dir)
+}
+
+fn init_project(arg: InitArg, project_dir: &Path) -> Result<()> {
+  let config_path = project_dir.join("sgconfig.yml");
+  if config_path.exists() {
+    return Err(anyhow::anyhow!(EC::FileAlreadyExist(config_path)));
+  }
+  let Some(rule) = sample_rule(arg.lang) else {
+    return Err(anyhow::anyhow!(EC::UnrecognizableLanguage(
+      arg.lang.to_string()
+    )));
+  };
+  let rule_dir = project_dir.join(&arg.name);
+  let rule_path = rule_dir.join(format!("{}.yml", rule.id));
+  if rule_path.exists() && !arg.force {
+    return Err(anyhow::anyhow!(EC::FileAlreadyExist(rule_path)));
+  }
+  fs::create_dir_all(&rule_dir)?;
+  fs::write(&rule_path, sample_rule_yaml(arg.lang, rule))?;
+  let root_config = AstGrepConfig {
+    rule_dirs: vec![arg.name.into()],
+    test_configs: None,
+    util_dirs: None,
+    custom_languages: None,
+    language_globs: None,
+    language_injections: vec![],
+  };
+  let f = File::create(config_path)?;
+  serde_yaml::to_writer(f, &root_config)?;
+  println!("Created sgconfig.yml and {}", rule_path.display());
+  println!("Run `ast-grep scan` to try the sample rule.");
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::config::ProjectConfig;
+  use ast_grep_config::{from_yaml_string, GlobalRules};
+  use ast_grep_language::Language;
+  use tempfile::TempDir;
+
+  fn init(dir: &Path, lang: &str) -> Result<()> {
+    let arg = InitArg::try_parse_from(["init", "--lang", lang])?;
+    init_project(arg, dir)
+  }
+
+  #[test]
+  fn test_init() -> Result<()> {
+    let dir = TempDir::new()?;
+    init(dir.path(), "ts")?;
+    let project = ProjectConfig::setup(Some(dir.path().join("sgconfig.yml")))??;
+    assert_eq!(project.rule_dirs, vec![Path::new("rules")]);
+    let yaml = fs::read_to_string(dir.path().join("rules/no-console-log.yml"))?;
+    let rule = from_yaml_string::<SgLang>(&yaml, &GlobalRules::default())?.remove(0);
+    let grep = rule.language.ast_grep("console.log(1)");
+    assert!(grep.root().find(&rule.matcher).is_some());
+    assert!(rule.matcher.fixer.is_some());
+    Ok(())
+  }
+
+  #[test]
+  fn test_init_existing_config() -> Result<()> {
+    let dir = TempDir::new()?;
+    init(dir.path(), "python")?;
+    let err = init(dir.path(), "python").expect_err("should not overwrite");
+    assert!(matches!(err.downcast_ref(), Some(EC::FileAlreadyExist(_))));
+    Ok(())
+  }
+
+  #[test]
+  fn test_init_existing_rule() -> Result<()> {
+    let dir = TempDir::new()?;
+    let rule_path = dir.path().join("rules/no-print.yml");
+    fs::create_dir_all(dir.path().join("rules"))?;
+    fs::write(&rule_path, "existing")?;
+    let err = init(dir.path(), "py").expect_err("should not overwrite");
+    assert!(matches!(err.downcast_ref(), Some(EC::FileAlreadyExist(_))));
+    assert_eq!(fs::read_to_string(&rule_path)?, "existing");
+    let arg = InitArg::try_parse_from(["init", "--lang", "py", "--force"])?;
+    init_project(arg, dir.path())?;
+    let yaml = fs::read_to_string(&rule_path)?;
+    let rule = from_yaml_string::<SgLang>(&yaml, &GlobalRules::default())?.remove(0);
+    assert_eq!(rule.id, "no-print");
+    Ok(())
+  }
+
+  #[test]
+  fn test_init_sample_rules() -> Result<()> {
+    let samples = [
+      ("rs", "println!(\"{}\", a)", "no-println"),
+      ("py", "print(a)", "no-print"),
+    ];
+    for (lang, src, id) in samples {
+      let lang: SgLang = lang.parse()?;
+      let rule = sample_rule(lang).expect("should have sample rule");
+      let yaml = sample_rule_yaml(lang, rule);
+      let rule = from_yaml_string::<SgLang>(&yaml, &GlobalRules::default())?.remove(0);
+      assert_eq!(rule.id, id);
+      assert!(lang.ast_grep(src).root().find(&rule.matcher).is_some());
+    }
+    let go = "go".parse()?;
+    assert!(sample_rule(go).is_none());
+    Ok(())
+  }
+}
@@ -58,6 +58,12 @@ impl SgLang {
     unsafe { injection::register_injetables(injections) }
   }
 
+  /// Detect a file's language by its path first, then by its content, e.g. a shebang line.
+  /// See [`SupportLang::guess_from_content`] for the content heuristics.
+  pub fn detect(path: &Path, content: &str) -> Option<Self> {
+    Self::from_path(path).or_else(|| SupportLang::guess_from_content(content).map(Builtin))
+  }
+
   pub fn all_langs() -> Vec<Self> {
     let builtin = SupportLang::all_langs().iter().copied().map(Self::Builtin);
     let customs = DynamicLang::all_langs().into_iter().map(Self::Custom);
@@ -191,6 +197,20 @@ impl Language for SgLang {
     }
   }
 
+  fn extra_node_kinds(&self) -> &'static [&'static str] {
+    match self {
+      Builtin(b) => b.extra_node_kinds(),
+      Custom(c) => c.extra_node_kinds(),
+    }
+  }
+
+  fn unwrap_expression(&self, src: &str) -> Option<String> {
+    match self {
+      Builtin(b) => b.unwrap_expression(src),
+      Custom(c) => c.unwrap_expression(src),
+    }
+  }
+
   fn injectable_languages(&self) -> Option<&'static [&'static str]> {
     injection::injectable_languages(*self)
   }
@@ -209,4 +229,24 @@ mod test {
   fn test_sg_lang_size() {
     assert_eq!(size_of::<SgLang>(), size_of::<DynamicLang>());
   }
+
+  #[test]
+  fn test_detect() {
+    let detect = |p: &str, content: &str| SgLang::detect(Path::new(p), content);
+    let ts = Builtin(SupportLang::TypeScript);
+    assert_eq!(detect("lib/index.mts", ""), Some(ts));
+    // extension wins over content
+    assert_eq!(detect("index.mts", "#!/usr/bin/env node"), Some(ts));
+    let node = "#!/usr/bin/env node\nconsole.log(1)";
+    assert_eq!(
+      detect("bin/cli", node),
+      Some(Builtin(SupportLang::JavaScript))
+    );
+    let python = "#!/usr/bin/python3\nprint(1)";
+    assert_eq!(
+      detect("scripts/run", python),
+      Some(Builtin(SupportLang::Python))
+    );
+    assert_eq!(detect("README", "hello"), None);
+  }
 }
@@ -1,13 +1,17 @@
 mod completions;
 mod config;
+mod init;
 mod lang;
 mod lsp;
+mod migrate;
 mod new;
 mod print;
+mod query;
 mod run;
 mod scan;
 mod utils;
 mod verify;
+mod watch;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
@@ -15,8 +19,11 @@ use std::path::PathBuf;
 
 use completions::{run_shell_completion, CompletionsArg};
 use config::ProjectConfig;
+use init::{run_init, InitArg};
 use lsp::{run_language_server, LspArg};
+use migrate::{run_migrate, MigrateArg};
 use new::{run_create_new, NewArg};
+use query::{run_query, QueryArg};
 use run::{run_with_pattern, RunArg};
 use scan::{run_with_config, ScanArg};
 use utils::exit_with_error;
@@ -42,6 +49,7 @@ struct App {
   #[clap(subcommand)]
   command: Commands,
   /// Path to ast-grep root config, default is sgconfig.yml.
+  /// If a directory is given, sgconfig.yml is searched from it upwards.
   #[clap(short, long, global = true, value_name = "CONFIG_FILE")]
   config: Option<PathBuf>,
 }
@@ -56,8 +64,14 @@ enum Commands {
   Test(TestArg),
   /// Create new ast-grep project or items like rules/tests.
   New(NewArg),
+  /// Create sgconfig.yml and a sample rule in the current directory.
+  Init(InitArg),
   /// Start language server.
   Lsp(LspArg),
+  /// Search a file interactively by typing patterns or rules.
+  Query(QueryArg),
+  /// Upgrade sgconfig.yml and rule files written for an older ast-grep version.
+  Migrate(MigrateArg),
   /// Generate shell completion script.
   Completions(CompletionsArg),
   /// Generate rule docs for current configuration. (Not Implemented Yet)
@@ -136,7 +150,10 @@ pub fn main_with_args(args: impl Iterator<Item = String>) -> Result<()> {
     Commands::Scan(arg) => run_with_config(arg, project),
     Commands::Test(arg) => run_test_rule(arg, project),
     Commands::New(arg) => run_create_new(arg, project),
+    Commands::Init(arg) => run_init(arg),
     Commands::Lsp(arg) => run_language_server(arg, project),
+    Commands::Query(arg) => run_query(arg),
+    Commands::Migrate(arg) => run_migrate(arg),
     Commands::Completions(arg) => run_shell_completion::<App>(arg),
     Commands::Docs => todo!("todo, generate rule docs based on current config"),
   }
@@ -229,6 +246,8 @@ mod test_cli {
     ok("run -p fubuki -j8");
     ok("run -p test --threads 12");
     ok("run -p test -l rs -c config.yml"); // global config arg
+    ok("run -p test -o out.txt");
+    ok("run -p test --json --output-file out.json dir");
     error("run test");
     error("run --debug-query test"); // missing lang
     error("run -r Test dir");
@@ -239,6 +258,7 @@ mod test_cli {
     error("run -p test -l rs --debug-query=not");
     error("run -p test --selector");
     error("run -p test --threads");
+    error("run -p test -i -o out.txt"); // conflict
   }
 
   #[test]
@@ -258,11 +278,15 @@ mod test_cli {
     ok("scan --globs '*.js'");
     ok("scan --globs '*.{js, ts}'");
     ok("scan --globs '*.js' --globs '*.ts'");
+    ok("scan --include '*.ts' --include '*.js' --exclude 'vendor/**'");
     ok("scan -j 12");
     ok("scan --threads 12");
     ok("scan -A 12");
     ok("scan --after 12");
     ok("scan --context 1");
+    ok("scan --format sarif -o report.sarif");
+    ok("scan --stats --json");
+    ok("scan --tag security --tag JS --exclude-tag style");
     error("scan -i --json dir"); // conflict
     error("scan --report-style rich --json dir"); // conflict
     error("scan -r test.yml --inline-rules '{}'"); // conflict
@@ -273,6 +297,9 @@ mod test_cli {
     error("scan --json= not-pretty"); // wrong json flag
     error("scan -j");
     error("scan --threads");
+    error("scan --watch -o out.txt"); // conflict
+    error("scan --watch --stats"); // conflict
+    error("scan -r test.yml --tag security"); // conflict
   }
 
   #[test]
@@ -282,7 +309,10 @@ mod test_cli {
     ok("test --skip-snapshot-tests");
     ok("test -U");
     ok("test --update-all");
+    ok("test --rules rules");
+    ok("test --rules rules/my-rule.yml -f my-rule");
     error("test --update-all --skip-snapshot-tests");
+    error("test --rules rules -t rule-tests");
   }
   #[test]
   fn test_new() {
@@ -296,6 +326,36 @@ mod test_cli {
     error("new --base-dir");
   }
 
+  #[test]
+  fn test_lsp() {
+    ok("lsp");
+    ok("lsp --stdio");
+    ok("lsp --socket 9257");
+    ok("lsp --socket 9257 --log-file lsp.log");
+    error("lsp --stdio --socket 9257");
+    error("lsp --socket");
+    error("lsp --socket not-port");
+    ok("lsp --tcp 9257");
+    ok("lsp --tcp 9257 --tcp-ipv6");
+    error("lsp --tcp-ipv6");
+  }
+
+  #[test]
+  fn test_query() {
+    ok("query test.ts --lang ts");
+    ok("query test.ts -l ts -C 2 --color never");
+    error("query test.ts");
+    error("query --lang ts");
+  }
+
+  #[test]
+  fn test_migrate() {
+    ok("migrate --from-version 0.11 rules");
+    ok("migrate --from-version v0.12 sgconfig.yml");
+    error("migrate rules");
+    error("migrate --from-version latest rules");
+  }
+
   #[test]
   fn test_shell() {
     ok("completions");
@@ -1,20 +1,85 @@
 use crate::config::ProjectConfig;
+use crate::lang::SgLang;
 use crate::utils::ErrorContext as EC;
 use anyhow::{Context, Result};
-use ast_grep_lsp::{Backend, LspService, Server};
+use ast_grep_config::RuleCollection;
+use ast_grep_lsp::{Backend, LspService, Server, SET_TAGS};
 use clap::Args;
 
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use tokio::io::{AsyncRead, AsyncWrite};
+
 #[derive(Args)]
-pub struct LspArg {}
+pub struct LspArg {
+  /// Communicate with the client over stdin/stdout. This is the default.
+  #[arg(long, conflicts_with = "socket")]
+  stdio: bool,
+  /// Listen on the TCP port and serve the first client that connects.
+  ///
+  /// Only one connection is served. The server exits when that session ends.
+  #[arg(long, visible_alias = "tcp", value_name = "PORT")]
+  socket: Option<u16>,
+  /// Bind the TCP port on the IPv6 loopback address instead of IPv4.
+  #[arg(long, requires = "socket")]
+  tcp_ipv6: bool,
+  /// Write server logs to the file instead of stderr.
+  #[arg(long, value_name = "PATH")]
+  log_file: Option<PathBuf>,
+}
 
-async fn run_language_server_impl(_arg: LspArg, project: Result<ProjectConfig>) -> Result<()> {
-  // env_logger::init();
-  // TODO: move this error to client
-  let project_config = project?;
-  let stdin = tokio::io::stdin();
-  let stdout = tokio::io::stdout();
-  let config_result = project_config.find_rules(Default::default());
-  let config_result_std: std::result::Result<_, String> = config_result
+/// How the language server talks to the client.
+enum LspTransport {
+  Stdio,
+  Socket(SocketAddr),
+}
+
+impl LspArg {
+  fn transport(&self) -> LspTransport {
+    let Some(port) = self.socket else {
+      return LspTransport::Stdio;
+    };
+    let addr = if self.tcp_ipv6 {
+      SocketAddr::from((Ipv6Addr::LOCALHOST, port))
+    } else {
+      SocketAddr::from((Ipv4Addr::LOCALHOST, port))
+    };
+    LspTransport::Socket(addr)
+  }
+}
+
+/// Server side logging. Stdio mode must not write to stdout,
+/// so logs go to stderr or to the file given by `--log-file`.
+struct ServerLog(Option<File>);
+
+impl ServerLog {
+  fn new(log_file: &Option<PathBuf>) -> Result<Self> {
+    let Some(path) = log_file else {
+      return Ok(Self(None));
+    };
+    let file = OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(path)
+      .with_context(|| EC::WriteFile(path.clone()))?;
+    Ok(Self(Some(file)))
+  }
+
+  fn log(&mut self, msg: &str) {
+    match &mut self.0 {
+      Some(file) => _ = writeln!(file, "{msg}"),
+      None => eprintln!("{msg}"),
+    }
+  }
+}
+
+fn load_rules(
+  project_config: &ProjectConfig,
+) -> std::result::Result<RuleCollection<SgLang>, String> {
+  project_config
+    .find_rules(Default::default())
     .map_err(|e| {
       // convert anyhow::Error to String with chain of causes
       e.chain()
@@ -22,14 +87,60 @@ async fn run_language_server_impl(_arg: LspArg, project: Result<ProjectConfig>)
         .collect::<Vec<_>>()
         .join(". ")
     })
-    .map(|r| r.0);
-  let config_base = project_config.project_dir;
-  let (service, socket) =
-    LspService::build(|client| Backend::new(client, config_base, config_result_std)).finish();
-  Server::new(stdin, stdout, socket).serve(service).await;
+    .map(|r| r.0)
+}
+
+async fn run_language_server_impl(arg: LspArg, project: Result<ProjectConfig>) -> Result<()> {
+  // env_logger::init();
+  let mut log = ServerLog::new(&arg.log_file)?;
+  // TODO: move this error to client
+  let project_config = project?;
+  let config_result_std = load_rules(&project_config);
+  if let Err(e) = &config_result_std {
+    log.log(&format!("Failed to load rules: {e}"));
+  }
+  match arg.transport() {
+    LspTransport::Stdio => {
+      let stdin = tokio::io::stdin();
+      let stdout = tokio::io::stdout();
+      serve(stdin, stdout, project_config, config_result_std).await;
+    }
+    LspTransport::Socket(addr) => {
+      let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .context(EC::StartLanguageServer)?;
+      log.log(&format!("Listening on {addr}"));
+      let (stream, client) = listener.accept().await.context(EC::StartLanguageServer)?;
+      log.log(&format!("Client connected from {client}"));
+      // stop listening so no other client can connect during the session
+      drop(listener);
+      let (read, write) = tokio::io::split(stream);
+      serve(read, write, project_config, config_result_std).await;
+    }
+  }
   Ok(())
 }
 
+async fn serve<I, O>(
+  input: I,
+  output: O,
+  project_config: ProjectConfig,
+  rules: std::result::Result<RuleCollection<SgLang>, String>,
+) where
+  I: AsyncRead + Unpin,
+  O: AsyncWrite,
+{
+  let config_base = project_config.project_dir.clone();
+  let rule_paths = project_config.rule_paths();
+  let (service, socket) = LspService::build(|client| {
+    Backend::new(client, config_base, rules)
+      .with_rule_loader(move || load_rules(&project_config), rule_paths)
+  })
+  .custom_method(SET_TAGS, Backend::set_tags)
+  .finish();
+  Server::new(input, output, socket).serve(service).await;
+}
+
 pub fn run_language_server(arg: LspArg, project: Result<ProjectConfig>) -> Result<()> {
   tokio::runtime::Builder::new_multi_thread()
     .enable_all()
@@ -45,7 +156,38 @@ mod test {
   #[test]
   #[ignore = "test lsp later"]
   fn test_lsp_start() {
-    let arg = LspArg {};
+    let arg = LspArg {
+      stdio: true,
+      socket: None,
+      tcp_ipv6: false,
+      log_file: None,
+    };
     assert!(run_language_server(arg, Err(anyhow::anyhow!("error"))).is_err())
   }
+
+  #[test]
+  fn test_transport() {
+    let mut arg = LspArg {
+      stdio: false,
+      socket: Some(9257),
+      tcp_ipv6: false,
+      log_file: None,
+    };
+    let LspTransport::Socket(addr) = arg.transport() else {
+      panic!("should use socket");
+    };
+    assert_eq!(addr, "127.0.0.1:9257".parse().unwrap());
+    arg.tcp_ipv6 = true;
+    let LspTransport::Socket(addr) = arg.transport() else {
+      panic!("should use socket");
+    };
+    assert_eq!(addr, "[::1]:9257".parse().unwrap());
+    let arg = LspArg {
+      stdio: true,
+      socket: None,
+      tcp_ipv6: false,
+      log_file: None,
+    };
+    assert!(matches!(arg.transport(), LspTransport::Stdio));
+  }
 }
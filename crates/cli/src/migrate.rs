@@ -0,0 +1,214 @@
+use crate::utils::ErrorContext as EC;
+
+use anyhow::{Context, Result};
+use clap::Args;
+use ignore::WalkBuilder;
+use serde::Deserialize;
+use serde_yaml::{Deserializer, Mapping, Value};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Args)]
+pub struct MigrateArg {
+  /// The ast-grep version the configuration was written for, e.g. 0.11.
+  #[clap(long, value_name = "VERSION")]
+  from_version: Version,
+  /// An sgconfig.yml, a rule file or a directory of rule files to migrate in place.
+  ///
+  /// Note the files are rewritten from parsed YAML, so comments are not kept.
+  #[clap(value_parser)]
+  path: PathBuf,
+}
+
+/// A `major.minor` ast-grep version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Version(u32, u32);
+
+impl FromStr for Version {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut parts = s.trim_start_matches('v').split('.');
+    let mut next = || -> Result<u32, String> {
+      let part = parts.next().unwrap_or("0");
+      part
+        .parse()
+        .map_err(|_| format!("`{s}` is not a version like 0.11"))
+    };
+    Ok(Self(next()?, next()?))
+  }
+}
+
+type Migration = fn(Value) -> Value;
+
+/// Each entry upgrades configurations written for the version to the next entry's version.
+/// Steps run in order, starting from the first entry not older than `--from-version`.
+const MIGRATIONS: &[(Version, Migration)] = &[
+  (Version(0, 11), lowercase_stop_by),
+  (Version(0, 12), rename_matches_all),
+];
+
+fn migrate(mut value: Value, from: Version) -> Value {
+  for (version, migration) in MIGRATIONS {
+    if *version >= from {
+      value = migration(value);
+    }
+  }
+  value
+}
+
+/// Apply `f` to every mapping in `value`, children first.
+fn map_mappings(value: Value, f: &impl Fn(Mapping) -> Mapping) -> Value {
+  match value {
+    Value::Mapping(map) => {
+      let map = map
+        .into_iter()
+        .map(|(k, v)| (k, map_mappings(v, f)))
+        .collect();
+      Value::Mapping(f(map))
+    }
+    Value::Sequence(seq) => Value::Sequence(seq.into_iter().map(|v| map_mappings(v, f)).collect()),
+    v => v,
+  }
+}
+
+/// 0.11 accepted capitalized `stopBy: End` and `stopBy: Neighbor`.
+fn lowercase_stop_by(value: Value) -> Value {
+  map_mappings(value, &|mut map| {
+    if let Some(Value::String(stop_by)) = map.get_mut("stopBy") {
+      if matches!(stop_by.as_str(), "End" | "Neighbor") {
+        *stop_by = stop_by.to_lowercase();
+      }
+    }
+    map
+  })
+}
+
+/// 0.12 called the `all` rule `matchesAll`.
+fn rename_matches_all(value: Value) -> Value {
+  map_mappings(value, &|map| {
+    map
+      .into_iter()
+      .map(|(k, v)| match k {
+        Value::String(s) if s == "matchesAll" => (Value::String("all".into()), v),
+        k => (k, v),
+      })
+      .collect()
+  })
+}
+
+/// Migrate every YAML document in `src`. Returns None if nothing changes.
+fn migrate_yaml(src: &str, from: Version) -> Result<Option<String>, serde_yaml::Error> {
+  let mut changed = false;
+  let mut docs = vec![];
+  for doc in Deserializer::from_str(src) {
+    let value = Value::deserialize(doc)?;
+    let migrated = migrate(value.clone(), from);
+    changed |= migrated != value;
+    docs.push(serde_yaml::to_string(&migrated)?);
+  }
+  Ok(changed.then(|| docs.join("---\n")))
+}
+
+fn is_yaml(path: &Path) -> bool {
+  path
+    .extension()
+    .map_or(false, |ext| ext == "yml" || ext == "yaml")
+}
+
+fn find_yaml_files(path: &Path) -> Vec<PathBuf> {
+  if path.is_file() {
+    return vec![path.to_path_buf()];
+  }
+  WalkBuilder::new(path)
+    .build()
+    .flatten()
+    .map(|entry| entry.into_path())
+    .filter(|p| p.is_file() && is_yaml(p))
+    .collect()
+}
+
+pub fn run_migrate(arg: MigrateArg) -> Result<()> {
+  for path in find_yaml_files(&arg.path) {
+    let src = fs::read_to_string(&path).with_context(|| EC::ReadRule(path.clone()))?;
+    let migrated =
+      migrate_yaml(&src, arg.from_version).with_context(|| EC::ParseRule(path.clone()))?;
+    if let Some(migrated) = migrated {
+      fs::write(&path, migrated).with_context(|| EC::WriteFile(path.clone()))?;
+      println!("Migrated {}", path.display());
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use tempfile::TempDir;
+
+  fn migrate_str(src: &str, from: &str) -> Option<String> {
+    let from = from.parse().expect("should parse version");
+    migrate_yaml(src, from).expect("should be valid yaml")
+  }
+
+  #[test]
+  fn test_parse_version() {
+    assert_eq!("0.11".parse(), Ok(Version(0, 11)));
+    assert_eq!("v1.2.3".parse(), Ok(Version(1, 2)));
+    assert_eq!("1".parse(), Ok(Version(1, 0)));
+    assert!("latest".parse::<Version>().is_err());
+  }
+
+  #[test]
+  fn test_lowercase_stop_by() {
+    let src = "rule:\n  inside:\n    kind: block\n    stopBy: End\n";
+    let expected = "rule:\n  inside:\n    kind: block\n    stopBy: end\n";
+    assert_eq!(migrate_str(src, "0.11").as_deref(), Some(expected));
+    let src = "rule:\n  has:\n    kind: a\n    stopBy: { kind: Neighbor }\n";
+    assert_eq!(migrate_str(src, "0.11"), None);
+  }
+
+  #[test]
+  fn test_rename_matches_all() {
+    let src = "rule:\n  matchesAll:\n  - kind: a\n  - not:\n      matchesAll: []\n";
+    let expected = "rule:\n  all:\n  - kind: a\n  - not:\n      all: []\n";
+    assert_eq!(migrate_str(src, "0.12").as_deref(), Some(expected));
+  }
+
+  #[test]
+  fn test_migration_order() {
+    let src = "rule: { matchesAll: [{ has: { kind: a, stopBy: End } }] }";
+    let expected = "rule:\n  all:\n  - has:\n      kind: a\n      stopBy: end\n";
+    assert_eq!(migrate_str(src, "0.11").as_deref(), Some(expected));
+    // configs from 0.12 already use lowercase stopBy
+    let expected = "rule:\n  all:\n  - has:\n      kind: a\n      stopBy: End\n";
+    assert_eq!(migrate_str(src, "0.12").as_deref(), Some(expected));
+    assert_eq!(migrate_str(src, "0.13"), None);
+  }
+
+  #[test]
+  fn test_run_migrate() -> Result<()> {
+    let dir = TempDir::new()?;
+    let rules = dir.path().join("rules");
+    fs::create_dir(&rules)?;
+    let rule = "id: a\nrule:\n  matchesAll: []\n---\nid: b\nrule:\n  kind: c\n";
+    fs::write(rules.join("rule.yml"), rule)?;
+    fs::write(rules.join("note.txt"), "matchesAll: []")?;
+    let arg = MigrateArg {
+      from_version: Version(0, 11),
+      path: dir.path().to_path_buf(),
+    };
+    run_migrate(arg)?;
+    let migrated = fs::read_to_string(rules.join("rule.yml"))?;
+    assert_eq!(
+      migrated,
+      "id: a\nrule:\n  all: []\n---\nid: b\nrule:\n  kind: c\n"
+    );
+    assert_eq!(
+      fs::read_to_string(rules.join("note.txt"))?,
+      "matchesAll: []"
+    );
+    Ok(())
+  }
+}
@@ -0,0 +1,227 @@
+use super::{Diff, NodeMatch, Printer};
+use crate::lang::SgLang;
+use ast_grep_config::{RuleConfig, Severity};
+use ast_grep_core::Span;
+
+use anyhow::Result;
+use codespan_reporting::files::SimpleFile;
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+/// One `<error>` element. Checkstyle lines and columns start from 1.
+struct CheckstyleError {
+  span: Span,
+  severity: &'static str,
+  message: String,
+  source: String,
+}
+
+/// Prints scan results as one Checkstyle XML document after scanning.
+/// Errors are grouped by file since Checkstyle nests them in `<file>` elements.
+pub struct CheckstylePrinter<W: Write> {
+  writer: W,
+  files: BTreeMap<String, Vec<CheckstyleError>>,
+}
+
+impl<W: Write> CheckstylePrinter<W> {
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer,
+      files: BTreeMap::new(),
+    }
+  }
+
+  fn add_errors<'a>(
+    &mut self,
+    path: &str,
+    matches: impl Iterator<Item = NodeMatch<'a>>,
+    rule: &RuleConfig<SgLang>,
+  ) {
+    let severity = match rule.severity {
+      Severity::Error => "error",
+      Severity::Warning => "warning",
+      Severity::Info | Severity::Hint => "info",
+      Severity::Off => unreachable!("turned-off rule should not have match."),
+    };
+    let errors: Vec<_> = matches
+      .map(|nm| CheckstyleError {
+        span: nm.span(),
+        severity,
+        message: rule.get_message(&nm),
+        source: rule.id.clone(),
+      })
+      .collect();
+    if !errors.is_empty() {
+      let file = self.files.entry(path.to_string()).or_default();
+      file.extend(errors);
+    }
+  }
+}
+
+impl<W: Write> Printer for CheckstylePrinter<W> {
+  fn print_rule(
+    &mut self,
+    matches: Vec<NodeMatch>,
+    file: SimpleFile<Cow<str>, &String>,
+    rule: &RuleConfig<SgLang>,
+  ) -> Result<()> {
+    self.add_errors(file.name(), matches.into_iter(), rule);
+    Ok(())
+  }
+
+  fn print_matches(&mut self, _m: Vec<NodeMatch>, _p: &Path) -> Result<()> {
+    unreachable!()
+  }
+
+  fn print_diffs(&mut self, _d: Vec<Diff>, _p: &Path) -> Result<()> {
+    unreachable!()
+  }
+
+  fn print_rule_diffs(
+    &mut self,
+    diffs: Vec<(Diff<'_>, &RuleConfig<SgLang>)>,
+    path: &Path,
+  ) -> Result<()> {
+    let path = path.to_string_lossy();
+    for (diff, rule) in diffs {
+      self.add_errors(&path, std::iter::once(diff.node_match), rule);
+    }
+    Ok(())
+  }
+
+  fn after_print(&mut self) -> Result<()> {
+    let writer = &mut self.writer;
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<checkstyle version="8.0">"#)?;
+    for (name, errors) in &self.files {
+      writeln!(writer, r#"  <file name="{}">"#, escape(name))?;
+      for error in errors {
+        writeln!(
+          writer,
+          r#"    <error line="{}" column="{}" severity="{}" message="{}" source="{}"/>"#,
+          error.span.start_line + 1,
+          error.span.start_col + 1,
+          error.severity,
+          escape(&error.message),
+          escape(&error.source),
+        )?;
+      }
+      writeln!(writer, "  </file>")?;
+    }
+    writeln!(writer, "</checkstyle>")?;
+    writer.flush()?;
+    Ok(())
+  }
+}
+
+/// Escape text for use in an XML attribute value.
+fn escape(text: &str) -> Cow<'_, str> {
+  if !text.contains(['&', '<', '>', '"', '\'', '\n', '\r', '\t']) {
+    return Cow::Borrowed(text);
+  }
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      // attribute values normalize raw whitespace, so keep it as references
+      '\n' => escaped.push_str("&#10;"),
+      '\r' => escaped.push_str("&#13;"),
+      '\t' => escaped.push_str("&#9;"),
+      c => escaped.push(c),
+    }
+  }
+  Cow::Owned(escaped)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use ast_grep_config::{from_yaml_string, GlobalRules};
+  use ast_grep_language::{Language, SupportLang};
+  use codespan_reporting::term::termcolor::Buffer;
+
+  fn make_rule(rule: &str) -> RuleConfig<SgLang> {
+    let globals = GlobalRules::default();
+    from_yaml_string(
+      &format!(
+        r"
+id: no-log
+message: Avoid <console> & friends
+language: TypeScript
+{rule}"
+      ),
+      &globals,
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+  }
+
+  fn get_output(files: &[(&str, &str)], rule_str: &str) -> String {
+    let mut printer = CheckstylePrinter::new(Buffer::no_color());
+    let rule = make_rule(rule_str);
+    for (name, src) in files {
+      let src = src.to_string();
+      let grep = SgLang::from(SupportLang::TypeScript).ast_grep(&src);
+      let matches = grep.root().find_all(&rule.matcher).collect();
+      let file = SimpleFile::new(Cow::Borrowed(*name), &src);
+      printer.print_rule(matches, file, &rule).unwrap();
+    }
+    printer.after_print().unwrap();
+    String::from_utf8(printer.writer.into_inner()).expect("should be utf8")
+  }
+
+  #[test]
+  fn test_checkstyle() {
+    let output = get_output(
+      &[
+        ("b.ts", "console.log(1)"),
+        ("a.ts", "let a\n  console.log(a)"),
+      ],
+      "rule: { pattern: console.log($A) }\nseverity: warning",
+    );
+    let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<checkstyle version="8.0">
+  <file name="a.ts">
+    <error line="2" column="3" severity="warning" message="Avoid &lt;console&gt; &amp; friends" source="no-log"/>
+  </file>
+  <file name="b.ts">
+    <error line="1" column="1" severity="warning" message="Avoid &lt;console&gt; &amp; friends" source="no-log"/>
+  </file>
+</checkstyle>
+"#;
+    assert_eq!(output, expected);
+  }
+
+  #[test]
+  fn test_checkstyle_empty() {
+    let output = get_output(&[("a.ts", "let a = 1")], "rule: { pattern: console }");
+    let expected =
+      "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<checkstyle version=\"8.0\">\n</checkstyle>\n";
+    assert_eq!(output, expected);
+  }
+
+  #[test]
+  fn test_severity() {
+    let output = get_output(&[("a.ts", "debugger")], "rule: { pattern: debugger }");
+    assert!(output.contains(r#"severity="info""#));
+    let output = get_output(
+      &[("a.ts", "debugger")],
+      "rule: { pattern: debugger }\nseverity: error",
+    );
+    assert!(output.contains(r#"severity="error""#));
+  }
+
+  #[test]
+  fn test_escape() {
+    assert_eq!(escape("plain"), "plain");
+    assert_eq!(escape("a\"b'\nc"), "a&quot;b&apos;&#10;c");
+  }
+}
@@ -5,7 +5,7 @@ use clap::ValueEnum;
 
 use anyhow::Result;
 use codespan_reporting::files::SimpleFile;
-use std::io::{Stdout, Write};
+use std::io::Write;
 
 use std::borrow::Cow;
 use std::path::{Path, PathBuf};
@@ -14,6 +14,14 @@ use std::path::{Path, PathBuf};
 #[clap(rename_all = "lower")]
 pub enum Platform {
   GitHub,
+  Sarif,
+  /// Newline-delimited JSON, one result per line
+  Json,
+  /// One JSON array of all results
+  #[value(name = "json-array")]
+  JsonArray,
+  /// Checkstyle XML, read by Jenkins, SonarQube and other CI tools
+  Checkstyle,
 }
 
 pub struct CloudPrinter<W: Write> {
@@ -26,12 +34,6 @@ impl<W: Write> CloudPrinter<W> {
   }
 }
 
-impl CloudPrinter<Stdout> {
-  pub fn stdout() -> Self {
-    Self::new(std::io::stdout())
-  }
-}
-
 impl<W: Write> Printer for CloudPrinter<W> {
   fn print_rule(
     &mut self,
@@ -80,8 +82,9 @@ fn print_rule<W: Write>(
   let title = &rule.id;
   let name = path.display();
   for m in matches {
-    let line = m.start_pos().line() + 1;
-    let end_line = m.end_pos().line() + 1;
+    let span = m.span();
+    let line = span.start_line + 1;
+    let end_line = span.end_line + 1;
     let message = rule.get_message(&m);
     writeln!(
       writer,
@@ -1,14 +1,14 @@
 use super::{Diff, NodeMatch, Printer};
 use crate::lang::SgLang;
 use ast_grep_config::{RuleConfig, Severity};
-use ast_grep_core::DisplayContext;
+use ast_grep_core::{ColorConfig, DisplayContext};
 
 use ansi_term::{Color, Style};
 use anyhow::Result;
 use clap::ValueEnum;
 use codespan_reporting::diagnostic::{self, Diagnostic, Label};
 use codespan_reporting::files::SimpleFile;
-use codespan_reporting::term::termcolor::{ColorChoice, StandardStream, WriteColor};
+use codespan_reporting::term::termcolor::{ColorChoice, WriteColor};
 use codespan_reporting::term::{self, DisplayStyle};
 use similar::{ChangeTag, DiffOp, TextDiff};
 
@@ -57,13 +57,6 @@ pub struct ColoredPrinter<W: WriteColor> {
   heading: Heading,
   context: (u16, u16),
 }
-impl ColoredPrinter<StandardStream> {
-  pub fn stdout<C: Into<ColorChoice>>(color: C) -> Self {
-    let color = color.into();
-    ColoredPrinter::new(StandardStream::stdout(color)).color(color)
-  }
-}
-
 impl<W: WriteColor> ColoredPrinter<W> {
   pub fn new(writer: W) -> Self {
     Self {
@@ -144,7 +137,7 @@ impl<W: WriteColor> Printer for ColoredPrinter<W> {
       let diagnostic = Diagnostic::new(severity)
         .with_code(&rule.id)
         .with_message(rule.get_message(&m))
-        .with_notes(rule.note.iter().cloned().collect())
+        .with_notes(rule.get_note(&m).into_iter().collect())
         .with_labels(labels);
       term::emit(&mut *writer, config, &file, &diagnostic)?;
     }
@@ -189,7 +182,7 @@ impl<W: WriteColor> Printer for ColoredPrinter<W> {
         &source[start..],
       );
       print_diff(source, &new_str, &self.styles, writer, context)?;
-      if let Some(note) = &rule.note {
+      if let Some(note) = rule.get_note(&diff.node_match) {
         writeln!(writer, "{}", self.styles.rule.note.paint("Note:"))?;
         writeln!(writer, "{note}")?;
       }
@@ -328,8 +321,8 @@ fn print_matches_with_heading<W: WriteColor>(
   let mut merger = MatchMerger::new(&first_match, printer.context);
 
   let display = merger.display(&first_match);
-  let mut ret = display.leading.to_string();
-  styles.push_matched_to_ret(&mut ret, &display.matched)?;
+  let mut ret = styles.matched.paint_context(display.leading);
+  styles.push_matched_to_ret(&mut ret, &display.matched);
 
   for nm in matches {
     if merger.check_overlapping(&nm) {
@@ -339,10 +332,10 @@ fn print_matches_with_heading<W: WriteColor>(
     // merge adjacent matches
     if let Some(last_end_offset) = merger.merge_adjacent(&nm) {
       ret.push_str(&source[last_end_offset..nm.range().start]);
-      styles.push_matched_to_ret(&mut ret, &display.matched)?;
+      styles.push_matched_to_ret(&mut ret, &display.matched);
       continue;
     }
-    ret.push_str(merger.last_trailing);
+    ret.push_str(&styles.matched.paint_context(merger.last_trailing));
     let lines = ret.lines().count();
     let mut num = merger.last_start_line;
     let width = (lines + num).checked_ilog10().unwrap_or(0) as usize + 1;
@@ -354,10 +347,10 @@ fn print_matches_with_heading<W: WriteColor>(
       writeln!(writer, "{:╴>width$}┤", "")?; // make separation
     }
     merger.conclude_match(&nm);
-    ret = display.leading.to_string();
-    styles.push_matched_to_ret(&mut ret, &display.matched)?;
+    ret = styles.matched.paint_context(display.leading);
+    styles.push_matched_to_ret(&mut ret, &display.matched);
   }
-  ret.push_str(merger.last_trailing);
+  ret.push_str(&styles.matched.paint_context(merger.last_trailing));
   let lines = ret.lines().count();
   let mut num = merger.last_start_line;
   let width = (lines + num).checked_ilog10().unwrap_or(0) as usize + 1;
@@ -386,8 +379,8 @@ fn print_matches_with_prefix<W: WriteColor>(
 
   let mut merger = MatchMerger::new(&first_match, printer.context);
   let display = merger.display(&first_match);
-  let mut ret = display.leading.to_string();
-  styles.push_matched_to_ret(&mut ret, &display.matched)?;
+  let mut ret = styles.matched.paint_context(display.leading);
+  styles.push_matched_to_ret(&mut ret, &display.matched);
   for nm in matches {
     if merger.check_overlapping(&nm) {
       continue;
@@ -396,10 +389,10 @@ fn print_matches_with_prefix<W: WriteColor>(
     // merge adjacent matches
     if let Some(last_end_offset) = merger.merge_adjacent(&nm) {
       ret.push_str(&source[last_end_offset..nm.range().start]);
-      styles.push_matched_to_ret(&mut ret, &display.matched)?;
+      styles.push_matched_to_ret(&mut ret, &display.matched);
       continue;
     }
-    ret.push_str(merger.last_trailing);
+    ret.push_str(&styles.matched.paint_context(merger.last_trailing));
     for (n, line) in ret.lines().enumerate() {
       let num = merger.last_start_line + n;
       writeln!(writer, "{path}:{num}:{line}")?;
@@ -408,10 +401,10 @@ fn print_matches_with_prefix<W: WriteColor>(
       writeln!(writer, "--")?; // make separation
     }
     merger.conclude_match(&nm);
-    ret = display.leading.to_string();
-    styles.push_matched_to_ret(&mut ret, &display.matched)?;
+    ret = styles.matched.paint_context(display.leading);
+    styles.push_matched_to_ret(&mut ret, &display.matched);
   }
-  ret.push_str(merger.last_trailing);
+  ret.push_str(&styles.matched.paint_context(merger.last_trailing));
   for (n, line) in ret.lines().enumerate() {
     let num = merger.last_start_line + n;
     writeln!(writer, "{path}:{num}:{line}")?;
@@ -555,7 +548,7 @@ struct RuleStyle {
 pub struct PrintStyles {
   // print match color
   file_path: Style,
-  matched: Style,
+  matched: ColorConfig,
   line_num: Style,
   // diff insert style
   insert: Style,
@@ -576,7 +569,7 @@ impl PrintStyles {
     let delete = Style::new().fg(RED);
     Self {
       file_path: Color::Cyan.italic(),
-      matched: Color::Red.bold(),
+      matched: ColorConfig::default(),
       line_num: Style::new().dimmed(),
       insert,
       insert_emphasis: insert.on(SEA_GREEN).bold(),
@@ -593,23 +586,14 @@ impl PrintStyles {
     }
   }
   fn no_color() -> Self {
-    Self::default()
+    Self {
+      matched: ColorConfig::plain(),
+      ..Self::default()
+    }
   }
 
-  fn push_matched_to_ret(&self, ret: &mut String, matched: &str) -> Result<()> {
-    use std::fmt::Write;
-    // TODO: use intersperse
-    let mut lines = matched.lines();
-    if let Some(line) = lines.next() {
-      write!(ret, "{}", self.matched.paint(line))?;
-    } else {
-      return Ok(());
-    }
-    for line in lines {
-      ret.push('\n');
-      write!(ret, "{}", self.matched.paint(line))?;
-    }
-    Ok(())
+  fn push_matched_to_ret(&self, ret: &mut String, matched: &str) {
+    ret.push_str(&self.matched.paint_matched(matched));
   }
 }
 impl From<ColorChoice> for PrintStyles {
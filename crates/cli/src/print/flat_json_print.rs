@@ -0,0 +1,206 @@
+use super::{Diff, NodeMatch, Printer};
+use crate::lang::SgLang;
+use ast_grep_config::{RuleConfig, Severity};
+use ast_grep_core::Span;
+
+use anyhow::Result;
+use codespan_reporting::files::SimpleFile;
+use serde::Serialize;
+
+use std::borrow::Cow;
+use std::io::Write;
+use std::path::Path;
+
+/// One scan result. Unlike `--json`, it only has the fields needed to report a rule violation.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanRecord {
+  rule_id: String,
+  severity: Severity,
+  file: String,
+  range: Span,
+  text: String,
+  replacement: Option<String>,
+}
+
+impl ScanRecord {
+  fn new(
+    nm: &NodeMatch,
+    file: &str,
+    rule: &RuleConfig<SgLang>,
+    replacement: Option<String>,
+  ) -> Self {
+    Self {
+      rule_id: rule.id.clone(),
+      severity: rule.severity.clone(),
+      file: file.to_string(),
+      range: nm.span(),
+      text: nm.text().to_string(),
+      replacement,
+    }
+  }
+}
+
+/// Prints scan results as newline-delimited JSON, one record per line.
+/// In array mode, all records are printed as one JSON array after scanning.
+pub struct FlatJsonPrinter<W: Write> {
+  writer: W,
+  array: bool,
+  records: Vec<ScanRecord>,
+}
+
+impl<W: Write> FlatJsonPrinter<W> {
+  pub fn new(writer: W, array: bool) -> Self {
+    Self {
+      writer,
+      array,
+      records: vec![],
+    }
+  }
+
+  fn add_record(&mut self, record: ScanRecord) -> Result<()> {
+    if self.array {
+      self.records.push(record);
+    } else {
+      serde_json::to_writer(&mut self.writer, &record)?;
+      writeln!(self.writer)?;
+    }
+    Ok(())
+  }
+}
+
+impl<W: Write> Printer for FlatJsonPrinter<W> {
+  fn print_rule(
+    &mut self,
+    matches: Vec<NodeMatch>,
+    file: SimpleFile<Cow<str>, &String>,
+    rule: &RuleConfig<SgLang>,
+  ) -> Result<()> {
+    let path = file.name();
+    for nm in matches {
+      self.add_record(ScanRecord::new(&nm, path, rule, None))?;
+    }
+    Ok(())
+  }
+
+  fn print_matches(&mut self, _m: Vec<NodeMatch>, _p: &Path) -> Result<()> {
+    unreachable!()
+  }
+
+  fn print_diffs(&mut self, _d: Vec<Diff>, _p: &Path) -> Result<()> {
+    unreachable!()
+  }
+
+  fn print_rule_diffs(
+    &mut self,
+    diffs: Vec<(Diff<'_>, &RuleConfig<SgLang>)>,
+    path: &Path,
+  ) -> Result<()> {
+    let path = path.to_string_lossy();
+    for (diff, rule) in diffs {
+      let replacement = Some(diff.replacement.into_owned());
+      self.add_record(ScanRecord::new(&diff.node_match, &path, rule, replacement))?;
+    }
+    Ok(())
+  }
+
+  fn after_print(&mut self) -> Result<()> {
+    if self.array {
+      serde_json::to_writer(&mut self.writer, &self.records)?;
+      writeln!(self.writer)?;
+    }
+    self.writer.flush()?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use ast_grep_config::{from_yaml_string, GlobalRules};
+  use ast_grep_language::{Language, SupportLang};
+  use codespan_reporting::term::termcolor::Buffer;
+  use serde_json::Value;
+
+  fn make_rule(rule: &str) -> RuleConfig<SgLang> {
+    let globals = GlobalRules::default();
+    from_yaml_string(
+      &format!(
+        r"
+id: test
+message: test rule
+language: TypeScript
+{rule}"
+      ),
+      &globals,
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+  }
+
+  fn get_output(src: &str, rule_str: &str, array: bool) -> String {
+    let src = src.to_owned();
+    let mut printer = FlatJsonPrinter::new(Buffer::no_color(), array);
+    let grep = SgLang::from(SupportLang::TypeScript).ast_grep(&src);
+    let rule = make_rule(rule_str);
+    if let Some(fixer) = &rule.matcher.fixer {
+      let diffs = grep
+        .root()
+        .find_all(&rule.matcher)
+        .map(|m| (Diff::generate(m, &rule.matcher, fixer), &rule))
+        .collect();
+      printer
+        .print_rule_diffs(diffs, Path::new("test.ts"))
+        .unwrap();
+    } else {
+      let matches = grep.root().find_all(&rule.matcher).collect();
+      let file = SimpleFile::new(Cow::Borrowed("test.ts"), &src);
+      printer.print_rule(matches, file, &rule).unwrap();
+    }
+    printer.after_print().unwrap();
+    String::from_utf8(printer.writer.into_inner()).expect("should be utf8")
+  }
+
+  #[test]
+  fn test_ndjson() {
+    let output = get_output(
+      "let a = 1\nconsole.log(a)\nconsole.log(2)",
+      "rule: { pattern: console.log($A) }\nseverity: warning",
+      false,
+    );
+    let lines: Vec<Value> = output
+      .lines()
+      .map(|l| serde_json::from_str(l).expect("each line should be json"))
+      .collect();
+    assert_eq!(lines.len(), 2);
+    let first = &lines[0];
+    assert_eq!(first["ruleId"], "test");
+    assert_eq!(first["severity"], "warning");
+    assert_eq!(first["file"], "test.ts");
+    assert_eq!(first["text"], "console.log(a)");
+    assert_eq!(first["replacement"], Value::Null);
+    let range = &first["range"];
+    assert_eq!(range["startLine"], 1);
+    assert_eq!(range["startCol"], 0);
+    assert_eq!(range["endLine"], 1);
+    assert_eq!(range["endCol"], 14);
+  }
+
+  #[test]
+  fn test_json_array() {
+    let output = get_output("let a = 1", "rule: { pattern: console }", true);
+    assert_eq!(output, "[]\n");
+    let output = get_output(
+      "var a = 1; var b = 2",
+      "rule: { pattern: var $A = $B }\nfix: let $A = $B",
+      true,
+    );
+    let records: Value = serde_json::from_str(&output).expect("should be json");
+    let records = records.as_array().expect("should be array");
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["text"], "var a = 1;");
+    assert_eq!(records[0]["replacement"], "let a = 1");
+    assert_eq!(records[1]["severity"], "hint");
+  }
+}
@@ -13,7 +13,7 @@ use codespan_reporting::files::SimpleFile;
 use serde::{Deserialize, Serialize};
 
 use std::borrow::Cow;
-use std::io::{Stdout, Write};
+use std::io::Write;
 use std::path::Path;
 
 #[derive(Serialize, Deserialize)]
@@ -130,17 +130,16 @@ fn from_env<'a>(nm: &NodeMatch<'a>) -> Option<MetaVariables<'a>> {
 }
 
 fn get_range(n: &Node<'_, SgLang>) -> Range {
-  let start_pos = n.start_pos();
-  let end_pos = n.end_pos();
+  let span = n.span();
   Range {
     byte_offset: n.range(),
     start: Position {
-      line: start_pos.line(),
-      column: start_pos.column(n),
+      line: span.start_line,
+      column: span.start_col,
     },
     end: Position {
-      line: end_pos.line(),
-      column: end_pos.column(n),
+      line: span.end_line,
+      column: span.end_col,
     },
   }
 }
@@ -201,13 +200,14 @@ struct RuleMatchJSON<'a> {
 impl<'a> RuleMatchJSON<'a> {
   fn new(nm: NodeMatch<'a>, path: &'a str, rule: &'a RuleConfig<SgLang>) -> Self {
     let message = rule.get_message(&nm);
+    let note = rule.get_note(&nm);
     let labels = get_labels(&nm);
     let matched = MatchJSON::new(nm, path, (0, 0));
     Self {
       matched,
       rule_id: &rule.id,
       severity: rule.severity.clone(),
-      note: rule.note.clone(),
+      note,
       message,
       labels,
     }
@@ -215,13 +215,14 @@ impl<'a> RuleMatchJSON<'a> {
   fn diff(diff: Diff<'a>, path: &'a str, rule: &'a RuleConfig<SgLang>) -> Self {
     let nm = &diff.node_match;
     let message = rule.get_message(nm);
+    let note = rule.get_note(nm);
     let labels = get_labels(nm);
     let matched = MatchJSON::diff(diff, path, (0, 0));
     Self {
       matched,
       rule_id: &rule.id,
       severity: rule.severity.clone(),
-      note: rule.note.clone(),
+      note,
       message,
       labels,
     }
@@ -250,12 +251,6 @@ pub struct JSONPrinter<W: Write> {
   // indicate if any matches happened
   matched: bool,
 }
-impl JSONPrinter<Stdout> {
-  pub fn stdout(style: JsonStyle) -> Self {
-    Self::new(std::io::stdout(), style)
-  }
-}
-
 impl<W: Write> JSONPrinter<W> {
   pub fn new(output: W, style: JsonStyle) -> Self {
     // no match happened yet
@@ -1,24 +1,34 @@
+mod checkstyle_print;
 mod cloud_print;
 mod colored_print;
+mod flat_json_print;
 mod interactive_print;
 mod json_print;
+mod sarif_print;
 
 use crate::lang::SgLang;
+use crate::utils::ErrorContext as EC;
 use ast_grep_config::{Fixer, RuleConfig};
 use ast_grep_core::{Matcher, NodeMatch as SgNodeMatch, StrDoc};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::ValueEnum;
+use codespan_reporting::term::termcolor::{ColorSpec, NoColor, StandardStream, WriteColor};
 
 use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 use std::path::Path;
 
+pub use checkstyle_print::CheckstylePrinter;
 pub use cloud_print::{CloudPrinter, Platform};
 pub use codespan_reporting::files::SimpleFile;
 pub use codespan_reporting::term::termcolor::ColorChoice;
 pub use colored_print::{print_diff, ColoredPrinter, Heading, PrintStyles, ReportStyle};
+pub use flat_json_print::FlatJsonPrinter;
 pub use interactive_print::InteractivePrinter;
 pub use json_print::{JSONPrinter, JsonStyle};
+pub use sarif_print::SarifPrinter;
 
 type NodeMatch<'a> = SgNodeMatch<'a, StrDoc<SgLang>>;
 
@@ -48,6 +58,67 @@ pub trait Printer {
   }
 }
 
+/// Where printers write matches: stdout, or the file given by `--output-file`.
+/// Files never get color escapes.
+pub enum OutputTarget {
+  Stdout(StandardStream),
+  File(NoColor<BufWriter<File>>),
+}
+
+impl OutputTarget {
+  /// Truncates `path` if it exists.
+  pub fn new<C: Into<ColorChoice>>(path: Option<&Path>, color: C) -> Result<Self> {
+    let Some(path) = path else {
+      return Ok(Self::Stdout(StandardStream::stdout(color.into())));
+    };
+    let file = File::create(path).with_context(|| EC::OpenOutputFile(path.to_path_buf()))?;
+    Ok(Self::File(NoColor::new(BufWriter::new(file))))
+  }
+
+  pub fn color_choice<C: Into<ColorChoice>>(&self, color: C) -> ColorChoice {
+    match self {
+      Self::Stdout(_) => color.into(),
+      Self::File(_) => ColorChoice::Never,
+    }
+  }
+}
+
+impl Write for OutputTarget {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    match self {
+      Self::Stdout(w) => w.write(buf),
+      Self::File(w) => w.write(buf),
+    }
+  }
+  fn flush(&mut self) -> io::Result<()> {
+    match self {
+      Self::Stdout(w) => w.flush(),
+      Self::File(w) => w.flush(),
+    }
+  }
+}
+
+impl WriteColor for OutputTarget {
+  fn supports_color(&self) -> bool {
+    match self {
+      Self::Stdout(w) => w.supports_color(),
+      Self::File(w) => w.supports_color(),
+    }
+  }
+  fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+    match self {
+      Self::Stdout(w) => w.set_color(spec),
+      Self::File(w) => w.set_color(spec),
+    }
+  }
+  fn reset(&mut self) -> io::Result<()> {
+    match self {
+      Self::Stdout(w) => w.reset(),
+      Self::File(w) => w.reset(),
+    }
+  }
+}
+
 #[derive(Clone)]
 pub struct Diff<'n> {
   /// the matched node
@@ -0,0 +1,424 @@
+use super::{Diff, NodeMatch, Printer};
+use crate::lang::SgLang;
+use ast_grep_config::{RuleConfig, Severity};
+
+use anyhow::Result;
+use codespan_reporting::files::SimpleFile;
+use serde::Serialize;
+
+use std::borrow::Cow;
+use std::io::Write;
+use std::path::Path;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+#[derive(Serialize)]
+struct SarifLog<'a> {
+  #[serde(rename = "$schema")]
+  schema: &'static str,
+  version: &'static str,
+  runs: [Run<'a>; 1],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Run<'a> {
+  tool: Tool<'a>,
+  results: &'a [SarifResult],
+  /// ast-grep reports columns in unicode characters, not UTF-16 code units
+  column_kind: &'static str,
+}
+
+#[derive(Serialize)]
+struct Tool<'a> {
+  driver: Driver<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver<'a> {
+  name: &'static str,
+  version: &'static str,
+  information_uri: &'static str,
+  rules: &'a [ReportingDescriptor],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportingDescriptor {
+  id: String,
+  short_description: Message,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  full_description: Option<Message>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  help_uri: Option<String>,
+  default_configuration: Configuration,
+}
+
+#[derive(Serialize)]
+struct Configuration {
+  level: &'static str,
+}
+
+#[derive(Serialize)]
+struct Message {
+  text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+  rule_id: String,
+  level: &'static str,
+  message: Message,
+  locations: Vec<Location>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  fixes: Option<Vec<Fix>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+  physical_location: PhysicalLocation,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+  artifact_location: ArtifactLocation,
+  region: Region,
+}
+
+#[derive(Serialize)]
+struct ArtifactLocation {
+  uri: String,
+}
+
+/// One-based line and column region.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+  start_line: usize,
+  start_column: usize,
+  end_line: usize,
+  end_column: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Fix {
+  description: Message,
+  artifact_changes: Vec<ArtifactChange>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactChange {
+  artifact_location: ArtifactLocation,
+  replacements: Vec<Replacement>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Replacement {
+  deleted_region: ByteRegion,
+  inserted_content: Message,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ByteRegion {
+  byte_offset: usize,
+  byte_length: usize,
+}
+
+/// Prints a single [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+/// log after all files are scanned. SARIF is one JSON document, so results are buffered.
+pub struct SarifPrinter<W: Write> {
+  writer: W,
+  /// all rules loaded for the scan, listed in the driver even if they have no result
+  loaded: Vec<ReportingDescriptor>,
+  rules: Vec<ReportingDescriptor>,
+  results: Vec<SarifResult>,
+}
+
+impl<W: Write> SarifPrinter<W> {
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer,
+      loaded: vec![],
+      rules: vec![],
+      results: vec![],
+    }
+  }
+
+  pub fn add_loaded_rule(&mut self, rule: &RuleConfig<SgLang>) {
+    self.loaded.push(to_descriptor(rule));
+  }
+
+  fn add_rule(&mut self, rule: &RuleConfig<SgLang>) {
+    if self.rules.iter().any(|r| r.id == rule.id) {
+      return;
+    }
+    self.rules.push(to_descriptor(rule));
+  }
+
+  fn add_result(&mut self, nm: &NodeMatch, uri: &str, rule: &RuleConfig<SgLang>) {
+    self.add_rule(rule);
+    self.results.push(SarifResult {
+      rule_id: rule.id.clone(),
+      level: get_level(&rule.severity),
+      message: Message {
+        text: rule.get_message(nm),
+      },
+      locations: vec![Location {
+        physical_location: PhysicalLocation {
+          artifact_location: ArtifactLocation { uri: uri.into() },
+          region: get_region(nm),
+        },
+      }],
+      fixes: None,
+    });
+  }
+}
+
+impl<W: Write> Printer for SarifPrinter<W> {
+  fn before_print(&mut self) -> Result<()> {
+    for descriptor in std::mem::take(&mut self.loaded) {
+      if !self.rules.iter().any(|r| r.id == descriptor.id) {
+        self.rules.push(descriptor);
+      }
+    }
+    Ok(())
+  }
+
+  fn print_rule(
+    &mut self,
+    matches: Vec<NodeMatch>,
+    file: SimpleFile<Cow<str>, &String>,
+    rule: &RuleConfig<SgLang>,
+  ) -> Result<()> {
+    let uri = to_uri(Path::new(file.name().as_ref()));
+    for nm in matches {
+      self.add_result(&nm, &uri, rule);
+    }
+    Ok(())
+  }
+
+  fn print_matches(&mut self, _m: Vec<NodeMatch>, _p: &Path) -> Result<()> {
+    unreachable!()
+  }
+
+  fn print_diffs(&mut self, _d: Vec<Diff>, _p: &Path) -> Result<()> {
+    unreachable!()
+  }
+
+  fn print_rule_diffs(
+    &mut self,
+    diffs: Vec<(Diff<'_>, &RuleConfig<SgLang>)>,
+    path: &Path,
+  ) -> Result<()> {
+    let uri = to_uri(path);
+    for (diff, rule) in diffs {
+      self.add_result(&diff.node_match, &uri, rule);
+      let fix = Fix {
+        description: Message {
+          text: format!("Apply fix of rule {}", rule.id),
+        },
+        artifact_changes: vec![ArtifactChange {
+          artifact_location: ArtifactLocation { uri: uri.clone() },
+          replacements: vec![Replacement {
+            deleted_region: ByteRegion {
+              byte_offset: diff.range.start,
+              byte_length: diff.range.len(),
+            },
+            inserted_content: Message {
+              text: diff.replacement.into_owned(),
+            },
+          }],
+        }],
+      };
+      if let Some(result) = self.results.last_mut() {
+        result.fixes = Some(vec![fix]);
+      }
+    }
+    Ok(())
+  }
+
+  fn after_print(&mut self) -> Result<()> {
+    let log = SarifLog {
+      schema: SARIF_SCHEMA,
+      version: SARIF_VERSION,
+      runs: [Run {
+        tool: Tool {
+          driver: Driver {
+            name: "ast-grep",
+            version: env!("CARGO_PKG_VERSION"),
+            information_uri: "https://ast-grep.github.io/",
+            rules: &self.rules,
+          },
+        },
+        results: &self.results,
+        column_kind: "unicodeCodePoints",
+      }],
+    };
+    serde_json::to_writer_pretty(&mut self.writer, &log)?;
+    writeln!(self.writer)?;
+    Ok(())
+  }
+}
+
+fn to_descriptor(rule: &RuleConfig<SgLang>) -> ReportingDescriptor {
+  let short_description = if rule.message.is_empty() {
+    rule.id.clone()
+  } else {
+    rule.message.clone()
+  };
+  ReportingDescriptor {
+    id: rule.id.clone(),
+    short_description: Message {
+      text: short_description,
+    },
+    full_description: rule.note.clone().map(|text| Message { text }),
+    help_uri: rule.url.clone(),
+    default_configuration: Configuration {
+      level: get_level(&rule.severity),
+    },
+  }
+}
+
+fn get_level(severity: &Severity) -> &'static str {
+  match severity {
+    Severity::Error => "error",
+    Severity::Warning => "warning",
+    Severity::Info => "note",
+    Severity::Hint => "none",
+    Severity::Off => unreachable!("turned-off rule should not have match."),
+  }
+}
+
+fn get_region(nm: &NodeMatch) -> Region {
+  let span = nm.span();
+  Region {
+    start_line: span.start_line + 1,
+    start_column: span.start_col + 1,
+    end_line: span.end_line + 1,
+    end_column: span.end_col + 1,
+  }
+}
+
+// SARIF uri uses forward slash as separator on all platforms
+fn to_uri(path: &Path) -> String {
+  path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use ast_grep_config::{from_yaml_string, GlobalRules};
+  use ast_grep_language::{Language, SupportLang};
+  use codespan_reporting::term::termcolor::Buffer;
+  use serde_json::Value;
+
+  fn make_rule(rule: &str) -> RuleConfig<SgLang> {
+    let globals = GlobalRules::default();
+    from_yaml_string(
+      &format!(
+        r"
+id: test
+message: test rule
+language: TypeScript
+{rule}"
+      ),
+      &globals,
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+  }
+
+  fn get_sarif(src: &str, rule_str: &str) -> Value {
+    let src = src.to_owned();
+    let mut printer = SarifPrinter::new(Buffer::no_color());
+    let grep = SgLang::from(SupportLang::TypeScript).ast_grep(&src);
+    let rule = make_rule(rule_str);
+    printer.add_loaded_rule(&rule);
+    printer.before_print().unwrap();
+    if let Some(fixer) = &rule.matcher.fixer {
+      let diffs = grep
+        .root()
+        .find_all(&rule.matcher)
+        .map(|m| (Diff::generate(m, &rule.matcher, fixer), &rule))
+        .collect();
+      printer
+        .print_rule_diffs(diffs, Path::new("test.ts"))
+        .unwrap();
+    } else {
+      let matches = grep.root().find_all(&rule.matcher).collect();
+      let file = SimpleFile::new(Cow::Borrowed("test.ts"), &src);
+      printer.print_rule(matches, file, &rule).unwrap();
+    }
+    printer.after_print().unwrap();
+    serde_json::from_slice(printer.writer.as_slice()).expect("should be valid json")
+  }
+
+  #[test]
+  fn test_empty_log() {
+    let sarif = get_sarif("let a = 123", "rule: { pattern: console }");
+    assert_eq!(sarif["version"], "2.1.0");
+    let run = &sarif["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "ast-grep");
+    assert_eq!(run["results"], Value::Array(vec![]));
+    let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0]["id"], "test");
+  }
+
+  #[test]
+  fn test_result_location() {
+    let sarif = get_sarif(
+      "let a = 1\nconsole.log(a)",
+      "
+rule: { pattern: console.log($A) }
+severity: error
+url: https://example.com/rule
+note: do not log",
+    );
+    let run = &sarif["runs"][0];
+    let rule = &run["tool"]["driver"]["rules"][0];
+    assert_eq!(rule["id"], "test");
+    assert_eq!(rule["shortDescription"]["text"], "test rule");
+    assert_eq!(rule["fullDescription"]["text"], "do not log");
+    assert_eq!(rule["helpUri"], "https://example.com/rule");
+    let result = &run["results"][0];
+    assert_eq!(result["ruleId"], "test");
+    assert_eq!(result["level"], "error");
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "test.ts");
+    let region = &location["region"];
+    assert_eq!(region["startLine"], 2);
+    assert_eq!(region["startColumn"], 1);
+    assert_eq!(region["endLine"], 2);
+    assert_eq!(region["endColumn"], 15);
+    assert!(result.get("fixes").is_none());
+  }
+
+  #[test]
+  fn test_result_fix() {
+    let sarif = get_sarif(
+      "let a = 1",
+      "
+rule: { pattern: let $A = $B }
+fix: const $A = $B",
+    );
+    let result = &sarif["runs"][0]["results"][0];
+    assert_eq!(result["level"], "none");
+    let change = &result["fixes"][0]["artifactChanges"][0];
+    assert_eq!(change["artifactLocation"]["uri"], "test.ts");
+    let replacement = &change["replacements"][0];
+    assert_eq!(replacement["deletedRegion"]["byteOffset"], 0);
+    assert_eq!(replacement["deletedRegion"]["byteLength"], 9);
+    assert_eq!(replacement["insertedContent"]["text"], "const a = 1");
+  }
+}
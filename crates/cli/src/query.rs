@@ -0,0 +1,196 @@
+use crate::lang::SgLang;
+use crate::print::ColorArg;
+use crate::utils::ErrorContext as EC;
+
+use anyhow::{Context, Result};
+use ast_grep_config::{from_str, DeserializeEnv, SerializableRule};
+use ast_grep_core::{AstGrep, ColorConfig, Matcher, Pattern, StrDoc};
+use ast_grep_language::Language;
+use clap::Args;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct QueryArg {
+  /// The file to query.
+  #[clap(value_parser)]
+  file: PathBuf,
+  /// The language of the file.
+  #[clap(short, long)]
+  lang: SgLang,
+  /// Show NUM lines around each match.
+  #[clap(short = 'C', long, default_value = "0", value_name = "NUM")]
+  context: usize,
+  /// Controls output color. Matches are highlighted when colors are used.
+  #[clap(long, default_value = "auto", value_name = "WHEN")]
+  color: ColorArg,
+}
+
+/// One line typed in the REPL.
+enum Command<'a> {
+  Pattern(&'a str),
+  Rule(&'a str),
+  Clear,
+  Quit,
+  Empty,
+}
+
+impl<'a> Command<'a> {
+  fn parse(line: &'a str) -> Self {
+    let line = line.trim();
+    if line.is_empty() {
+      return Self::Empty;
+    }
+    match line {
+      ":clear" => Self::Clear,
+      ":quit" | ":q" => Self::Quit,
+      _ => match line.strip_prefix(":rule ") {
+        Some(rule) => Self::Rule(rule),
+        None => Self::Pattern(line),
+      },
+    }
+  }
+}
+
+const HELP: &str = "\
+Type a pattern to search the file.
+  :rule <yaml>  search with a rule object, e.g. :rule {kind: identifier}
+  :clear        clear the screen
+  :quit         exit";
+
+pub fn run_query(arg: QueryArg) -> Result<()> {
+  let src = std::fs::read_to_string(&arg.file).with_context(|| EC::ReadFile(arg.file.clone()))?;
+  let grep = arg.lang.ast_grep(src);
+  let color = if arg.color.should_use_color() {
+    ColorConfig::default()
+  } else {
+    ColorConfig::plain()
+  };
+  let mut editor = DefaultEditor::new()?;
+  println!("{HELP}");
+  loop {
+    let line = match editor.readline("> ") {
+      Ok(line) => line,
+      Err(ReadlineError::Interrupted | ReadlineError::Eof) => return Ok(()),
+      Err(e) => return Err(e.into()),
+    };
+    let command = Command::parse(&line);
+    if !matches!(command, Command::Empty) {
+      editor.add_history_entry(line.as_str())?;
+    }
+    match command {
+      Command::Pattern(pattern) => {
+        let ret = Pattern::try_new(pattern, arg.lang).context(EC::ParsePattern);
+        print_result(ret.map(|p| render_matches(&grep, p, arg.context, &color)));
+      }
+      Command::Rule(rule) => {
+        let ret = parse_rule(rule, arg.lang);
+        print_result(ret.map(|r| render_matches(&grep, r, arg.context, &color)));
+      }
+      Command::Clear => editor.clear_screen()?,
+      Command::Quit => return Ok(()),
+      Command::Empty => (),
+    }
+  }
+}
+
+fn print_result(ret: Result<String>) {
+  match ret {
+    Ok(output) => print!("{output}"),
+    // keep the session alive so the user can fix the query
+    Err(e) => eprintln!("Error: {e:#}"),
+  }
+}
+
+fn parse_rule(yaml: &str, lang: SgLang) -> Result<impl Matcher<SgLang>> {
+  let rule: SerializableRule = from_str(yaml)?;
+  let rule = DeserializeEnv::new(lang).deserialize_rule(rule)?;
+  Ok(rule)
+}
+
+/// Render every match with `context` lines around it, prefixed by one-based line numbers.
+fn render_matches(
+  grep: &AstGrep<StrDoc<SgLang>>,
+  matcher: impl Matcher<SgLang>,
+  context: usize,
+  color: &ColorConfig,
+) -> String {
+  let mut ret = String::new();
+  let mut count = 0;
+  for nm in grep.root().find_all(matcher) {
+    count += 1;
+    let display = nm.display_context(context, context);
+    let annotated = display.to_annotated_string(color);
+    for (n, line) in annotated.lines().enumerate() {
+      // writing to String never fails
+      _ = writeln!(ret, "{:>4}│{line}", display.start_line + n + 1);
+    }
+    ret.push('\n');
+  }
+  _ = writeln!(ret, "{count} match(es)");
+  ret
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use ast_grep_language::SupportLang;
+
+  fn query(src: &str, command: &str, context: usize) -> Result<String> {
+    let lang = SgLang::from(SupportLang::TypeScript);
+    let grep = lang.ast_grep(src);
+    let color = ColorConfig::plain();
+    match Command::parse(command) {
+      Command::Pattern(p) => Ok(render_matches(
+        &grep,
+        Pattern::try_new(p, lang)?,
+        context,
+        &color,
+      )),
+      Command::Rule(r) => Ok(render_matches(&grep, parse_rule(r, lang)?, context, &color)),
+      _ => panic!("should be a query"),
+    }
+  }
+
+  #[test]
+  fn test_parse_command() {
+    assert!(matches!(Command::parse("  "), Command::Empty));
+    assert!(matches!(Command::parse(":clear"), Command::Clear));
+    assert!(matches!(Command::parse(":quit\n"), Command::Quit));
+    assert!(matches!(
+      Command::parse(":rule {kind: number}"),
+      Command::Rule("{kind: number}")
+    ));
+    assert!(matches!(
+      Command::parse("foo($A)"),
+      Command::Pattern("foo($A)")
+    ));
+  }
+
+  #[test]
+  fn test_query_pattern() -> Result<()> {
+    let src = "let a = 1\nconsole.log(a)\nlet b = 2";
+    let output = query(src, "console.log($A)", 0)?;
+    assert_eq!(output, "   2│console.log(a)\n\n1 match(es)\n");
+    let output = query(src, "console.log($A)", 1)?;
+    assert_eq!(
+      output,
+      "   1│let a = 1\n   2│console.log(a)\n   3│let b = 2\n\n1 match(es)\n"
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_query_rule() -> Result<()> {
+    let output = query("let a = 1 + 2", ":rule {kind: number}", 0)?;
+    assert_eq!(
+      output,
+      "   1│let a = 1 + 2\n\n   1│let a = 1 + 2\n\n2 match(es)\n"
+    );
+    assert!(query("let a = 1", ":rule {kind: not_a_kind}", 0).is_err());
+    Ok(())
+  }
+}
@@ -9,9 +9,13 @@ use ignore::WalkParallel;
 
 use crate::config::ProjectConfig;
 use crate::lang::SgLang;
-use crate::print::{ColoredPrinter, Diff, Heading, InteractivePrinter, JSONPrinter, Printer};
+use crate::print::{
+  ColoredPrinter, Diff, Heading, InteractivePrinter, JSONPrinter, OutputTarget, Printer,
+};
 use crate::utils::ErrorContext as EC;
-use crate::utils::{filter_file_pattern, ContextArgs, InputArgs, MatchUnit, OutputArgs};
+use crate::utils::{
+  detect_file_lang, filter_file_pattern, ContextArgs, InputArgs, MatchUnit, OutputArgs,
+};
 use crate::utils::{DebugFormat, FileTrace, RunTrace};
 use crate::utils::{Items, PathWorker, StdInWorker, Worker};
 
@@ -141,16 +145,28 @@ impl RunArg {
 
 // Every run will include Search or Replace
 // Search or Replace by arguments `pattern` and `rewrite` passed from CLI
-pub fn run_with_pattern(arg: RunArg, project: Result<ProjectConfig>) -> Result<()> {
+pub fn run_with_pattern(mut arg: RunArg, project: Result<ProjectConfig>) -> Result<()> {
+  // `-` as the only path reads code from StdIn, same as `--stdin`
+  if arg.input.paths == [PathBuf::from("-")] {
+    arg.input.stdin = true;
+  }
   let proj = arg.output.inspect.project_trace();
   proj.print_project(&project)?;
   let context = arg.context.get();
+  let target = OutputTarget::new(arg.output.output_file.as_deref(), arg.output.color)?;
   if let Some(json) = arg.output.json {
-    let printer = JSONPrinter::stdout(json).context(context);
+    let printer = JSONPrinter::new(target, json).context(context);
     return run_pattern_with_printer(arg, printer);
   }
-  let printer = ColoredPrinter::stdout(arg.output.color)
-    .heading(arg.heading)
+  let color = target.color_choice(arg.output.color);
+  // auto heading checks stdout, which a file target does not write to
+  let heading = match (&target, arg.heading) {
+    (OutputTarget::File(_), Heading::Auto) => Heading::Never,
+    (_, heading) => heading,
+  };
+  let printer = ColoredPrinter::new(target)
+    .color(color)
+    .heading(heading)
     .context(context);
   let interactive = arg.output.needs_interactive();
   if interactive {
@@ -213,7 +229,7 @@ impl PathWorker for RunWithInferredLang {
   }
 
   fn produce_item(&self, path: &Path) -> Option<Vec<Self::Item>> {
-    let lang = SgLang::from_path(path)?;
+    let lang = detect_file_lang(path)?;
     self.trace.print_file(path, lang).ok()?;
     let matcher = self.arg.build_pattern(lang).ok()?;
     // match sub region
@@ -279,7 +295,7 @@ impl Worker for RunWithSpecificLang {
 impl PathWorker for RunWithSpecificLang {
   fn build_walk(&self) -> Result<WalkParallel> {
     let lang = self.arg.lang.expect("must present");
-    Ok(self.arg.input.walk_lang(lang))
+    self.arg.input.walk_lang(lang)
   }
   fn get_trace(&self) -> &FileTrace {
     &self.stats.inner
@@ -288,7 +304,7 @@ impl PathWorker for RunWithSpecificLang {
     let arg = &self.arg;
     let pattern = self.pattern.clone();
     let lang = arg.lang.expect("must present");
-    let path_lang = SgLang::from_path(path)?;
+    let path_lang = detect_file_lang(path)?;
     self.stats.print_file(path, path_lang).ok()?;
     let ret = if path_lang == lang {
       filter_file_pattern(path, lang, Some(pattern), std::iter::empty())?
@@ -305,7 +321,7 @@ impl StdInWorker for RunWithSpecificLang {
     let grep = lang.ast_grep(src);
     let has_match = grep.root().find(&self.pattern).is_some();
     has_match.then(|| MatchUnit {
-      path: PathBuf::from("STDIN"),
+      path: PathBuf::from("<stdin>"),
       matcher: self.pattern.clone(),
       grep,
     })
@@ -335,7 +351,7 @@ fn match_one_file(
 #[cfg(test)]
 mod test {
   use super::*;
-  use crate::print::ColorArg;
+  use crate::print::{ColorArg, JsonStyle};
   use ast_grep_language::SupportLang;
 
   fn default_run_arg() -> RunArg {
@@ -354,6 +370,8 @@ mod test {
         paths: vec![PathBuf::from(".")],
         globs: vec![],
         threads: 0,
+        include: vec![],
+        exclude: vec![],
       },
       output: OutputArgs {
         color: ColorArg::Never,
@@ -361,6 +379,7 @@ mod test {
         json: None,
         update_all: false,
         inspect: Default::default(),
+        output_file: None,
       },
       context: ContextArgs {
         before: 0,
@@ -401,4 +420,36 @@ mod test {
     let proj = Err(anyhow::anyhow!("no project"));
     assert!(run_with_pattern(arg, proj).is_ok())
   }
+
+  #[test]
+  fn test_run_with_output_file() -> Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    std::fs::write(dir.path().join("a.ts"), "let a = 1\nconsole.log(a)")?;
+    let output = dir.path().join("out.json");
+    std::fs::write(&output, "stale content")?;
+    let mut arg = RunArg {
+      pattern: "console.log($A)".to_string(),
+      lang: Some(SupportLang::TypeScript.into()),
+      ..default_run_arg()
+    };
+    arg.input.paths = vec![dir.path().to_path_buf()];
+    arg.output.json = Some(JsonStyle::Compact);
+    arg.output.output_file = Some(output.clone());
+    run_with_pattern(arg, Err(anyhow::anyhow!("no project")))?;
+    let written = std::fs::read_to_string(&output)?;
+    assert!(!written.contains("stale"));
+    assert!(written.contains(r#""text":"console.log(a)""#));
+    Ok(())
+  }
+
+  #[test]
+  fn test_run_with_bad_output_file() {
+    let mut arg = RunArg {
+      pattern: "console.log".to_string(),
+      ..default_run_arg()
+    };
+    arg.output.output_file = Some(PathBuf::from("not/exist/dir/out.txt"));
+    let proj = Err(anyhow::anyhow!("no project"));
+    assert!(run_with_pattern(arg, proj).is_err())
+  }
 }
@@ -12,16 +12,21 @@ use ignore::WalkParallel;
 use crate::config::{read_rule_file, with_rule_stats, ProjectConfig};
 use crate::lang::SgLang;
 use crate::print::{
-  CloudPrinter, ColoredPrinter, Diff, InteractivePrinter, JSONPrinter, Platform, Printer,
-  ReportStyle, SimpleFile,
+  CheckstylePrinter, CloudPrinter, ColoredPrinter, Diff, FlatJsonPrinter, InteractivePrinter,
+  JSONPrinter, OutputTarget, Platform, Printer, ReportStyle, SarifPrinter, SimpleFile,
 };
 use crate::utils::ErrorContext as EC;
 use crate::utils::RuleOverwrite;
-use crate::utils::{filter_file_interactive, ContextArgs, InputArgs, OutputArgs, OverwriteArgs};
+use crate::utils::{
+  filter_file_interactive, ContextArgs, FailureLevel, InputArgs, OutputArgs, OverwriteArgs,
+};
 use crate::utils::{FileTrace, ScanTrace};
 use crate::utils::{Items, PathWorker, StdInWorker, Worker};
+use crate::watch::run_watch;
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+use std::time::Instant;
 
 type AstGrep = ast_grep_core::AstGrep<StrDoc<SgLang>>;
 
@@ -41,15 +46,36 @@ pub struct ScanArg {
   #[clap(long, conflicts_with = "rule", value_name = "RULE_TEXT")]
   inline_rules: Option<String>,
 
-  /// Output warning/error messages in GitHub Action format.
+  /// Output warning/error messages in GitHub Action format, as a SARIF 2.1.0 log,
+  /// as JSON or as Checkstyle XML.
   ///
-  /// Currently, only GitHub is supported.
-  #[clap(long, conflicts_with = "json", conflicts_with = "interactive")]
+  /// `json` prints one compact result object per line and `json-array` prints them in one array.
+  /// Use `--json` instead for the full match information.
+  #[clap(
+    long,
+    alias = "output-format",
+    conflicts_with = "json",
+    conflicts_with = "interactive"
+  )]
   format: Option<Platform>,
 
   #[clap(long, default_value = "rich", conflicts_with = "json")]
   report_style: ReportStyle,
 
+  /// Keep running and rescan files when they change.
+  ///
+  /// New matches are printed with `+` and resolved matches with `-`.
+  /// Press Ctrl-C to stop watching.
+  #[clap(
+    long,
+    conflicts_with_all = ["json", "format", "interactive", "stdin", "update_all", "output_file"]
+  )]
+  watch: bool,
+
+  /// Print a summary of matches per rule, file counts and elapsed time to stderr after scanning.
+  #[clap(long, conflicts_with = "watch")]
+  stats: bool,
+
   /// severity related options
   #[clap(flatten)]
   overwrite: OverwriteArgs,
@@ -73,18 +99,45 @@ impl ScanArg {
 }
 
 pub fn run_with_config(arg: ScanArg, project: Result<ProjectConfig>) -> Result<()> {
+  arg.input.build_thread_pool();
   let project_trace = arg.output.inspect.project_trace();
   project_trace.print_project(&project)?;
   let context = arg.context.get();
-  if let Some(_format) = &arg.format {
-    let printer = CloudPrinter::stdout();
-    return run_scan(arg, printer, project);
+  if arg.watch {
+    let roots = arg.input.paths.clone();
+    let worker = ScanWithConfig::try_new(arg, project)?;
+    return run_watch(worker, &roots);
+  }
+  let target = OutputTarget::new(arg.output.output_file.as_deref(), arg.output.color)?;
+  match &arg.format {
+    Some(Platform::GitHub) => {
+      let printer = CloudPrinter::new(target);
+      return run_scan(arg, printer, project);
+    }
+    Some(Platform::Sarif) => {
+      return run_sarif(arg, target, project);
+    }
+    Some(Platform::Json) => {
+      let printer = FlatJsonPrinter::new(target, false);
+      return run_scan(arg, printer, project);
+    }
+    Some(Platform::JsonArray) => {
+      let printer = FlatJsonPrinter::new(target, true);
+      return run_scan(arg, printer, project);
+    }
+    Some(Platform::Checkstyle) => {
+      let printer = CheckstylePrinter::new(target);
+      return run_scan(arg, printer, project);
+    }
+    None => (),
   }
   if let Some(json) = arg.output.json {
-    let printer = JSONPrinter::stdout(json);
+    let printer = JSONPrinter::new(target, json);
     return run_scan(arg, printer, project);
   }
-  let printer = ColoredPrinter::stdout(arg.output.color)
+  let color = target.color_choice(arg.output.color);
+  let printer = ColoredPrinter::new(target)
+    .color(color)
     .style(arg.report_style)
     .context(context);
   let interactive = arg.output.needs_interactive();
@@ -112,6 +165,24 @@ fn run_scan<P: Printer + 'static>(
   }
 }
 
+// SARIF lists every loaded rule in its driver, so rules are loaded before the printer
+fn run_sarif(arg: ScanArg, target: OutputTarget, project: Result<ProjectConfig>) -> Result<()> {
+  let mut printer = SarifPrinter::new(target);
+  if arg.input.stdin {
+    let worker = ScanWithRule::try_new(arg)?;
+    for rule in &worker.rules {
+      printer.add_loaded_rule(rule);
+    }
+    worker.run_std_in(printer)
+  } else {
+    let worker = ScanWithConfig::try_new(arg, project)?;
+    worker
+      .configs
+      .for_each_rule(|rule| printer.add_loaded_rule(rule));
+    worker.run_path(printer)
+  }
+}
+
 struct ScanWithConfig {
   arg: ScanArg,
   configs: RuleCollection<SgLang>,
@@ -149,6 +220,11 @@ impl Worker for ScanWithConfig {
   fn consume_items<P: Printer>(&self, items: Items<Self::Item>, mut printer: P) -> Result<()> {
     printer.before_print()?;
     let mut error_count = 0usize;
+    let mut rule_ids = vec![];
+    self
+      .configs
+      .for_each_rule(|rule| rule_ids.push(rule.id.clone()));
+    let mut stats = ScanStats::new(rule_ids);
     for (path, grep, pre_scan) in items {
       let file_content = grep.source().to_string();
       let path = &path;
@@ -163,14 +239,25 @@ impl Worker for ScanWithConfig {
         match_rule_diff_on_file(path, diffs, &mut printer)?;
       }
       for (rule, matches) in scanned.matches {
-        if matches!(rule.severity, Severity::Error) {
+        if self.arg.overwrite.error_on.fails_on(&rule.severity) {
           error_count = error_count.saturating_add(matches.len());
         }
+        stats.add_matches(path, rule, matches.len());
         match_rule_on_file(path, matches, rule, &file_content, &mut printer)?;
       }
     }
     printer.after_print()?;
+    if self.arg.stats {
+      #[cfg(feature = "profiling")]
+      self
+        .configs
+        .for_each_rule(|rule| stats.add_node_visits(rule));
+      let files = self.trace.inner.file_trace.parsed_count();
+      stats.print(files, &mut std::io::stderr())?;
+    }
     self.trace.print()?;
+    #[cfg(feature = "profiling")]
+    self.trace.print_rule_profile(&self.configs)?;
     if error_count > 0 {
       Err(anyhow::anyhow!(EC::DiagnosticError(error_count)))
     } else {
@@ -216,6 +303,9 @@ impl PathWorker for ScanWithConfig {
 
 struct ScanWithRule {
   rules: Vec<RuleConfig<SgLang>>,
+  error_on: FailureLevel,
+  stats: bool,
+  file_trace: FileTrace,
 }
 impl ScanWithRule {
   fn try_new(arg: ScanArg) -> Result<Self> {
@@ -227,7 +317,12 @@ impl ScanWithRule {
     } else {
       return Err(anyhow::anyhow!(EC::RuleNotSpecified));
     };
-    Ok(Self { rules })
+    Ok(Self {
+      rules,
+      error_on: arg.overwrite.error_on,
+      stats: arg.stats,
+      file_trace: FileTrace::default(),
+    })
   }
 }
 
@@ -237,18 +332,28 @@ impl Worker for ScanWithRule {
     printer.before_print()?;
     let mut error_count = 0usize;
     let combined = CombinedScan::new(self.rules.iter().collect());
+    let mut stats = ScanStats::new(self.rules.iter().map(|rule| rule.id.clone()));
     for (path, grep, pre_scan) in items {
       let file_content = grep.source().to_string();
       // do not exclude_fix rule in run_with_rule
       let scanned = combined.scan(&grep, pre_scan, false);
       for (rule, matches) in scanned.matches {
-        if matches!(rule.severity, Severity::Error) {
+        if self.error_on.fails_on(&rule.severity) {
           error_count = error_count.saturating_add(matches.len());
         }
+        stats.add_matches(&path, rule, matches.len());
         match_rule_on_file(&path, matches, rule, &file_content, &mut printer)?;
       }
     }
     printer.after_print()?;
+    if self.stats {
+      #[cfg(feature = "profiling")]
+      for rule in &self.rules {
+        stats.add_node_visits(rule);
+      }
+      let files = self.file_trace.parsed_count();
+      stats.print(files, &mut std::io::stderr())?;
+    }
     if error_count > 0 {
       Err(anyhow::anyhow!(EC::DiagnosticError(error_count)))
     } else {
@@ -264,9 +369,11 @@ impl StdInWorker for ScanWithRule {
     let combined = CombinedScan::new(self.rules.iter().collect());
     let grep = lang.ast_grep(src);
     let pre_scan = combined.find(&grep);
+    self.file_trace.add_scanned();
     if !pre_scan.is_empty() {
       Some((PathBuf::from("STDIN"), grep, pre_scan))
     } else {
+      self.file_trace.add_skipped();
       None
     }
   }
@@ -308,6 +415,80 @@ fn match_rule_on_file(
   Ok(())
 }
 
+/// Match counts printed by `--stats`.
+struct ScanStats {
+  start: Instant,
+  /// rule id to match count and files with matches
+  rules: BTreeMap<String, (usize, HashSet<PathBuf>)>,
+  files_with_matches: HashSet<PathBuf>,
+  /// rule id to nodes visited by its matcher
+  #[cfg(feature = "profiling")]
+  nodes_visited: BTreeMap<String, u64>,
+}
+
+impl ScanStats {
+  /// Rules in `rule_ids` are listed even if they do not match anything.
+  fn new(rule_ids: impl IntoIterator<Item = String>) -> Self {
+    Self {
+      start: Instant::now(),
+      rules: rule_ids
+        .into_iter()
+        .map(|id| (id, Default::default()))
+        .collect(),
+      files_with_matches: HashSet::new(),
+      #[cfg(feature = "profiling")]
+      nodes_visited: BTreeMap::new(),
+    }
+  }
+
+  fn add_matches(&mut self, path: &Path, rule: &RuleConfig<SgLang>, count: usize) {
+    if count == 0 {
+      return;
+    }
+    let (matches, files) = self.rules.entry(rule.id.clone()).or_default();
+    *matches += count;
+    files.insert(path.to_path_buf());
+    self.files_with_matches.insert(path.to_path_buf());
+  }
+
+  #[cfg(feature = "profiling")]
+  fn add_node_visits(&mut self, rule: &RuleConfig<SgLang>) {
+    let count = rule.matcher.count_nodes_scanned();
+    self.nodes_visited.insert(rule.id.clone(), count);
+  }
+
+  fn print(&self, files_scanned: usize, w: &mut impl Write) -> Result<()> {
+    const MATCHES: &str = "matches";
+    const FILES: &str = "files_with_matches";
+    let id_width = self
+      .rules
+      .keys()
+      .map(String::len)
+      .fold("rule_id".len(), usize::max);
+    #[cfg(feature = "profiling")]
+    const NODES: &str = "nodes_visited";
+    write!(w, "{:id_width$} | {MATCHES} | {FILES}", "rule_id")?;
+    #[cfg(feature = "profiling")]
+    write!(w, " | {NODES}")?;
+    writeln!(w)?;
+    for (id, (matches, files)) in &self.rules {
+      let (m, f) = (MATCHES.len(), FILES.len());
+      write!(w, "{id:id_width$} | {matches:>m$} | {:>f$}", files.len())?;
+      #[cfg(feature = "profiling")]
+      {
+        let nodes = self.nodes_visited.get(id).copied().unwrap_or_default();
+        write!(w, " | {nodes:>n$}", n = NODES.len())?;
+      }
+      writeln!(w)?;
+    }
+    writeln!(w, "total_files_scanned: {files_scanned}")?;
+    let matched = self.files_with_matches.len();
+    writeln!(w, "total_files_with_matches: {matched}")?;
+    writeln!(w, "elapsed_ms: {}", self.start.elapsed().as_millis())?;
+    Ok(())
+  }
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -351,14 +532,19 @@ rule:
         follow: false,
         globs: vec![],
         threads: 0,
+        include: vec![],
+        exclude: vec![],
       },
       overwrite: OverwriteArgs {
         filter: None,
+        tag: vec![],
+        exclude_tag: vec![],
         error: None,
         warning: None,
         info: None,
         hint: None,
         off: None,
+        error_on: FailureLevel::Error,
       },
       output: OutputArgs {
         interactive: false,
@@ -366,6 +552,7 @@ rule:
         update_all: false,
         color: ColorArg::Never,
         inspect: Default::default(),
+        output_file: None,
       },
       context: ContextArgs {
         before: 0,
@@ -373,6 +560,8 @@ rule:
         context: 0,
       },
       format: None,
+      watch: false,
+      stats: false,
     }
   }
 
@@ -425,4 +614,69 @@ rule:
     assert!(err.is::<EC>());
     assert_eq!(err.to_string(), "Cannot parse rule INLINE_RULES");
   }
+
+  #[test]
+  fn test_scan_stats() -> Result<()> {
+    let inline_rules = "{id: no-log, language: ts, rule: {pattern: console.log($A)}}\n---\n\
+      {id: no-debugger, language: ts, rule: {kind: debugger_statement}}";
+    let rules = from_yaml_string(inline_rules, &Default::default())?;
+    let configs = RuleCollection::try_new(rules)?;
+    let mut stats = ScanStats::new(["no-log".into(), "no-debugger".into()]);
+    let rule = configs.get_rule("no-log").expect("should exist");
+    stats.add_matches(Path::new("a.ts"), rule, 2);
+    stats.add_matches(Path::new("b.ts"), rule, 1);
+    let mut output = vec![];
+    stats.print(3, &mut output)?;
+    let output = String::from_utf8(output)?;
+    let lines: Vec<_> = output.lines().collect();
+    // `profiling` feature appends a nodes_visited column
+    assert!(lines[0].starts_with("rule_id     | matches | files_with_matches"));
+    assert!(lines[1].starts_with("no-debugger |       0 |                  0"));
+    assert!(lines[2].starts_with("no-log      |       3 |                  2"));
+    assert_eq!(lines[3], "total_files_scanned: 3");
+    assert_eq!(lines[4], "total_files_with_matches: 2");
+    assert!(lines[5].starts_with("elapsed_ms: "));
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(feature = "profiling")]
+  fn test_scan_stats_node_visits() -> Result<()> {
+    use ast_grep_core::Language;
+    let inline_rules = "{id: no-debugger, language: ts, rule: {kind: debugger_statement}}";
+    let rules = from_yaml_string(inline_rules, &Default::default())?;
+    let configs = RuleCollection::try_new(rules)?;
+    let rule = configs.get_rule("no-debugger").expect("should exist");
+    let grep = SgLang::from(SupportLang::TypeScript).ast_grep("debugger; let a = 1");
+    let matches: Vec<_> = grep.root().find_all(&rule.matcher).collect();
+    let mut stats = ScanStats::new(["no-debugger".into()]);
+    stats.add_matches(Path::new("a.ts"), rule, matches.len());
+    stats.add_node_visits(rule);
+    let mut output = vec![];
+    stats.print(1, &mut output)?;
+    let output = String::from_utf8(output)?;
+    let lines: Vec<_> = output.lines().collect();
+    let visited = rule.matcher.count_nodes_scanned();
+    assert!(visited > 0);
+    assert_eq!(
+      lines[0],
+      "rule_id     | matches | files_with_matches | nodes_visited"
+    );
+    assert_eq!(
+      lines[1],
+      format!("no-debugger |       1 |                  1 | {visited:>13}")
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_scan_with_stats() {
+    let inline_rules = "{id: test, language: ts, rule: {pattern: readFileSync}}".to_string();
+    let arg = ScanArg {
+      inline_rules: Some(inline_rules),
+      stats: true,
+      ..default_scan_arg()
+    };
+    assert!(run_with_config(arg, Err(anyhow::anyhow!("not found"))).is_ok());
+  }
 }
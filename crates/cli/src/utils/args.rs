@@ -2,9 +2,11 @@ use crate::lang::SgLang;
 use crate::print::{ColorArg, JsonStyle};
 use crate::utils::ErrorContext as EC;
 use crate::utils::Granularity;
+use ast_grep_config::Severity;
 
 use anyhow::{Context, Result};
 use clap::{Args, ValueEnum};
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use ignore::{
   overrides::{Override, OverrideBuilder},
   WalkBuilder, WalkParallel,
@@ -12,7 +14,7 @@ use ignore::{
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// input related options
 #[derive(Args)]
@@ -39,6 +41,7 @@ pub struct InputArgs {
   /// Enable search code from StdIn.
   ///
   /// Use this if you need to take code stream from standard input.
+  /// For `run`, passing `-` as the only path does the same.
   #[clap(long)]
   pub stdin: bool,
 
@@ -52,12 +55,32 @@ pub struct InputArgs {
   #[clap(long, action = clap::ArgAction::Append)]
   pub globs: Vec<String>,
 
+  /// Only search files whose path matches the glob.
+  ///
+  /// Unlike --globs, it is applied after ignore files are respected, so ignored
+  /// files stay ignored. Multiple include flags may be used.
+  #[clap(long, action = clap::ArgAction::Append, value_name = "GLOB")]
+  pub include: Vec<String>,
+
+  /// Skip files and directories whose path matches the glob.
+  ///
+  /// Like --include, it is applied after ignore files are respected.
+  /// Multiple exclude flags may be used.
+  #[clap(long, action = clap::ArgAction::Append, value_name = "GLOB")]
+  pub exclude: Vec<String>,
+
   /// Set the approximate number of threads to use.
   ///
   /// This flag sets the approximate number of threads to use. A value of 0
   /// (which is the default) causes ast-grep to choose the thread count using
-  /// heuristics.
-  #[clap(short = 'j', long, default_value = "0", value_name = "NUM")]
+  /// heuristics. In scan, the same number of threads checks rules of large rule sets in parallel.
+  #[clap(
+    short = 'j',
+    long,
+    visible_alias = "jobs",
+    default_value = "0",
+    value_name = "NUM"
+  )]
   pub threads: usize,
 }
 
@@ -71,40 +94,41 @@ impl InputArgs {
       self.threads
     }
   }
-  pub fn walk(&self) -> Result<WalkParallel> {
+  /// Size the global rayon pool used to check rules of a file in parallel.
+  /// Call it once at the command entry, before any rayon task runs.
+  pub fn build_thread_pool(&self) {
+    // the global pool can only be built once, e.g. tests run many scans in one process
+    let _ = rayon::ThreadPoolBuilder::new()
+      .num_threads(self.get_threads())
+      .build_global();
+  }
+  fn walker(&self) -> Result<WalkBuilder> {
     let threads = self.get_threads();
+    let filter = self.build_path_filter().context(EC::BuildGlobs)?;
+    let mut builder = NoIgnore::disregard(&self.no_ignore).walk(&self.paths);
+    builder.threads(threads).follow_links(self.follow);
+    if let Some(filter) = filter {
+      builder.filter_entry(move |entry| {
+        let is_dir = entry.file_type().map_or(false, |ft| ft.is_dir());
+        filter.is_allowed(entry.path(), is_dir)
+      });
+    }
+    Ok(builder)
+  }
+
+  pub fn walk(&self) -> Result<WalkParallel> {
     let globs = self.build_globs().context(EC::BuildGlobs)?;
-    Ok(
-      NoIgnore::disregard(&self.no_ignore)
-        .walk(&self.paths)
-        .threads(threads)
-        .follow_links(self.follow)
-        .overrides(globs)
-        .build_parallel(),
-    )
+    Ok(self.walker()?.overrides(globs).build_parallel())
   }
 
   pub fn walk_langs(&self, langs: impl Iterator<Item = SgLang>) -> Result<WalkParallel> {
     let types = SgLang::file_types_for_langs(langs);
-    let threads = self.get_threads();
-    Ok(
-      NoIgnore::disregard(&self.no_ignore)
-        .walk(&self.paths)
-        .threads(threads)
-        .follow_links(self.follow)
-        .types(types)
-        .build_parallel(),
-    )
+    Ok(self.walker()?.types(types).build_parallel())
   }
 
-  pub fn walk_lang(&self, lang: SgLang) -> WalkParallel {
-    let threads = self.get_threads();
-    NoIgnore::disregard(&self.no_ignore)
-      .walk(&self.paths)
-      .threads(threads)
-      .follow_links(self.follow)
-      .types(lang.augmented_file_type())
-      .build_parallel()
+  pub fn walk_lang(&self, lang: SgLang) -> Result<WalkParallel> {
+    let types = lang.augmented_file_type();
+    Ok(self.walker()?.types(types).build_parallel())
   }
 
   fn build_globs(&self) -> Result<Override> {
@@ -115,6 +139,48 @@ impl InputArgs {
     }
     Ok(builder.build()?)
   }
+
+  fn build_path_filter(&self) -> Result<Option<PathFilter>> {
+    if self.include.is_empty() && self.exclude.is_empty() {
+      return Ok(None);
+    }
+    let include = if self.include.is_empty() {
+      None
+    } else {
+      Some(build_glob_set(&self.include)?)
+    };
+    let exclude = build_glob_set(&self.exclude)?;
+    Ok(Some(PathFilter { include, exclude }))
+  }
+}
+
+fn build_glob_set(globs: &[String]) -> Result<GlobSet> {
+  let mut builder = GlobSetBuilder::new();
+  for glob in globs {
+    builder.add(Glob::new(glob)?);
+  }
+  Ok(builder.build()?)
+}
+
+/// Path filter from --include and --exclude, checked after ignore files.
+struct PathFilter {
+  include: Option<GlobSet>,
+  exclude: GlobSet,
+}
+
+impl PathFilter {
+  fn is_allowed(&self, path: &Path, is_dir: bool) -> bool {
+    let path = path.strip_prefix("./").unwrap_or(path);
+    if self.exclude.is_match(path) {
+      return false;
+    }
+    // include globs usually name files, so always descend into directories
+    is_dir
+      || self
+        .include
+        .as_ref()
+        .map_or(true, |globs| globs.is_match(path))
+  }
 }
 
 /// output related options
@@ -160,6 +226,13 @@ pub struct OutputArgs {
   #[clap(long, default_value = "auto", value_name = "WHEN")]
   pub color: ColorArg,
 
+  /// Write matches to PATH instead of stdout.
+  ///
+  /// The file is created or truncated before searching and never contains color.
+  /// Errors, inspection and other diagnostics are still printed to stderr.
+  #[clap(short = 'o', long, value_name = "PATH", conflicts_with = "interactive")]
+  pub output_file: Option<PathBuf>,
+
   /// Inspect information for file/rule discovery and scanning.
   ///
   /// This flag helps user to observe ast-grep's internal filtering of files and rules.
@@ -297,6 +370,17 @@ pub struct OverwriteArgs {
   /// set of rule definitions within a project.
   #[clap(long, conflicts_with = "rule", value_name = "REGEX")]
   pub filter: Option<Regex>,
+  /// Scan the codebase with rules having all the TAGs.
+  ///
+  /// Rules declare tags in the `tags` field. Tags are compared case-insensitively.
+  /// You can require multiple tags by using the flag multiple times, e.g. `--tag security --tag js`.
+  #[clap(long, conflicts_with = "rule", value_name = "TAG")]
+  pub tag: Vec<String>,
+  /// Skip rules having any of the TAGs.
+  ///
+  /// This is the inverse of --tag and can also be used multiple times.
+  #[clap(long, conflicts_with = "rule", value_name = "TAG")]
+  pub exclude_tag: Vec<String>,
   /// Set rule severity to error
   ///
   /// This flag sets the specified RULE_ID's severity to error. You can specify multiple rules by using the flag multiple times,
@@ -332,18 +416,58 @@ pub struct OverwriteArgs {
   /// Note, this flag must use `=` to specify its value.
   #[clap(long, action = clap::ArgAction::Append, value_name = "RULE_ID", num_args(0..), require_equals = true)]
   pub off: Option<Vec<String>>,
+  /// Exit with a non-zero code if any diagnostic at or above LEVEL is found.
+  ///
+  /// By default only error diagnostics fail the scan. Use `--error-on warning` to also fail on warnings.
+  #[clap(long, value_name = "LEVEL", default_value = "error")]
+  pub error_on: FailureLevel,
+}
+
+/// The lowest severity that makes `scan` exit with a non-zero code.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum FailureLevel {
+  Error,
+  Warning,
+  Info,
+  Hint,
+}
+
+impl FailureLevel {
+  /// Whether a diagnostic of the severity should fail the scan.
+  pub fn fails_on(&self, severity: &Severity) -> bool {
+    let rank = |severity: &Severity| match severity {
+      Severity::Error => 4,
+      Severity::Warning => 3,
+      Severity::Info => 2,
+      Severity::Hint => 1,
+      Severity::Off => 0,
+    };
+    let level = match self {
+      FailureLevel::Error => Severity::Error,
+      FailureLevel::Warning => Severity::Warning,
+      FailureLevel::Info => Severity::Info,
+      FailureLevel::Hint => Severity::Hint,
+    };
+    rank(severity) >= rank(&level)
+  }
 }
 
 impl OverwriteArgs {
   /// Returns true if none rule is turned off on CLI nor filtered out
   pub fn include_all_rules(&self) -> bool {
-    self.filter.is_none() && self.off.is_none()
+    self.filter.is_none()
+      && self.off.is_none()
+      && self.tag.is_empty()
+      && self.exclude_tag.is_empty()
   }
 }
 
 #[cfg(test)]
 mod test {
   use super::*;
+  use crate::utils::collect_paths;
+  use ast_grep_language::SupportLang;
+  use tempfile::TempDir;
 
   #[test]
   fn test_build_globs() {
@@ -354,6 +478,8 @@ mod test {
       stdin: false,
       globs: vec!["*.rs".to_string(), "!*.toml".to_string()],
       threads: 0,
+      include: vec![],
+      exclude: vec![],
     };
     assert!(input.build_globs().is_ok());
     let input = InputArgs {
@@ -363,7 +489,66 @@ mod test {
       stdin: false,
       globs: vec!["*.{rs".to_string()],
       threads: 0,
+      include: vec![],
+      exclude: vec![],
     };
     assert!(input.build_globs().is_err());
   }
+
+  fn walk_files(input: &InputArgs, root: &Path) -> Vec<String> {
+    let langs = [SupportLang::TypeScript, SupportLang::Tsx].map(SgLang::from);
+    let walker = input.walk_langs(langs.into_iter());
+    let mut files: Vec<_> = collect_paths(walker.expect("should build walker"))
+      .into_iter()
+      .map(|p| {
+        let p = p.strip_prefix(root).expect("should be under root");
+        p.to_string_lossy().replace('\\', "/")
+      })
+      .collect();
+    files.sort();
+    files
+  }
+
+  #[test]
+  fn test_include_exclude() -> Result<()> {
+    let dir = TempDir::new()?;
+    let root = dir.path();
+    for file in [
+      "a.ts",
+      "b.tsx",
+      "ignored.ts",
+      "src/c.ts",
+      "vendor/d.ts",
+      "e.py",
+    ] {
+      let path = root.join(file);
+      std::fs::create_dir_all(path.parent().expect("should have parent"))?;
+      std::fs::write(path, "let a = 1")?;
+    }
+    std::fs::write(root.join(".ignore"), "ignored.ts")?;
+    let mut input = InputArgs {
+      paths: vec![root.to_path_buf()],
+      follow: false,
+      no_ignore: vec![],
+      stdin: false,
+      globs: vec![],
+      threads: 1,
+      include: vec![],
+      exclude: vec![],
+    };
+    assert_eq!(
+      walk_files(&input, root),
+      ["a.ts", "b.tsx", "src/c.ts", "vendor/d.ts"]
+    );
+    input.include = vec!["*.ts".into()];
+    input.exclude = vec!["**/vendor".into()];
+    // ignored.ts is still skipped since include composes with ignore files
+    assert_eq!(walk_files(&input, root), ["a.ts", "src/c.ts"]);
+    input.include = vec![];
+    input.exclude = vec!["*.tsx".into(), "**/src/**".into()];
+    assert_eq!(walk_files(&input, root), ["a.ts", "vendor/d.ts"]);
+    input.include = vec!["*.{ts".into()];
+    assert!(input.walk().is_err());
+    Ok(())
+  }
 }
@@ -65,12 +65,13 @@ fn dump_pattern(
 ) -> FmtResult {
   write!(ret, "{}", "  ".repeat(indent))?;
   match pattern {
-    PatternNode::MetaVar { meta_var } => {
+    PatternNode::MetaVar { meta_var, .. } => {
       let meta_var = match meta_var {
         MetaVariable::Capture(name, _) => format!("${name}"),
         MetaVariable::MultiCapture(name) => format!("$$${name}"),
         MetaVariable::Multiple => "$$$".to_string(),
         MetaVariable::Dropped(_) => "$_".to_string(),
+        MetaVariable::Optional(name) => format!("$?{name}"),
       };
       let meta_var = style.kind_style.paint(meta_var);
       writeln!(ret, "{} {meta_var}", style.field_style.paint("MetaVar"))?;
@@ -44,8 +44,10 @@ pub enum ErrorContext {
   PatternHasError,
   // Scan
   DiagnosticError(usize),
+  OpenOutputFile(PathBuf),
   RuleNotSpecified,
   RuleNotFound(String),
+  RuleTagNotFound(String),
   // LSP
   StartLanguageServer,
   // Edit
@@ -62,6 +64,8 @@ pub enum ErrorContext {
   InsufficientCLIArgument(&'static str),
   // Completions
   CannotInferShell,
+  // Query
+  ReadFile(PathBuf),
 }
 
 impl ErrorContext {
@@ -69,11 +73,12 @@ impl ErrorContext {
     use ErrorContext::*;
     // reference: https://mariadb.com/kb/en/operating-system-error-codes/
     match self {
-      DiagnosticError(_) => 1,
-      ProjectNotExist | LanguageNotSpecified | RuleNotSpecified | RuleNotFound(_) => 2,
+      DiagnosticError(_) | OpenOutputFile(_) => 1,
+      ProjectNotExist | LanguageNotSpecified | RuleNotSpecified | RuleNotFound(_)
+      | RuleTagNotFound(_) => 2,
       TestFail(_) => 3,
       NoTestDirConfigured | NoUtilDirConfigured => 4,
-      ReadConfiguration | ReadRule(_) | WalkRuleDir(_) | WriteFile(_) => 5,
+      ReadConfiguration | ReadRule(_) | WalkRuleDir(_) | WriteFile(_) | ReadFile(_) => 5,
       StdInIsNotInteractive => 6,
       ParseTest(_) | ParseRule(_) | ParseConfiguration | ParsePattern | InvalidGlobalUtils
       | LangInjection => 8,
@@ -156,7 +161,7 @@ impl ErrorMessage {
       ),
       BuildGlobs => Self::new(
         "Cannot build glob from CLI flag",
-        "The patterns in --globs is invalid. Please refer to doc and fix the error.",
+        "The patterns in --globs, --include or --exclude are invalid. Please refer to doc and fix the error.",
         CLI_USAGE,
       ),
       LangInjection => Self::new(
@@ -219,6 +224,11 @@ impl ErrorMessage {
         format!("Rule with id '{id}' not found in project configuration. Please make sure it exists."),
         TOOL_OVERVIEW,
       ),
+      RuleTagNotFound(tags) => Self::new(
+        "No rule is selected by tags.",
+        format!("No rule has all tags [{tags}] without the excluded tags. Please check the `tags` field of rules."),
+        TOOL_OVERVIEW,
+      ),
       StartLanguageServer => Self::new(
         "Cannot start language server.",
         "Please see language server logging file.",
@@ -229,6 +239,16 @@ impl ErrorMessage {
         "Please check if the editor is installed and the EDITOR environment variable is correctly set.",
         CLI_USAGE,
       ),
+      ReadFile(file) => Self::new(
+        format!("Cannot read file {}", file.display()),
+        "Please check if the file exists and is readable.",
+        None,
+      ),
+      OpenOutputFile(file) => Self::new(
+        format!("Cannot open output file {}", file.display()),
+        "Please check if the parent directory exists and the file is writable.",
+        CLI_USAGE,
+      ),
       WriteFile(file) => Self::new(
         format!("Cannot rewrite file {}", file.display()),
         "Fail to apply fix to the file. Skip to next file",
@@ -95,6 +95,11 @@ impl FileTrace {
   pub fn add_skipped(&self) {
     self.files_skipped.fetch_add(1, Ordering::AcqRel);
   }
+  /// Number of files parsed, excluding skipped files.
+  pub fn parsed_count(&self) -> usize {
+    let scanned = self.files_scanned.load(Ordering::Acquire);
+    scanned - self.files_skipped.load(Ordering::Acquire)
+  }
 }
 
 pub struct TraceInfo<T, W: Write> {
@@ -218,6 +223,20 @@ impl<W: Write + Sync> TraceInfo<RuleTrace, W> {
     });
     Ok(())
   }
+
+  #[cfg(feature = "profiling")]
+  pub fn print_rule_profile(&self, rules: &RuleCollection<SgLang>) -> Result<()> {
+    if self.level < Granularity::Entity {
+      return Ok(());
+    }
+    rules.for_each_rule(|rule| {
+      _ = self.print_entity("rule", &rule.id, |w| {
+        write!(w, "scannedNodeCount={}", rule.matcher.count_nodes_scanned())?;
+        Ok(())
+      });
+    });
+    Ok(())
+  }
 }
 
 #[derive(Default)]
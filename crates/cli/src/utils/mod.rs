@@ -5,12 +5,12 @@ mod inspect;
 mod rule_overwrite;
 mod worker;
 
-pub use args::{ContextArgs, InputArgs, OutputArgs, OverwriteArgs};
+pub use args::{ContextArgs, FailureLevel, InputArgs, OutputArgs, OverwriteArgs};
 pub use debug_query::DebugFormat;
 pub use error_context::{exit_with_error, ErrorContext};
 pub use inspect::{FileTrace, Granularity, RuleTrace, RunTrace, ScanTrace};
 pub use rule_overwrite::RuleOverwrite;
-pub use worker::{Items, PathWorker, StdInWorker, Worker};
+pub use worker::{collect_paths, Items, PathWorker, StdInWorker, Worker};
 
 use crate::lang::SgLang;
 
@@ -28,9 +28,9 @@ use ast_grep_core::Pattern;
 use ast_grep_core::{Matcher, StrDoc};
 use ast_grep_language::Language;
 
-use std::fs::read_to_string;
+use std::fs::{read_to_string, File};
 use std::io::stdout;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
@@ -104,6 +104,9 @@ fn read_file(path: &Path) -> Option<String> {
   }
 }
 
+/// Number of rules for a file above which its subtrees are checked in parallel.
+const PAR_RULE_THRESHOLD: usize = 32;
+
 fn filter(
   grep: &AstGrep,
   path: &Path,
@@ -113,8 +116,14 @@ fn filter(
 ) -> Option<PreScan> {
   let rules = configs.get_rule_from_lang(path, lang);
   rule_stats.print_file(path, lang, &rules).ok()?;
+  let many_rules = rules.len() >= PAR_RULE_THRESHOLD;
   let combined = CombinedScan::new(rules);
-  let pre_scan = combined.find(grep);
+  // files are already scanned in parallel, only split a file's work for large rule sets
+  let pre_scan = if many_rules {
+    combined.find_par(grep)
+  } else {
+    combined.find(grep)
+  };
   if pre_scan.is_empty() {
     None
   } else {
@@ -122,12 +131,28 @@ fn filter(
   }
 }
 
+/// Content prefix read to detect the language of files without extension.
+const DETECT_HEAD_LEN: u64 = 1024;
+
+/// Language of a file found in scanning. Files without extension, e.g. scripts
+/// with a shebang line, are detected by a short content prefix.
+pub fn detect_file_lang(path: &Path) -> Option<SgLang> {
+  let mut head = String::new();
+  if path.extension().is_none() {
+    let mut bytes = vec![];
+    let file = File::open(path).ok()?;
+    file.take(DETECT_HEAD_LEN).read_to_end(&mut bytes).ok()?;
+    head = String::from_utf8_lossy(&bytes).into_owned();
+  }
+  SgLang::detect(path, &head)
+}
+
 pub fn filter_file_interactive(
   path: &Path,
   configs: &RuleCollection<SgLang>,
   trace: &ScanTrace,
 ) -> Option<Vec<(PathBuf, AstGrep, PreScan)>> {
-  let lang = SgLang::from_path(path)?;
+  let lang = detect_file_lang(path)?;
   let file_content = read_file(path)?;
   let grep = lang.ast_grep(file_content);
   let mut ret = vec![];
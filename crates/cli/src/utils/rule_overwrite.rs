@@ -14,6 +14,8 @@ pub struct RuleOverwrite {
   default_severity: Option<Severity>,
   by_rule_id: HashMap<String, Severity>,
   rule_filter: Option<Regex>,
+  tags: Vec<String>,
+  excluded_tags: Vec<String>,
 }
 
 fn read_severity(
@@ -70,6 +72,8 @@ impl RuleOverwrite {
       default_severity,
       by_rule_id,
       rule_filter: cli.filter.clone(),
+      tags: cli.tag.clone(),
+      excluded_tags: cli.exclude_tag.clone(),
     })
   }
 
@@ -82,6 +86,9 @@ impl RuleOverwrite {
     } else {
       configs
     };
+    if !self.tags.is_empty() || !self.excluded_tags.is_empty() {
+      configs = self.filter_rule_by_tags(configs)?;
+    }
     for config in &mut configs {
       let overwrite = self.find(&config.id);
       overwrite.overwrite(config);
@@ -89,6 +96,23 @@ impl RuleOverwrite {
     Ok(configs)
   }
 
+  fn filter_rule_by_tags(
+    &self,
+    configs: Vec<RuleConfig<SgLang>>,
+  ) -> Result<Vec<RuleConfig<SgLang>>> {
+    let selected: Vec<_> = configs
+      .into_iter()
+      .filter(|c| self.tags.iter().all(|t| c.has_tag(t)))
+      .filter(|c| !self.excluded_tags.iter().any(|t| c.has_tag(t)))
+      .collect();
+    if selected.is_empty() {
+      let tags = self.tags.join(", ");
+      Err(anyhow::anyhow!(EC::RuleTagNotFound(tags)))
+    } else {
+      Ok(selected)
+    }
+  }
+
   pub fn find(&self, id: &str) -> OverwriteResult {
     let severity = self
       .by_rule_id
@@ -129,3 +153,55 @@ impl OverwriteResult {
     }
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::utils::FailureLevel;
+  use ast_grep_config::{from_yaml_string, GlobalRules};
+
+  const RULES: &str = "
+id: a
+language: ts
+tags: [Security, js]
+rule: {kind: number}
+---
+id: b
+language: ts
+tags: [security, style]
+rule: {kind: number}
+---
+id: c
+language: ts
+rule: {kind: number}
+";
+
+  fn select(tag: &[&str], exclude_tag: &[&str]) -> Result<Vec<String>> {
+    let to_vec = |tags: &[&str]| tags.iter().map(|t| t.to_string()).collect();
+    let args = OverwriteArgs {
+      filter: None,
+      tag: to_vec(tag),
+      exclude_tag: to_vec(exclude_tag),
+      error: None,
+      warning: None,
+      info: None,
+      hint: None,
+      off: None,
+      error_on: FailureLevel::Error,
+    };
+    let configs = from_yaml_string(RULES, &GlobalRules::default())?;
+    let configs = RuleOverwrite::new(&args)?.process_configs(configs)?;
+    Ok(configs.into_iter().map(|c| c.id.clone()).collect())
+  }
+
+  #[test]
+  fn test_filter_by_tags() -> Result<()> {
+    assert_eq!(select(&[], &[])?, ["a", "b", "c"]);
+    assert_eq!(select(&["SECURITY"], &[])?, ["a", "b"]);
+    assert_eq!(select(&["security", "js"], &[])?, ["a"]);
+    assert_eq!(select(&[], &["Style"])?, ["a", "c"]);
+    assert_eq!(select(&["security"], &["js"])?, ["b"]);
+    assert!(select(&["perf"], &[]).is_err());
+    Ok(())
+  }
+}
@@ -71,6 +71,14 @@ impl<T> Iterator for Items<T> {
   }
 }
 impl<T> Items<T> {
+  pub fn from_vec(items: Vec<T>) -> Self {
+    let (tx, rx) = mpsc::channel();
+    for item in items {
+      // receiver is alive so send never fails
+      let _ = tx.send(item);
+    }
+    Items(rx)
+  }
   fn once(t: T) -> Result<Self> {
     let (tx, rx) = mpsc::channel();
     // use write to avoid send/sync trait bound
@@ -101,6 +109,24 @@ fn filter_result(result: Result<DirEntry, ignore::Error>) -> Option<PathBuf> {
   }
 }
 
+/// Collect all files in the walk. Used when files need to be revisited, e.g. in watch mode.
+pub fn collect_paths(walker: WalkParallel) -> Vec<PathBuf> {
+  let (tx, rx) = mpsc::channel();
+  walker.run(|| {
+    let tx = tx.clone();
+    Box::new(move |result| {
+      if let Some(p) = filter_result(result) {
+        if tx.send(p).is_err() {
+          return WalkState::Quit;
+        }
+      }
+      WalkState::Continue
+    })
+  });
+  drop(tx);
+  rx.into_iter().collect()
+}
+
 fn run_worker<W: PathWorker + ?Sized + 'static, P: Printer>(
   worker: Arc<W>,
   printer: P,
@@ -62,16 +62,18 @@ fn run_test_rule_impl<R: Reporter + Send>(
   project: ProjectConfig,
 ) -> Result<()> {
   let collections = &project.find_rules(Default::default())?.0;
+  let mut harness = if let Some(test_dirname) = &arg.test_dir {
+    let snapshot_dirname = arg.snapshot_dir.as_deref();
+    TestHarness::from_dir(test_dirname, snapshot_dirname, arg.filter.as_ref())?
+  } else {
+    TestHarness::from_config(&project, arg.filter.as_ref())?
+  };
+  harness.add_inline_tests(&project, arg.filter.as_ref())?;
   let TestHarness {
     test_cases,
     snapshots,
     path_map,
-  } = if let Some(test_dirname) = arg.test_dir {
-    let snapshot_dirname = arg.snapshot_dir.as_deref();
-    TestHarness::from_dir(&test_dirname, snapshot_dirname, arg.filter.as_ref())?
-  } else {
-    TestHarness::from_config(project, arg.filter.as_ref())?
-  };
+  } = harness;
   let snapshots = (!arg.skip_snapshot_tests).then_some(snapshots);
   let reporter = &Arc::new(Mutex::new(reporter));
   {
@@ -93,7 +95,11 @@ fn run_test_rule_impl<R: Reporter + Send>(
   reporter.report_failed_cases(&mut results)?;
   let action = reporter.collect_snapshot_action();
   apply_snapshot_action(action, &results, snapshots, path_map)?;
-  reporter.report_summaries(&results)?;
+  if arg.rules.is_some() {
+    reporter.report_table(&results)?;
+  } else {
+    reporter.report_summaries(&results)?;
+  }
   let (passed, message) = reporter.after_report(&results)?;
   if passed {
     writeln!(reporter.get_output(), "{message}",)?;
@@ -139,6 +145,7 @@ fn verify_test_case_simple<'a>(
   snapshots: Option<&SnapshotCollection>,
 ) -> Option<CaseResult<'a>> {
   let rule_config = rules.get_rule(&test_case.id)?;
+  let snapshots = snapshots.filter(|_| !test_case.inline);
   let test_case = if let Some(snapshots) = snapshots {
     let snaps = snapshots.get(&test_case.id);
     test_case.verify_with_snapshot(rule_config, snaps)
@@ -181,10 +188,24 @@ pub struct TestArg {
   /// Only run rule test cases that matches REGEX.
   #[clap(short, long, value_name = "REGEX")]
   filter: Option<Regex>,
+  /// Run the inline `test` of rule files in PATH, a rule file or directory.
+  /// The project configuration sgconfig.yml is not needed with this option.
+  #[clap(long, value_name = "PATH", conflicts_with = "test_dir")]
+  rules: Option<PathBuf>,
 }
 
 pub fn run_test_rule(arg: TestArg, project: Result<ProjectConfig>) -> Result<()> {
-  let project = project?;
+  let project = if let Some(rules) = &arg.rules {
+    ProjectConfig {
+      project_dir: std::env::current_dir()?,
+      config_path: None,
+      rule_dirs: vec![rules.clone()],
+      test_configs: None,
+      util_dirs: None,
+    }
+  } else {
+    project?
+  };
   if arg.interactive {
     let reporter = InteractiveReporter {
       output: std::io::stdout(),
@@ -241,6 +262,7 @@ rule:
       id: TEST_RULE.into(),
       valid: vec!["123".into()],
       invalid: vec![],
+      inline: false,
     }
   }
 
@@ -249,6 +271,7 @@ rule:
       id: TEST_RULE.into(),
       valid: vec![],
       invalid: vec!["123".into()],
+      inline: false,
     }
   }
 
@@ -295,6 +318,7 @@ rule:
       id: "no-such-rule".into(),
       valid: vec![],
       invalid: vec![],
+      inline: false,
     };
     let rule = never_report_rule();
     let ret = verify_test_case_simple(&case, &rule, None);
@@ -310,6 +334,7 @@ rule:
       test_dir: None,
       update_all: false,
       filter: None,
+      rules: None,
     };
     assert!(run_test_rule(arg, Err(anyhow!("error"))).is_err());
   }
@@ -335,6 +360,7 @@ fix: 'log($B)'";
       id: TEST_RULE.into(),
       valid: vec![],
       invalid: vec!["console.log(123)".to_string()],
+      inline: false,
     };
     let snapshots = SnapshotCollection::new();
     let mut ret = verify_test_case_simple(&case, &rule, Some(&snapshots)).unwrap();
@@ -96,6 +96,11 @@ impl<'a> CaseStatus<'a> {
     true
   }
 
+  /// Is the status from checking valid code, instead of invalid code?
+  pub fn is_valid_case(&self) -> bool {
+    matches!(self, CaseStatus::Validated | CaseStatus::Noisy(_))
+  }
+
   pub fn is_pass(&self) -> bool {
     matches!(
       self,
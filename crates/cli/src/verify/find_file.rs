@@ -1,3 +1,4 @@
+use super::test_case::InlineTestCase;
 use super::{SnapshotCollection, TestCase, TestSnapshots};
 use crate::config::ProjectConfig;
 use crate::utils::ErrorContext as EC;
@@ -23,7 +24,7 @@ pub struct TestHarness {
 }
 
 impl TestHarness {
-  pub fn from_config(project_config: ProjectConfig, regex_filter: Option<&Regex>) -> Result<Self> {
+  pub fn from_config(project_config: &ProjectConfig, regex_filter: Option<&Regex>) -> Result<Self> {
     find_tests(project_config, regex_filter)
   }
 
@@ -40,6 +41,24 @@ impl TestHarness {
     builder.read_test_files(test_dirname, snapshot_dirname)?;
     Ok(builder.dest)
   }
+
+  /// Add test cases declared in the `test` field of rule files in the project's rule dirs.
+  pub fn add_inline_tests(
+    &mut self,
+    project_config: &ProjectConfig,
+    regex_filter: Option<&Regex>,
+  ) -> Result<()> {
+    let mut builder = HarnessBuilder {
+      dest: std::mem::take(self),
+      base_dir: project_config.project_dir.clone(),
+      regex_filter,
+    };
+    for rule_dir in &project_config.rule_dirs {
+      builder.read_rule_files(rule_dir)?;
+    }
+    *self = builder.dest;
+    Ok(())
+  }
 }
 
 struct HarnessBuilder<'a> {
@@ -84,10 +103,32 @@ impl HarnessBuilder<'_> {
     }
     Ok(())
   }
+
+  fn read_rule_files(&mut self, rule_dirname: &Path) -> Result<()> {
+    let rule_path = self.base_dir.join(rule_dirname);
+    let walker = WalkBuilder::new(&rule_path)
+      .types(config_file_type())
+      .build();
+    for dir in walker {
+      let config_file = dir.with_context(|| EC::WalkRuleDir(rule_path.clone()))?;
+      // file_type is None only if it is stdin, safe to unwrap here
+      if !config_file
+        .file_type()
+        .expect("file type should be available for non-stdin")
+        .is_file()
+      {
+        continue;
+      }
+      let path = config_file.path();
+      let yaml = read_to_string(path).with_context(|| EC::ReadRule(path.to_path_buf()))?;
+      deserialize_inline_test_yaml(path, yaml, self)?;
+    }
+    Ok(())
+  }
 }
 
 pub fn find_tests(
-  project_config: ProjectConfig,
+  project_config: &ProjectConfig,
   regex_filter: Option<&Regex>,
 ) -> Result<TestHarness> {
   let ProjectConfig {
@@ -95,9 +136,9 @@ pub fn find_tests(
     test_configs,
     ..
   } = project_config;
-  let test_configs = test_configs.unwrap_or_default();
+  let test_configs = test_configs.as_deref().unwrap_or_default();
   let mut builder = HarnessBuilder {
-    base_dir: project_dir,
+    base_dir: project_dir.clone(),
     regex_filter,
     dest: TestHarness::default(),
   };
@@ -145,6 +186,24 @@ fn deserialize_test_yaml(
   Ok(())
 }
 
+fn deserialize_inline_test_yaml(
+  path: &Path,
+  yaml: String,
+  builder: &mut HarnessBuilder<'_>,
+) -> Result<()> {
+  for deser in Deserializer::from_str(&yaml) {
+    let inline: InlineTestCase =
+      deserialize(deser).with_context(|| EC::ParseRule(path.to_path_buf()))?;
+    let Some(test_case) = inline.into_test_case() else {
+      continue;
+    };
+    if builder.included_in_filter(&test_case.id) {
+      builder.dest.test_cases.push(test_case);
+    }
+  }
+  Ok(())
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -183,6 +242,35 @@ invalid: [a]
     assert_eq!(harness.test_cases[1].id, "test2");
   }
 
+  const INLINE: &str = "
+id: with-test
+rule: { pattern: a }
+test:
+  valid: [b]
+  invalid: [a, c]
+---
+id: without-test
+rule: { pattern: a }
+";
+
+  #[test]
+  fn test_read_inline() {
+    let mut builder = HarnessBuilder {
+      dest: TestHarness::default(),
+      base_dir: PathBuf::new(),
+      regex_filter: None,
+    };
+    let path = Path::new(".");
+    deserialize_inline_test_yaml(path, INLINE.to_string(), &mut builder).expect("should ok");
+    let cases = builder.dest.test_cases;
+    assert_eq!(cases.len(), 1);
+    assert_eq!(cases[0].id, "with-test");
+    assert_eq!(cases[0].valid, ["b"]);
+    assert_eq!(cases[0].invalid, ["a", "c"]);
+    assert!(cases[0].inline);
+    assert!(builder.dest.path_map.is_empty());
+  }
+
   const SNAPSHOTS: &str = "
 id: test-1
 snapshots:
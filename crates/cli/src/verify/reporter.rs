@@ -62,6 +62,34 @@ pub(super) trait Reporter {
     Ok(())
   }
 
+  /// Report one row per rule and test type, used for inline rule tests.
+  fn report_table(&mut self, results: &[CaseResult]) -> Result<()> {
+    let width = results
+      .iter()
+      .map(|r| r.id.len())
+      .chain(Some(TABLE_HEADER.len()))
+      .max()
+      .unwrap_or_default();
+    let output = self.get_output();
+    writeln!(output, "{TABLE_HEADER:width$}  {:7}  RESULT", "TYPE")?;
+    for result in results {
+      let (valid, invalid): (Vec<_>, Vec<_>) = result.cases.iter().partition(|c| c.is_valid_case());
+      for (test_type, cases) in [("valid", valid), ("invalid", invalid)] {
+        if cases.is_empty() {
+          continue;
+        }
+        let status = if cases.iter().all(|c| c.is_pass()) {
+          Color::Green.paint("PASS")
+        } else {
+          Color::Red.paint("FAIL")
+        };
+        writeln!(output, "{:width$}  {test_type:7}  {status}", result.id)?;
+      }
+    }
+    writeln!(output)?;
+    Ok(())
+  }
+
   fn report_case_summary(&mut self, case_id: &str, summary: &[CaseStatus]) -> Result<()> {
     let passed = summary.iter().all(CaseStatus::is_pass);
     let style = Style::new().fg(Color::White).bold();
@@ -83,6 +111,8 @@ pub(super) trait Reporter {
   fn collect_snapshot_action(&self) -> SnapshotAction;
 }
 
+const TABLE_HEADER: &str = "RULE ID";
+
 fn report_case_number(output: &mut impl Write, test_cases: &[TestCase]) -> Result<()> {
   writeln!(output, "Running {} tests", test_cases.len())?;
   Ok(())
@@ -360,6 +390,35 @@ mod test {
     Ok(())
   }
 
+  #[test]
+  fn test_report_table() -> Result<()> {
+    let output = vec![];
+    let mut reporter = DefaultReporter {
+      output,
+      update_all: false,
+    };
+    let results = [
+      CaseResult {
+        id: TEST_RULE,
+        cases: vec![CaseStatus::Validated, CaseStatus::Missing(MOCK)],
+      },
+      CaseResult {
+        id: "only-valid",
+        cases: vec![CaseStatus::Validated],
+      },
+    ];
+    reporter.report_table(&results)?;
+    let s = String::from_utf8(reporter.output)?;
+    let lines: Vec<_> = s.lines().collect();
+    assert!(lines[0].starts_with("RULE ID"));
+    assert!(lines[1].starts_with(TEST_RULE));
+    assert!(lines[1].contains("valid") && lines[1].contains("PASS"));
+    assert!(lines[2].contains("invalid") && lines[2].contains("FAIL"));
+    assert!(lines[3].starts_with("only-valid"));
+    assert_eq!(lines.len(), 5);
+    Ok(())
+  }
+
   #[test]
   fn test_valid_case_detail() -> Result<()> {
     let output = vec![];
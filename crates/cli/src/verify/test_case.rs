@@ -19,6 +19,45 @@ pub struct TestCase {
   pub valid: Vec<String>,
   #[serde(default)]
   pub invalid: Vec<String>,
+  /// Declared in the `test` field of a rule file. Inline tests do not use snapshots.
+  #[serde(skip)]
+  pub inline: bool,
+}
+
+/// Test cases written inside a rule file, e.g.
+///
+/// ```yaml
+/// id: no-console
+/// rule: { pattern: console.log($A) }
+/// test:
+///   valid: [alert(1)]
+///   invalid: [console.log(1)]
+/// ```
+#[derive(Deserialize)]
+pub struct InlineTestCase {
+  id: String,
+  test: Option<InlineTest>,
+}
+
+#[derive(Deserialize)]
+struct InlineTest {
+  #[serde(default)]
+  valid: Vec<String>,
+  #[serde(default)]
+  invalid: Vec<String>,
+}
+
+impl InlineTestCase {
+  /// Returns None if the rule has no inline test.
+  pub fn into_test_case(self) -> Option<TestCase> {
+    let test = self.test?;
+    Some(TestCase {
+      id: self.id,
+      valid: test.valid,
+      invalid: test.invalid,
+      inline: true,
+    })
+  }
 }
 
 impl TestCase {
@@ -85,6 +124,7 @@ mod tests {
       id: TEST_RULE.to_string(),
       valid: valid.iter().map(|s| s.to_string()).collect(),
       invalid: invalid.iter().map(|s| s.to_string()).collect(),
+      inline: false,
     }
   }
 
@@ -194,6 +234,7 @@ mod tests {
       id: "non-matching".into(),
       valid: vec![],
       invalid: vec![],
+      inline: false,
     };
     test_case.verify_rule(&rule_config);
   }
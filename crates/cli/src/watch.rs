@@ -0,0 +1,294 @@
+use crate::lang::SgLang;
+use crate::print::{Diff, Printer, SimpleFile};
+use crate::utils::{collect_paths, Items, PathWorker};
+
+use anyhow::{Context, Result};
+use ast_grep_config::{RuleConfig, Severity};
+use ast_grep_core::{NodeMatch, StrDoc};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+/// Events arriving within this window are handled in one batch.
+const DEBOUNCE: Duration = Duration::from_millis(50);
+/// How often the watch loop checks for Ctrl-C when no file changes.
+const POLL: Duration = Duration::from_millis(200);
+
+/// A match reported in watch mode. Only the data needed to tell
+/// whether a match is new or resolved between two scans is kept.
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct WatchMatch {
+  path: String,
+  /// one-based line
+  line: usize,
+  /// one-based column
+  column: usize,
+  severity: &'static str,
+  rule_id: String,
+  message: String,
+}
+
+impl WatchMatch {
+  fn new(nm: &NodeMatch<StrDoc<SgLang>>, path: &str, rule: &RuleConfig<SgLang>) -> Self {
+    let span = nm.span();
+    Self {
+      path: path.to_string(),
+      line: span.start_line + 1,
+      column: span.start_col + 1,
+      severity: severity_name(&rule.severity),
+      rule_id: rule.id.clone(),
+      message: rule.get_message(nm),
+    }
+  }
+}
+
+fn severity_name(severity: &Severity) -> &'static str {
+  match severity {
+    Severity::Error => "error",
+    Severity::Warning => "warning",
+    Severity::Info => "info",
+    Severity::Hint => "hint",
+    Severity::Off => "off",
+  }
+}
+
+/// Records matches instead of printing them so that scans can be compared.
+#[derive(Clone, Default)]
+struct MatchRecorder(Rc<RefCell<Vec<WatchMatch>>>);
+
+impl MatchRecorder {
+  fn take(self) -> Vec<WatchMatch> {
+    self.0.take()
+  }
+}
+
+impl Printer for MatchRecorder {
+  fn print_rule(
+    &mut self,
+    matches: Vec<NodeMatch<StrDoc<SgLang>>>,
+    file: SimpleFile<Cow<str>, &String>,
+    rule: &RuleConfig<SgLang>,
+  ) -> Result<()> {
+    let path = file.name();
+    let mut recorded = self.0.borrow_mut();
+    recorded.extend(matches.iter().map(|nm| WatchMatch::new(nm, path, rule)));
+    Ok(())
+  }
+
+  fn print_matches(&mut self, _m: Vec<NodeMatch<StrDoc<SgLang>>>, _p: &Path) -> Result<()> {
+    unreachable!("watch mode only runs scan")
+  }
+
+  fn print_diffs(&mut self, _d: Vec<Diff>, _p: &Path) -> Result<()> {
+    unreachable!("watch mode only runs scan")
+  }
+
+  fn print_rule_diffs(
+    &mut self,
+    diffs: Vec<(Diff, &RuleConfig<SgLang>)>,
+    path: &Path,
+  ) -> Result<()> {
+    let path = path.to_string_lossy();
+    let mut recorded = self.0.borrow_mut();
+    for (diff, rule) in diffs {
+      recorded.push(WatchMatch::new(&diff.node_match, &path, rule));
+    }
+    Ok(())
+  }
+}
+
+struct WatchState<W: PathWorker> {
+  worker: W,
+  cwd: PathBuf,
+  matches: HashMap<PathBuf, Vec<WatchMatch>>,
+}
+
+impl<W: PathWorker> WatchState<W> {
+  fn new(worker: W) -> Result<Self> {
+    let cwd = std::env::current_dir()?;
+    let cwd = std::fs::canonicalize(&cwd).unwrap_or(cwd);
+    Ok(Self {
+      worker,
+      cwd,
+      matches: HashMap::new(),
+    })
+  }
+
+  /// Run the same file scanning as `sg scan` on one file.
+  fn scan_file(&self, path: &Path) -> Vec<WatchMatch> {
+    let Some(items) = self.worker.produce_item(path) else {
+      return vec![];
+    };
+    let recorder = MatchRecorder::default();
+    // error severity matches are reported as Err which does not stop watching
+    let _ = self
+      .worker
+      .consume_items(Items::from_vec(items), recorder.clone());
+    recorder.take()
+  }
+
+  /// Rescan files affected by changed paths, or all files if `changed` is None.
+  /// A changed directory affects all files in it. Returns resolved and new matches.
+  fn rescan(&mut self, changed: Option<&[PathBuf]>) -> Result<MatchDiff> {
+    let files: HashSet<_> = collect_paths(self.worker.build_walk()?)
+      .into_iter()
+      .collect();
+    let mut diff = MatchDiff::default();
+    // files removed or newly ignored
+    let gone: Vec<_> = self
+      .matches
+      .keys()
+      .filter(|p| !files.contains(*p))
+      .cloned()
+      .collect();
+    for path in gone {
+      if let Some(old) = self.matches.remove(&path) {
+        diff.resolved.extend(old);
+      }
+    }
+    for file in files {
+      let abs = self.cwd.join(&file);
+      if !changed.map_or(true, |c| c.iter().any(|c| abs.starts_with(c))) {
+        continue;
+      }
+      let new = self.scan_file(&file);
+      let old = self.matches.remove(&file).unwrap_or_default();
+      diff.extend(&old, &new);
+      if !new.is_empty() {
+        self.matches.insert(file, new);
+      }
+    }
+    Ok(diff)
+  }
+}
+
+#[derive(Default)]
+struct MatchDiff {
+  resolved: Vec<WatchMatch>,
+  added: Vec<WatchMatch>,
+}
+
+impl MatchDiff {
+  fn extend(&mut self, old: &[WatchMatch], new: &[WatchMatch]) {
+    let resolved = old.iter().filter(|m| !new.contains(m));
+    self.resolved.extend(resolved.cloned());
+    let added = new.iter().filter(|m| !old.contains(m));
+    self.added.extend(added.cloned());
+  }
+
+  fn print(&self, output: &mut impl Write) -> Result<()> {
+    for (sign, m) in self
+      .resolved
+      .iter()
+      .map(|m| ('-', m))
+      .chain(self.added.iter().map(|m| ('+', m)))
+    {
+      writeln!(
+        output,
+        "{sign} {}:{}:{} {}[{}]: {}",
+        m.path, m.line, m.column, m.severity, m.rule_id, m.message
+      )?;
+    }
+    output.flush()?;
+    Ok(())
+  }
+}
+
+fn changed_paths(event: notify::Result<Event>) -> Vec<PathBuf> {
+  match event {
+    // reading files during scan also produces access events
+    Ok(event) if matches!(event.kind, EventKind::Access(_)) => vec![],
+    Ok(event) => event.paths,
+    Err(err) => {
+      eprintln!("ERROR: {err}");
+      vec![]
+    }
+  }
+}
+
+/// Scan all files once, then rescan changed files until Ctrl-C is pressed.
+/// New matches are printed with `+` and resolved matches with `-`.
+pub fn run_watch<W: PathWorker>(worker: W, roots: &[PathBuf]) -> Result<()> {
+  let interrupted = Arc::new(AtomicBool::new(false));
+  let flag = interrupted.clone();
+  ctrlc::set_handler(move || flag.store(true, Ordering::SeqCst))
+    .context("Cannot register Ctrl-C handler.")?;
+  let (tx, rx) = mpsc::channel();
+  let mut watcher = notify::recommended_watcher(tx)?;
+  for root in roots {
+    watcher
+      .watch(root, RecursiveMode::Recursive)
+      .with_context(|| format!("Cannot watch {}.", root.display()))?;
+  }
+  let mut state = WatchState::new(worker)?;
+  let mut output = std::io::stdout();
+  state.rescan(None)?.print(&mut output)?;
+  eprintln!("Watching for changes. Press Ctrl-C to stop.");
+  while !interrupted.load(Ordering::SeqCst) {
+    let mut changed = match rx.recv_timeout(POLL) {
+      Ok(event) => changed_paths(event),
+      Err(mpsc::RecvTimeoutError::Timeout) => continue,
+      Err(mpsc::RecvTimeoutError::Disconnected) => break,
+    };
+    while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+      changed.extend(changed_paths(event));
+    }
+    if changed.is_empty() {
+      continue;
+    }
+    state.rescan(Some(&changed))?.print(&mut output)?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn make_match(line: usize, rule_id: &str) -> WatchMatch {
+    WatchMatch {
+      path: "test.ts".into(),
+      line,
+      column: 1,
+      severity: "warning",
+      rule_id: rule_id.into(),
+      message: "test rule".into(),
+    }
+  }
+
+  #[test]
+  fn test_match_diff() {
+    let old = vec![make_match(1, "a"), make_match(2, "b")];
+    let new = vec![make_match(2, "b"), make_match(3, "a")];
+    let mut diff = MatchDiff::default();
+    diff.extend(&old, &new);
+    assert_eq!(diff.resolved, [make_match(1, "a")]);
+    assert_eq!(diff.added, [make_match(3, "a")]);
+    let mut output = vec![];
+    diff.print(&mut output).expect("should print");
+    let output = String::from_utf8(output).expect("should be utf8");
+    assert_eq!(
+      output,
+      "- test.ts:1:1 warning[a]: test rule\n+ test.ts:3:1 warning[a]: test rule\n"
+    );
+  }
+
+  #[test]
+  fn test_ignore_access_event() {
+    let path = PathBuf::from("test.ts");
+    let access =
+      Event::new(EventKind::Access(notify::event::AccessKind::Any)).add_path(path.clone());
+    assert!(changed_paths(Ok(access)).is_empty());
+    let modify =
+      Event::new(EventKind::Modify(notify::event::ModifyKind::Any)).add_path(path.clone());
+    assert_eq!(changed_paths(Ok(modify)), [path]);
+  }
+}
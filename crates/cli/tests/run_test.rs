@@ -32,6 +32,47 @@ fn test_simple_specific_lang() -> Result<()> {
   Ok(())
 }
 
+#[test]
+fn test_run_stdin() -> Result<()> {
+  for stdin_arg in ["--stdin", "-"] {
+    Command::cargo_bin("ast-grep")?
+      .args(["run", "-p", "console.log($A)", "-l", "ts", stdin_arg])
+      .write_stdin("let a = 1\nconsole.log(a)")
+      .assert()
+      .success()
+      .stdout(contains("<stdin>"))
+      .stdout(contains("console.log(a)"));
+  }
+  Ok(())
+}
+
+#[test]
+fn test_run_stdin_without_lang() -> Result<()> {
+  Command::cargo_bin("ast-grep")?
+    .args(["run", "-p", "console.log($A)", "-"])
+    .write_stdin("console.log(a)")
+    .assert()
+    .failure()
+    .stderr(contains("Language must be specified"));
+  Ok(())
+}
+
+#[test]
+fn test_shebang_script() -> Result<()> {
+  let dir = create_test_files([
+    ("script", "#!/usr/bin/env node\nconsole.log(123)"),
+    ("notes", "console.log(456)"),
+  ])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["-p", "console.log($A)"])
+    .assert()
+    .success()
+    .stdout(contains("console.log(123)"))
+    .stdout(contains("console.log(456)").not());
+  Ok(())
+}
+
 #[test]
 fn test_js_in_html() -> Result<()> {
   let dir = create_test_files([
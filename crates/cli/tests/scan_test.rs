@@ -232,3 +232,172 @@ fn test_severity_override() -> Result<()> {
     .stdout(contains("warning"));
   Ok(())
 }
+
+#[test]
+fn test_exit_code_by_severity() -> Result<()> {
+  let dir = create_test_files([("test.ts", "Some(123)")])?;
+  let scan = |severity: &str, extra: &[&str]| -> Result<_> {
+    let rule = format!(
+      "id: test\nmessage: test\nseverity: {severity}\nlanguage: TypeScript\nrule: {{ pattern: Some($A) }}"
+    );
+    let mut cmd = Command::cargo_bin("ast-grep")?;
+    cmd
+      .current_dir(dir.path())
+      .args(["scan", "--inline-rules", &rule])
+      .args(extra);
+    Ok(cmd.assert())
+  };
+  scan("error", &[])?.failure().code(1);
+  scan("warning", &[])?.success();
+  scan("info", &[])?.success();
+  scan("hint", &[])?.success();
+  scan("error", &["--error-on", "warning"])?.failure().code(1);
+  scan("warning", &["--error-on", "warning"])?
+    .failure()
+    .code(1);
+  scan("info", &["--error-on", "warning"])?.success();
+  scan("hint", &["--error-on", "hint"])?.failure().code(1);
+  Ok(())
+}
+
+// check required properties of SARIF 2.1.0 schema
+fn assert_sarif(sarif: &Value) {
+  assert_eq!(sarif["version"], "2.1.0");
+  assert!(sarif["$schema"].is_string());
+  let runs = sarif["runs"].as_array().expect("runs should be array");
+  assert_eq!(runs.len(), 1);
+  let driver = &runs[0]["tool"]["driver"];
+  assert_eq!(driver["name"], "ast-grep");
+  for rule in driver["rules"].as_array().expect("rules should be array") {
+    assert!(rule["id"].is_string());
+    assert!(rule["shortDescription"]["text"].is_string());
+  }
+  for result in runs[0]["results"]
+    .as_array()
+    .expect("results should be array")
+  {
+    assert!(result["message"]["text"].is_string());
+    let location = &result["locations"][0]["physicalLocation"];
+    assert!(location["artifactLocation"]["uri"].is_string());
+    for key in ["startLine", "startColumn", "endLine", "endColumn"] {
+      assert!(location["region"][key].as_u64().expect("should be integer") >= 1);
+    }
+  }
+}
+
+#[test]
+fn test_scan_sarif() -> Result<()> {
+  let dir = setup()?;
+  for flag in ["--format=sarif", "--output-format=sarif"] {
+    let output = Command::cargo_bin("ast-grep")?
+      .current_dir(dir.path())
+      .args(["scan", flag])
+      .assert()
+      .success()
+      .get_output()
+      .stdout
+      .clone();
+    let sarif: Value = from_slice(&output)?;
+    assert_sarif(&sarif);
+    let run = &sarif["runs"][0];
+    assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "on-rule");
+    let result = &run["results"][0];
+    assert_eq!(result["ruleId"], "on-rule");
+    assert_eq!(result["level"], "warning");
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "test.ts");
+    assert_eq!(location["region"]["endColumn"], 10);
+  }
+  Ok(())
+}
+
+fn assert_scan_record(record: &Value) {
+  assert!(record["ruleId"].is_string());
+  assert!(record["severity"].is_string());
+  assert!(record["file"].is_string());
+  assert!(record["text"].is_string());
+  for key in ["startLine", "startCol", "endLine", "endCol"] {
+    assert!(record["range"][key].is_u64());
+  }
+}
+
+#[test]
+fn test_scan_ndjson() -> Result<()> {
+  let dir = setup()?;
+  let output = Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--output-format=json"])
+    .assert()
+    .success()
+    .get_output()
+    .stdout
+    .clone();
+  let output = String::from_utf8(output)?;
+  let records = output
+    .lines()
+    .map(serde_json::from_str)
+    .collect::<Result<Vec<Value>, _>>()?;
+  assert_eq!(records.len(), 1);
+  let record = &records[0];
+  assert_scan_record(record);
+  assert_eq!(record["ruleId"], "on-rule");
+  assert_eq!(record["severity"], "warning");
+  assert_eq!(record["file"], "test.ts");
+  assert_eq!(record["range"]["endCol"], 9);
+  assert!(record["replacement"].is_null());
+  Ok(())
+}
+
+#[test]
+fn test_scan_json_array() -> Result<()> {
+  let inline_rules = "{id: test, language: ts, rule: {pattern: var $A = $B}, fix: let $A = $B}";
+  let output = Command::cargo_bin("ast-grep")?
+    .args([
+      "scan",
+      "--stdin",
+      "--inline-rules",
+      inline_rules,
+      "--format=json-array",
+    ])
+    .write_stdin("var a = 123\nvar b = 456")
+    .assert()
+    .success()
+    .get_output()
+    .stdout
+    .clone();
+  let records: Value = from_slice(&output)?;
+  let records = records.as_array().expect("should be array");
+  assert_eq!(records.len(), 2);
+  for record in records {
+    assert_scan_record(record);
+  }
+  assert_eq!(records[1]["text"], "var b = 456");
+  assert_eq!(records[1]["replacement"], "let b = 456");
+  assert_eq!(records[1]["range"]["startLine"], 1);
+  Ok(())
+}
+
+#[test]
+fn test_scan_sarif_fix() -> Result<()> {
+  let inline_rules = "{id: test, language: ts, rule: {pattern: var $A = $B}, fix: let $A = $B}";
+  let output = Command::cargo_bin("ast-grep")?
+    .args([
+      "scan",
+      "--stdin",
+      "--inline-rules",
+      inline_rules,
+      "--format=sarif",
+    ])
+    .write_stdin("var a = 123")
+    .assert()
+    .success()
+    .get_output()
+    .stdout
+    .clone();
+  let sarif: Value = from_slice(&output)?;
+  assert_sarif(&sarif);
+  let fix = &sarif["runs"][0]["results"][0]["fixes"][0];
+  let replacement = &fix["artifactChanges"][0]["replacements"][0];
+  assert_eq!(replacement["insertedContent"]["text"], "let a = 123");
+  Ok(())
+}
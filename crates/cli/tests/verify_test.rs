@@ -107,3 +107,70 @@ fn test_sg_test_filter() -> Result<()> {
   drop(dir);
   Ok(())
 }
+
+const INLINE_RULE: &str = "
+id: inline-rule
+message: test rule
+severity: warning
+language: TypeScript
+rule:
+  pattern: Some($A)
+test:
+  valid:
+  - None
+  invalid:
+  - Some(123)
+";
+
+const WRONG_INLINE_RULE: &str = "
+id: inline-rule
+message: test rule
+severity: warning
+language: TypeScript
+rule:
+  pattern: Some($A)
+test:
+  valid:
+  - Some(123)
+";
+
+#[test]
+fn test_sg_test_inline_rules() -> Result<()> {
+  let dir = create_test_files([("rules/inline-rule.yml", INLINE_RULE)])?;
+  let rules = dir.path().join("rules");
+  let ret = sg(&format!("ast-grep test --rules {}", rules.display()));
+  assert!(ret.is_ok());
+  let rule_file = rules.join("inline-rule.yml");
+  let ret = sg(&format!("ast-grep test --rules {}", rule_file.display()));
+  assert!(ret.is_ok());
+  let dir = create_test_files([("rules/inline-rule.yml", WRONG_INLINE_RULE)])?;
+  let rules = dir.path().join("rules");
+  let ret = sg(&format!("ast-grep test --rules {}", rules.display()));
+  assert!(ret.is_err());
+  Ok(())
+}
+
+#[test]
+fn test_sg_test_inline_in_project() -> Result<()> {
+  let dir = create_test_files([
+    ("sgconfig.yml", CONFIG),
+    ("rules/test-rule.yml", RULE),
+    ("rules/inline-rule.yml", WRONG_INLINE_RULE),
+    ("rule-tests/test-rule-test.yml", TEST),
+  ])?;
+  let config = dir.path().join("sgconfig.yml");
+  let ret = sg(&format!(
+    "ast-grep test -c {} --skip-snapshot-tests -f test-rule",
+    config.display()
+  ));
+  assert!(ret.is_ok());
+  // inline tests do not use snapshots
+  let ret = sg(&format!(
+    "ast-grep test -c {} -f inline-rule",
+    config.display()
+  ));
+  assert!(ret.is_err());
+  let ret = sg(&format!("ast-grep scan -c {}", config.display()));
+  assert!(ret.is_ok());
+  Ok(())
+}
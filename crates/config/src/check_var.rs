@@ -271,6 +271,18 @@ fix: $D
     assert_eq!(section, "fix");
   }
 
+  #[test]
+  fn test_missing_var_in_fix() {
+    let (name, section) = get_undefined(
+      r"
+rule: {pattern: $A + $B}
+fix: return $MISSING
+",
+    );
+    assert_eq!(name, "MISSING");
+    assert_eq!(section, "fix");
+  }
+
   #[test]
   fn test_defined_vars_in_utils() {
     let env = DeserializeEnv::new(TypeScript::Tsx);
@@ -4,6 +4,7 @@ use ast_grep_core::language::Language;
 use ast_grep_core::{AstGrep, Doc, Matcher, Node, NodeMatch};
 
 use bit_set::BitSet;
+use rayon::prelude::*;
 use std::collections::{HashMap, HashSet};
 
 pub struct ScanResult<'t, 'r, D: Doc, L: Language> {
@@ -121,6 +122,15 @@ pub struct PreScan {
   suppressions: Suppressions,
 }
 
+impl Default for PreScan {
+  fn default() -> Self {
+    Self {
+      hit_set: BitSet::new(),
+      suppressions: Suppressions(HashMap::new()),
+    }
+  }
+}
+
 impl PreScan {
   pub fn is_empty(&self) -> bool {
     self.hit_set.is_empty() && self.suppressions.0.is_empty()
@@ -177,9 +187,52 @@ impl<'r, L: Language> CombinedScan<'r, L> {
   where
     D: Doc<Lang = L>,
   {
-    let mut hit = BitSet::new();
-    let mut suppressions = Suppressions(HashMap::new());
-    for node in root.root().dfs() {
+    let mut pre = PreScan::default();
+    self.find_in(root.root().dfs(), &mut pre);
+    pre
+  }
+
+  /// Same as [`CombinedScan::find`], but the top level subtrees of the file are checked
+  /// in parallel on the rayon thread pool. Every node is still visited once.
+  /// Use it for files checked by many rules, since spawning tasks has a cost.
+  pub fn find_par<D>(&self, root: &AstGrep<D>) -> PreScan
+  where
+    D: Doc<Lang = L>,
+    AstGrep<D>: Sync,
+    RuleConfig<L>: Sync,
+  {
+    let top = root.root();
+    let child_count = top.children().len();
+    if child_count <= 1 {
+      return self.find(root);
+    }
+    let mut pre = (0..child_count)
+      .into_par_iter()
+      .map(|i| {
+        let mut pre = PreScan::default();
+        if let Some(child) = root.root().child(i) {
+          self.find_in(child.dfs(), &mut pre);
+        }
+        pre
+      })
+      .reduce(PreScan::default, |mut pre, other| {
+        pre.hit_set.union_with(&other.hit_set);
+        pre.suppressions.0.extend(other.suppressions.0);
+        pre
+      });
+    self.find_in(std::iter::once(top), &mut pre);
+    pre
+  }
+
+  fn find_in<'t, D>(&self, nodes: impl Iterator<Item = Node<'t, D>>, pre: &mut PreScan)
+  where
+    D: Doc<Lang = L> + 't,
+  {
+    let PreScan {
+      hit_set: hit,
+      suppressions,
+    } = pre;
+    for node in nodes {
       suppressions.collect(&node);
       let kind = node.kind_id() as usize;
       let Some(rule_idx) = self.kind_rule_mapping.get(kind) else {
@@ -195,10 +248,6 @@ impl<'r, L: Language> CombinedScan<'r, L> {
         }
       }
     }
-    PreScan {
-      hit_set: hit,
-      suppressions,
-    }
   }
 
   pub fn scan<'a, D>(
@@ -277,6 +326,8 @@ impl<'r, L: Language> CombinedScan<'r, L> {
       fix: crate::from_str(r#"''"#).unwrap(),
       transform: None,
       utils: None,
+      extends: None,
+      tags: None,
     };
     let config = SerializableRuleConfig {
       core,
@@ -350,6 +401,35 @@ language: Tsx",
     assert_eq!(matches.1[1].text(), "console.log('ignore another')");
   }
 
+  #[test]
+  fn test_find_par() {
+    let source = r#"
+    // ast-grep-ignore
+    console.log('ignored')
+    let a = 123
+    "#;
+    let root = TypeScript::Tsx.ast_grep(source);
+    let rules: Vec<_> = ["console.log($A)", "let $A = 123", "var $A = 123"]
+      .iter()
+      .enumerate()
+      .map(|(i, pattern)| {
+        let yaml = format!("{{id: rule-{i}, language: Tsx, rule: {{pattern: '{pattern}'}}}}");
+        let rule: SerializableRuleConfig<TypeScript> = from_str(&yaml).expect("parse");
+        RuleConfig::try_from(rule, &Default::default()).expect("work")
+      })
+      .collect();
+    let scan = CombinedScan::new(rules.iter().collect());
+    let pool = rayon::ThreadPoolBuilder::new()
+      .num_threads(3)
+      .build()
+      .expect("should build pool");
+    let par = pool.install(|| scan.find_par(&root));
+    let seq = scan.find(&root);
+    assert_eq!(par.hit_set, seq.hit_set);
+    assert_eq!(par.hit_set.len(), 2);
+    assert_eq!(par.suppressions.0.len(), seq.suppressions.0.len());
+  }
+
   #[test]
   fn test_ignore_node_same_line() {
     let source = r#"
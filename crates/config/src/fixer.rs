@@ -2,10 +2,12 @@ use crate::maybe::Maybe;
 use crate::rule::{Relation, Rule, RuleSerializeError, StopBy};
 use crate::transform::Transformation;
 use crate::DeserializeEnv;
+use ast_grep_core::matcher::MatchAll;
 use ast_grep_core::replacer::{Content, Replacer, TemplateFix, TemplateFixError};
-use ast_grep_core::{Doc, Language, Matcher, NodeMatch};
+use ast_grep_core::{Doc, Language, Matcher, NodeMatch, StrDoc};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use similar::TextDiff;
 use thiserror::Error;
 
 use std::collections::{HashMap, HashSet};
@@ -70,6 +72,7 @@ impl<L: Language> Fixer<L> {
   fn do_parse(
     serialized: &SerializableFixConfig,
     env: &DeserializeEnv<L>,
+    transform: &Option<HashMap<String, Transformation>>,
   ) -> Result<Self, FixerError> {
     let SerializableFixConfig {
       template,
@@ -78,13 +81,17 @@ impl<L: Language> Fixer<L> {
     } = serialized;
     let expand_start = Expansion::parse(expand_start, env)?;
     let expand_end = Expansion::parse(expand_end, env)?;
+    let fixer = Self::with_transform(template, env, transform)?;
     Ok(Self {
-      template: TemplateFix::try_new(template, &env.lang)?,
       expand_start,
       expand_end,
+      ..fixer
     })
   }
 
+  /// Parse the fixer along with the keys of the rule's `transform`.
+  /// The transformation itself is run by `RuleCore` when matching,
+  /// so the fixer only reads transformed variables from the match env.
   pub fn parse(
     fixer: &SerializableFixer,
     env: &DeserializeEnv<L>,
@@ -92,7 +99,7 @@ impl<L: Language> Fixer<L> {
   ) -> Result<Self, FixerError> {
     match fixer {
       SerializableFixer::Str(fix) => Self::with_transform(fix, env, transform),
-      SerializableFixer::Config(cfg) => Self::do_parse(cfg, env),
+      SerializableFixer::Config(cfg) => Self::do_parse(cfg, env, transform),
     }
   }
 
@@ -126,6 +133,27 @@ impl<L: Language> Fixer<L> {
   pub(crate) fn used_vars(&self) -> HashSet<&str> {
     self.template.used_vars()
   }
+
+  /// Produce a unified diff of the whole file after applying the fix to `nm`,
+  /// with `context` unchanged lines around each hunk.
+  /// The header uses the document's file path, or `original`/`fixed` if the doc has no path.
+  pub fn generate_diff(&self, nm: &NodeMatch<StrDoc<L>>, context: usize) -> String {
+    let old = nm.root().get_text();
+    let edit = nm.make_edit(&MatchAll, self);
+    let start = edit.position;
+    let end = start + edit.deleted_length;
+    let inserted = String::from_utf8_lossy(&edit.inserted_text);
+    let new = format!("{}{inserted}{}", &old[..start], &old[end..]);
+    let (a, b) = match nm.source_file_path() {
+      Some(path) => (format!("a/{path}"), format!("b/{path}")),
+      None => ("original".to_string(), "fixed".to_string()),
+    };
+    TextDiff::from_lines(old, &new)
+      .unified_diff()
+      .context_radius(context)
+      .header(&a, &b)
+      .to_string()
+  }
 }
 
 impl<D, L, C> Replacer<D> for Fixer<L>
@@ -163,6 +191,7 @@ fn expand_start<D: Doc>(expansion: Option<&Expansion<D::Lang>>, nm: &NodeMatch<D
     || node.prev(),
     || node.prev_all(),
     |n| start.matches.match_node_with_env(n, &mut env),
+    None,
   );
   node
     .map(|n| n.range().start)
@@ -179,6 +208,7 @@ fn expand_end<D: Doc>(expansion: Option<&Expansion<D::Lang>>, nm: &NodeMatch<D>)
     || node.next(),
     || node.next_all(),
     |n| end.matches.match_node_with_env(n, &mut env),
+    None,
   );
   node
     .map(|n| n.range().end)
@@ -190,7 +220,42 @@ mod test {
   use super::*;
   use crate::from_str;
   use crate::maybe::Maybe;
+  use crate::rule::RegexRule;
   use crate::test::TypeScript;
+  use crate::SerializableRuleCore;
+  use ast_grep_core::AstGrep;
+
+  #[test]
+  fn test_generate_diff() {
+    let src = "let a = 1\nlet b = 2\nlet c = 3\nlet d = 4\nlet e = 5\n";
+    let doc = StrDoc::new(src, TypeScript::Tsx).with_file_path("src/test.ts");
+    let grep = AstGrep::doc(doc);
+    let nm = grep.root().find("let c = $A").expect("should match");
+    let fixer = Fixer::from_str("const c = $A", &TypeScript::Tsx).expect("should parse");
+    let diff = fixer.generate_diff(&nm, 1);
+    let expected = "\
+--- a/src/test.ts
++++ b/src/test.ts
+@@ -2,3 +2,3 @@
+ let b = 2
+-let c = 3
++const c = 3
+ let d = 4
+";
+    assert_eq!(diff, expected);
+    // no context line and no file path
+    let grep = TypeScript::Tsx.ast_grep(src);
+    let nm = grep.root().find("let c = $A").expect("should match");
+    let diff = fixer.generate_diff(&nm, 0);
+    let expected = "\
+--- original
++++ fixed
+@@ -3 +3 @@
+-let c = 3
++const c = 3
+";
+    assert_eq!(diff, expected);
+  }
 
   #[test]
   fn test_parse() {
@@ -209,7 +274,7 @@ mod test {
       panic!("wrong parsing")
     };
     let rule = relation.rule;
-    assert_eq!(rule.regex, Maybe::Present(",".to_string()));
+    assert_eq!(rule.regex, Maybe::Present(RegexRule::Simple(",".into())));
     assert!(rule.pattern.is_absent());
     Ok(())
   }
@@ -282,4 +347,25 @@ mod test {
     assert_eq!(edit.deleted_length, 7);
     Ok(())
   }
+
+  #[test]
+  fn test_fixer_transform() {
+    // RuleCore transforms the env when matching, and the fixer reads it
+    let core: SerializableRuleCore = from_str(
+      r"
+rule: {pattern: let $A = 123}
+transform:
+  B: {convert: {source: $A, toCase: upperCase}}
+fix: {template: 'const $B = 1'}",
+    )
+    .expect("should parse");
+    let rule = core
+      .get_matcher(DeserializeEnv::new(TypeScript::Tsx))
+      .expect("should work");
+    let grep = TypeScript::Tsx.ast_grep("let abc = 123");
+    let node = grep.root().find(&rule).expect("should found");
+    let fixer = rule.fixer.as_ref().expect("should have fixer");
+    let edit = fixer.generate_replacement(&node);
+    assert_eq!(String::from_utf8_lossy(&edit), "const ABC = 1");
+  }
 }
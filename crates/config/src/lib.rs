@@ -3,6 +3,7 @@ mod combined;
 mod fixer;
 mod maybe;
 mod rule;
+mod rule_builder;
 mod rule_collection;
 mod rule_config;
 mod rule_core;
@@ -12,12 +13,18 @@ use serde::Deserialize;
 use serde_yaml::{with::singleton_map_recursive::deserialize, Deserializer, Error as YamlError};
 
 use ast_grep_core::language::Language;
+use ast_grep_core::matcher::PatternCache;
+
+use std::sync::Arc;
 
 pub use combined::{CombinedScan, PreScan};
 pub use fixer::Fixer;
 pub use rule::referent_rule::GlobalRules;
 pub use rule::DeserializeEnv;
-pub use rule::{Rule, RuleSerializeError, SerializableRule};
+pub use rule::{
+  MatchExplanation, Relation, Rule, RuleSerializeError, SerializableRule, ValidationError,
+};
+pub use rule_builder::RuleBuilder;
 pub use rule_collection::RuleCollection;
 pub use rule_config::{RuleConfig, RuleConfigError, SerializableRuleConfig, Severity};
 pub use rule_core::{RuleCore, RuleCoreError, SerializableRuleCore};
@@ -31,10 +38,21 @@ pub fn from_str<'de, T: Deserialize<'de>>(s: &'de str) -> Result<T, YamlError> {
 pub fn from_yaml_string<'a, L: Language + Deserialize<'a>>(
   yamls: &'a str,
   registration: &GlobalRules<L>,
+) -> Result<Vec<RuleConfig<L>>, RuleConfigError> {
+  from_yaml_string_with_cache(yamls, registration, &Default::default())
+}
+
+/// Same as [`from_yaml_string`] but compiles patterns through `cache`.
+/// Share one cache among all rules of a [`RuleCollection`] so a pattern is parsed only once.
+pub fn from_yaml_string_with_cache<'a, L: Language + Deserialize<'a>>(
+  yamls: &'a str,
+  registration: &GlobalRules<L>,
+  cache: &Arc<PatternCache<L>>,
 ) -> Result<Vec<RuleConfig<L>>, RuleConfigError> {
   let mut ret = vec![];
   for yaml in Deserializer::from_str(yamls) {
-    let config = RuleConfig::deserialize(yaml, registration)?;
+    let inner = deserialize(yaml)?;
+    let config = RuleConfig::try_from_with_cache(inner, registration, cache)?;
     ret.push(config);
   }
   Ok(ret)
@@ -86,6 +104,24 @@ rule:
     )
   }
 
+  #[test]
+  fn test_share_pattern_cache() {
+    let globals = GlobalRules::default();
+    let cache = Arc::default();
+    let yaml = format!(
+      "{}---{}",
+      make_yaml("  pattern: a($A)"),
+      make_yaml("  pattern: a($A)")
+    );
+    let configs = from_yaml_string_with_cache::<TypeScript>(&yaml, &globals, &cache)
+      .expect("rule should parse");
+    assert_eq!(configs.len(), 2);
+    assert_eq!(cache.len(), 1);
+    let yaml = make_yaml("  any: [pattern: a($A), pattern: b($B)]");
+    from_yaml_string_with_cache::<TypeScript>(&yaml, &globals, &cache).expect("rule should parse");
+    assert_eq!(cache.len(), 2);
+  }
+
   #[test]
   fn test_deserialize_rule_config() {
     let yaml = &make_yaml(
@@ -16,6 +16,13 @@ impl<T> Maybe<T> {
   pub fn is_absent(&self) -> bool {
     matches!(self, Maybe::Absent)
   }
+  /// Returns self if present, otherwise `other`.
+  pub fn or(self, other: Maybe<T>) -> Maybe<T> {
+    match self {
+      Maybe::Present(_) => self,
+      Maybe::Absent => other,
+    }
+  }
   pub fn unwrap(self) -> T {
     match self {
       Maybe::Absent => panic!("called `Maybe::unwrap()` on an `Absent` value"),
@@ -0,0 +1,273 @@
+use super::Rule;
+use crate::rule::{FieldWriter, RuleSerializeError};
+
+use ast_grep_core::meta_var::MetaVarEnv;
+use ast_grep_core::{Doc, Language, Matcher, Node};
+
+use bit_set::BitSet;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Bounds on how many nodes can match the rest of the rule object.
+/// Under a relational rule, hits among the relatives are counted,
+/// e.g. `has` counts matching descendants of the target node.
+/// Elsewhere, hits in the target node, including itself, are counted.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CountConstraint {
+  /// Inclusive lower bound. Defaults to 0.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub min: Option<usize>,
+  /// Inclusive upper bound. Defaults to unbounded.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max: Option<usize>,
+}
+
+impl fmt::Display for CountConstraint {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut w = FieldWriter::new(f);
+    if let Some(min) = self.min {
+      w.field("min", min)?;
+    }
+    if let Some(max) = self.max {
+      w.field("max", max)?;
+    }
+    w.finish()
+  }
+}
+
+impl CountConstraint {
+  /// the lower bound and the optional upper bound
+  pub(crate) fn bounds(&self) -> Result<(usize, Option<usize>), CountError> {
+    let Self { min, max } = *self;
+    if min.is_none() && max.is_none() {
+      return Err(CountError::NoBound);
+    }
+    let min = min.unwrap_or(0);
+    if let Some(max) = max.filter(|max| min > *max) {
+      return Err(CountError::MinGreaterThanMax(min, max));
+    }
+    Ok((min, max))
+  }
+}
+
+#[derive(Debug, Error)]
+pub enum CountError {
+  #[error("`count` must specify `min` or `max`.")]
+  NoBound,
+  #[error("`count` min {0} is greater than max {1}.")]
+  MinGreaterThanMax(usize, usize),
+}
+
+/// Matches a node matching the inner rule if the number of its descendant-or-self nodes
+/// matching the inner rule is within the bounds, e.g. `if` statements nesting other `if`s.
+/// Relational rules count their relatives with `match_relatives` instead.
+pub struct Count<L: Language> {
+  inner: Rule<L>,
+  min: usize,
+  max: Option<usize>,
+}
+
+impl<L: Language> Count<L> {
+  pub fn try_new(inner: Rule<L>, count: CountConstraint) -> Result<Self, RuleSerializeError> {
+    let (min, max) = count.bounds()?;
+    Ok(Self { inner, min, max })
+  }
+
+  pub fn inner(&self) -> &Rule<L> {
+    &self.inner
+  }
+
+  pub(crate) fn to_serializable(&self) -> CountConstraint {
+    CountConstraint {
+      min: Some(self.min).filter(|m| *m > 0),
+      max: self.max,
+    }
+  }
+
+  pub fn defined_vars(&self) -> HashSet<&str> {
+    self.inner.defined_vars()
+  }
+
+  pub fn verify_util(&self) -> Result<(), RuleSerializeError> {
+    self.inner.verify_util()
+  }
+
+  fn in_range(&self, count: usize) -> bool {
+    count >= self.min && self.max.map_or(true, |max| count <= max)
+  }
+
+  /// Count the relatives visited by a relational rule that match the inner rule.
+  /// Returns the first hit, or the target if zero hits are allowed.
+  /// Each relative is matched independently and meta variables are captured from the first hit.
+  pub(crate) fn match_relatives<'tree, D: Doc<Lang = L>>(
+    &self,
+    target: Node<'tree, D>,
+    relatives: Vec<Node<'tree, D>>,
+    env: &mut Cow<MetaVarEnv<'tree, D>>,
+  ) -> Option<Node<'tree, D>> {
+    let mut first = None;
+    let mut count = 0usize;
+    for relative in relatives {
+      let mut new_env = Cow::Borrowed(env.as_ref());
+      let Some(hit) = self.inner.match_node_with_env(relative, &mut new_env) else {
+        continue;
+      };
+      count += 1;
+      // stop counting once max is exceeded
+      if self.max.map_or(false, |max| count > max) {
+        return None;
+      }
+      if first.is_none() {
+        first = Some((hit, new_env.into_owned()));
+      }
+    }
+    if !self.in_range(count) {
+      return None;
+    }
+    let Some((hit, new_env)) = first else {
+      return Some(target);
+    };
+    *env = Cow::Owned(new_env);
+    Some(hit)
+  }
+}
+
+impl<L: Language> Matcher<L> for Count<L> {
+  fn match_node_with_env<'tree, D: Doc<Lang = L>>(
+    &self,
+    node: Node<'tree, D>,
+    env: &mut Cow<MetaVarEnv<'tree, D>>,
+  ) -> Option<Node<'tree, D>> {
+    // only nodes matching the inner rule are counted, so the walk is limited to them
+    let node = self.inner.match_node_with_env(node, env)?;
+    // stop counting once max is exceeded
+    let limit = self.max.map_or(usize::MAX, |m| m.saturating_add(1));
+    let count = node.find_all(&self.inner).take(limit).count();
+    self.in_range(count).then_some(node)
+  }
+
+  fn potential_kinds(&self) -> Option<BitSet> {
+    self.inner.potential_kinds()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::from_str;
+  use crate::rule::{deserialize_rule, DeserializeEnv, SerializableRule};
+  use crate::test::TypeScript;
+
+  fn count_rule(src: &str) -> Rule<TypeScript> {
+    let rule: SerializableRule = from_str(src).expect("should parse");
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    deserialize_rule(rule, &env).expect("should deserialize")
+  }
+
+  fn count_matches(rule: &str, src: &str) -> Vec<String> {
+    let rule = count_rule(rule);
+    let grep = TypeScript::Tsx.ast_grep(src);
+    let found = grep.root().find_all(rule);
+    found.map(|n| n.text().to_string()).collect()
+  }
+
+  const NESTED_IFS: &str = "if (a) { if (b) { if (c) {} } }";
+
+  #[test]
+  fn test_exact_count() {
+    let rule = "{kind: if_statement, count: {min: 2, max: 2}}";
+    assert_eq!(count_matches(rule, NESTED_IFS), ["if (b) { if (c) {} }"]);
+    assert!(count_matches(rule, "if (a) {}").is_empty());
+  }
+
+  #[test]
+  fn test_open_bound() {
+    let at_least = "{kind: if_statement, count: {min: 3}}";
+    assert_eq!(count_matches(at_least, NESTED_IFS), [NESTED_IFS]);
+    // only nodes matching the inner rule are counted
+    let at_most = "{kind: if_statement, count: {max: 1}}";
+    assert_eq!(count_matches(at_most, NESTED_IFS), ["if (c) {}"]);
+    assert!(count_matches(at_most, "let a = 1").is_empty());
+  }
+
+  #[test]
+  fn test_count_relatives() {
+    let rule = r#"
+kind: function_declaration
+has:
+  kind: return_statement
+  stopBy: end
+  count: {min: 1, max: 1}"#;
+    let one = "function a() { if (b) { return 1 } }";
+    assert_eq!(count_matches(rule, one), [one]);
+    assert!(count_matches(rule, "function a() { return 1; return 2 }").is_empty());
+    assert!(count_matches(rule, "function a() {}").is_empty());
+    let none =
+      "{kind: function_declaration, has: {kind: return_statement, stopBy: end, count: {max: 0}}}";
+    assert_eq!(count_matches(none, "function a() {}"), ["function a() {}"]);
+    assert!(count_matches(none, "function a() { return }").is_empty());
+  }
+
+  #[test]
+  fn test_count_relatives_env() {
+    let rule = r#"
+kind: function_declaration
+has:
+  pattern: "console.log($A)"
+  stopBy: end
+  count: {min: 2}"#;
+    let rule = count_rule(rule);
+    let grep = TypeScript::Tsx.ast_grep("function a() { console.log(1); console.log(2) }");
+    let found = grep.root().find(rule).expect("should match");
+    let a = found.get_env().get_match("A").expect("should capture");
+    assert_eq!(a.text(), "1");
+  }
+
+  #[test]
+  fn test_potential_kinds() {
+    let rule = count_rule("{kind: if_statement, count: {max: 1}}");
+    let kinds = rule.potential_kinds().expect("should have kinds");
+    assert_eq!(kinds.len(), 1);
+  }
+
+  #[test]
+  fn test_invalid_count() {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let rule: SerializableRule = from_str("{kind: return_statement, count: {}}").expect("parse");
+    let ret = deserialize_rule(rule, &env);
+    assert!(matches!(
+      ret,
+      Err(RuleSerializeError::InvalidCount(CountError::NoBound))
+    ));
+    let rule: SerializableRule =
+      from_str("{kind: return_statement, count: {min: 2, max: 1}}").expect("parse");
+    let ret = deserialize_rule(rule, &env);
+    assert!(matches!(
+      ret,
+      Err(RuleSerializeError::InvalidCount(
+        CountError::MinGreaterThanMax(2, 1)
+      ))
+    ));
+    let rule: SerializableRule = from_str("{count: {min: 1}}").expect("parse");
+    let ret = deserialize_rule(rule, &env);
+    assert!(matches!(ret, Err(RuleSerializeError::MissPositiveMatcher)));
+  }
+
+  #[test]
+  fn test_round_trip() {
+    let src = "{kind: if_statement, count: {min: 1, max: 1}}";
+    let rule = count_rule(src);
+    let ser = rule.to_serializable(&TypeScript::Tsx);
+    let expected: SerializableRule = from_str(src).expect("should parse");
+    assert!(ser == expected);
+    let yaml = serde_yaml::to_string(&ser).expect("should serialize");
+    let back: SerializableRule = from_str(&yaml).expect("should parse");
+    assert!(back == expected);
+  }
+}
@@ -1,16 +1,18 @@
 use super::referent_rule::{GlobalRules, ReferentRuleError, RuleRegistration};
 use crate::check_var::CheckHint;
 use crate::maybe::Maybe;
-use crate::rule::{self, Rule, RuleSerializeError, SerializableRule};
+use crate::rule::{self, Rule, RuleSerializeError, SerializableRule, ValidationError};
 use crate::rule_core::{RuleCoreError, SerializableRuleCore};
 use crate::transform::Transformation;
 
 use ast_grep_core::language::Language;
+use ast_grep_core::matcher::PatternCache;
 
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub struct SerializableGlobalRule<L: Language> {
@@ -31,6 +33,36 @@ fn into_map<L: Language>(
     .collect()
 }
 
+/// Flatten `extends` chains among global rules so every rule carries its inherited fields.
+fn flatten_extends<L: Language>(
+  utils: &HashMap<String, (L, SerializableRuleCore)>,
+) -> Result<HashMap<String, (L, SerializableRuleCore)>, RuleSerializeError> {
+  let mut flattened = HashMap::new();
+  for (id, (lang, core)) in utils {
+    let mut chain = vec![core];
+    let mut seen = vec![id.as_str()];
+    let mut root = core;
+    while let Some(base_id) = &root.extends {
+      if seen.contains(&base_id.as_str()) {
+        return Err(RuleSerializeError::CircularExtends(base_id.clone()));
+      }
+      let Some((_, base)) = utils.get(base_id) else {
+        return Err(RuleSerializeError::UndefinedExtends(base_id.clone()));
+      };
+      seen.push(base_id);
+      chain.push(base);
+      root = base;
+    }
+    // overlay from the root base down to the rule itself
+    let mut resolved = chain.pop().expect("must have one rule").clone();
+    while let Some(child) = chain.pop() {
+      resolved = child.overlay(&resolved);
+    }
+    flattened.insert(id.clone(), (lang.clone(), resolved));
+  }
+  Ok(flattened)
+}
+
 type OrderResult<T> = Result<T, String>;
 
 /// A struct to store information to deserialize rules.
@@ -39,6 +71,8 @@ pub struct DeserializeEnv<L: Language> {
   pub(crate) registration: RuleRegistration<L>,
   /// current rules' language
   pub(crate) lang: L,
+  /// compiled patterns shared by rules deserialized from this env
+  pub(crate) pattern_cache: Arc<PatternCache<L>>,
 }
 
 trait DependentRule: Sized {
@@ -147,6 +181,15 @@ impl<L: Language> DeserializeEnv<L> {
     Self {
       registration: Default::default(),
       lang,
+      pattern_cache: Default::default(),
+    }
+  }
+
+  /// share compiled patterns with other envs using the same cache.
+  pub fn with_pattern_cache(self, pattern_cache: Arc<PatternCache<L>>) -> Self {
+    Self {
+      pattern_cache,
+      ..self
     }
   }
 
@@ -173,14 +216,15 @@ impl<L: Language> DeserializeEnv<L> {
     utils: Vec<SerializableGlobalRule<L>>,
   ) -> Result<GlobalRules<L>, RuleCoreError> {
     let registration = GlobalRules::default();
-    let utils = into_map(utils);
+    let utils = flatten_extends(&into_map(utils))?;
     let order = TopologicalSort::get_order(&utils)
       .map_err(ReferentRuleError::CyclicRule)
       .map_err(RuleSerializeError::from)?;
     for id in order {
       let (lang, core) = utils.get(id).expect("must exist");
       let env = DeserializeEnv::new(lang.clone()).with_globals(&registration);
-      let matcher = core.get_matcher_with_hint(env, CheckHint::Global)?;
+      let mut matcher = core.get_matcher_with_hint(env, CheckHint::Global)?;
+      matcher.source = Some(core.clone());
       registration
         .insert(id, matcher)
         .map_err(RuleSerializeError::MatchesReference)?;
@@ -188,11 +232,23 @@ impl<L: Language> DeserializeEnv<L> {
     Ok(registration)
   }
 
+  /// Validate the rule with all its sub-rules first, so every fatal problem is reported at once.
   pub fn deserialize_rule(
     &self,
     serialized: SerializableRule,
   ) -> Result<Rule<L>, RuleSerializeError> {
-    rule::deserialize_rule(serialized, self)
+    let mut errors = serialized.validate(self);
+    errors.retain(ValidationError::is_fatal);
+    if !errors.is_empty() {
+      return Err(RuleSerializeError::Invalid(errors));
+    }
+    let text = serialized.to_string();
+    rule::deserialize_rule(serialized, self).map_err(|e| match e {
+      RuleSerializeError::MissPositiveMatcher | RuleSerializeError::FieldNotSupported => {
+        RuleSerializeError::InvalidRule(text, Box::new(e))
+      }
+      e => e,
+    })
   }
 
   pub(crate) fn get_transform_order<'a>(
@@ -205,13 +261,13 @@ impl<L: Language> DeserializeEnv<L> {
   pub fn with_globals(self, globals: &GlobalRules<L>) -> Self {
     Self {
       registration: RuleRegistration::from_globals(globals),
-      lang: self.lang,
+      ..self
     }
   }
   pub fn with_rewriters(self, globals: &GlobalRules<L>) -> Self {
     Self {
       registration: self.registration.with_rewriters(globals),
-      lang: self.lang,
+      ..self
     }
   }
 }
@@ -324,4 +380,26 @@ local-rule-b:
     ));
     Ok(())
   }
+
+  #[test]
+  fn test_shared_pattern_cache() -> Result<()> {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let cache = env.pattern_cache.clone();
+    let rule = from_str(
+      "
+any:
+  - pattern: console.log($A)
+  - pattern: {context: console.log($A), strictness: cst}
+",
+    )?;
+    let rule = env.deserialize_rule(rule)?;
+    assert_eq!(cache.len(), 1);
+    let other = DeserializeEnv::new(TypeScript::Tsx).with_pattern_cache(cache.clone());
+    let relaxed = other.deserialize_rule(from_str("pattern: console.log($A)")?)?;
+    assert_eq!(cache.len(), 1);
+    let grep = TypeScript::Tsx.ast_grep("console.log(1)");
+    assert!(grep.root().find(&rule).is_some());
+    assert!(grep.root().find(&relaxed).is_some());
+    Ok(())
+  }
 }
@@ -0,0 +1,257 @@
+use super::Rule;
+
+use ast_grep_core::language::Language;
+use ast_grep_core::meta_var::MetaVarEnv;
+use ast_grep_core::{Doc, Matcher, Node};
+
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fmt;
+
+/// Why a rule does or does not match a node. Used to debug false negatives.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchExplanation {
+  Matched,
+  /// An atomic rule, or a rule that cannot be broken down further, did not match.
+  FailedAtomicPattern(String),
+  /// A meta variable and its captured text which failed the variable's constraint.
+  FailedConstraint(String, String),
+  /// No related node matched. Explains the inner rule against every candidate visited by `stopBy`,
+  /// nested directly if there is one candidate, or as a `FailedComposite` otherwise.
+  FailedRelational(String, Box<MatchExplanation>),
+  /// Failed sub-rules of `all`, every branch of `any`, or the matched rule of `not`.
+  FailedComposite(Vec<MatchExplanation>),
+}
+
+impl MatchExplanation {
+  pub fn is_matched(&self) -> bool {
+    matches!(self, MatchExplanation::Matched)
+  }
+
+  fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+    use MatchExplanation as E;
+    write!(f, "{:indent$}", "")?;
+    match self {
+      E::Matched => f.write_str("matched"),
+      E::FailedAtomicPattern(rule) => write!(f, "failed: {rule}"),
+      E::FailedConstraint(var, text) => write!(f, "failed constraint: ${var} = `{text}`"),
+      E::FailedRelational(relation, nested) => {
+        writeln!(f, "failed {relation}:")?;
+        nested.fmt_indented(f, indent + 2)
+      }
+      E::FailedComposite(explanations) if explanations.is_empty() => f.write_str("no candidate"),
+      E::FailedComposite(explanations) => {
+        f.write_str("failed:")?;
+        for explanation in explanations {
+          writeln!(f)?;
+          explanation.fmt_indented(f, indent + 2)?;
+        }
+        Ok(())
+      }
+    }
+  }
+}
+
+/// Human-readable report of the explanation, one sub-rule per line indented by nesting.
+impl fmt::Display for MatchExplanation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.fmt_indented(f, 0)
+  }
+}
+
+impl<L: Language> Rule<L> {
+  /// Explains why the rule does or does not match `node`.
+  pub fn explain_match<D: Doc<Lang = L>>(&self, node: Node<D>) -> MatchExplanation {
+    self.explain_with_env(node, &mut Cow::Owned(MetaVarEnv::new()))
+  }
+
+  pub(crate) fn explain_with_env<'tree, D: Doc<Lang = L>>(
+    &self,
+    node: Node<'tree, D>,
+    env: &mut Cow<MetaVarEnv<'tree, D>>,
+  ) -> MatchExplanation {
+    use MatchExplanation as E;
+    use Rule::*;
+    // trace sub-rules only on failure so the result always agrees with matching
+    let mut attempt = env.clone();
+    if self
+      .match_node_with_env(node.clone(), &mut attempt)
+      .is_some()
+    {
+      *env = attempt;
+      return E::Matched;
+    }
+    match self {
+      Inside(inside) => {
+        let candidates = inside.candidates(node);
+        explain_candidates("inside", &inside.outer, candidates, env)
+      }
+      Has(has) => explain_candidates("has", &has.inner, has.candidates(node), env),
+      Precedes(precedes) => {
+        let candidates = precedes.candidates(node);
+        explain_candidates("precedes", &precedes.later, candidates, env)
+      }
+      Follows(follows) => {
+        let candidates = follows.candidates(node);
+        explain_candidates("follows", &follows.former, candidates, env)
+      }
+      All(all) => {
+        let failed = all
+          .inner()
+          .iter()
+          .map(|rule| rule.explain_with_env(node.clone(), env))
+          .filter(|e| !e.is_matched())
+          .collect();
+        E::FailedComposite(failed)
+      }
+      Any(any) => {
+        let branches = any
+          .inner()
+          .iter()
+          .map(|rule| rule.explain_with_env(node.clone(), &mut env.clone()))
+          .collect();
+        E::FailedComposite(branches)
+      }
+      Not(not) => {
+        let matched = not.inner().explain_with_env(node, &mut env.clone());
+        E::FailedComposite(vec![matched])
+      }
+      _ => E::FailedAtomicPattern(self.to_serializable(node.lang()).to_string()),
+    }
+  }
+}
+
+impl<L: Language> Rule<L> {
+  /// Relational sub-rules of `all` and `any`, in rule order. Rules under `not` are skipped.
+  pub(crate) fn relational_rules(&self) -> Vec<&Self> {
+    match self {
+      Rule::Inside(_) | Rule::Has(_) | Rule::Precedes(_) | Rule::Follows(_) => vec![self],
+      Rule::All(all) => all
+        .inner()
+        .iter()
+        .flat_map(Rule::relational_rules)
+        .collect(),
+      Rule::Any(any) => any
+        .inner()
+        .iter()
+        .flat_map(Rule::relational_rules)
+        .collect(),
+      _ => vec![],
+    }
+  }
+}
+
+fn explain_candidates<'tree, L: Language, D: Doc<Lang = L>>(
+  relation: &str,
+  rule: &Rule<L>,
+  candidates: Vec<Node<'tree, D>>,
+  env: &mut Cow<MetaVarEnv<'tree, D>>,
+) -> MatchExplanation {
+  let mut explanations: Vec<_> = candidates
+    .into_iter()
+    .map(|node| rule.explain_with_env(node, &mut env.clone()))
+    .collect();
+  let nested = if explanations.len() == 1 {
+    explanations.remove(0)
+  } else {
+    MatchExplanation::FailedComposite(explanations)
+  };
+  MatchExplanation::FailedRelational(relation.to_string(), Box::new(nested))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::test::TypeScript;
+  use crate::{from_str, DeserializeEnv, SerializableRule};
+  use ast_grep_core::language::Language;
+  use MatchExplanation as E;
+
+  fn explain(rule: &str, src: &str) -> MatchExplanation {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let rule: SerializableRule = from_str(rule).expect("should parse");
+    let rule = env.deserialize_rule(rule).expect("should deserialize");
+    let grep = TypeScript::Tsx.ast_grep(src);
+    let node = grep.root().find("console.log($A)").expect("should find");
+    rule.explain_match(node.get_node().clone())
+  }
+
+  #[test]
+  fn test_explain_matched() {
+    let ret = explain("pattern: console.log($A)", "console.log(1)");
+    assert_eq!(ret, E::Matched);
+    let ret = explain(
+      "any: [{kind: number}, {kind: call_expression}]",
+      "console.log(1)",
+    );
+    assert_eq!(ret, E::Matched);
+  }
+
+  #[test]
+  fn test_explain_atomic() {
+    let ret = explain("regex: warn", "console.log(1)");
+    assert_eq!(ret, E::FailedAtomicPattern(r#"regex("warn")"#.into()));
+  }
+
+  #[test]
+  fn test_explain_composite() {
+    let rule = "all: [{kind: call_expression}, {regex: warn}, {has: {kind: identifier}}]";
+    let ret = explain(rule, "console.log(1)");
+    let E::FailedComposite(failed) = ret else {
+      panic!("should fail composite");
+    };
+    assert_eq!(failed.len(), 2);
+    assert!(matches!(failed[0], E::FailedAtomicPattern(_)));
+    let E::FailedRelational(relation, nested) = &failed[1] else {
+      panic!("should fail relational");
+    };
+    assert_eq!(relation, "has");
+    // both children of the call are explained, not only the first one
+    let E::FailedComposite(children) = &**nested else {
+      panic!("should explain every child");
+    };
+    assert_eq!(children.len(), 2);
+    assert!(matches!(children[0], E::FailedAtomicPattern(_)));
+    let ret = explain("not: {kind: call_expression}", "console.log(1)");
+    assert_eq!(ret, E::FailedComposite(vec![E::Matched]));
+  }
+
+  #[test]
+  fn test_explain_relational() {
+    let ret = explain("inside: {kind: function_declaration}", "console.log(1)");
+    let E::FailedRelational(relation, nested) = ret else {
+      panic!("should fail relational");
+    };
+    assert_eq!(relation, "inside");
+    assert!(matches!(*nested, E::FailedAtomicPattern(_)));
+    let ret = explain("follows: {kind: number}", "console.log(1)");
+    let expected = E::FailedRelational("follows".into(), Box::new(E::FailedComposite(vec![])));
+    assert_eq!(ret, expected);
+  }
+
+  #[test]
+  fn test_explain_stop_by_candidates() {
+    let ret = explain("has: {kind: string, stopBy: end}", "console.log(1)");
+    let E::FailedRelational(_, nested) = ret else {
+      panic!("should fail relational");
+    };
+    let E::FailedComposite(descendants) = *nested else {
+      panic!("should explain every descendant");
+    };
+    // member_expression, console, ., log, arguments, (, 1, )
+    assert_eq!(descendants.len(), 8);
+    let ret = explain(
+      "has: {kind: number, stopBy: {kind: arguments}}",
+      "console.log(foo(1))",
+    );
+    let E::FailedRelational(_, nested) = ret else {
+      panic!("should fail relational");
+    };
+    // stops at the outer arguments without visiting `foo(1)`
+    let E::FailedComposite(visited) = *nested else {
+      panic!("should explain visited nodes");
+    };
+    assert_eq!(visited.len(), 5);
+  }
+}
@@ -1,19 +1,25 @@
+mod count;
 mod deserialize_env;
+mod explain;
 mod nth_child;
 mod range;
 pub mod referent_rule;
 mod relational_rule;
 mod stop_by;
 
+pub use count::CountConstraint;
 pub use deserialize_env::DeserializeEnv;
+pub use explain::MatchExplanation;
 pub use relational_rule::Relation;
 pub use stop_by::StopBy;
 
 use crate::maybe::Maybe;
+use count::{Count, CountError};
 use nth_child::{NthChild, NthChildError, SerializableNthChild};
 use range::{RangeMatcher, RangeMatcherError, SerializableRange};
 use referent_rule::{ReferentRule, ReferentRuleError};
 use relational_rule::{Follows, Has, Inside, Precedes};
+use stop_by::SerializableStopBy;
 
 use ast_grep_core::language::Language;
 use ast_grep_core::matcher::{KindMatcher, KindMatcherError, RegexMatcher, RegexMatcherError};
@@ -26,6 +32,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::HashSet;
+use std::fmt;
 use thiserror::Error;
 
 /// A rule object to find matching AST nodes. We have three categories of rules in ast-grep.
@@ -37,7 +44,7 @@ use thiserror::Error;
 /// * Composite: use logic operation all/any/not to compose the above rules to larger rules.
 ///
 /// Every rule has it's unique name so we can combine several rules in one object.
-#[derive(Serialize, Deserialize, Clone, Default, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct SerializableRule {
   // avoid embedding AtomicRule/RelationalRule/CompositeRule with flatten here for better error message
@@ -50,8 +57,9 @@ pub struct SerializableRule {
   #[serde(default, skip_serializing_if = "Maybe::is_absent")]
   pub kind: Maybe<String>,
   /// A Rust regular expression to match the node's text. https://docs.rs/regex/latest/regex/#syntax
+  /// Or an object with field `pattern` and `flags`.
   #[serde(default, skip_serializing_if = "Maybe::is_absent")]
-  pub regex: Maybe<String>,
+  pub regex: Maybe<RegexRule>,
   /// `nth_child` accepts number, string or object.
   /// It specifies the position in nodes' sibling list.
   #[serde(default, skip_serializing_if = "Maybe::is_absent", rename = "nthChild")]
@@ -93,15 +101,45 @@ pub struct SerializableRule {
   /// A utility rule id and matches a node if the utility rule matches.
   #[serde(default, skip_serializing_if = "Maybe::is_absent")]
   pub matches: Maybe<String>,
+
+  // cardinality
+  /// `count` accepts an object with optional `min` and `max`.
+  /// The target node must match the other fields of this rule and contain (itself included)
+  /// between `min` and `max` nodes matching them.
+  /// Inside a relational rule, it bounds how many relatives match instead,
+  /// e.g. `has: {kind: return_statement, stopBy: end, count: {max: 1}}`.
+  #[serde(default, skip_serializing_if = "Maybe::is_absent")]
+  pub count: Maybe<CountConstraint>,
 }
 
 struct Categorized {
   pub atomic: AtomicRule,
   pub relational: RelationalRule,
   pub composite: CompositeRule,
+  pub count: Option<CountConstraint>,
 }
 
 impl SerializableRule {
+  /// Use fields present in `self` and take absent ones from `base`.
+  pub(crate) fn overlay(self, base: Self) -> Self {
+    Self {
+      pattern: self.pattern.or(base.pattern),
+      kind: self.kind.or(base.kind),
+      regex: self.regex.or(base.regex),
+      nth_child: self.nth_child.or(base.nth_child),
+      range: self.range.or(base.range),
+      inside: self.inside.or(base.inside),
+      has: self.has.or(base.has),
+      precedes: self.precedes.or(base.precedes),
+      follows: self.follows.or(base.follows),
+      all: self.all.or(base.all),
+      any: self.any.or(base.any),
+      not: self.not.or(base.not),
+      matches: self.matches.or(base.matches),
+      count: self.count.or(base.count),
+    }
+  }
+
   fn categorized(self) -> Categorized {
     Categorized {
       atomic: AtomicRule {
@@ -123,18 +161,113 @@ impl SerializableRule {
         not: self.not.into(),
         matches: self.matches.into(),
       },
+      count: self.count.into(),
+    }
+  }
+}
+
+/// Writes present fields as `name(value)`, separated by spaces.
+pub(crate) struct FieldWriter<'a, 'b> {
+  f: &'a mut fmt::Formatter<'b>,
+  empty: bool,
+}
+
+impl<'a, 'b> FieldWriter<'a, 'b> {
+  pub fn new(f: &'a mut fmt::Formatter<'b>) -> Self {
+    Self { f, empty: true }
+  }
+
+  /// Append fields after text already written to `f`.
+  pub fn continued(f: &'a mut fmt::Formatter<'b>) -> Self {
+    Self { f, empty: false }
+  }
+
+  pub fn field(&mut self, name: &str, value: impl fmt::Display) -> fmt::Result {
+    if !self.empty {
+      self.f.write_str(" ")?;
+    }
+    self.empty = false;
+    write!(self.f, "{name}({value})")
+  }
+
+  pub fn maybe<T: fmt::Display>(&mut self, name: &str, value: &Maybe<T>) -> fmt::Result {
+    match value {
+      Maybe::Present(v) => self.field(name, v),
+      Maybe::Absent => Ok(()),
+    }
+  }
+
+  pub fn flag(&mut self, name: &str) -> fmt::Result {
+    if !self.empty {
+      self.f.write_str(" ")?;
+    }
+    self.empty = false;
+    self.f.write_str(name)
+  }
+
+  /// Write `{}` if no field is present, like an empty YAML mapping.
+  pub fn finish(self) -> fmt::Result {
+    if self.empty {
+      self.f.write_str("{}")?;
+    }
+    Ok(())
+  }
+}
+
+/// Comma separated sub-rules of `all` and `any`.
+struct RuleList<'a>(&'a [SerializableRule]);
+
+impl fmt::Display for RuleList<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (i, rule) in self.0.iter().enumerate() {
+      if i > 0 {
+        f.write_str(", ")?;
+      }
+      write!(f, "{rule}")?;
+    }
+    Ok(())
+  }
+}
+
+/// A compact one-liner like `pattern("foo($A)") inside(kind("function_declaration"))`,
+/// used in error messages where a YAML dump would be too verbose.
+impl fmt::Display for SerializableRule {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut w = FieldWriter::new(f);
+    w.maybe("pattern", &self.pattern)?;
+    if let Maybe::Present(kind) = &self.kind {
+      w.field("kind", format_args!("{kind:?}"))?;
     }
+    w.maybe("regex", &self.regex)?;
+    w.maybe("nthChild", &self.nth_child)?;
+    w.maybe("range", &self.range)?;
+    w.maybe("inside", &self.inside)?;
+    w.maybe("has", &self.has)?;
+    w.maybe("precedes", &self.precedes)?;
+    w.maybe("follows", &self.follows)?;
+    if let Maybe::Present(all) = &self.all {
+      w.field("all", RuleList(all))?;
+    }
+    if let Maybe::Present(any) = &self.any {
+      w.field("any", RuleList(any))?;
+    }
+    w.maybe("not", &self.not)?;
+    if let Maybe::Present(matches) = &self.matches {
+      w.field("matches", format_args!("{matches:?}"))?;
+    }
+    w.maybe("count", &self.count)?;
+    w.finish()
   }
 }
 
 pub struct AtomicRule {
   pub pattern: Option<PatternStyle>,
   pub kind: Option<String>,
-  pub regex: Option<String>,
+  pub regex: Option<RegexRule>,
   pub nth_child: Option<SerializableNthChild>,
   pub range: Option<SerializableRange>,
 }
-#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum Strictness {
   /// all nodes are matched
@@ -179,7 +312,7 @@ impl From<Strictness> for MatchStrictness {
 
 /// A String pattern will match one single AST node according to pattern syntax.
 /// Or an object with field `context`, `selector` and optionally `strictness`.
-#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 #[serde(untagged)]
 pub enum PatternStyle {
   Str(String),
@@ -193,6 +326,99 @@ pub enum PatternStyle {
   },
 }
 
+/// A regex string, or an object with the regex `pattern` and its `flags`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum RegexRule {
+  Simple(String),
+  Flags {
+    /// A Rust regular expression.
+    pattern: String,
+    /// Any of `i` (case-insensitive), `m` (multi-line) and `s` (`.` matches new line).
+    flags: String,
+  },
+}
+
+impl RegexRule {
+  pub fn pattern(&self) -> &str {
+    match self {
+      RegexRule::Simple(pattern) => pattern,
+      RegexRule::Flags { pattern, .. } => pattern,
+    }
+  }
+
+  pub fn flags(&self) -> &str {
+    match self {
+      RegexRule::Simple(_) => "",
+      RegexRule::Flags { flags, .. } => flags,
+    }
+  }
+
+  fn to_matcher<L: Language>(&self) -> Result<RegexMatcher<L>, RegexMatcherError> {
+    RegexMatcher::try_new_with_flags(self.pattern(), self.flags())
+  }
+
+  fn from_matcher<L: Language>(matcher: &RegexMatcher<L>) -> Self {
+    let pattern = matcher.as_str().to_string();
+    if matcher.flags().is_empty() {
+      RegexRule::Simple(pattern)
+    } else {
+      let flags = matcher.flags().to_string();
+      RegexRule::Flags { pattern, flags }
+    }
+  }
+}
+
+impl fmt::Display for RegexRule {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      RegexRule::Simple(pattern) => write!(f, "{pattern:?}"),
+      RegexRule::Flags { pattern, flags } => {
+        let mut w = FieldWriter::new(f);
+        w.field("pattern", format_args!("{pattern:?}"))?;
+        w.field("flags", flags)?;
+        w.finish()
+      }
+    }
+  }
+}
+
+impl fmt::Display for Strictness {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let name = match self {
+      Strictness::Cst => "cst",
+      Strictness::Smart => "smart",
+      Strictness::Ast => "ast",
+      Strictness::Relaxed => "relaxed",
+      Strictness::Signature => "signature",
+    };
+    f.write_str(name)
+  }
+}
+
+impl fmt::Display for PatternStyle {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      PatternStyle::Str(pattern) => write!(f, "{pattern:?}"),
+      PatternStyle::Contextual {
+        context,
+        selector,
+        strictness,
+      } => {
+        let mut w = FieldWriter::new(f);
+        w.field("context", format_args!("{context:?}"))?;
+        if let Some(selector) = selector {
+          w.field("selector", format_args!("{selector:?}"))?;
+        }
+        if let Some(strictness) = strictness {
+          w.field("strictness", strictness)?;
+        }
+        w.finish()
+      }
+    }
+  }
+}
+
 pub struct RelationalRule {
   pub inside: Option<Box<Relation>>,
   pub has: Option<Box<Relation>>,
@@ -224,6 +450,8 @@ pub enum Rule<L: Language> {
   Any(o::Any<L, Rule<L>>),
   Not(Box<o::Not<L, Rule<L>>>),
   Matches(ReferentRule<L>),
+  // cardinality
+  Count(Box<Count<L>>),
 }
 impl<L: Language> Rule<L> {
   pub fn is_atomic(&self) -> bool {
@@ -243,6 +471,93 @@ impl<L: Language> Rule<L> {
     matches!(self, All(_) | Any(_) | Not(_) | Matches(_))
   }
 
+  /// Returns the reason if the rule trivially matches every node, e.g. `all: []`.
+  /// The analysis is conservative: None does not mean the rule can fail.
+  pub fn is_always_true(&self) -> Option<&'static str> {
+    match self {
+      Rule::All(all) if all.inner().is_empty() => Some("empty `all` matches every node"),
+      Rule::All(all) => {
+        let mut reason = None;
+        for rule in all.inner() {
+          reason = Some(rule.is_always_true()?);
+        }
+        reason
+      }
+      Rule::Any(any) => any.inner().iter().find_map(|r| r.is_always_true()),
+      Rule::Not(not) => not
+        .inner()
+        .is_always_false()
+        .map(|_| "`not` negates a rule that never matches"),
+      _ => None,
+    }
+  }
+
+  /// Returns the reason if the rule can never match any node, e.g. `any: []`.
+  /// The analysis is conservative: None does not mean the rule can match.
+  pub fn is_always_false(&self) -> Option<&'static str> {
+    match self {
+      Rule::Any(any) if any.inner().is_empty() => Some("empty `any` matches no node"),
+      Rule::Any(any) => {
+        let mut reason = None;
+        for rule in any.inner() {
+          reason = Some(rule.is_always_false()?);
+        }
+        reason
+      }
+      Rule::All(all) => {
+        if let Some(reason) = all.inner().iter().find_map(|r| r.is_always_false()) {
+          return Some(reason);
+        }
+        let kinds = all.potential_kinds()?;
+        kinds
+          .is_empty()
+          .then_some("sub-rules in `all` require different node kinds")
+      }
+      Rule::Not(not) => not
+        .inner()
+        .is_always_true()
+        .map(|_| "`not` negates a rule that matches every node"),
+      _ => None,
+    }
+  }
+
+  /// Convert the compiled rule back to its serializable form.
+  /// Patterns keep their source text. The round trip is lossy:
+  /// a rule with multiple fields comes back as an `all` of single-field rules.
+  pub fn to_serializable(&self, lang: &L) -> SerializableRule {
+    let mut ret = SerializableRule::default();
+    match self {
+      Rule::Pattern(p) => ret.pattern = Maybe::Present(pattern_to_style(p)),
+      Rule::Kind(k) => {
+        let ts_lang = lang.get_ts_language();
+        let kind = ts_lang.node_kind_for_id(k.kind_id()).unwrap_or_default();
+        ret.kind = Maybe::Present(kind.into_owned());
+      }
+      Rule::Regex(r) => ret.regex = Maybe::Present(RegexRule::from_matcher(r)),
+      Rule::NthChild(n) => ret.nth_child = Maybe::Present(n.to_serializable(lang)),
+      Rule::Range(r) => ret.range = Maybe::Present(r.to_serializable()),
+      Rule::Inside(i) => ret.inside = Maybe::Present(Box::new(i.to_relation(lang))),
+      Rule::Has(h) => ret.has = Maybe::Present(Box::new(h.to_relation(lang))),
+      Rule::Precedes(p) => ret.precedes = Maybe::Present(Box::new(p.to_relation(lang))),
+      Rule::Follows(f) => ret.follows = Maybe::Present(Box::new(f.to_relation(lang))),
+      Rule::All(all) => {
+        let rules = all.inner().iter().map(|r| r.to_serializable(lang));
+        ret.all = Maybe::Present(rules.collect());
+      }
+      Rule::Any(any) => {
+        let rules = any.inner().iter().map(|r| r.to_serializable(lang));
+        ret.any = Maybe::Present(rules.collect());
+      }
+      Rule::Not(not) => ret.not = Maybe::Present(Box::new(not.inner().to_serializable(lang))),
+      Rule::Matches(m) => ret.matches = Maybe::Present(m.rule_id.clone()),
+      Rule::Count(c) => {
+        ret = c.inner().to_serializable(lang);
+        ret.count = Maybe::Present(c.to_serializable());
+      }
+    }
+    ret
+  }
+
   /// Check if it has a cyclic referent rule with the id.
   pub(crate) fn check_cyclic(&self, id: &str) -> bool {
     match self {
@@ -250,6 +565,7 @@ impl<L: Language> Rule<L> {
       Rule::Any(any) => any.inner().iter().any(|r| r.check_cyclic(id)),
       Rule::Not(not) => not.inner().check_cyclic(id),
       Rule::Matches(m) => m.rule_id == id,
+      Rule::Count(c) => c.inner().check_cyclic(id),
       rule => {
         debug_assert!(!rule.is_composite());
         false
@@ -273,6 +589,7 @@ impl<L: Language> Rule<L> {
       Rule::Not(sub) => sub.inner().defined_vars(),
       // TODO: this is not correct, we are collecting util vars else where
       Rule::Matches(_r) => HashSet::new(),
+      Rule::Count(c) => c.defined_vars(),
     }
   }
 
@@ -292,6 +609,51 @@ impl<L: Language> Rule<L> {
       Rule::Any(sub) => sub.inner().iter().try_for_each(|r| r.verify_util()),
       Rule::Not(sub) => sub.inner().verify_util(),
       Rule::Matches(r) => Ok(r.verify_util()?),
+      Rule::Count(c) => c.verify_util(),
+    }
+  }
+}
+
+/// Mirrors the `Display` of `SerializableRule`.
+/// Kinds not created from a name, e.g. by `KindMatcher::from_id`, are shown by id.
+impl<L: Language> fmt::Debug for Rule<L> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let list = |f: &mut fmt::Formatter<'_>, rules: &[Rule<L>]| {
+      for (i, rule) in rules.iter().enumerate() {
+        if i > 0 {
+          f.write_str(", ")?;
+        }
+        write!(f, "{rule:?}")?;
+      }
+      Ok(())
+    };
+    match self {
+      Rule::Pattern(p) => write!(f, "pattern({})", pattern_to_style(p)),
+      Rule::Kind(k) => match k.kind_name() {
+        Some(name) => write!(f, "kind({name:?})"),
+        None => write!(f, "kind(#{})", k.kind_id()),
+      },
+      Rule::Regex(r) if r.flags().is_empty() => write!(f, "regex({:?})", r.as_str()),
+      Rule::Regex(r) => write!(f, "regex({:?}, {})", r.as_str(), r.flags()),
+      Rule::NthChild(_) => write!(f, "nthChild(..)"),
+      Rule::Range(r) => write!(f, "range({})", r.to_serializable()),
+      Rule::Inside(i) => write!(f, "inside({i:?})"),
+      Rule::Has(h) => write!(f, "has({h:?})"),
+      Rule::Precedes(p) => write!(f, "precedes({p:?})"),
+      Rule::Follows(p) => write!(f, "follows({p:?})"),
+      Rule::All(all) => {
+        f.write_str("all(")?;
+        list(f, all.inner())?;
+        f.write_str(")")
+      }
+      Rule::Any(any) => {
+        f.write_str("any(")?;
+        list(f, any.inner())?;
+        f.write_str(")")
+      }
+      Rule::Not(not) => write!(f, "not({:?})", not.inner()),
+      Rule::Matches(m) => write!(f, "matches({:?})", m.rule_id),
+      Rule::Count(c) => write!(f, "{:?} count({})", c.inner(), c.to_serializable()),
     }
   }
 }
@@ -320,6 +682,8 @@ impl<L: Language> Matcher<L> for Rule<L> {
       Any(any) => any.match_node_with_env(node, env),
       Not(not) => not.match_node_with_env(node, env),
       Matches(rule) => rule.match_node_with_env(node, env),
+      // cardinality
+      Count(count) => count.match_node_with_env(node, env),
     }
   }
 
@@ -342,6 +706,8 @@ impl<L: Language> Matcher<L> for Rule<L> {
       Any(any) => any.potential_kinds(),
       Not(not) => not.potential_kinds(),
       Matches(rule) => rule.potential_kinds(),
+      // cardinality
+      Count(count) => count.potential_kinds(),
     }
   }
 }
@@ -354,6 +720,22 @@ impl<L: Language> Default for Rule<L> {
   }
 }
 
+fn pattern_to_style<L: Language>(pattern: &Pattern<L>) -> PatternStyle {
+  let src = pattern.source().to_string();
+  let selector = pattern.selector().map(String::from);
+  let strictness = Strictness::from(pattern.strictness.clone());
+  let strictness = (!matches!(strictness, Strictness::Smart)).then_some(strictness);
+  if selector.is_none() && strictness.is_none() {
+    PatternStyle::Str(src)
+  } else {
+    PatternStyle::Contextual {
+      context: src,
+      selector,
+      strictness,
+    }
+  }
+}
+
 fn match_and_add_label<'tree, D: Doc, M: Matcher<D::Lang>>(
   inner: &M,
   node: Node<'tree, D>,
@@ -364,6 +746,101 @@ fn match_and_add_label<'tree, D: Doc, M: Matcher<D::Lang>>(
   Some(matched)
 }
 
+/// Problems found by [`SerializableRule::validate`] without compiling the rule.
+#[derive(Debug, Error)]
+pub enum ValidationError {
+  #[error("`{0}` is not a valid kind name.")]
+  UnknownKind(String),
+  #[error("Rule contains invalid regex `{0}`.")]
+  InvalidRegex(String, #[source] RegexMatcherError),
+  #[error("`{0}` has no sub-rule.")]
+  EmptyComposite(&'static str),
+  #[error("`matches` refers to undefined rule `{0}`.")]
+  MissingMatchesTarget(String),
+  #[error(transparent)]
+  InvalidCount(CountError),
+}
+
+impl ValidationError {
+  /// Whether the rule cannot be compiled with this problem.
+  /// Empty composites are still valid, e.g. `any: []` never matches,
+  /// and `matches` targets can be registered later, e.g. by mutually recursive utils.
+  pub fn is_fatal(&self) -> bool {
+    !matches!(
+      self,
+      Self::EmptyComposite(_) | Self::MissingMatchesTarget(_)
+    )
+  }
+}
+
+impl SerializableRule {
+  /// Check kinds, regexes, composite rules and `matches` references in the rule and
+  /// all its sub-rules. Unlike deserialization, all problems are collected.
+  /// `matches` targets are looked up in the utils registered in `env`.
+  pub fn validate<L: Language>(&self, env: &DeserializeEnv<L>) -> Vec<ValidationError> {
+    let mut errors = vec![];
+    self.collect_errors(env, &mut errors);
+    errors
+  }
+
+  fn collect_errors<L: Language>(
+    &self,
+    env: &DeserializeEnv<L>,
+    errors: &mut Vec<ValidationError>,
+  ) {
+    use ValidationError as E;
+    if let Maybe::Present(kind) = &self.kind {
+      if KindMatcher::try_new(kind, env.lang.clone()).is_err() {
+        errors.push(E::UnknownKind(kind.clone()));
+      }
+    }
+    if let Maybe::Present(regex) = &self.regex {
+      if let Err(e) = regex.to_matcher::<L>() {
+        errors.push(E::InvalidRegex(regex.pattern().to_string(), e));
+      }
+    }
+    if let Maybe::Present(count) = &self.count {
+      if let Err(e) = count.bounds() {
+        errors.push(E::InvalidCount(e));
+      }
+    }
+    if let Maybe::Present(SerializableNthChild::Complex {
+      of_rule: Some(rule),
+      ..
+    }) = &self.nth_child
+    {
+      rule.collect_errors(env, errors);
+    }
+    let relations = [&self.inside, &self.has, &self.precedes, &self.follows];
+    for relation in relations {
+      if let Maybe::Present(relation) = relation {
+        relation.rule.collect_errors(env, errors);
+        if let SerializableStopBy::Rule(rule) = &relation.stop_by {
+          rule.collect_errors(env, errors);
+        }
+      }
+    }
+    for (name, rules) in [("all", &self.all), ("any", &self.any)] {
+      if let Maybe::Present(rules) = rules {
+        if rules.is_empty() {
+          errors.push(E::EmptyComposite(name));
+        }
+        for rule in rules {
+          rule.collect_errors(env, errors);
+        }
+      }
+    }
+    if let Maybe::Present(not) = &self.not {
+      not.collect_errors(env, errors);
+    }
+    if let Maybe::Present(id) = &self.matches {
+      if !env.registration.contains_rule(id) {
+        errors.push(E::MissingMatchesTarget(id.clone()));
+      }
+    }
+  }
+}
+
 #[derive(Debug, Error)]
 pub enum RuleSerializeError {
   #[error("Rule must have one positive matcher.")]
@@ -384,6 +861,24 @@ pub enum RuleSerializeError {
   FieldNotSupported,
   #[error("Relational rule contains invalid field {0}.")]
   InvalidField(String),
+  #[error("Rule contains invalid count.")]
+  InvalidCount(#[from] CountError),
+  #[error("Rule `{0}` has a circular `extends` chain.")]
+  CircularExtends(String),
+  #[error("`extends` refers to undefined rule `{0}`.")]
+  UndefinedExtends(String),
+  #[error("Relational rule cannot use `{0}` together with `{1}`.")]
+  ConflictingRelationOptions(String, String),
+  #[error("Rule is invalid: {}", join_errors(.0))]
+  Invalid(Vec<ValidationError>),
+  /// Adds the rule text to errors that do not say where they occur.
+  #[error("Rule `{0}` is invalid.")]
+  InvalidRule(String, #[source] Box<RuleSerializeError>),
+}
+
+fn join_errors(errors: &[ValidationError]) -> String {
+  let messages: Vec<_> = errors.iter().map(ToString::to_string).collect();
+  messages.join(" ")
 }
 
 // TODO: implement positive/non positive
@@ -400,12 +895,17 @@ pub fn deserialize_rule<L: Language>(
   deserialze_composite_rule(categorized.composite, &mut rules, env)?;
   deserialize_relational_rule(categorized.relational, &mut rules, env)?;
 
-  if rules.is_empty() {
-    Err(RuleSerializeError::MissPositiveMatcher)
+  let rule = if rules.is_empty() {
+    return Err(RuleSerializeError::MissPositiveMatcher);
   } else if rules.len() == 1 {
-    Ok(rules.pop().expect("should not be empty"))
+    rules.pop().expect("should not be empty")
   } else {
-    Ok(R::All(o::All::new(rules)))
+    R::All(o::All::new(rules))
+  };
+  // count applies to the rule built from all other fields
+  match categorized.count {
+    Some(count) => Ok(R::Count(Box::new(Count::try_new(rule, count)?))),
+    None => Ok(rule),
   }
 }
 
@@ -469,16 +969,20 @@ fn deserialze_atomic_rule<L: Language>(
   use Rule as R;
   if let Some(pattern) = atomic.pattern {
     rules.push(match pattern {
-      PatternStyle::Str(pat) => R::Pattern(Pattern::try_new(&pat, env.lang.clone())?),
+      PatternStyle::Str(pat) => R::Pattern(Pattern::try_new_cached(
+        &pat,
+        env.lang.clone(),
+        &env.pattern_cache,
+      )?),
       PatternStyle::Contextual {
         context,
         selector,
         strictness,
       } => {
         let pattern = if let Some(selector) = selector {
-          Pattern::contextual(&context, &selector, env.lang.clone())?
+          Pattern::contextual_cached(&context, &selector, env.lang.clone(), &env.pattern_cache)?
         } else {
-          Pattern::try_new(&context, env.lang.clone())?
+          Pattern::try_new_cached(&context, env.lang.clone(), &env.pattern_cache)?
         };
         let pattern = if let Some(strictness) = strictness {
           pattern.with_strictness(strictness.into())
@@ -493,7 +997,7 @@ fn deserialze_atomic_rule<L: Language>(
     rules.push(R::Kind(KindMatcher::try_new(&kind, env.lang.clone())?));
   }
   if let Some(regex) = atomic.regex {
-    rules.push(R::Regex(RegexMatcher::try_new(&regex)?));
+    rules.push(R::Regex(regex.to_matcher()?));
   }
   if let Some(nth_child) = atomic.nth_child {
     rules.push(R::NthChild(NthChild::try_new(nth_child, env)?));
@@ -556,6 +1060,27 @@ has:
     assert!(rule.pattern.is_present());
   }
 
+  #[test]
+  fn test_rule_eq() {
+    let src1 = r"
+pattern: class A {}
+inside: {kind: class_body, stopBy: end}
+nthChild: 1
+";
+    let src2 = r"
+nthChild: 1
+inside:
+  stopBy: end
+  kind: class_body
+pattern: 'class A {}'
+";
+    let rule1: SerializableRule = from_str(src1).expect("cannot parse rule");
+    let rule2: SerializableRule = from_str(src2).expect("cannot parse rule");
+    assert!(rule1 == rule2);
+    let rule3: SerializableRule = from_str("pattern: class A {}").expect("cannot parse rule");
+    assert!(rule1 != rule3);
+  }
+
   #[test]
   fn test_maybe_not() {
     let src = "not: 123";
@@ -650,6 +1175,40 @@ inside:
     assert_eq!(rule.defined_vars(), ["A", "B"].into_iter().collect());
   }
 
+  fn deserialize_str(src: &str) -> Rule<TypeScript> {
+    let rule: SerializableRule = from_str(src).expect("cannot parse rule");
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    deserialize_rule(rule, &env).expect("should deserialize")
+  }
+
+  #[test]
+  fn test_always_true() {
+    assert!(deserialize_str("all: []").is_always_true().is_some());
+    assert!(deserialize_str("not: {any: []}").is_always_true().is_some());
+    assert!(deserialize_str("any: [{kind: number}, {all: []}]")
+      .is_always_true()
+      .is_some());
+    assert!(deserialize_str("all: [{kind: number}, {all: []}]")
+      .is_always_true()
+      .is_none());
+    assert!(deserialize_str("kind: number").is_always_true().is_none());
+  }
+
+  #[test]
+  fn test_always_false() {
+    assert!(deserialize_str("any: []").is_always_false().is_some());
+    assert!(deserialize_str("not: {all: []}")
+      .is_always_false()
+      .is_some());
+    let src = "all: [{kind: identifier}, {kind: string}]";
+    assert!(deserialize_str(src).is_always_false().is_some());
+    let src = "all: [{kind: identifier}, {regex: a}]";
+    assert!(deserialize_str(src).is_always_false().is_none());
+    let src = "any: [{kind: identifier}, {any: []}]";
+    assert!(deserialize_str(src).is_always_false().is_none());
+    assert!(deserialize_str("kind: number").is_always_false().is_none());
+  }
+
   #[test]
   fn test_issue_1164() {
     let src = r"
@@ -687,4 +1246,252 @@ inside:
     );
     assert!(root.root().find(rule).is_some());
   }
+
+  #[test]
+  fn test_to_serializable() {
+    let lang = TypeScript::Tsx;
+    let rule = deserialize_str("pattern: console.log($A)").to_serializable(&lang);
+    assert!(matches!(&rule.pattern, Maybe::Present(Str(s)) if s == "console.log($A)"));
+    let rule = deserialize_str("pattern: foo('x', \"a  b\")").to_serializable(&lang);
+    assert!(matches!(&rule.pattern, Maybe::Present(Str(s)) if s == "foo('x', \"a  b\")"));
+    let rule = deserialize_str("kind: number").to_serializable(&lang);
+    assert!(matches!(&rule.kind, Maybe::Present(k) if k == "number"));
+    let src = r"
+all:
+  - regex: ^a
+  - nthChild: 2n+1
+  - not: {matches: util}
+  - inside: {kind: class_body, stopBy: end, field: body}
+  - pattern: {context: 'class A { $F = 1 }', selector: public_field_definition}
+  - pattern: {context: 'let a = 1', strictness: relaxed}
+";
+    let rule: SerializableRule = from_str(src).expect("cannot parse rule");
+    let utils = [("util".to_string(), from_str("kind: number").unwrap())].into();
+    let env = DeserializeEnv::new(lang.clone())
+      .register_local_utils(&utils)
+      .expect("should register");
+    let compiled = deserialize_rule(rule.clone(), &env).expect("should deserialize");
+    assert!(compiled.to_serializable(&lang) == rule);
+  }
+
+  #[test]
+  fn test_validate() {
+    let src = r"
+all:
+  - kind: not_a_kind
+  - regex: '('
+  - any: []
+  - inside: {matches: missing, stopBy: {kind: also_bad}}
+  - not: {kind: number}
+";
+    let rule: SerializableRule = from_str(src).expect("cannot parse rule");
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let errors: Vec<_> = rule.validate(&env).iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+      errors,
+      [
+        "`not_a_kind` is not a valid kind name.",
+        "Rule contains invalid regex `(`.",
+        "`any` has no sub-rule.",
+        "`matches` refers to undefined rule `missing`.",
+        "`also_bad` is not a valid kind name.",
+      ]
+    );
+    let utils = [("missing".to_string(), from_str("kind: number").unwrap())].into();
+    let env = env.register_local_utils(&utils).expect("should register");
+    let rule: SerializableRule = from_str("matches: missing").expect("cannot parse rule");
+    assert!(rule.validate(&env).is_empty());
+  }
+
+  #[test]
+  fn test_deserialize_reports_all_errors() {
+    let src = r"
+any:
+  - kind: not_a_kind
+  - has: {kind: number, count: {min: 2, max: 1}}
+  - all: []
+";
+    let rule: SerializableRule = from_str(src).expect("cannot parse rule");
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let Err(RuleSerializeError::Invalid(errors)) = env.deserialize_rule(rule) else {
+      panic!("rule should be invalid");
+    };
+    let errors: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+      errors,
+      [
+        "`not_a_kind` is not a valid kind name.",
+        "`count` min 2 is greater than max 1.",
+      ]
+    );
+  }
+
+  /// A tiny xorshift generator so the fuzz test is reproducible without extra dependencies.
+  struct Rng(u64);
+  impl Rng {
+    fn next(&mut self, n: usize) -> usize {
+      self.0 ^= self.0 << 13;
+      self.0 ^= self.0 >> 7;
+      self.0 ^= self.0 << 17;
+      (self.0 % n as u64) as usize
+    }
+  }
+
+  fn random_rule(rng: &mut Rng, depth: usize) -> String {
+    const ATOMS: [&str; 10] = [
+      "kind: number",
+      "kind: bad_kind",
+      "regex: '^a'",
+      "regex: '(['",
+      "pattern: a + $B",
+      "pattern: '{'",
+      "matches: util",
+      "nthChild: 2n+1",
+      "nthChild: bad",
+      "range: {start: {line: 0, column: 0}, end: {line: 0, column: 1}}",
+    ];
+    const COMPOSITES: [&str; 8] = [
+      "all", "any", "not", "inside", "has", "precedes", "follows", "stopBy",
+    ];
+    if depth == 0 || rng.next(3) == 0 {
+      return format!("{{{}}}", ATOMS[rng.next(ATOMS.len())]);
+    }
+    let key = COMPOSITES[rng.next(COMPOSITES.len())];
+    match key {
+      "all" | "any" => {
+        let subs: Vec<_> = (0..rng.next(3))
+          .map(|_| random_rule(rng, depth - 1))
+          .collect();
+        format!("{{{key}: [{}]}}", subs.join(", "))
+      }
+      // stopBy outside of a relation is an unknown field
+      "stopBy" => format!(
+        "{{inside: {{kind: number, stopBy: {}}}}}",
+        random_rule(rng, depth - 1)
+      ),
+      _ => format!("{{{key}: {}}}", random_rule(rng, depth - 1)),
+    }
+  }
+
+  #[test]
+  fn test_validate_random_rules() {
+    let mut rng = Rng(0x5eed);
+    let utils = [("util".to_string(), from_str("kind: number").unwrap())].into();
+    for _ in 0..500 {
+      let src = random_rule(&mut rng, 4);
+      let Ok(rule) = from_str::<SerializableRule>(&src) else {
+        continue;
+      };
+      let env = DeserializeEnv::new(TypeScript::Tsx)
+        .register_local_utils(&utils)
+        .expect("should register");
+      let errors = rule.validate(&env);
+      let compiled = env.deserialize_rule(rule);
+      // fatal problems are reported by deserialization before compiling
+      let fatal = errors.iter().any(ValidationError::is_fatal);
+      let invalid = matches!(compiled, Err(RuleSerializeError::Invalid(_)));
+      assert_eq!(fatal, invalid, "{src}");
+    }
+  }
+
+  #[test]
+  fn test_display() {
+    let display = |src: &str| {
+      let rule: SerializableRule = from_str(src).expect("cannot parse rule");
+      rule.to_string()
+    };
+    assert_eq!(
+      display(
+        r"
+pattern: console.log($A)
+inside: {kind: function_declaration}
+not: {has: {kind: await_expression, stopBy: end}}
+"
+      ),
+      r#"pattern("console.log($A)") inside(kind("function_declaration")) not(has(kind("await_expression") stopBy(end)))"#,
+    );
+    assert_eq!(
+      display("any: [{regex: ^a}, {matches: util}]\ncount: {min: 1}"),
+      r#"any(regex("^a"), matches("util")) count(min(1))"#,
+    );
+    assert_eq!(
+      display("pattern: {context: 'a = 1', selector: number, strictness: ast}"),
+      r#"pattern(context("a = 1") selector("number") strictness(ast))"#,
+    );
+    assert_eq!(
+      display("nthChild: {position: 2n+1, ofRule: {kind: number}, reverse: true}"),
+      r#"nthChild(position("2n+1") ofRule(kind("number")) reverse)"#,
+    );
+    assert_eq!(display("{}"), "{}");
+  }
+
+  #[test]
+  fn test_regex_flags() {
+    let found = |rule: &str, src: &str| {
+      let rule = deserialize_str(rule);
+      TypeScript::Tsx.ast_grep(src).root().find(rule).is_some()
+    };
+    assert!(found("regex: {pattern: '^hello', flags: i}", "HeLLo()"));
+    assert!(!found("regex: '^hello'", "HeLLo()"));
+    let template = "`a\nb\nc`";
+    assert!(found(
+      "{kind: template_string, regex: {pattern: 'a.b', flags: s}}",
+      template
+    ));
+    assert!(!found("{kind: template_string, regex: 'a.b'}", template));
+    assert!(found(
+      "{kind: template_string, regex: {pattern: '^B$', flags: mi}}",
+      template
+    ));
+    assert!(!found("{kind: template_string, regex: '^B$'}", template));
+  }
+
+  #[test]
+  fn test_regex_flags_error() {
+    let rule: SerializableRule =
+      from_str("regex: {pattern: a, flags: x}").expect("cannot parse rule");
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let err = deserialize_rule(rule, &env).expect_err("should fail");
+    assert!(matches!(
+      err,
+      RuleSerializeError::WrongRegex(RegexMatcherError::InvalidFlag('x'))
+    ));
+  }
+
+  #[test]
+  fn test_regex_flags_round_trip() {
+    let rule = deserialize_str("regex: {pattern: '^a', flags: i}");
+    assert_eq!(format!("{rule:?}"), r#"regex("^a", i)"#);
+    let ser = rule.to_serializable(&TypeScript::Tsx);
+    assert_eq!(ser.to_string(), r#"regex(pattern("^a") flags(i))"#);
+    let simple = deserialize_str("regex: '^a'").to_serializable(&TypeScript::Tsx);
+    assert_eq!(simple.regex, Maybe::Present(RegexRule::Simple("^a".into())));
+  }
+
+  #[test]
+  fn test_debug_rule() {
+    let rule = deserialize_str("any: [{regex: ^a}, {not: {regex: b}}]\ncount: {max: 2}");
+    let expected = r#"any(regex("^a"), not(regex("b"))) count(max(2))"#;
+    assert_eq!(format!("{rule:?}"), expected);
+    let src = "pattern: {context: 'a = 1', selector: number}\ninside: {kind: program}";
+    let rule = deserialize_str(src);
+    let expected = r#"all(pattern(context("a = 1") selector("number")), inside(kind("program")))"#;
+    assert_eq!(format!("{rule:?}"), expected);
+  }
+
+  #[test]
+  fn test_error_message_display() {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let rule: SerializableRule =
+      from_str("precedes: {kind: number, field: body}").expect("cannot parse rule");
+    let error = env.deserialize_rule(rule).expect_err("should fail");
+    assert_eq!(
+      error.to_string(),
+      r#"Rule `precedes(kind("number") field("body"))` is invalid."#
+    );
+    let source = std::error::Error::source(&error).expect("should have source");
+    assert_eq!(source.to_string(), "field is only supported in has/inside.");
+    let rule: SerializableRule = from_str("has: {kind: number}").expect("cannot parse rule");
+    assert!(env.deserialize_rule(rule).is_ok());
+  }
 }
@@ -1,4 +1,6 @@
-use super::{DeserializeEnv, Rule, RuleSerializeError, SerializableRule};
+use super::{
+  deserialize_rule, DeserializeEnv, FieldWriter, Rule, RuleSerializeError, SerializableRule,
+};
 
 use ast_grep_core::language::Language;
 use ast_grep_core::meta_var::MetaVarEnv;
@@ -6,6 +8,7 @@ use ast_grep_core::{Doc, Matcher, Node};
 
 use std::borrow::Cow;
 use std::collections::HashSet;
+use std::fmt;
 
 use bit_set::BitSet;
 use schemars::JsonSchema;
@@ -23,7 +26,7 @@ pub enum NthChildError {
 }
 
 /// A string or number describing the indices of matching nodes in a list of siblings.
-#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 #[serde(untagged)]
 pub enum NthChildSimple {
   /// A number indicating the precise element index
@@ -118,6 +121,15 @@ fn parse_an_b(input: &str) -> Result<FunctionalPosition, NthChildError> {
   }
 }
 
+impl fmt::Display for NthChildSimple {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      NthChildSimple::Numeric(n) => write!(f, "{n}"),
+      NthChildSimple::Functional(s) => write!(f, "{s:?}"),
+    }
+  }
+}
+
 impl NthChildSimple {
   fn try_parse(&self) -> Result<FunctionalPosition, NthChildError> {
     match self {
@@ -131,7 +143,7 @@ impl NthChildSimple {
 }
 
 /// `nthChild` accepts either a number, a string or an object.
-#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 #[serde(untagged, rename_all = "camelCase")]
 pub enum SerializableNthChild {
   /// Simple syntax
@@ -149,6 +161,29 @@ pub enum SerializableNthChild {
   },
 }
 
+impl fmt::Display for SerializableNthChild {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SerializableNthChild::Simple(position) => write!(f, "{position}"),
+      SerializableNthChild::Complex {
+        position,
+        of_rule,
+        reverse,
+      } => {
+        let mut w = FieldWriter::new(f);
+        w.field("position", position)?;
+        if let Some(rule) = of_rule {
+          w.field("ofRule", rule)?;
+        }
+        if *reverse {
+          w.flag("reverse")?;
+        }
+        w.finish()
+      }
+    }
+  }
+}
+
 /// Corresponds to the CSS syntax An+B
 /// See https://developer.mozilla.org/en-US/docs/Web/CSS/:nth-child#functional_notation
 struct FunctionalPosition {
@@ -157,6 +192,25 @@ struct FunctionalPosition {
 }
 
 impl FunctionalPosition {
+  /// convert back to the An+B notation, or a plain number if step size is zero
+  fn to_simple(&self) -> NthChildSimple {
+    let FunctionalPosition { step_size, offset } = *self;
+    if step_size == 0 {
+      return NthChildSimple::Numeric(offset.max(0) as usize);
+    }
+    let step = match step_size {
+      1 => String::new(),
+      -1 => "-".to_string(),
+      s => s.to_string(),
+    };
+    let repr = match offset.cmp(&0) {
+      std::cmp::Ordering::Equal => format!("{step}n"),
+      std::cmp::Ordering::Greater => format!("{step}n+{offset}"),
+      std::cmp::Ordering::Less => format!("{step}n{offset}"),
+    };
+    NthChildSimple::Functional(repr)
+  }
+
   /// index is 0-based, but output is 1-based
   fn is_matched(&self, index: usize) -> bool {
     let index = (index + 1) as i32; // Convert 0-based index to 1-based
@@ -194,7 +248,7 @@ impl<L: Language> NthChild<L> {
       } => Ok(NthChild {
         position: position.try_parse()?,
         of_rule: of_rule
-          .map(|r| env.deserialize_rule(*r))
+          .map(|r| deserialize_rule(*r, env))
           .transpose()
           .map_err(Box::new)?
           .map(Box::new),
@@ -228,6 +282,21 @@ impl<L: Language> NthChild<L> {
       .iter()
       .position(|child| child.node_id() == node.node_id())
   }
+  pub(crate) fn to_serializable(&self, lang: &L) -> SerializableNthChild {
+    let position = self.position.to_simple();
+    if self.of_rule.is_none() && !self.reverse {
+      return SerializableNthChild::Simple(position);
+    }
+    SerializableNthChild::Complex {
+      position,
+      of_rule: self
+        .of_rule
+        .as_ref()
+        .map(|r| Box::new(r.to_serializable(lang))),
+      reverse: self.reverse,
+    }
+  }
+
   pub fn defined_vars(&self) -> HashSet<&str> {
     if let Some(rule) = &self.of_rule {
       rule.defined_vars()
@@ -3,7 +3,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// Represents a zero-based character-wise position in a document
-#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 pub struct SerializablePosition {
   /// 0-based line number in the source code
   pub line: usize,
@@ -12,7 +12,7 @@ pub struct SerializablePosition {
 }
 
 /// Represents a position in source code using 0-based line and column numbers
-#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 pub struct SerializableRange {
   /// start position in the source code
   pub start: SerializablePosition,
@@ -20,7 +20,19 @@ pub struct SerializableRange {
   pub end: SerializablePosition,
 }
 
-use std::{borrow::Cow, marker::PhantomData};
+/// Displayed as `line:column-line:column`, zero-based like the rule object.
+impl fmt::Display for SerializableRange {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let (start, end) = (&self.start, &self.end);
+    write!(
+      f,
+      "{}:{}-{}:{}",
+      start.line, start.column, end.line, end.column
+    )
+  }
+}
+
+use std::{borrow::Cow, fmt, marker::PhantomData};
 
 use bit_set::BitSet;
 use thiserror::Error;
@@ -65,6 +77,13 @@ impl<L: Language> RangeMatcher<L> {
     let range = Self::new(start_pos, end_pos);
     Ok(range)
   }
+
+  pub(crate) fn to_serializable(&self) -> SerializableRange {
+    SerializableRange {
+      start: self.start.clone(),
+      end: self.end.clone(),
+    }
+  }
 }
 
 impl<L: Language> Matcher<L> for RangeMatcher<L> {
@@ -1,4 +1,4 @@
-use crate::{Rule, RuleCore};
+use crate::{Rule, RuleCore, SerializableRuleCore};
 
 use ast_grep_core::language::Language;
 use ast_grep_core::meta_var::MetaVarEnv;
@@ -73,6 +73,10 @@ impl<L: Language> RuleRegistration<L> {
     self.global.read()
   }
 
+  pub(crate) fn get_global_source(&self, id: &str) -> Option<SerializableRuleCore> {
+    self.get_global().get(id)?.source.clone()
+  }
+
   pub fn get_rewriters(&self) -> GlobalRules<L> {
     self.rewriters.clone()
   }
@@ -114,6 +118,11 @@ impl<L: Language> RuleRegistration<L> {
     Ok(())
   }
 
+  /// Whether a local or global utility rule with the id is registered.
+  pub(crate) fn contains_rule(&self, id: &str) -> bool {
+    self.get_local().contains_key(id) || self.get_global().contains_key(id)
+  }
+
   pub(crate) fn get_local_util_vars<'a>(&'a self) -> HashSet<&'a str> {
     let mut ret = HashSet::new();
     let utils = self.get_local();
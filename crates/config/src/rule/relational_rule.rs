@@ -1,6 +1,7 @@
 use super::deserialize_env::DeserializeEnv;
 use super::stop_by::{SerializableStopBy, StopBy};
-use crate::rule::{Rule, RuleSerializeError, SerializableRule};
+use crate::maybe::Maybe;
+use crate::rule::{deserialize_rule, FieldWriter, Rule, RuleSerializeError, SerializableRule};
 use ast_grep_core::language::Language;
 use ast_grep_core::meta_var::MetaVarEnv;
 use ast_grep_core::{Doc, Matcher, Node};
@@ -9,8 +10,9 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::HashSet;
+use std::fmt;
 
-#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Relation {
   #[serde(flatten)]
@@ -18,6 +20,29 @@ pub struct Relation {
   #[serde(default)]
   pub stop_by: SerializableStopBy,
   pub field: Option<String>,
+  /// Maximum number of levels to traverse. `depth: 1` is the same as `stopBy: neighbor`.
+  #[serde(default, skip_serializing_if = "Maybe::is_absent")]
+  pub depth: Maybe<usize>,
+  /// Shorthand for `stopBy: neighbor`. It overrides `end` or numeric `stopBy`,
+  /// but cannot be used with a rule `stopBy`.
+  #[serde(default, skip_serializing_if = "Maybe::is_absent")]
+  pub immediate: Maybe<bool>,
+}
+
+impl fmt::Display for Relation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.rule)?;
+    let mut w = FieldWriter::continued(f);
+    if !matches!(self.stop_by, SerializableStopBy::Neighbor) {
+      w.field("stopBy", &self.stop_by)?;
+    }
+    if let Some(field) = &self.field {
+      w.field("field", format_args!("{field:?}"))?;
+    }
+    w.maybe("depth", &self.depth)?;
+    w.maybe("immediate", &self.immediate)?;
+    Ok(())
+  }
 }
 
 fn field_name_to_id<L: Language>(
@@ -34,17 +59,54 @@ fn field_name_to_id<L: Language>(
   }
 }
 
+/// Apply `immediate: true`, which is the same as `stopBy: neighbor`.
+/// It overrides `end` and numeric stopBy, but a rule stopBy is a conflict.
+fn stop_by_from<L: Language>(
+  stop_by: SerializableStopBy,
+  immediate: Maybe<bool>,
+  env: &DeserializeEnv<L>,
+) -> Result<StopBy<L>, RuleSerializeError> {
+  let immediate = Option::from(immediate).unwrap_or(false);
+  if !immediate {
+    return StopBy::try_from(stop_by, env);
+  }
+  if matches!(stop_by, SerializableStopBy::Rule(_)) {
+    return Err(RuleSerializeError::ConflictingRelationOptions(
+      "immediate".into(),
+      "stopBy".into(),
+    ));
+  }
+  Ok(StopBy::Neighbor)
+}
+
+fn field_id_to_name<L: Language>(field: Option<u16>, lang: &L) -> Option<String> {
+  let ts_lang = lang.get_ts_language();
+  Some(ts_lang.field_name_for_id(field?)?.into_owned())
+}
+
 pub struct Inside<L: Language> {
-  outer: Rule<L>,
+  pub(crate) outer: Rule<L>,
   field: Option<u16>,
   stop_by: StopBy<L>,
+  depth: Option<usize>,
 }
 impl<L: Language> Inside<L> {
+  pub(crate) fn to_relation(&self, lang: &L) -> Relation {
+    Relation {
+      rule: self.outer.to_serializable(lang),
+      stop_by: self.stop_by.to_serializable(lang),
+      field: field_id_to_name(self.field, lang),
+      depth: self.depth.into(),
+      immediate: Maybe::Absent,
+    }
+  }
+
   pub fn try_new(relation: Relation, env: &DeserializeEnv<L>) -> Result<Self, RuleSerializeError> {
     Ok(Self {
-      stop_by: StopBy::try_from(relation.stop_by, env)?,
+      stop_by: stop_by_from(relation.stop_by, relation.immediate, env)?,
       field: field_name_to_id(relation.field, env)?,
-      outer: env.deserialize_rule(relation.rule)?, // TODO
+      outer: deserialize_rule(relation.rule, env)?, // TODO
+      depth: relation.depth.into(),
     })
   }
 
@@ -61,14 +123,25 @@ impl<L: Language> Inside<L> {
     self.outer.verify_util()?;
     self.stop_by.verify_util()
   }
-}
 
-impl<L: Language> Matcher<L> for Inside<L> {
-  fn match_node_with_env<'tree, D: Doc<Lang = L>>(
+  /// Ancestors visited by `stopBy`, used to explain why no ancestor matched.
+  pub(crate) fn candidates<'tree, D: Doc<Lang = L>>(
     &self,
     node: Node<'tree, D>,
-    env: &mut Cow<MetaVarEnv<'tree, D>>,
-  ) -> Option<Node<'tree, D>> {
+  ) -> Vec<Node<'tree, D>> {
+    let mut ret = vec![];
+    self.find_node(node, |n| {
+      ret.push(n);
+      None
+    });
+    ret
+  }
+
+  fn find_node<'tree, D, F>(&self, node: Node<'tree, D>, mut finder: F) -> Option<Node<'tree, D>>
+  where
+    D: Doc<Lang = L>,
+    F: FnMut(Node<'tree, D>) -> Option<Node<'tree, D>>,
+  {
     let parent = || node.parent();
     let ancestors = || node.ancestors();
     if let Some(field) = self.field {
@@ -80,28 +153,58 @@ impl<L: Language> Matcher<L> for Inside<L> {
         if n.node_id() != expect_id {
           None
         } else {
-          self.outer.match_node_with_env(nd, env)
+          finder(nd)
         }
       };
-      self.stop_by.find(parent, ancestors, finder)
+      self.stop_by.find(parent, ancestors, finder, self.depth)
     } else {
-      let finder = |n| self.outer.match_node_with_env(n, env);
-      self.stop_by.find(parent, ancestors, finder)
+      self.stop_by.find(parent, ancestors, finder, self.depth)
     }
   }
 }
 
+impl<L: Language> fmt::Debug for Inside<L> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:?}", self.outer)
+  }
+}
+
+impl<L: Language> Matcher<L> for Inside<L> {
+  fn match_node_with_env<'tree, D: Doc<Lang = L>>(
+    &self,
+    node: Node<'tree, D>,
+    env: &mut Cow<MetaVarEnv<'tree, D>>,
+  ) -> Option<Node<'tree, D>> {
+    if let Rule::Count(count) = &self.outer {
+      return count.match_relatives(node.clone(), self.candidates(node), env);
+    }
+    self.find_node(node, |n| self.outer.match_node_with_env(n, env))
+  }
+}
+
 pub struct Has<L: Language> {
-  inner: Rule<L>,
+  pub(crate) inner: Rule<L>,
   stop_by: StopBy<L>,
   field: Option<u16>,
+  depth: Option<usize>,
 }
 impl<L: Language> Has<L> {
+  pub(crate) fn to_relation(&self, lang: &L) -> Relation {
+    Relation {
+      rule: self.inner.to_serializable(lang),
+      stop_by: self.stop_by.to_serializable(lang),
+      field: field_id_to_name(self.field, lang),
+      depth: self.depth.into(),
+      immediate: Maybe::Absent,
+    }
+  }
+
   pub fn try_new(relation: Relation, env: &DeserializeEnv<L>) -> Result<Self, RuleSerializeError> {
     Ok(Self {
-      stop_by: StopBy::try_from(relation.stop_by, env)?,
-      inner: env.deserialize_rule(relation.rule)?,
+      stop_by: stop_by_from(relation.stop_by, relation.immediate, env)?,
+      inner: deserialize_rule(relation.rule, env)?,
       field: field_name_to_id(relation.field, env)?,
+      depth: relation.depth.into(),
     })
   }
 
@@ -118,70 +221,173 @@ impl<L: Language> Has<L> {
     self.inner.verify_util()?;
     self.stop_by.verify_util()
   }
-}
 
-impl<L: Language> Matcher<L> for Has<L> {
-  fn match_node_with_env<'tree, D: Doc<Lang = L>>(
+  /// Descendants visited by `stopBy`, used to explain why no descendant matched.
+  pub(crate) fn candidates<'tree, D: Doc<Lang = L>>(
     &self,
     node: Node<'tree, D>,
-    env: &mut Cow<MetaVarEnv<'tree, D>>,
-  ) -> Option<Node<'tree, D>> {
+  ) -> Vec<Node<'tree, D>> {
+    let mut ret = vec![];
+    self.find_node(node, &mut |n| {
+      ret.push(n);
+      None
+    });
+    ret
+  }
+
+  fn find_node<'tree, D, F>(&self, node: Node<'tree, D>, finder: &mut F) -> Option<Node<'tree, D>>
+  where
+    D: Doc<Lang = L>,
+    F: FnMut(Node<'tree, D>) -> Option<Node<'tree, D>>,
+  {
+    // counting stopBy is a depth limit for descendants
+    let depth = match (&self.stop_by, self.depth) {
+      (StopBy::Count(count), Some(depth)) => Some(depth.min(*count)),
+      (StopBy::Count(count), None) => Some(*count),
+      (_, depth) => depth,
+    };
+    if let Some(depth) = depth {
+      return self.find_within_depth(node, finder, depth);
+    }
     if let Some(field) = self.field {
       let nd = node.child_by_field_id(field)?;
       return match &self.stop_by {
-        StopBy::Neighbor => self.inner.match_node_with_env(nd, env),
-        StopBy::End => nd
-          .dfs()
-          .find_map(|n| self.inner.match_node_with_env(n, env)),
+        StopBy::Neighbor => finder(nd),
+        StopBy::End => nd.dfs().find_map(finder),
         StopBy::Rule(matcher) => {
           // TODO: use Pre traversal to reduce stack allocation
-          self.inner.match_node_with_env(nd.clone(), env).or_else(|| {
+          finder(nd.clone()).or_else(|| {
             if nd.matches(matcher) {
               None
             } else {
-              nd.children()
-                .find_map(|n| self.inner.match_node_with_env(n, env))
+              nd.children().find_map(finder)
             }
           })
         }
+        StopBy::Count(_) => unreachable!("count is handled as depth"),
       };
     }
     match &self.stop_by {
-      StopBy::Neighbor => node
-        .children()
-        .find_map(|n| self.inner.match_node_with_env(n, env)),
-      StopBy::End => node
-        .dfs()
-        .skip(1)
-        .find_map(|n| self.inner.match_node_with_env(n, env)),
+      StopBy::Neighbor => node.children().find_map(finder),
+      StopBy::End => node.dfs().skip(1).find_map(finder),
       StopBy::Rule(matcher) => {
         // TODO: use Pre traversal to reduce stack allocation
         node.children().find_map(|n| {
-          self.inner.match_node_with_env(n.clone(), env).or_else(|| {
+          finder(n.clone()).or_else(|| {
             if n.matches(matcher) {
               None
             } else {
-              self.match_node_with_env(n, env)
+              self.find_node(n, finder)
             }
           })
         })
       }
+      StopBy::Count(_) => unreachable!("count is handled as depth"),
+    }
+  }
+
+  /// Find descendants no deeper than `depth` levels below the node.
+  /// The field child, if specified, counts as the first level.
+  fn find_within_depth<'tree, D, F>(
+    &self,
+    node: Node<'tree, D>,
+    finder: &mut F,
+    depth: usize,
+  ) -> Option<Node<'tree, D>>
+  where
+    D: Doc<Lang = L>,
+    F: FnMut(Node<'tree, D>) -> Option<Node<'tree, D>>,
+  {
+    let stop = match &self.stop_by {
+      StopBy::Rule(matcher) => Some(matcher),
+      StopBy::Neighbor | StopBy::End | StopBy::Count(_) => None,
+    };
+    let Some(field) = self.field else {
+      return find_descendant(node, finder, depth, stop);
+    };
+    if depth == 0 {
+      return None;
+    }
+    let nd = node.child_by_field_id(field)?;
+    finder(nd.clone()).or_else(|| {
+      if stop.map_or(false, |s| nd.matches(s)) {
+        None
+      } else {
+        find_descendant(nd, finder, depth - 1, stop)
+      }
+    })
+  }
+}
+
+/// Pre-order search that does not go into nodes matching the `stop` rule.
+fn find_descendant<'tree, L, D, F>(
+  node: Node<'tree, D>,
+  finder: &mut F,
+  depth: usize,
+  stop: Option<&Rule<L>>,
+) -> Option<Node<'tree, D>>
+where
+  L: Language,
+  D: Doc<Lang = L>,
+  F: FnMut(Node<'tree, D>) -> Option<Node<'tree, D>>,
+{
+  if depth == 0 {
+    return None;
+  }
+  node.children().find_map(|n| {
+    finder(n.clone()).or_else(|| {
+      if stop.map_or(false, |s| n.matches(s)) {
+        None
+      } else {
+        find_descendant(n, finder, depth - 1, stop)
+      }
+    })
+  })
+}
+
+impl<L: Language> fmt::Debug for Has<L> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:?}", self.inner)
+  }
+}
+
+impl<L: Language> Matcher<L> for Has<L> {
+  fn match_node_with_env<'tree, D: Doc<Lang = L>>(
+    &self,
+    node: Node<'tree, D>,
+    env: &mut Cow<MetaVarEnv<'tree, D>>,
+  ) -> Option<Node<'tree, D>> {
+    if let Rule::Count(count) = &self.inner {
+      return count.match_relatives(node.clone(), self.candidates(node), env);
     }
+    self.find_node(node, &mut |n| self.inner.match_node_with_env(n, env))
   }
 }
 
 pub struct Precedes<L: Language> {
-  later: Rule<L>,
+  pub(crate) later: Rule<L>,
   stop_by: StopBy<L>,
+  depth: Option<usize>,
 }
 impl<L: Language> Precedes<L> {
+  pub(crate) fn to_relation(&self, lang: &L) -> Relation {
+    Relation {
+      rule: self.later.to_serializable(lang),
+      stop_by: self.stop_by.to_serializable(lang),
+      field: None,
+      depth: self.depth.into(),
+      immediate: Maybe::Absent,
+    }
+  }
+
   pub fn try_new(relation: Relation, env: &DeserializeEnv<L>) -> Result<Self, RuleSerializeError> {
     if relation.field.is_some() {
       return Err(RuleSerializeError::FieldNotSupported);
     }
     Ok(Self {
-      stop_by: StopBy::try_from(relation.stop_by, env)?,
-      later: env.deserialize_rule(relation.rule)?,
+      stop_by: stop_by_from(relation.stop_by, relation.immediate, env)?,
+      later: deserialize_rule(relation.rule, env)?,
+      depth: relation.depth.into(),
     })
   }
 
@@ -198,32 +404,73 @@ impl<L: Language> Precedes<L> {
     self.later.verify_util()?;
     self.stop_by.verify_util()
   }
+
+  /// Siblings visited by `stopBy`, used to explain why no sibling matched.
+  pub(crate) fn candidates<'tree, D: Doc<Lang = L>>(
+    &self,
+    node: Node<'tree, D>,
+  ) -> Vec<Node<'tree, D>> {
+    let mut ret = vec![];
+    self.find_node(node, |n| {
+      ret.push(n);
+      None
+    });
+    ret
+  }
+
+  fn find_node<'tree, D, F>(&self, node: Node<'tree, D>, finder: F) -> Option<Node<'tree, D>>
+  where
+    D: Doc<Lang = L>,
+    F: FnMut(Node<'tree, D>) -> Option<Node<'tree, D>>,
+  {
+    let next = || node.next();
+    let next_all = || node.next_all();
+    self.stop_by.find(next, next_all, finder, self.depth)
+  }
+}
+impl<L: Language> fmt::Debug for Precedes<L> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:?}", self.later)
+  }
 }
+
 impl<L: Language> Matcher<L> for Precedes<L> {
   fn match_node_with_env<'tree, D: Doc<Lang = L>>(
     &self,
     node: Node<'tree, D>,
     env: &mut Cow<MetaVarEnv<'tree, D>>,
   ) -> Option<Node<'tree, D>> {
-    let next = || node.next();
-    let next_all = || node.next_all();
-    let finder = |n| self.later.match_node_with_env(n, env);
-    self.stop_by.find(next, next_all, finder)
+    if let Rule::Count(count) = &self.later {
+      return count.match_relatives(node.clone(), self.candidates(node), env);
+    }
+    self.find_node(node, |n| self.later.match_node_with_env(n, env))
   }
 }
 
 pub struct Follows<L: Language> {
-  former: Rule<L>,
+  pub(crate) former: Rule<L>,
   stop_by: StopBy<L>,
+  depth: Option<usize>,
 }
 impl<L: Language> Follows<L> {
+  pub(crate) fn to_relation(&self, lang: &L) -> Relation {
+    Relation {
+      rule: self.former.to_serializable(lang),
+      stop_by: self.stop_by.to_serializable(lang),
+      field: None,
+      depth: self.depth.into(),
+      immediate: Maybe::Absent,
+    }
+  }
+
   pub fn try_new(relation: Relation, env: &DeserializeEnv<L>) -> Result<Self, RuleSerializeError> {
     if relation.field.is_some() {
       return Err(RuleSerializeError::FieldNotSupported);
     }
     Ok(Self {
-      stop_by: StopBy::try_from(relation.stop_by, env)?,
-      former: env.deserialize_rule(relation.rule)?,
+      stop_by: stop_by_from(relation.stop_by, relation.immediate, env)?,
+      former: deserialize_rule(relation.rule, env)?,
+      depth: relation.depth.into(),
     })
   }
   pub fn defined_vars(&self) -> HashSet<&str> {
@@ -239,17 +486,46 @@ impl<L: Language> Follows<L> {
     self.former.verify_util()?;
     self.stop_by.verify_util()
   }
+
+  /// Siblings visited by `stopBy`, used to explain why no sibling matched.
+  pub(crate) fn candidates<'tree, D: Doc<Lang = L>>(
+    &self,
+    node: Node<'tree, D>,
+  ) -> Vec<Node<'tree, D>> {
+    let mut ret = vec![];
+    self.find_node(node, |n| {
+      ret.push(n);
+      None
+    });
+    ret
+  }
+
+  fn find_node<'tree, D, F>(&self, node: Node<'tree, D>, finder: F) -> Option<Node<'tree, D>>
+  where
+    D: Doc<Lang = L>,
+    F: FnMut(Node<'tree, D>) -> Option<Node<'tree, D>>,
+  {
+    let prev = || node.prev();
+    let prev_all = || node.prev_all();
+    self.stop_by.find(prev, prev_all, finder, self.depth)
+  }
 }
+impl<L: Language> fmt::Debug for Follows<L> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:?}", self.former)
+  }
+}
+
 impl<L: Language> Matcher<L> for Follows<L> {
   fn match_node_with_env<'tree, D: Doc<Lang = L>>(
     &self,
     node: Node<'tree, D>,
     env: &mut Cow<MetaVarEnv<'tree, D>>,
   ) -> Option<Node<'tree, D>> {
-    let prev = || node.prev();
-    let prev_all = || node.prev_all();
-    let finder = |n| self.former.match_node_with_env(n, env);
-    self.stop_by.find(prev, prev_all, finder)
+    if let Rule::Count(count) = &self.former {
+      return count.match_relatives(node.clone(), self.candidates(node), env);
+    }
+    self.find_node(node, |n| self.former.match_node_with_env(n, env))
   }
 }
 
@@ -287,6 +563,7 @@ mod test {
     let precedes = Precedes {
       later: Rule::Pattern(Pattern::new("var a = 1", TS::Tsx)),
       stop_by: StopBy::End,
+      depth: None,
     };
     let rule = make_rule("var b = 2", Rule::Precedes(Box::new(precedes)));
     test_found(
@@ -315,6 +592,7 @@ mod test {
     let precedes = Precedes {
       later: Rule::Pattern(Pattern::new("var a = 1", TS::Tsx)),
       stop_by: StopBy::Neighbor,
+      depth: None,
     };
     let rule = make_rule("var b = 2", Rule::Precedes(Box::new(precedes)));
     test_found(
@@ -344,6 +622,7 @@ mod test {
     let follows = Follows {
       former: Rule::Pattern(Pattern::new("var b = 2", TS::Tsx)),
       stop_by: StopBy::End,
+      depth: None,
     };
     let rule = make_rule("var a = 1", Rule::Follows(Box::new(follows)));
     test_found(
@@ -375,6 +654,7 @@ mod test {
     let follows = Follows {
       former: Rule::Pattern(Pattern::new("var b = 2", TS::Tsx)),
       stop_by: StopBy::Neighbor,
+      depth: None,
     };
     let rule = make_rule("var a = 1", Rule::Follows(Box::new(follows)));
     test_found(
@@ -407,6 +687,7 @@ mod test {
       stop_by: StopBy::End,
       inner: Rule::Pattern(Pattern::new("var a = 1", TS::Tsx)),
       field: None,
+      depth: None,
     };
     let rule = make_rule("function test() { $$$ }", Rule::Has(Box::new(has)));
     test_found(
@@ -438,6 +719,7 @@ mod test {
       ))),
       inner: Rule::Pattern(Pattern::new("var a = 1", TS::Tsx)),
       field: None,
+      depth: None,
     };
     let rule = make_rule("function test() { $$$ }", Rule::Has(Box::new(has)));
     test_found(
@@ -465,6 +747,7 @@ mod test {
       ))),
       inner: Rule::Pattern(Pattern::new("function inner() {$$$}", TS::Tsx)),
       field: None,
+      depth: None,
     };
     let rule = make_rule("function test() { $$$ }", Rule::Has(Box::new(has)));
     test_found(
@@ -490,6 +773,7 @@ mod test {
       stop_by: StopBy::Neighbor,
       inner: Rule::Pattern(Pattern::new("var a = 1", TS::Tsx)),
       field: None,
+      depth: None,
     };
     let rule = o::All::new(vec![
       Rule::Pattern(Pattern::new("{ $$$ }", TS::Tsx)),
@@ -497,6 +781,7 @@ mod test {
         outer: Rule::Pattern(Pattern::new("function test() { $$$ }", TS::Tsx)),
         stop_by: StopBy::Neighbor,
         field: None,
+        depth: None,
       })),
       Rule::Has(Box::new(has)),
     ]);
@@ -527,6 +812,7 @@ mod test {
       stop_by: StopBy::End,
       outer: Rule::Pattern(Pattern::new("function test() { $$$ }", TS::Tsx)),
       field: None,
+      depth: None,
     };
     let rule = make_rule("var a = 1", Rule::Inside(Box::new(inside)));
     test_found(
@@ -558,6 +844,7 @@ mod test {
       ))),
       outer: Rule::Pattern(Pattern::new("function test() { $$$ }", TS::Tsx)),
       field: None,
+      depth: None,
     };
     let rule = make_rule("var a = 1", Rule::Inside(Box::new(inside)));
     test_found(
@@ -590,9 +877,11 @@ mod test {
           outer: Rule::Pattern(Pattern::new("function test() { $$$ }", TS::Tsx)),
           stop_by: StopBy::Neighbor,
           field: None,
+          depth: None,
         })),
       ])),
       field: None,
+      depth: None,
     };
     let rule = make_rule("var a = 1", Rule::Inside(Box::new(inside)));
     test_found(
@@ -622,6 +911,7 @@ mod test {
       stop_by: StopBy::End,
       outer: Rule::Kind(KindMatcher::new("for_statement", TS::Tsx)),
       field: TS::Tsx.get_ts_language().field_id_for_name("condition"),
+      depth: None,
     };
     let rule = make_rule("a = 1", Rule::Inside(Box::new(inside)));
     test_found(&["for (;a = 1;) {}"], &rule);
@@ -634,6 +924,7 @@ mod test {
       stop_by: StopBy::End,
       inner: Rule::Pattern(Pattern::new("a = 1", TS::Tsx)),
       field: TS::Tsx.get_ts_language().field_id_for_name("condition"),
+      depth: None,
     };
     let rule = o::All::new(vec![
       Rule::Kind(KindMatcher::new("for_statement", TS::Tsx)),
@@ -650,6 +941,8 @@ mod test {
       rule: crate::from_str("pattern: test").unwrap(),
       stop_by: SerializableStopBy::End,
       field: Some("invalid_field".to_string()),
+      depth: Maybe::Absent,
+      immediate: Maybe::Absent,
     };
     let inside = Inside::try_new(relation, &env);
     assert!(inside.is_err());
@@ -664,24 +957,254 @@ mod test {
     let precedes = Precedes {
       later: Rule::Pattern(Pattern::new("var a = $A", TS::Tsx)),
       stop_by: StopBy::Rule(Rule::Pattern(Pattern::new("var b = $B", TS::Tsx))),
+      depth: None,
     };
     assert_eq!(precedes.defined_vars(), ["A", "B"].into_iter().collect());
     let follows = Follows {
       former: Rule::Pattern(Pattern::new("var a = 123", TS::Tsx)),
       stop_by: StopBy::Rule(Rule::Pattern(Pattern::new("var b = $B", TS::Tsx))),
+      depth: None,
     };
     assert_eq!(follows.defined_vars(), ["B"].into_iter().collect());
     let inside = Inside {
       stop_by: StopBy::Rule(Rule::Pattern(Pattern::new("var $C", TS::Tsx))),
       outer: Rule::Pattern(Pattern::new("var a = $A", TS::Tsx)),
       field: TS::Tsx.get_ts_language().field_id_for_name("condition"),
+      depth: None,
     };
     assert_eq!(inside.defined_vars(), ["A", "C"].into_iter().collect());
     let has = Has {
       stop_by: StopBy::Rule(Rule::Kind(KindMatcher::new("for_statement", TS::Tsx))),
       inner: Rule::Pattern(Pattern::new("var a = $A", TS::Tsx)),
       field: TS::Tsx.get_ts_language().field_id_for_name("condition"),
+      depth: None,
     };
     assert_eq!(has.defined_vars(), ["A"].into_iter().collect());
   }
+
+  fn deserialize_rule(src: &str) -> Rule<TS> {
+    let env = DeserializeEnv::new(TS::Tsx);
+    let rule: SerializableRule = crate::from_str(src).expect("should parse");
+    env.deserialize_rule(rule).expect("should deserialize")
+  }
+
+  #[test]
+  fn test_deserialize_depth() {
+    let rule: SerializableRule = crate::from_str("has: { kind: number, depth: 2 }").unwrap();
+    let has = rule.has.unwrap();
+    assert_eq!(has.depth, Maybe::Present(2));
+    assert!(matches!(has.stop_by, SerializableStopBy::Neighbor));
+    let rule: SerializableRule = crate::from_str("inside: { kind: number }").unwrap();
+    assert!(rule.inside.unwrap().depth.is_absent());
+    let ret = crate::from_str::<SerializableRule>("has: { kind: number, depth: -1 }");
+    assert!(ret.is_err());
+    let ret = crate::from_str::<SerializableRule>("has: { kind: number, depth: null }");
+    assert!(ret.is_err());
+  }
+
+  #[test]
+  fn test_has_depth() {
+    let rule = deserialize_rule(
+      "
+kind: function_declaration
+has:
+  pattern: var a = 1
+  depth: 2",
+    );
+    test_found(
+      &[
+        "function test() { var a = 1 }",
+        "function test() { let b = 2; var a = 1 }",
+      ],
+      &rule,
+    );
+    test_not_found(
+      &[
+        "function test() { if (b) { var a = 1 } }",
+        "function test() { { var a = 1 } }",
+      ],
+      &rule,
+    );
+    let rule = deserialize_rule(
+      "
+kind: function_declaration
+has:
+  pattern: var a = 1
+  depth: 1",
+    );
+    test_not_found(&["function test() { var a = 1 }"], &rule);
+    let rule = deserialize_rule(
+      "
+kind: function_declaration
+has:
+  pattern: var a = 1
+  stopBy: end
+  depth: 4",
+    );
+    test_found(&["function test() { if (b) { var a = 1 } }"], &rule);
+  }
+
+  #[test]
+  fn test_has_depth_with_stop_by_rule() {
+    let rule = deserialize_rule(
+      "
+kind: function_declaration
+has:
+  pattern: var a = 1
+  stopBy: { kind: if_statement }
+  depth: 6",
+    );
+    test_found(&["function test() { while (b) { var a = 1 } }"], &rule);
+    test_not_found(&["function test() { if (b) { var a = 1 } }"], &rule);
+  }
+
+  #[test]
+  fn test_depth_one_is_neighbor() {
+    let sources = [
+      "function test() { var a = 1 }",
+      "function test() { if (b) { var a = 1 } }",
+      "{ var a = 1 }",
+      "var a = 1",
+    ];
+    let pairs = [
+      (
+        "{ kind: statement_block, has: { pattern: var a = 1, depth: 1 } }",
+        "{ kind: statement_block, has: { pattern: var a = 1, stopBy: neighbor } }",
+      ),
+      (
+        "{ pattern: var a = 1, inside: { kind: statement_block, depth: 1 } }",
+        "{ pattern: var a = 1, inside: { kind: statement_block, stopBy: neighbor } }",
+      ),
+      (
+        "{ kind: statement_block, has: { pattern: var a = 1, stopBy: end, depth: 1 } }",
+        "{ kind: statement_block, has: { pattern: var a = 1 } }",
+      ),
+    ];
+    for (depth, neighbor) in pairs {
+      let depth = deserialize_rule(depth);
+      let neighbor = deserialize_rule(neighbor);
+      for src in sources {
+        assert_eq!(find_rule(src, &depth), find_rule(src, &neighbor));
+      }
+    }
+  }
+
+  #[test]
+  fn test_immediate() {
+    let rule = deserialize_rule(
+      "
+pattern: var a = 1
+inside:
+  kind: statement_block
+  stopBy: end
+  immediate: false",
+    );
+    test_found(&["{ if (b) { var a = 1 } }", "{ var a = 1 }"], &rule);
+    test_not_found(&["var a = 1"], &rule);
+    let rule = deserialize_rule(
+      "
+kind: function_declaration
+has:
+  pattern: var a = 1
+  immediate: true",
+    );
+    test_not_found(&["function test() { var a = 1 }"], &rule);
+    let rule =
+      deserialize_rule("{ kind: statement_block, has: { pattern: var a = 1, immediate: true } }");
+    test_found(&["function test() { var a = 1 }"], &rule);
+    test_not_found(&["function test() { if (b) var a = 1 }"], &rule);
+  }
+
+  #[test]
+  fn test_immediate_conflict() {
+    let env = DeserializeEnv::new(TS::Tsx);
+    let src = "has: { kind: number, immediate: true, stopBy: { kind: if_statement } }";
+    let rule: SerializableRule = crate::from_str(src).expect("should parse");
+    let ret = env.deserialize_rule(rule);
+    assert!(matches!(
+      ret,
+      Err(RuleSerializeError::ConflictingRelationOptions(..))
+    ));
+    for stop_by in ["neighbor", "end", "2"] {
+      let src = format!(
+        "{{ kind: variable_declarator, has: {{ kind: number, immediate: true, stopBy: {stop_by} }} }}"
+      );
+      let rule: SerializableRule = crate::from_str(&src).expect("should parse");
+      let rule = env.deserialize_rule(rule).expect("should work");
+      test_found(&["let a = 1"], &rule);
+      test_not_found(&["let a = [1]"], &rule);
+    }
+  }
+
+  #[test]
+  fn test_inside_follows_depth() {
+    let rule = deserialize_rule(
+      "
+pattern: var a = 1
+inside:
+  kind: function_declaration
+  depth: 2",
+    );
+    test_found(&["function test() { var a = 1 }"], &rule);
+    test_not_found(&["function test() { if (b) { var a = 1 } }"], &rule);
+    let rule = deserialize_rule(
+      "
+pattern: var a = 1
+follows:
+  pattern: var b = 2
+  stopBy: end
+  depth: 2",
+    );
+    test_found(&["var b = 2; var c = 3; var a = 1"], &rule);
+    test_not_found(&["var b = 2; var c = 3; var d = 4; var a = 1"], &rule);
+  }
+
+  #[test]
+  fn test_inside_stop_by_count() {
+    let rule = deserialize_rule(
+      "
+pattern: var a = 1
+inside:
+  kind: function_declaration
+  stopBy: 2",
+    );
+    test_found(&["function test() { var a = 1 }"], &rule);
+    test_not_found(&["function test() { if (b) { var a = 1 } }"], &rule);
+    let rule = deserialize_rule(
+      "
+kind: function_declaration
+has:
+  pattern: var a = 1
+  stopBy: 4",
+    );
+    test_found(&["function test() { if (b) { var a = 1 } }"], &rule);
+    test_not_found(
+      &["function test() { if (b) { if (c) { var a = 1 } } }"],
+      &rule,
+    );
+  }
+
+  #[test]
+  fn test_stop_by_one_is_neighbor() {
+    let sources = [
+      "function test() { var a = 1 }",
+      "function test() { if (b) { var a = 1 } }",
+      "var b = 2; var a = 1",
+      "var b = 2; var c = 3; var a = 1",
+    ];
+    for relation in [
+      "inside: { kind: statement_block",
+      "follows: { pattern: var b = 2",
+    ] {
+      let count = deserialize_rule(&format!(
+        "{{ pattern: var a = 1, {relation}, stopBy: 1 }} }}"
+      ));
+      let neighbor = deserialize_rule(&format!(
+        "{{ pattern: var a = 1, {relation}, stopBy: neighbor }} }}"
+      ));
+      for src in sources {
+        assert_eq!(find_rule(src, &count), find_rule(src, &neighbor));
+      }
+    }
+  }
 }
@@ -1,5 +1,5 @@
 use super::deserialize_env::DeserializeEnv;
-use crate::rule::{Rule, RuleSerializeError, SerializableRule};
+use crate::rule::{deserialize_rule, Rule, RuleSerializeError, SerializableRule};
 
 use ast_grep_core::language::Language;
 use ast_grep_core::{Doc, Node};
@@ -13,13 +13,26 @@ use std::fmt;
 
 // NB StopBy's JsonSchema is changed in xtask/schema.rs
 // revise schema is easier than manually implementation
-#[derive(Clone, Default, JsonSchema)]
+#[derive(Clone, Default, PartialEq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum SerializableStopBy {
   #[default]
   Neighbor,
   End,
   Rule(SerializableRule),
+  /// Stop after visiting this many nodes. `1` is the same as `neighbor`.
+  Count(usize),
+}
+
+impl fmt::Display for SerializableStopBy {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SerializableStopBy::Neighbor => f.write_str(Self::NEIGHBOR_KEY),
+      SerializableStopBy::End => f.write_str(Self::END_KEY),
+      SerializableStopBy::Rule(rule) => write!(f, "{rule}"),
+      SerializableStopBy::Count(count) => write!(f, "{count}"),
+    }
+  }
 }
 
 impl SerializableStopBy {
@@ -33,7 +46,31 @@ struct StopByVisitor;
 impl<'de> Visitor<'de> for StopByVisitor {
   type Value = SerializableStopBy;
   fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-    formatter.write_str("`neighbor`, `end` or a rule object")
+    formatter.write_str("`neighbor`, `end`, a positive integer or a rule object")
+  }
+
+  fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+  where
+    E: de::Error,
+  {
+    match usize::try_from(value) {
+      Ok(count) if count > 0 => Ok(SerializableStopBy::Count(count)),
+      _ => Err(de::Error::custom(format!(
+        "invalid stopBy `{value}`, expected a positive integer"
+      ))),
+    }
+  }
+
+  fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+  where
+    E: de::Error,
+  {
+    match u64::try_from(value) {
+      Ok(value) => self.visit_u64(value),
+      Err(_) => Err(de::Error::custom(format!(
+        "invalid stopBy `{value}`, expected a positive integer"
+      ))),
+    }
   }
 
   fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
@@ -78,6 +115,7 @@ impl Serialize for SerializableStopBy {
       SerializableStopBy::Neighbor => serializer.serialize_str(SerializableStopBy::NEIGHBOR_KEY),
       SerializableStopBy::End => serializer.serialize_str(SerializableStopBy::END_KEY),
       SerializableStopBy::Rule(rule) => rule.serialize(serializer),
+      SerializableStopBy::Count(count) => serializer.serialize_u64(*count as u64),
     }
   }
 }
@@ -86,6 +124,7 @@ pub enum StopBy<L: Language> {
   Neighbor,
   End,
   Rule(Rule<L>),
+  Count(usize),
 }
 
 impl<L: Language> StopBy<L> {
@@ -97,34 +136,44 @@ impl<L: Language> StopBy<L> {
     Ok(match relation {
       S::Neighbor => StopBy::Neighbor,
       S::End => StopBy::End,
-      S::Rule(r) => StopBy::Rule(env.deserialize_rule(r)?),
+      S::Rule(r) => StopBy::Rule(deserialize_rule(r, env)?),
+      S::Count(count) => StopBy::Count(count),
     })
   }
 
   pub fn defined_vars(&self) -> HashSet<&str> {
     match self {
       StopBy::Rule(rule) => rule.defined_vars(),
-      StopBy::End => HashSet::new(),
-      StopBy::Neighbor => HashSet::new(),
+      StopBy::End | StopBy::Neighbor | StopBy::Count(_) => HashSet::new(),
     }
   }
 
   pub fn verify_util(&self) -> Result<(), RuleSerializeError> {
     match self {
       StopBy::Rule(rule) => rule.verify_util(),
-      StopBy::End => Ok(()),
-      StopBy::Neighbor => Ok(()),
+      StopBy::End | StopBy::Neighbor | StopBy::Count(_) => Ok(()),
+    }
+  }
+
+  pub(crate) fn to_serializable(&self, lang: &L) -> SerializableStopBy {
+    match self {
+      StopBy::Neighbor => SerializableStopBy::Neighbor,
+      StopBy::End => SerializableStopBy::End,
+      StopBy::Rule(rule) => SerializableStopBy::Rule(rule.to_serializable(lang)),
+      StopBy::Count(count) => SerializableStopBy::Count(*count),
     }
   }
 }
 
 impl<L: Language> StopBy<L> {
   // TODO: document this monster method
+  /// `depth` limits how many nodes `multi` yields. It overrides `Neighbor`.
   pub(crate) fn find<'t, O, M, I, F, D>(
     &self,
     once: O,
     multi: M,
     mut finder: F,
+    depth: Option<usize>,
   ) -> Option<Node<'t, D>>
   where
     D: Doc<Lang = L> + 't,
@@ -133,15 +182,23 @@ impl<L: Language> StopBy<L> {
     M: FnOnce() -> I,
     F: FnMut(Node<'t, D>) -> Option<Node<'t, D>>,
   {
+    let limit = depth.unwrap_or(usize::MAX);
     match self {
-      StopBy::Neighbor => finder(once()?),
-      StopBy::End => {
-        let mut iter = multi();
+      StopBy::Neighbor if depth.is_none() => finder(once()?),
+      StopBy::Neighbor | StopBy::End => {
+        let mut iter = multi().take(limit);
+        iter.find_map(finder)
+      }
+      StopBy::Count(count) => {
+        let mut iter = multi().take(limit.min(*count));
         iter.find_map(finder)
       }
       StopBy::Rule(stop) => {
         let iter = multi();
-        iter.take_while(inclusive_until(stop)).find_map(finder)
+        iter
+          .take_while(inclusive_until(stop))
+          .take(limit)
+          .find_map(finder)
       }
     }
   }
@@ -189,6 +246,8 @@ inside:
     assert!(matches!(stop, SerializableStopBy::End));
     let stop = to_stop_by("kind: some-kind").expect("cannot parse stopBy");
     assert!(matches!(stop, SerializableStopBy::Rule(_)));
+    let stop = to_stop_by("3").expect("cannot parse stopBy");
+    assert!(matches!(stop, SerializableStopBy::Count(3)));
   }
 
   macro_rules! cast_err {
@@ -207,6 +266,12 @@ inside:
     assert!(err.contains("ddd"));
     let err = cast_err!(to_stop_by("pattern: 1233"));
     assert!(err.to_string().contains("variant"));
+    for count in ["0", "-1"] {
+      let err = cast_err!(to_stop_by(count)).to_string();
+      assert!(err.contains("positive integer"), "{err}");
+    }
+    let err = cast_err!(to_stop_by("1.5")).to_string();
+    assert!(err.contains("positive integer"), "{err}");
   }
 
   fn parse_stop_by(src: &str) -> StopBy<TypeScript> {
@@ -246,5 +311,11 @@ inside:
     let serialized = serde_yaml::to_string(&stop).expect("cannot serialize stopBy");
     let deserialized = to_stop_by(&serialized).expect("cannot parse stopBy");
     assert!(matches!(deserialized, SerializableStopBy::Rule(_)));
+
+    let stop = to_stop_by("2").expect("cannot parse stopBy");
+    let serialized = serde_yaml::to_string(&stop).expect("cannot serialize stopBy");
+    assert_eq!(serialized, "2\n");
+    let deserialized = to_stop_by(&serialized).expect("cannot parse stopBy");
+    assert!(matches!(deserialized, SerializableStopBy::Count(2)));
   }
 }
@@ -0,0 +1,229 @@
+//! Build rules in Rust without writing YAML.
+//!
+//! Every rule added to a [`RuleBuilder`] must match, like sub-rules in a YAML rule object.
+//! Errors are reported by [`RuleBuilder::build`], which also checks meta variables
+//! used in constraints are defined by the rule.
+//!
+//! ```
+//! # use ast_grep_core::language::{Language, TSLanguage};
+//! # #[derive(Clone)]
+//! # struct Tsx;
+//! # impl Language for Tsx {
+//! #   fn get_ts_language(&self) -> TSLanguage {
+//! #     tree_sitter_typescript::language_tsx().into()
+//! #   }
+//! # }
+//! use ast_grep_config::{from_str, Relation, RuleBuilder};
+//!
+//! // console.log calls inside a function, whose argument is not a string literal
+//! let arg = RuleBuilder::new(Tsx).kind("string").build_rule().unwrap();
+//! let inside: Relation = from_str("{kind: function_declaration, stopBy: end}").unwrap();
+//! let rule = RuleBuilder::new(Tsx)
+//!   .pattern("console.log($ARG)")
+//!   .inside(inside)
+//!   .constraint("ARG", RuleBuilder::new(Tsx).not(arg).build_rule().unwrap())
+//!   .build()
+//!   .unwrap();
+//!
+//! let grep = Tsx.ast_grep("console.log(a); function f() { console.log('b'); console.log(c) }");
+//! let found: Vec<_> = grep.root().find_all(&rule).map(|n| n.text().to_string()).collect();
+//! assert_eq!(found, ["console.log(c)"]);
+//! ```
+
+use crate::check_var::{check_rule_with_hint, CheckHint};
+use crate::maybe::Maybe;
+use crate::rule::{DeserializeEnv, Relation, Rule, RuleSerializeError, SerializableRule};
+use crate::rule_core::{RuleCore, RuleCoreError};
+
+use ast_grep_core::language::Language;
+use ast_grep_core::matcher::KindMatcher;
+use ast_grep_core::{ops as o, Pattern};
+
+use std::collections::HashMap;
+
+/// A fluent builder for [`RuleCore`].
+pub struct RuleBuilder<L: Language> {
+  env: DeserializeEnv<L>,
+  rules: Vec<Rule<L>>,
+  // relational rules are matched last, after the target node is decided
+  relations: Vec<Rule<L>>,
+  constraints: HashMap<String, Rule<L>>,
+  // the first error is reported in build
+  error: Option<RuleSerializeError>,
+}
+
+impl<L: Language> RuleBuilder<L> {
+  pub fn new(lang: L) -> Self {
+    Self::with_env(DeserializeEnv::new(lang))
+  }
+
+  /// Use `env` to parse patterns and relations, e.g. to refer to registered util rules.
+  pub fn with_env(env: DeserializeEnv<L>) -> Self {
+    Self {
+      env,
+      rules: vec![],
+      relations: vec![],
+      constraints: HashMap::new(),
+      error: None,
+    }
+  }
+
+  fn push(mut self, rule: Result<Rule<L>, RuleSerializeError>) -> Self {
+    match rule {
+      Ok(rule) => self.rules.push(rule),
+      Err(e) => {
+        self.error.get_or_insert(e);
+      }
+    }
+    self
+  }
+
+  pub fn pattern(self, src: &str) -> Self {
+    let lang = self.env.lang.clone();
+    let pattern = Pattern::try_new_cached(src, lang, &self.env.pattern_cache);
+    self.push(pattern.map(Rule::Pattern).map_err(Into::into))
+  }
+
+  pub fn kind(self, name: &str) -> Self {
+    let kind = KindMatcher::try_new(name, self.env.lang.clone());
+    self.push(kind.map(Rule::Kind).map_err(Into::into))
+  }
+
+  pub fn inside(mut self, relation: Relation) -> Self {
+    let inside = SerializableRule {
+      inside: Maybe::Present(Box::new(relation)),
+      ..Default::default()
+    };
+    match self.env.deserialize_rule(inside) {
+      Ok(rule) => self.relations.push(rule),
+      Err(e) => {
+        self.error.get_or_insert(e);
+      }
+    }
+    self
+  }
+
+  /// Require the meta variable `name` to also match `rule`.
+  pub fn constraint(mut self, name: &str, rule: Rule<L>) -> Self {
+    self.constraints.insert(name.to_string(), rule);
+    self
+  }
+
+  pub fn any(self, rules: Vec<Rule<L>>) -> Self {
+    self.push(Ok(Rule::Any(o::Any::new(rules))))
+  }
+
+  pub fn all(self, rules: Vec<Rule<L>>) -> Self {
+    self.push(Ok(Rule::All(o::All::new(rules))))
+  }
+
+  pub fn not(self, rule: Rule<L>) -> Self {
+    self.push(Ok(Rule::Not(Box::new(o::Not::new(rule)))))
+  }
+
+  /// Build a plain rule without constraints, e.g. to nest it in `any`, `not` or a constraint.
+  pub fn build_rule(mut self) -> Result<Rule<L>, RuleSerializeError> {
+    self.take_rule()
+  }
+
+  pub fn build(mut self) -> Result<RuleCore<L>, RuleCoreError> {
+    let rule = self.take_rule()?;
+    let utils = self.env.registration;
+    let constraints = self.constraints;
+    check_rule_with_hint(&rule, &utils, &constraints, &None, &None, CheckHint::Normal)?;
+    Ok(
+      RuleCore::new(rule)
+        .with_matchers(constraints)
+        .with_utils(utils),
+    )
+  }
+
+  fn take_rule(&mut self) -> Result<Rule<L>, RuleSerializeError> {
+    if let Some(e) = self.error.take() {
+      return Err(e);
+    }
+    let mut rules = std::mem::take(&mut self.rules);
+    rules.append(&mut self.relations);
+    if rules.is_empty() {
+      return Err(RuleSerializeError::MissPositiveMatcher);
+    }
+    if rules.len() == 1 {
+      Ok(rules.pop().expect("should not be empty"))
+    } else {
+      Ok(Rule::All(o::All::new(rules)))
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::from_str;
+  use crate::test::TypeScript;
+
+  fn builder() -> RuleBuilder<TypeScript> {
+    RuleBuilder::new(TypeScript::Tsx)
+  }
+
+  fn find_all(rule: &RuleCore<TypeScript>, src: &str) -> Vec<String> {
+    let grep = TypeScript::Tsx.ast_grep(src);
+    let matches = grep.root().find_all(rule);
+    matches.map(|n| n.text().to_string()).collect()
+  }
+
+  #[test]
+  fn test_build_pattern() {
+    let rule = builder()
+      .pattern("let $A = 1")
+      .build()
+      .expect("should build");
+    assert_eq!(find_all(&rule, "let a = 1\nlet b = 2"), ["let a = 1"]);
+  }
+
+  #[test]
+  fn test_build_composite() {
+    let num = builder().kind("number").build_rule().expect("should build");
+    let str = builder().kind("string").build_rule().expect("should build");
+    let one = builder().pattern("1").build_rule().expect("should build");
+    let rule = builder()
+      .any(vec![num, str])
+      .not(one)
+      .build()
+      .expect("should build");
+    assert_eq!(find_all(&rule, "f(1, 2, 'a', b)"), ["2", "'a'"]);
+  }
+
+  #[test]
+  fn test_build_inside_and_constraint() {
+    let relation: Relation = from_str("kind: arguments").expect("should parse");
+    let upper = builder().pattern("A").build_rule().expect("should build");
+    let rule = builder()
+      .kind("identifier")
+      .pattern("$X")
+      .inside(relation)
+      .constraint("X", upper)
+      .build()
+      .expect("should build");
+    assert_eq!(find_all(&rule, "A(A, B)"), ["A"]);
+  }
+
+  #[test]
+  fn test_build_error() {
+    let ret = builder().kind("not_a_kind").pattern("a").build();
+    assert!(matches!(
+      ret,
+      Err(RuleCoreError::Rule(RuleSerializeError::InvalidKind(_)))
+    ));
+    let ret = builder().build();
+    assert!(matches!(
+      ret,
+      Err(RuleCoreError::Rule(RuleSerializeError::MissPositiveMatcher))
+    ));
+    let any = builder().kind("number").build_rule().expect("should build");
+    let ret = builder()
+      .any(vec![any])
+      .constraint("A", builder().kind("string").build_rule().unwrap())
+      .build();
+    assert!(matches!(ret, Err(RuleCoreError::UndefinedMetaVar(..))));
+  }
+}
@@ -6,6 +6,7 @@ use crate::rule::DeserializeEnv;
 use crate::rule_core::{RuleCore, RuleCoreError, SerializableRuleCore};
 
 use ast_grep_core::language::Language;
+use ast_grep_core::matcher::PatternCache;
 use ast_grep_core::replacer::Replacer;
 use ast_grep_core::{Matcher, NodeMatch, StrDoc};
 
@@ -17,6 +18,7 @@ use thiserror::Error;
 
 use std::collections::{HashMap, HashSet};
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
 #[derive(Serialize, Deserialize, Clone, Default, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -89,15 +91,26 @@ pub struct SerializableRuleConfig<L: Language> {
 
 impl<L: Language> SerializableRuleConfig<L> {
   pub fn get_matcher(&self, globals: &GlobalRules<L>) -> Result<RuleCore<L>, RuleConfigError> {
+    self.get_matcher_with_cache(globals, &Default::default())
+  }
+
+  /// Same as [`SerializableRuleConfig::get_matcher`] but compiles patterns through `cache`,
+  /// which is shared by all rules loaded into one rule collection.
+  pub fn get_matcher_with_cache(
+    &self,
+    globals: &GlobalRules<L>,
+    cache: &Arc<PatternCache<L>>,
+  ) -> Result<RuleCore<L>, RuleConfigError> {
     // every RuleConfig has one rewriters, and the rewriter is shared between sub-rules
     // all RuleConfigs has one common globals
     // every sub-rule has one util
     let rewriters = GlobalRules::default();
     let env = DeserializeEnv::new(self.language.clone())
       .with_globals(globals)
-      .with_rewriters(&rewriters);
+      .with_rewriters(&rewriters)
+      .with_pattern_cache(cache.clone());
     let rule = self.core.get_matcher(env)?;
-    self.register_rewriters(&rule, globals, &rewriters)?;
+    self.register_rewriters(&rule, globals, &rewriters, cache)?;
     Ok(rule)
   }
 
@@ -106,6 +119,7 @@ impl<L: Language> SerializableRuleConfig<L> {
     rule: &RuleCore<L>,
     globals: &GlobalRules<L>,
     rewriters: &GlobalRules<L>,
+    cache: &Arc<PatternCache<L>>,
   ) -> Result<(), RuleConfigError> {
     let Some(ser) = &self.rewriters else {
       return Ok(());
@@ -116,7 +130,7 @@ impl<L: Language> SerializableRuleConfig<L> {
         return Err(RuleConfigError::NoFixInRewriter(val.id.clone()));
       }
       self
-        .register_one_rewriter(val, &vars, globals, rewriters)
+        .register_one_rewriter(val, &vars, globals, rewriters, cache)
         .map_err(|e| RuleConfigError::Rewriter(e, val.id.clone()))?;
     }
     check_rewriters_in_transform(rule, rewriters)?;
@@ -129,12 +143,14 @@ impl<L: Language> SerializableRuleConfig<L> {
     vars: &HashSet<&str>,
     globals: &GlobalRules<L>,
     rewriters: &GlobalRules<L>,
+    cache: &Arc<PatternCache<L>>,
   ) -> Result<(), RuleCoreError> {
     // NB should inherit env from matcher to inherit utils
     // TODO: optimize duplicate env creation/util registration
     let env = DeserializeEnv::new(self.language.clone())
       .with_globals(globals)
-      .with_rewriters(rewriters);
+      .with_rewriters(rewriters)
+      .with_pattern_cache(cache.clone());
     let env = self.get_deserialize_env(env)?;
     let rewriter = val
       .core
@@ -167,7 +183,16 @@ impl<L: Language> RuleConfig<L> {
     inner: SerializableRuleConfig<L>,
     globals: &GlobalRules<L>,
   ) -> Result<Self, RuleConfigError> {
-    let matcher = inner.get_matcher(globals)?;
+    Self::try_from_with_cache(inner, globals, &Default::default())
+  }
+
+  /// Same as [`RuleConfig::try_from`] but shares compiled patterns with other rules in `cache`.
+  pub fn try_from_with_cache(
+    inner: SerializableRuleConfig<L>,
+    globals: &GlobalRules<L>,
+    cache: &Arc<PatternCache<L>>,
+  ) -> Result<Self, RuleConfigError> {
+    let matcher = inner.get_matcher_with_cache(globals, cache)?;
     if matcher.potential_kinds().is_none() {
       return Err(RuleConfigError::MissingPotentialKinds);
     }
@@ -186,8 +211,18 @@ impl<L: Language> RuleConfig<L> {
   }
 
   pub fn get_message(&self, node: &NodeMatch<StrDoc<L>>) -> String {
+    self.expand_meta_vars(&self.message, node)
+  }
+
+  /// The note with meta variables replaced by the matched text, if the rule has one.
+  pub fn get_note(&self, node: &NodeMatch<StrDoc<L>>) -> Option<String> {
+    let note = self.note.as_ref()?;
+    Some(self.expand_meta_vars(note, node))
+  }
+
+  fn expand_meta_vars(&self, text: &str, node: &NodeMatch<StrDoc<L>>) -> String {
     let env = self.matcher.get_env(self.language.clone());
-    let parsed = Fixer::with_transform(&self.message, &env, &self.transform).expect("should work");
+    let parsed = Fixer::with_transform(text, &env, &self.transform).expect("should work");
     let bytes = parsed.generate_replacement(node);
     String::from_utf8(bytes).expect("replacement must be valid utf-8")
   }
@@ -228,6 +263,8 @@ mod test {
       transform: None,
       utils: None,
       fix: None,
+      extends: None,
+      tags: None,
     };
     SerializableRuleConfig {
       core,
@@ -260,6 +297,38 @@ mod test {
     assert_eq!(config.get_message(&node_match), "Found TestClass");
   }
 
+  #[test]
+  fn test_message_with_meta_var() {
+    let globals = GlobalRules::default();
+    let rule = from_str("pattern: $FUNC('$STR')").expect("cannot parse rule");
+    let mut config = ts_rule_config(rule);
+    config.message = "Avoid calling $FUNC with hardcoded strings".into();
+    let config = RuleConfig::try_from(config, &Default::default()).expect("should work");
+    let grep = TypeScript::Tsx.ast_grep("fetch('http://example.com')");
+    let node_match = grep
+      .root()
+      .find(config.get_matcher(&globals).unwrap())
+      .expect("should find match");
+    let message = config.get_message(&node_match);
+    assert_eq!(message, "Avoid calling fetch with hardcoded strings");
+  }
+
+  #[test]
+  fn test_rule_note() {
+    let globals = GlobalRules::default();
+    let rule = from_str("pattern: var $A = $B").expect("cannot parse rule");
+    let mut config = ts_rule_config(rule);
+    config.note = Some("consider using `let $A` instead".into());
+    let config = RuleConfig::try_from(config, &Default::default()).expect("should work");
+    let grep = TypeScript::Tsx.ast_grep("var a = 123");
+    let node_match = grep
+      .root()
+      .find(config.get_matcher(&globals).unwrap())
+      .expect("should find match");
+    let note = config.get_note(&node_match).expect("should have note");
+    assert_eq!(note, "consider using `let a` instead");
+  }
+
   #[test]
   fn test_augmented_rule() {
     let globals = GlobalRules::default();
@@ -357,6 +426,32 @@ B:
     assert_eq!(b, b"2");
   }
 
+  #[test]
+  fn test_transform_in_message() {
+    let globals = GlobalRules::default();
+    let rule = from_str("pattern: $FUNC()").expect("should parse");
+    let mut config = ts_rule_config(rule);
+    config.message = "Use $UPPER_FUNC instead".into();
+    let transform = from_str(
+      "
+UPPER_FUNC:
+  convert:
+    source: $FUNC
+    toCase: upperCase
+",
+    )
+    .expect("should parse");
+    config.transform = Some(transform);
+    assert!(config.fix.is_none());
+    let config = RuleConfig::try_from(config, &globals).expect("should work");
+    let grep = TypeScript::Tsx.ast_grep("foo()");
+    let node_match = grep
+      .root()
+      .find(config.get_matcher(&globals).unwrap())
+      .expect("should find match");
+    assert_eq!(config.get_message(&node_match), "Use FOO instead");
+  }
+
   fn get_matches_config() -> SerializableRuleConfig<TypeScript> {
     let rule = from_str(
       "
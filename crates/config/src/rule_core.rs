@@ -1,15 +1,15 @@
 use crate::check_var::{check_rule_with_hint, CheckHint};
 use crate::fixer::{Fixer, FixerError, SerializableFixer};
 use crate::rule::referent_rule::RuleRegistration;
-use crate::rule::Rule;
+use crate::rule::{MatchExplanation, Rule};
 use crate::rule::{RuleSerializeError, SerializableRule};
 use crate::transform::{Transform, TransformError, Transformation};
 use crate::DeserializeEnv;
 
 use ast_grep_core::language::Language;
 use ast_grep_core::meta_var::MetaVarEnv;
-use ast_grep_core::{Doc, Matcher, Node};
-use serde::{Deserialize, Serialize};
+use ast_grep_core::{Doc, Matcher, Node, NodeMatch};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use serde_yaml::Error as YamlError;
 
 use bit_set::BitSet;
@@ -19,6 +19,10 @@ use thiserror::Error;
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
+#[cfg(feature = "profiling")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "profiling")]
+use std::sync::Arc;
 
 #[derive(Debug, Error)]
 pub enum RuleCoreError {
@@ -42,8 +46,11 @@ type RResult<T> = std::result::Result<T, RuleCoreError>;
 
 /// Used for global rules, rewriters, and pyo3/napi
 #[derive(Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(remote = "Self")]
 pub struct SerializableRuleCore {
-  /// A rule object to find matching AST nodes
+  /// A rule object to find matching AST nodes.
+  /// It can be omitted if the rule `extends` a base rule.
+  #[serde(default)]
   pub rule: SerializableRule,
   /// Additional meta variables pattern to filter matching
   pub constraints: Option<HashMap<String, SerializableRule>>,
@@ -57,9 +64,78 @@ pub struct SerializableRuleCore {
   /// It can reference metavariables appeared in rule.
   /// See details in fix [object reference](https://ast-grep.github.io/reference/yaml/fix.html#fixconfig).
   pub fix: Option<SerializableFixer>,
+  /// Id of a global utility rule to inherit `rule`, `constraints` and `utils` from.
+  /// Fields in this rule override the inherited ones.
+  pub extends: Option<String>,
+  /// Categories of the rule, e.g. security. `scan --tag` selects rules by tags.
+  pub tags: Option<Vec<String>>,
+}
+
+impl Serialize for SerializableRuleCore {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    Self::serialize(self, serializer)
+  }
+}
+
+impl<'de> Deserialize<'de> for SerializableRuleCore {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let core = Self::deserialize(deserializer)?;
+    if core.extends.is_none() && core.rule == SerializableRule::default() {
+      let msg = "missing field `rule`, which can only be omitted if the rule `extends` another";
+      return Err(de::Error::custom(msg));
+    }
+    Ok(core)
+  }
+}
+
+fn overlay_map<T: Clone>(
+  child: &Option<HashMap<String, T>>,
+  base: &Option<HashMap<String, T>>,
+) -> Option<HashMap<String, T>> {
+  let (Some(child), Some(base)) = (child, base) else {
+    return child.clone().or_else(|| base.clone());
+  };
+  let mut ret = base.clone();
+  ret.extend(child.iter().map(|(k, v)| (k.clone(), v.clone())));
+  Some(ret)
 }
 
 impl SerializableRuleCore {
+  /// Inherit `rule`, `constraints` and `utils` from `base`.
+  /// Fields present in `self` take precedence. The result does not extend any rule.
+  pub(crate) fn overlay(&self, base: &Self) -> Self {
+    Self {
+      rule: self.rule.clone().overlay(base.rule.clone()),
+      constraints: overlay_map(&self.constraints, &base.constraints),
+      utils: overlay_map(&self.utils, &base.utils),
+      transform: self.transform.clone(),
+      fix: self.fix.clone(),
+      extends: None,
+      tags: self.tags.clone(),
+    }
+  }
+
+  /// Whether the rule has `tag`, compared case-insensitively.
+  pub fn has_tag(&self, tag: &str) -> bool {
+    let tag = tag.to_lowercase();
+    let tags = self.tags.iter().flatten();
+    tags.map(|t| t.to_lowercase()).any(|t| t == tag)
+  }
+
+  fn resolve_extends<L: Language>(
+    &self,
+    env: &DeserializeEnv<L>,
+  ) -> Result<Cow<'_, Self>, RuleSerializeError> {
+    let Some(base_id) = &self.extends else {
+      return Ok(Cow::Borrowed(self));
+    };
+    let base = env
+      .registration
+      .get_global_source(base_id)
+      .ok_or_else(|| RuleSerializeError::UndefinedExtends(base_id.clone()))?;
+    Ok(Cow::Owned(self.overlay(&base)))
+  }
+
   pub(crate) fn get_deserialize_env<L: Language>(
     &self,
     env: DeserializeEnv<L>,
@@ -127,13 +203,14 @@ impl SerializableRuleCore {
     env: DeserializeEnv<L>,
     hint: CheckHint,
   ) -> RResult<RuleCore<L>> {
-    let env = self.get_deserialize_env(env)?;
-    let ret = self.get_matcher_from_env(&env)?;
+    let core = self.resolve_extends(&env)?;
+    let env = core.get_deserialize_env(env)?;
+    let ret = core.get_matcher_from_env(&env)?;
     check_rule_with_hint(
       &ret.rule,
       &ret.utils,
       &ret.constraints,
-      &self.transform,
+      &core.transform,
       &ret.fixer,
       hint,
     )?;
@@ -149,6 +226,10 @@ pub struct RuleCore<L: Language> {
   pub fixer: Option<Fixer<L>>,
   // this is required to hold util rule reference
   utils: RuleRegistration<L>,
+  // serializable form of global rules, used by rules that `extends` them
+  pub(crate) source: Option<SerializableRuleCore>,
+  #[cfg(feature = "profiling")]
+  nodes_scanned: Arc<AtomicU64>,
 }
 
 impl<L: Language> RuleCore<L> {
@@ -189,6 +270,7 @@ impl<L: Language> RuleCore<L> {
     DeserializeEnv {
       lang,
       registration: self.utils.clone(),
+      pattern_cache: Default::default(),
     }
   }
 
@@ -210,12 +292,105 @@ impl<L: Language> RuleCore<L> {
     ret
   }
 
+  /// Preview the fix of the first match in `source`.
+  /// Returns the full source before and after the fix,
+  /// or None if the rule has no fixer or nothing matches.
+  pub fn get_fix_preview(&self, source: &str, lang: L) -> Option<(String, String)> {
+    let fixer = self.fixer.as_ref()?;
+    let mut grep = lang.ast_grep(source);
+    let edit = grep.root().find(self)?.make_edit(self, fixer);
+    grep.edit(edit).ok()?;
+    Some((source.to_string(), grep.generate()))
+  }
+
+  /// Number of nodes this rule has inspected since creation or the last reset.
+  #[cfg(feature = "profiling")]
+  pub fn count_nodes_scanned(&self) -> u64 {
+    self.nodes_scanned.load(Ordering::Relaxed)
+  }
+
+  #[cfg(feature = "profiling")]
+  pub fn reset_counter(&self) {
+    self.nodes_scanned.store(0, Ordering::Relaxed);
+  }
+
+  /// Explains why the rule does or does not match `node`, checking constraints after the rule.
+  pub fn explain_match<D: Doc<Lang = L>>(&self, node: Node<D>) -> MatchExplanation {
+    let mut env = Cow::Owned(MetaVarEnv::new());
+    let explanation = self.rule.explain_with_env(node, &mut env);
+    if !explanation.is_matched() {
+      return explanation;
+    }
+    // the first failing constraint in variable order is reported
+    for (var, constraint) in self.sorted_constraints() {
+      let Some(captured) = env.get_match(var) else {
+        continue;
+      };
+      let mut attempt = env.clone();
+      if constraint
+        .match_node_with_env(captured.clone(), &mut attempt)
+        .is_none()
+      {
+        let text = captured.text().to_string();
+        return MatchExplanation::FailedConstraint(var.clone(), text);
+      }
+    }
+    explanation
+  }
+
+  /// Constraints sorted by variable name so explanations are deterministic.
+  fn sorted_constraints(&self) -> Vec<(&String, &Rule<L>)> {
+    let mut constraints: Vec<_> = self.constraints.iter().collect();
+    constraints.sort_by_key(|(var, _)| *var);
+    constraints
+  }
+
+  /// Human-readable report of a match for debugging. It lists the matched node,
+  /// its captured meta variables, whether each relational sub-rule is satisfied
+  /// and whether each constraint passes. The output format is not stable.
+  pub fn explain<D: Doc<Lang = L>>(&self, nm: &NodeMatch<D>) -> String {
+    let mut ret = nm.explain();
+    let node = nm.get_node();
+    let env = nm.get_env();
+    for rule in self.rule.relational_rules() {
+      let satisfied = rule
+        .match_node_with_env(node.clone(), &mut Cow::Borrowed(env))
+        .is_some();
+      let status = if satisfied {
+        "satisfied"
+      } else {
+        "not satisfied"
+      };
+      let rule = rule.to_serializable(node.lang());
+      ret.push_str(&format!("\n  {rule}: {status}"));
+    }
+    for (var, constraint) in self.sorted_constraints() {
+      let status = match env.get_match(var) {
+        Some(captured) => {
+          let passed = constraint
+            .match_node_with_env(captured.clone(), &mut Cow::Borrowed(env))
+            .is_some();
+          if passed {
+            "pass"
+          } else {
+            "fail"
+          }
+        }
+        None => "not captured",
+      };
+      ret.push_str(&format!("\n  constraint ${var}: {status}"));
+    }
+    ret
+  }
+
   pub(crate) fn do_match<'tree, D: Doc<Lang = L>>(
     &self,
     node: Node<'tree, D>,
     env: &mut Cow<MetaVarEnv<'tree, D>>,
     enclosing_env: Option<&MetaVarEnv<'tree, D>>,
   ) -> Option<Node<'tree, D>> {
+    #[cfg(feature = "profiling")]
+    self.nodes_scanned.fetch_add(1, Ordering::Relaxed);
     if let Some(kinds) = &self.kinds {
       if !kinds.contains(node.kind_id().into()) {
         return None;
@@ -256,6 +431,9 @@ impl<L: Language> Default for RuleCore<L> {
       transform: None,
       fixer: None,
       utils: RuleRegistration::default(),
+      source: None,
+      #[cfg(feature = "profiling")]
+      nodes_scanned: Arc::default(),
     }
   }
 }
@@ -279,7 +457,7 @@ mod test {
   use super::*;
   use crate::rule::referent_rule::{ReferentRule, ReferentRuleError};
   use crate::test::TypeScript;
-  use crate::{from_str, GlobalRules};
+  use crate::{from_str, GlobalRules, SerializableRuleConfig};
   use ast_grep_core::matcher::{Pattern, RegexMatcher};
 
   fn get_matcher(src: &str) -> RResult<RuleCore<TypeScript>> {
@@ -397,6 +575,147 @@ transform:
     assert_eq!(matched, "2");
   }
 
+  #[test]
+  fn test_rule_is_send_sync() {
+    // scan shares compiled rules across file walker threads
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Rule<TypeScript>>();
+    assert_send_sync::<RuleCore<TypeScript>>();
+  }
+
+  #[test]
+  fn test_negated_constraints() {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let ser_rule: SerializableRuleCore =
+      from_str("{rule: {pattern: foo($A)}, constraints: {A: {not: {kind: number}}} }")
+        .expect("should deser");
+    let matcher = ser_rule.get_matcher(env).expect("should parse");
+    let grep = TypeScript::Tsx.ast_grep("foo(123)");
+    assert!(grep.root().find(&matcher).is_none());
+    let grep = TypeScript::Tsx.ast_grep("foo('abc')");
+    assert!(grep.root().find(&matcher).is_some());
+  }
+
+  #[test]
+  fn test_composite_constraints() {
+    let matches = |constraint: &str, src: &str| {
+      let src_rule = format!("{{rule: {{pattern: foo($A)}}, constraints: {{A: {constraint}}} }}");
+      let matcher = get_matcher(&src_rule).expect("should parse");
+      let grep = TypeScript::Tsx.ast_grep(src);
+      grep.root().find(&matcher).is_some()
+    };
+    let all = r#"{all: [{regex: "^get"}, {kind: identifier}]}"#;
+    assert!(matches(all, "foo(getName)"));
+    assert!(!matches(all, "foo(setName)"));
+    assert!(!matches(all, "foo('getName')"));
+    let any = r#"{any: [{regex: "^get"}, {kind: number}]}"#;
+    assert!(matches(any, "foo(getName)"));
+    assert!(matches(any, "foo(123)"));
+    assert!(!matches(any, "foo(setName)"));
+  }
+
+  #[test]
+  fn test_explain_match_constraint() {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let ser_rule: SerializableRuleCore =
+      from_str("{rule: {pattern: foo($A)}, constraints: {A: {kind: number}} }")
+        .expect("should deser");
+    let matcher = ser_rule.get_matcher(env).expect("should parse");
+    let grep = TypeScript::Tsx.ast_grep("foo('abc')");
+    let node = grep.root().find("foo($A)").expect("should find");
+    let expected = MatchExplanation::FailedConstraint("A".into(), "'abc'".into());
+    assert_eq!(matcher.explain_match(node.get_node().clone()), expected);
+    let grep = TypeScript::Tsx.ast_grep("foo(123)");
+    let node = grep.root().find("foo($A)").expect("should find");
+    let explanation = matcher.explain_match(node.get_node().clone());
+    assert_eq!(explanation, MatchExplanation::Matched);
+  }
+
+  #[test]
+  fn test_explain_match_constraint_order() {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let ser_rule: SerializableRuleCore = from_str(
+      r"
+rule: {pattern: 'foo($C, $A, $B)'}
+constraints:
+  C: {kind: number}
+  A: {kind: number}
+  B: {kind: number}",
+    )
+    .expect("should deser");
+    let matcher = ser_rule.get_matcher(env).expect("should parse");
+    let grep = TypeScript::Tsx.ast_grep("foo(c, a, b)");
+    let node = grep.root().find("foo($$$)").expect("should find");
+    // every constraint fails, the first variable in sorted order is reported
+    let expected = MatchExplanation::FailedConstraint("A".into(), "a".into());
+    for _ in 0..10 {
+      assert_eq!(matcher.explain_match(node.get_node().clone()), expected);
+    }
+  }
+
+  #[test]
+  fn test_explain() {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let ser_rule: SerializableRuleCore = from_str(
+      r"
+rule:
+  pattern: $A = $B
+  any:
+    - inside: {kind: statement_block, stopBy: end}
+    - has: {kind: number}
+constraints:
+  B: {pattern: $C + $D}
+  A: {regex: '^a'}",
+    )
+    .expect("should deser");
+    let matcher = ser_rule.get_matcher(env).expect("should parse");
+    let grep = TypeScript::Tsx.ast_grep("if (x) { a = 1 + 2 }");
+    let nm = grep.root().find(&matcher).expect("should match");
+    let expected = r#"matched `assignment_expression` at line 1: `a = 1 + 2`
+  $A = `a`
+  $B = `1 + 2`
+  $C = `1`
+  $D = `2`
+  inside(kind("statement_block") stopBy(end)): satisfied
+  has(kind("number")): not satisfied
+  constraint $A: pass
+  constraint $B: pass"#;
+    assert_eq!(matcher.explain(&nm), expected);
+  }
+
+  #[test]
+  fn test_explain_constrained_match() {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let ser_rule: SerializableRuleCore = from_str(
+      r"
+rule:
+  pattern: $A = $B
+  inside: {kind: statement_block, stopBy: end}
+constraints:
+  B: {pattern: $C + $D}",
+    )
+    .expect("should deser");
+    let matcher = ser_rule.get_matcher(env).expect("should parse");
+    let grep = TypeScript::Tsx.ast_grep("if (x) { a = 1 + 2 }");
+    let node = grep.root().find("$A = $B").expect("should find");
+    let explanation = matcher.explain_match(node.get_node().clone());
+    assert_eq!(explanation.to_string(), "matched");
+    let grep = TypeScript::Tsx.ast_grep("if (x) { a = 1 }");
+    let node = grep.root().find("$A = $B").expect("should find");
+    let explanation = matcher.explain_match(node.get_node().clone());
+    assert_eq!(explanation.to_string(), "failed constraint: $B = `1`");
+    let grep = TypeScript::Tsx.ast_grep("a = 1 + 2");
+    let node = grep.root().find("$A = $B").expect("should find");
+    let explanation = matcher.explain_match(node.get_node().clone());
+    // expression_statement and program are both visited by `stopBy: end`
+    let expected = r#"failed:
+  failed inside:
+    failed:
+      failed: kind("statement_block")
+      failed: kind("statement_block")"#;
+    assert_eq!(explanation.to_string(), expected);
+  }
+
   fn get_rewriters() -> GlobalRules<TypeScript> {
     // NOTE: initialize a DeserializeEnv here is not 100% correct
     // it does not inherit global rules or local rules
@@ -443,4 +762,158 @@ transform:
     let transformed = env.get_transformed("C").expect("should transform");
     assert_eq!(String::from_utf8_lossy(transformed), "a");
   }
+
+  #[test]
+  fn test_fix_preview() {
+    let rule =
+      get_matcher("rule: {pattern: 'let $A = $B'}\nfix: 'const $A = $B'").expect("should parse");
+    let src = "let a = 1\nlet b = 2";
+    let (original, fixed) = rule
+      .get_fix_preview(src, TypeScript::Tsx)
+      .expect("should preview");
+    assert_eq!(original, src);
+    assert_eq!(fixed, "const a = 1\nlet b = 2");
+    assert!(rule.get_fix_preview("var c = 3", TypeScript::Tsx).is_none());
+    let no_fix = get_matcher("rule: {pattern: 'let $A = $B'}").expect("should parse");
+    assert!(no_fix.get_fix_preview(src, TypeScript::Tsx).is_none());
+  }
+
+  #[test]
+  fn test_typed_meta_var_with_constraints() {
+    let typed =
+      get_matcher("{rule: {pattern: 'foo($A:identifier)'}, constraints: {A: {regex: '^a'}} }")
+        .expect("should parse");
+    let constrained = get_matcher(
+      "{rule: {pattern: 'foo($A)'}, constraints: {A: {kind: identifier, regex: '^a'}} }",
+    )
+    .expect("should parse");
+    for (src, matched) in [
+      ("foo(abc)", true),
+      ("foo(bcd)", false),
+      ("foo('abc')", false),
+    ] {
+      let grep = TypeScript::Tsx.ast_grep(src);
+      assert_eq!(grep.root().find(&typed).is_some(), matched);
+      assert_eq!(grep.root().find(&constrained).is_some(), matched);
+    }
+  }
+
+  fn get_globals(src: &str) -> RResult<GlobalRules<TypeScript>> {
+    let utils = from_str(src).expect("should deser");
+    DeserializeEnv::parse_global_utils(utils)
+  }
+
+  const BASE_RULES: &str = r"
+- id: foo-call
+  language: Tsx
+  rule: {pattern: 'foo($A)'}
+  constraints: {A: {regex: '^a'}}
+- id: foo-ident
+  language: Tsx
+  extends: foo-call
+  rule: {pattern: 'foo($A, $B)'}
+  utils: {is-ident: {kind: identifier}}
+  constraints: {B: {matches: is-ident}}
+";
+
+  fn get_extended(src: &str) -> RResult<RuleCore<TypeScript>> {
+    let globals = get_globals(BASE_RULES)?;
+    let env = DeserializeEnv::new(TypeScript::Tsx).with_globals(&globals);
+    let rule: SerializableRuleCore = from_str(src).expect("should deser");
+    rule.get_matcher(env)
+  }
+
+  fn test_extended(rule: &RuleCore<TypeScript>, expected: &[(&str, bool)]) {
+    for (src, matched) in expected {
+      let grep = TypeScript::Tsx.ast_grep(src);
+      assert_eq!(grep.root().find(rule).is_some(), *matched, "{src}");
+    }
+  }
+
+  #[test]
+  fn test_extends() {
+    let rule = get_extended("extends: foo-call").expect("should parse");
+    test_extended(&rule, &[("foo(abc)", true), ("foo(bcd)", false)]);
+    // child rule fields are merged with the base rule
+    let rule =
+      get_extended("{extends: foo-call, rule: {inside: {kind: if_statement, stopBy: end}}}")
+        .expect("should parse");
+    test_extended(&rule, &[("if (a) { foo(abc) }", true), ("foo(abc)", false)]);
+  }
+
+  #[test]
+  fn test_extends_two_level() {
+    let rule = get_extended("extends: foo-ident").expect("should parse");
+    test_extended(
+      &rule,
+      &[
+        ("foo(abc, b)", true),
+        ("foo(abc, 1)", false),
+        ("foo(bcd, b)", false),
+        ("foo(abc)", false),
+      ],
+    );
+  }
+
+  #[test]
+  fn test_extends_override() {
+    let rule =
+      get_extended("{extends: foo-call, constraints: {A: {regex: '^b'}}}").expect("should parse");
+    test_extended(&rule, &[("foo(abc)", false), ("foo(bcd)", true)]);
+  }
+
+  #[test]
+  fn test_extends_error() {
+    let ret = get_extended("extends: not-exist");
+    assert!(matches!(
+      ret,
+      Err(RuleCoreError::Rule(RuleSerializeError::UndefinedExtends(_)))
+    ));
+    let ret = get_globals(
+      r"
+- {id: a, language: Tsx, extends: b}
+- {id: b, language: Tsx, extends: c}
+- {id: c, language: Tsx, extends: a, rule: {kind: number}}
+",
+    );
+    assert!(matches!(
+      ret,
+      Err(RuleCoreError::Rule(RuleSerializeError::CircularExtends(_)))
+    ));
+  }
+
+  #[test]
+  fn test_rule_required_without_extends() {
+    let ret: Result<SerializableRuleCore, _> = from_str("constraints: {A: {kind: number}}");
+    let err = ret.err().expect("should fail").to_string();
+    assert!(err.contains("missing field `rule`"), "{err}");
+    let ret: Result<SerializableRuleConfig<TypeScript>, _> = from_str("{id: a, language: Tsx}");
+    assert!(ret.is_err());
+    let ret: Result<SerializableRuleConfig<TypeScript>, _> =
+      from_str("{id: a, language: Tsx, extends: b}");
+    assert!(ret.is_ok());
+  }
+
+  #[test]
+  fn test_has_tag() {
+    let rule: SerializableRuleCore =
+      from_str("{rule: {kind: number}, tags: [Security, style]}").expect("should parse");
+    assert!(rule.has_tag("security"));
+    assert!(rule.has_tag("STYLE"));
+    assert!(!rule.has_tag("perf"));
+    let rule: SerializableRuleCore = from_str("rule: {kind: number}").expect("should parse");
+    assert!(!rule.has_tag("security"));
+  }
+
+  #[cfg(feature = "profiling")]
+  #[test]
+  fn test_count_nodes_scanned() {
+    let rule = get_matcher("rule: {pattern: 'let $A = $B'}").expect("should parse");
+    assert_eq!(rule.count_nodes_scanned(), 0);
+    let grep = TypeScript::Tsx.ast_grep("let a = 1; let b = 2");
+    assert_eq!(grep.root().find_all(&rule).count(), 2);
+    assert!(rule.count_nodes_scanned() >= 2);
+    rule.reset_counter();
+    assert_eq!(rule.count_nodes_scanned(), 0);
+  }
 }
@@ -1,5 +1,5 @@
 use crate::meta_var::{extract_meta_var, MetaVariable};
-use crate::{AstGrep, Doc, Node, StrDoc};
+use crate::{AstGrep, Doc, Node, Pattern, StrDoc};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::path::Path;
@@ -28,6 +28,18 @@ pub trait Language: Clone {
   fn skippable_kind_ids(&self) -> &'static [u16] {
     &[]
   }
+  /// kinds of extra nodes that can appear anywhere in the tree, typically comments.
+  /// Most tree-sitter grammars name their comment node `comment`.
+  fn extra_node_kinds(&self) -> &'static [&'static str] {
+    &["comment"]
+  }
+
+  /// strip the statement wrapper around an expression, e.g. a trailing `;`,
+  /// so that `Node::text()` of an expression statement can be reused as an expression.
+  /// Languages that do not wrap expressions return the source unchanged.
+  fn unwrap_expression(&self, src: &str) -> Option<String> {
+    Some(src.to_string())
+  }
 
   /// normalize pattern code before matching
   /// e.g. remove expression_statement, or prefer parsing {} to object over block
@@ -60,6 +72,12 @@ pub trait Language: Clone {
     None
   }
 
+  /// pattern matching import statements, with the imported module captured as `$PATH`.
+  /// Used by [`Node::resolve_imports`]. Languages without a known import syntax return None.
+  fn import_pattern(&self) -> Option<Pattern<Self>> {
+    None
+  }
+
   /// get injected language regions in the root document. e.g. get JavaScripts in HTML
   /// it will return a list of tuples of (language, regions).
   /// The first item is the embedded region language, e.g. javascript
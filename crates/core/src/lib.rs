@@ -23,16 +23,16 @@ mod node;
 pub use language::Language;
 pub use match_tree::MatchStrictness;
 pub use matcher::{Matcher, NodeMatch, Pattern, PatternError};
-pub use node::{Node, Position};
+pub use node::{annotate_range, ChildEdge, Node, Position, Span};
 pub use source::{Doc, StrDoc};
 
 #[doc(hidden)]
-pub use node::DisplayContext;
+pub use node::{ColorConfig, DisplayContext};
 
 use replacer::Replacer;
 
 use node::Root;
-use source::{Edit, TSParseError};
+use source::{Edit, EditError, TSParseError};
 
 #[derive(Clone)]
 pub struct AstGrep<D: Doc> {
@@ -44,11 +44,28 @@ impl<D: Doc> AstGrep<D> {
     self.inner.root()
   }
 
+  /// The whole tree with one named node per line, see [`Node::to_pretty_ast`].
+  pub fn to_pretty_ast(&self) -> String {
+    self.inner.to_pretty_ast()
+  }
+
+  /// Find the node reached by a path from [`Node::path_from_root`].
+  /// See [`Root::node_at_path`] for details.
+  pub fn node_at_path(&self, path: &[ChildEdge]) -> Option<Node<D>> {
+    self.inner.node_at_path(path)
+  }
+
   pub fn edit(&mut self, edit: Edit<D::Source>) -> Result<&mut Self, TSParseError> {
     self.inner.do_edit(edit)?;
     Ok(self)
   }
 
+  /// Apply non-overlapping edits at once. See [`Root::apply_edits`] for details.
+  pub fn apply_edits(&mut self, edits: Vec<Edit<D::Source>>) -> Result<&mut Self, EditError> {
+    self.inner.apply_edits(edits)?;
+    Ok(self)
+  }
+
   pub fn replace<M: Matcher<D::Lang>, R: Replacer<D>>(
     &mut self,
     pattern: M,
@@ -62,10 +79,61 @@ impl<D: Doc> AstGrep<D> {
     }
   }
 
+  /// Replace every match of `matcher` and reparse once. Returns the number of applied edits.
+  /// If edits overlap, e.g. when a replacer expands the matched range,
+  /// only the one starting first is applied.
+  pub fn replace_all_mut<M: Matcher<D::Lang>, R: Replacer<D>>(
+    &mut self,
+    matcher: M,
+    replacer: R,
+  ) -> Result<usize, TSParseError> {
+    let mut edits = self.root().replace_all(matcher, replacer);
+    edits.sort_by_key(|e| (e.position, e.deleted_length));
+    let mut end = None;
+    edits.retain(|e| {
+      if end.map_or(false, |end| end > e.position) {
+        return false;
+      }
+      end = Some(e.position + e.deleted_length);
+      true
+    });
+    let count = edits.len();
+    match self.inner.apply_edits(edits) {
+      Ok(_) => Ok(count),
+      Err(EditError::Parse(e)) => Err(e),
+      Err(EditError::Conflict(_)) => unreachable!("overlapping edits are removed"),
+    }
+  }
+
+  /// If the whole file matches `pattern`. See [`matches_root`].
+  pub fn matches_root<M: Matcher<D::Lang>>(&self, pattern: M) -> bool {
+    // a file of one statement is wrapped by nodes like program and expression_statement
+    let mut node = Some(self.root());
+    while let Some(n) = node {
+      if pattern.match_node(n.clone()).is_some() {
+        return true;
+      }
+      node = match n.named_child_count() {
+        1 => n.named_children().next(),
+        _ => None,
+      };
+    }
+    false
+  }
+
   pub fn lang(&self) -> &D::Lang {
     self.inner.lang()
   }
 
+  /// Consume the AstGrep and return a root node that owns its tree.
+  /// See [`Root::into_root_node`] for details.
+  pub fn into_root_node(self) -> pinned::PinnedNodeData<D, Node<'static, D>>
+  where
+    D: 'static,
+  {
+    self.inner.into_root_node()
+  }
+
   /// Use this method to avoid expensive string encoding overhead
   /// TODO: add more documents on what is happening
   pub fn doc(d: D) -> Self {
@@ -98,6 +166,15 @@ impl<L: Language> AstGrep<StrDoc<L>> {
   }
 }
 
+/// Test if the whole file of `ast` matches `pattern`, anchored at the tree root.
+///
+/// Unlike [`Node::find`], no other subtree is searched. Besides the root node itself,
+/// nodes that wrap the rest of the file as their only named child are tried,
+/// so the pattern `a + 1` matches the file `a + 1` whose root is a `program` node.
+pub fn matches_root<D: Doc, M: Matcher<D::Lang>>(ast: &AstGrep<D>, pattern: M) -> bool {
+  ast.matches_root(pattern)
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -115,6 +192,32 @@ mod test {
     Ok(())
   }
 
+  #[test]
+  fn test_matches_root() {
+    let ast_grep = Tsx.ast_grep("a + 1");
+    assert!(ast_grep.matches_root("a + 1"));
+    assert!(ast_grep.matches_root("$A + $B"));
+    assert!(matches_root(&ast_grep, "$$$STMTS"));
+    assert!(!ast_grep.matches_root("a"));
+    let ast_grep = Tsx.ast_grep("a + 1; b");
+    assert!(!ast_grep.matches_root("a + 1"));
+    assert!(ast_grep.root().find("a + 1").is_some());
+  }
+
+  #[test]
+  fn test_replace_all_mut() -> Result {
+    let mut ast_grep = Tsx.ast_grep("var a = 1; let b = 2; var c = 3;");
+    let count = ast_grep.replace_all_mut("var $A = $B", "let $A = $B")?;
+    assert_eq!(count, 2);
+    assert_eq!(ast_grep.source(), "let a = 1; let b = 2; let c = 3;");
+    assert_eq!(ast_grep.replace_all_mut("var $A = $B", "let $A = $B")?, 0);
+    // nested matches are not replaced twice
+    let mut ast_grep = Tsx.ast_grep("f(f(1))");
+    assert_eq!(ast_grep.replace_all_mut("f($A)", "g($A)")?, 1);
+    assert_eq!(ast_grep.source(), "g(f(1))");
+    Ok(())
+  }
+
   #[test]
   fn test_replace_by_rule() -> Result {
     let rule = Op::either("let a = 123").or("let b = 456");
@@ -158,4 +261,98 @@ mod test {
     assert_eq!(source, "return bar(1, 2) /*haha*/;"); // semicolon
     Ok(())
   }
+
+  #[test]
+  fn test_apply_edits() {
+    let mut ast_grep = Tsx.ast_grep("var a = 1; var b = 2; var c = 3;");
+    let mut edits = ast_grep.root().replace_all("var $A = $B", "let $A = $B");
+    assert_eq!(edits.len(), 3);
+    // order should not matter
+    edits.reverse();
+    ast_grep.apply_edits(edits).expect("should apply");
+    assert_eq!(ast_grep.source(), "let a = 1; let b = 2; let c = 3;");
+    assert!(ast_grep.root().find("let c = 3").is_some());
+  }
+
+  #[test]
+  fn test_apply_insertion_and_deletion() {
+    let mut ast_grep = Tsx.ast_grep("a + b");
+    let edits = vec![
+      Edit {
+        position: 4,
+        deleted_length: 1,
+        inserted_text: b"c".to_vec(),
+      },
+      Edit {
+        position: 4,
+        deleted_length: 0,
+        inserted_text: b"(".to_vec(),
+      },
+      Edit {
+        position: 5,
+        deleted_length: 0,
+        inserted_text: b")".to_vec(),
+      },
+    ];
+    ast_grep.apply_edits(edits).expect("should apply");
+    assert_eq!(ast_grep.source(), "a + (c)");
+  }
+
+  #[test]
+  fn test_edit_round_trip_ranges() -> Result {
+    fn spans(grep: &AstGrep<StrDoc<Tsx>>) -> Vec<(String, std::ops::Range<usize>)> {
+      let root = grep.root();
+      let nodes = root.dfs().map(|n| (n.kind().to_string(), n.range()));
+      nodes.collect()
+    }
+    let mut ast_grep = Tsx.ast_grep("let a = 1;\nlet b = 2;");
+    let edits = [
+      (8, 1, "123"),            // grow a token
+      (0, 0, "const z = 0;\n"), // shift every node
+      (21, 10, "c"),            // shrink across statements
+      (22, 0, " + a"),          // extend an expression
+    ];
+    for (position, deleted_length, text) in edits {
+      ast_grep.edit(Edit {
+        position,
+        deleted_length,
+        inserted_text: text.as_bytes().to_vec(),
+      })?;
+      let fresh = Tsx.ast_grep(ast_grep.source());
+      assert_eq!(
+        spans(&ast_grep),
+        spans(&fresh),
+        "source: {}",
+        ast_grep.source()
+      );
+    }
+    assert_eq!(ast_grep.source(), "const z = 0;\nlet a = c + a = 2;");
+    Ok(())
+  }
+
+  #[test]
+  fn test_apply_overlapping_edits() {
+    let mut ast_grep = Tsx.ast_grep("let a = 123");
+    let edits = vec![
+      Edit {
+        position: 8,
+        deleted_length: 3,
+        inserted_text: b"456".to_vec(),
+      },
+      Edit {
+        position: 4,
+        deleted_length: 5,
+        inserted_text: b"b".to_vec(),
+      },
+    ];
+    let Err(EditError::Conflict(conflict)) = ast_grep.apply_edits(edits) else {
+      panic!("edits should conflict");
+    };
+    assert_eq!(conflict.first, 4..9);
+    assert_eq!(conflict.first_text, "a = 1");
+    assert_eq!(conflict.second, 8..11);
+    assert_eq!(conflict.second_text, "123");
+    // source is untouched
+    assert_eq!(ast_grep.source(), "let a = 123");
+  }
 }
@@ -28,6 +28,23 @@ pub(super) fn match_node_impl<'tree, D: Doc>(
       }
       c => c,
     },
+    P::MetaVar {
+      meta_var: meta_var @ MetaVariable::Optional(name),
+      kind_id,
+    } => {
+      // bind the candidate if possible, otherwise skip the optional goal
+      let is_candidate = candidate.is_named() && kind_id.map_or(true, |k| k == candidate.kind_id());
+      if is_candidate && agg.match_meta_var(meta_var, candidate).is_some() {
+        MatchOneNode::MatchedBoth
+      } else if agg.match_absent(name).is_some() {
+        MatchOneNode::SkipGoal
+      } else {
+        MatchOneNode::NoMatch
+      }
+    }
+    P::MetaVar {
+      kind_id: Some(k), ..
+    } if *k != candidate.kind_id() => MatchOneNode::NoMatch,
     P::MetaVar { meta_var, .. } => match agg.match_meta_var(meta_var, candidate) {
       Some(()) => MatchOneNode::MatchedBoth,
       None => MatchOneNode::NoMatch, // TODO: this may be wrong
@@ -78,8 +95,29 @@ fn match_nodes_impl_recursive<'tree, D: Doc + 'tree>(
       let has_trailing = cand_children.all(|n| strictness.should_skip_trailing(&n));
       return has_trailing.then_some(());
     }
-    cand_children.peek()?;
+    if cand_children.peek().is_none() {
+      return match_remaining_goals(&mut goal_children, agg, strictness);
+    }
+  }
+}
+
+/// When candidates run out, remaining goals must be skippable or optional meta variables.
+fn match_remaining_goals<'p, 't: 'p, D: Doc + 't>(
+  goal_children: &mut Peekable<impl Iterator<Item = &'p PatternNode>>,
+  agg: &mut impl Aggregator<'t, D>,
+  strictness: &MatchStrictness,
+) -> Option<()> {
+  while !strictness.should_skip_goal(goal_children) {
+    let Some(PatternNode::MetaVar {
+      meta_var: MetaVariable::Optional(name),
+      ..
+    }) = goal_children.next()
+    else {
+      return None;
+    };
+    agg.match_absent(name)?;
   }
+  Some(())
 }
 
 enum ControlFlow {
@@ -173,10 +211,9 @@ fn match_single_node_while_skip_trivial<'p, 't: 'p, D: Doc + 't>(
   loop {
     let Some(cand) = cand_children.peek() else {
       // if cand runs out, check remaining goal
-      // if goal is skippable, it is a match, else a non match
-      return strictness
-        .should_skip_goal(goal_children)
-        .then_some(ControlFlow::Fallthrough);
+      // if goal is skippable or optional, it is a match, else a non match
+      match_remaining_goals(goal_children, agg, strictness)?;
+      return Some(ControlFlow::Fallthrough);
     };
     // try match goal node with candidate node
     match match_node_impl(goal_children.peek().unwrap(), cand, agg, strictness) {
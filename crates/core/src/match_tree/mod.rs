@@ -13,6 +13,8 @@ use std::borrow::Cow;
 trait Aggregator<'t, D: Doc> {
   fn match_terminal(&mut self, node: &Node<'t, D>) -> Option<()>;
   fn match_meta_var(&mut self, var: &MetaVariable, node: &Node<'t, D>) -> Option<()>;
+  /// optional meta variable `$?A` matches no node
+  fn match_absent(&mut self, var: &str) -> Option<()>;
   fn match_ellipsis(
     &mut self,
     var: Option<&str>,
@@ -32,6 +34,9 @@ impl<'t, D: Doc> Aggregator<'t, D> for ComputeEnd {
     self.0 = node.range().end;
     Some(())
   }
+  fn match_absent(&mut self, _: &str) -> Option<()> {
+    Some(())
+  }
   fn match_ellipsis(
     &mut self,
     _var: Option<&str>,
@@ -70,6 +75,14 @@ fn match_leaf_meta_var<'tree, D: Doc>(
         Some(())
       }
     }
+    MV::Optional(name) => {
+      if !candidate.is_named() {
+        None
+      } else {
+        env.to_mut().insert(name, candidate.clone())?;
+        Some(())
+      }
+    }
     MV::Dropped(named) => {
       if *named && !candidate.is_named() {
         None
@@ -96,6 +109,10 @@ impl<'t, D: Doc> Aggregator<'t, D> for Cow<'_, MetaVarEnv<'t, D>> {
   fn match_meta_var(&mut self, var: &MetaVariable, node: &Node<'t, D>) -> Option<()> {
     match_leaf_meta_var(var, node, self)
   }
+  fn match_absent(&mut self, var: &str) -> Option<()> {
+    self.to_mut().insert_absent(var)?;
+    Some(())
+  }
   fn match_ellipsis(
     &mut self,
     var: Option<&str>,
@@ -123,29 +140,6 @@ pub fn match_node_non_recursive<'tree, D: Doc>(
   }
 }
 
-pub fn does_node_match_exactly<D: Doc>(goal: &Node<D>, candidate: &Node<D>) -> bool {
-  // return true if goal and candidate are the same node
-  if goal.node_id() == candidate.node_id() {
-    return true;
-  }
-  // gh issue #1087, we make pattern matching a little bit more permissive
-  // compare node text if at least one node is leaf
-  if goal.is_named_leaf() || candidate.is_named_leaf() {
-    return goal.text() == candidate.text();
-  }
-  if goal.kind_id() != candidate.kind_id() {
-    return false;
-  }
-  let goal_children = goal.children();
-  let cand_children = candidate.children();
-  if goal_children.len() != cand_children.len() {
-    return false;
-  }
-  goal_children
-    .zip(cand_children)
-    .all(|(g, c)| does_node_match_exactly(&g, &c))
-}
-
 #[cfg(test)]
 mod test {
   use super::*;
@@ -337,4 +331,33 @@ mod test {
   fn test_gh_1087() {
     test_match("($P) => $F($P)", "(x) => bar(x)");
   }
+
+  #[test]
+  fn test_optional_meta_var() {
+    let env = test_match("return $?VALUE", "function f() { return 42; }");
+    assert_eq!(env["VALUE"], "42");
+    let env = test_match("return $?VALUE", "function f() { return; }");
+    assert!(!env.contains_key("VALUE"));
+    let env = test_match("foo($?A)", "foo()");
+    assert!(!env.contains_key("A"));
+    let env = test_match("foo($?A)", "foo(bar)");
+    assert_eq!(env["A"], "bar");
+    test_non_match("foo($?A)", "foo(a, b)");
+    test_non_match("foo($?A, $A)", "foo(a, b)");
+  }
+
+  #[test]
+  fn test_get_optional() {
+    let grep = Root::<StrDoc<_>>::new("function f() { return; }", Tsx);
+    let node = grep.root().find("return $?VALUE").expect("should match");
+    let env = node.get_env();
+    assert!(matches!(env.get_optional("VALUE"), Some(None)));
+    assert!(env.get_optional("OTHER").is_none());
+    let grep = Root::<StrDoc<_>>::new("function f() { return 42; }", Tsx);
+    let node = grep.root().find("return $?VALUE").expect("should match");
+    let value = node.get_env().get_optional("VALUE");
+    assert_eq!(value.flatten().map(|n| n.text()), Some("42".into()));
+    let end = test_end("return $?VALUE", "return;");
+    assert_eq!(end.expect("should match"), 6);
+  }
 }
@@ -87,21 +87,23 @@ impl MatchStrictness {
       let skipped = match self {
         M::Cst => false,
         M::Smart => match pattern {
-          PatternNode::MetaVar { meta_var } => match meta_var {
+          PatternNode::MetaVar { meta_var, .. } => match meta_var {
             MetaVariable::Multiple => true,
             MetaVariable::MultiCapture(_) => true,
             MetaVariable::Dropped(_) => false,
             MetaVariable::Capture(..) => false,
+            MetaVariable::Optional(_) => false,
           },
           PatternNode::Terminal { .. } => false,
           PatternNode::Internal { .. } => false,
         },
         M::Ast | M::Relaxed | M::Signature => match pattern {
-          PatternNode::MetaVar { meta_var } => match meta_var {
+          PatternNode::MetaVar { meta_var, .. } => match meta_var {
             MetaVariable::Multiple => true,
             MetaVariable::MultiCapture(_) => true,
             MetaVariable::Dropped(named) => !named,
             MetaVariable::Capture(_, named) => !named,
+            MetaVariable::Optional(_) => false,
           },
           PatternNode::Terminal { is_named, .. } => !is_named,
           PatternNode::Internal { .. } => false,
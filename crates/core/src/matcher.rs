@@ -12,6 +12,7 @@ mod pattern;
 mod text;
 
 use crate::meta_var::MetaVarEnv;
+use crate::ops::{And, Not, Or};
 use crate::traversal::Pre;
 use crate::{Doc, Language, Node};
 
@@ -20,7 +21,7 @@ use std::borrow::Cow;
 
 pub use kind::{kind_utils, KindMatcher, KindMatcherError};
 pub use node_match::NodeMatch;
-pub use pattern::{Pattern, PatternError, PatternNode};
+pub use pattern::{Pattern, PatternCache, PatternError, PatternNode};
 #[cfg(feature = "regex")]
 pub use text::{RegexMatcher, RegexMatcherError};
 
@@ -72,6 +73,27 @@ pub trait Matcher<L: Language> {
   }
 }
 
+/// Method syntax for combining matchers, e.g. `kind.and(regex).not()`.
+/// Implemented for every [`Matcher`].
+pub trait MatcherExt<L: Language>: Matcher<L> + Sized {
+  /// Matches if both `self` and `other` match.
+  fn and<M: Matcher<L>>(self, other: M) -> And<L, Self, M> {
+    And::new(self, other)
+  }
+
+  /// Matches if either `self` or `other` matches.
+  fn or<M: Matcher<L>>(self, other: M) -> Or<L, Self, M> {
+    Or::new(self, other)
+  }
+
+  /// Matches if `self` does not match.
+  fn not(self) -> Not<L, Self> {
+    Not::new(self)
+  }
+}
+
+impl<L: Language, T: Matcher<L>> MatcherExt<L> for T {}
+
 impl<L: Language> Matcher<L> for str {
   fn match_node_with_env<'tree, D: Doc<Lang = L>>(
     &self,
@@ -138,6 +160,18 @@ impl<'tree, D: Doc, M: Matcher<D::Lang>> FindAllNodes<'tree, D, M> {
       matcher,
     }
   }
+
+  /// Keep only matches whose meta variable `key` is captured and satisfies `pred`.
+  pub fn filter_env<F>(self, key: &str, pred: F) -> EnvFilterIter<Self, F>
+  where
+    F: Fn(&Node<'tree, D>) -> bool,
+  {
+    EnvFilterIter {
+      inner: self,
+      key: key.to_string(),
+      pred,
+    }
+  }
 }
 
 impl<'tree, D: Doc, M: Matcher<D::Lang>> Iterator for FindAllNodes<'tree, D, M> {
@@ -158,6 +192,26 @@ impl<'tree, D: Doc, M: Matcher<D::Lang>> Iterator for FindAllNodes<'tree, D, M>
   }
 }
 
+/// Iterator returned by [`FindAllNodes::filter_env`].
+pub struct EnvFilterIter<I, F> {
+  inner: I,
+  key: String,
+  pred: F,
+}
+
+impl<'tree, D, I, F> Iterator for EnvFilterIter<I, F>
+where
+  D: Doc + 'tree,
+  I: Iterator<Item = NodeMatch<'tree, D>>,
+  F: Fn(&Node<'tree, D>) -> bool,
+{
+  type Item = NodeMatch<'tree, D>;
+  fn next(&mut self) -> Option<Self::Item> {
+    let Self { inner, key, pred } = self;
+    inner.find(|nm| nm.get_env().get_match(key).map_or(false, &*pred))
+  }
+}
+
 pub struct MatchAll;
 impl<L: Language> Matcher<L> for MatchAll {
   fn match_node_with_env<'tree, D: Doc<Lang = L>>(
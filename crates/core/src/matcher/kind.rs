@@ -25,6 +25,8 @@ pub enum KindMatcherError {
 #[derive(Clone)]
 pub struct KindMatcher<L: Language> {
   kind: KindId,
+  /// the kind name the matcher is created from
+  name: Option<String>,
   lang: PhantomData<L>,
 }
 
@@ -34,6 +36,7 @@ impl<L: Language> KindMatcher<L> {
       kind: lang
         .get_ts_language()
         .id_for_node_kind(node_kind, /*named*/ true),
+      name: Some(node_kind.into()),
       lang: PhantomData,
     }
   }
@@ -50,6 +53,7 @@ impl<L: Language> KindMatcher<L> {
   pub fn from_id(kind: KindId) -> Self {
     Self {
       kind,
+      name: None,
       lang: PhantomData,
     }
   }
@@ -63,6 +67,16 @@ impl<L: Language> KindMatcher<L> {
   pub fn error_matcher() -> Self {
     Self::from_id(TS_BUILTIN_SYM_ERROR)
   }
+
+  /// The tree-sitter kind id this matcher accepts.
+  pub fn kind_id(&self) -> KindId {
+    self.kind
+  }
+
+  /// The kind name this matcher is created from, None if it is created from an id.
+  pub fn kind_name(&self) -> Option<&str> {
+    self.name.as_deref()
+  }
 }
 
 pub mod kind_utils {
@@ -1,7 +1,7 @@
 use super::Matcher;
-use crate::meta_var::MetaVarEnv;
+use crate::meta_var::{is_valid_meta_var_char, MetaVarEnv, MetaVariable};
 use crate::replacer::Replacer;
-use crate::source::Edit;
+use crate::source::{Content, Edit};
 use crate::{Doc, Node};
 
 use std::borrow::Borrow;
@@ -29,6 +29,10 @@ impl<'tree, D: Doc> NodeMatch<'tree, D> {
   pub fn get_env_mut(&mut self) -> &mut MetaVarEnv<'tree, D> {
     &mut self.1
   }
+  /// Splits the match into the matched node and its env without cloning.
+  pub fn into_parts(self) -> (Node<'tree, D>, MetaVarEnv<'tree, D>) {
+    (self.0, self.1)
+  }
   /// # Safety
   /// should only called for readopting nodes
   pub(crate) unsafe fn get_node_mut(&mut self) -> &mut Node<'tree, D> {
@@ -37,6 +41,47 @@ impl<'tree, D: Doc> NodeMatch<'tree, D> {
 }
 
 impl<D: Doc> NodeMatch<'_, D> {
+  /// Returns a human-readable report of the matched node and the meta-variables it captured.
+  /// Internal labels like `secondary` are skipped. It is intended for debugging,
+  /// so the output format is not stable. See `RuleCore::explain` for sub-rules and constraints.
+  pub fn explain(&self) -> String {
+    let node = self.get_node();
+    let env = self.get_env();
+    let is_user_var = |name: &str| name.chars().all(is_valid_meta_var_char);
+    let mut vars: Vec<_> = env
+      .get_matched_variables()
+      .filter_map(|var| match var {
+        MetaVariable::Capture(name, _) if is_user_var(&name) => {
+          if let Some(n) = env.get_match(&name) {
+            Some(format!("${name} = `{}`", n.text()))
+          } else {
+            let bytes = env.get_transformed(&name)?;
+            let text = D::Source::encode_bytes(bytes);
+            Some(format!("${name} (transformed) = `{text}`"))
+          }
+        }
+        MetaVariable::MultiCapture(name) if is_user_var(&name) => {
+          let nodes = env.get_multiple_matches(&name);
+          let texts: Vec<_> = nodes.iter().map(|n| format!("`{}`", n.text())).collect();
+          Some(format!("$$${name} = [{}]", texts.join(", ")))
+        }
+        _ => None,
+      })
+      .collect();
+    vars.sort();
+    let mut ret = format!(
+      "matched `{}` at line {}: `{}`",
+      node.kind(),
+      node.start_pos().line() + 1,
+      node.text()
+    );
+    for var in vars {
+      ret.push_str("\n  ");
+      ret.push_str(&var);
+    }
+    ret
+  }
+
   pub fn replace_by<R: Replacer<D>>(&self, replacer: R) -> Edit<D::Source> {
     let range = self.range();
     let position = range.start;
@@ -131,6 +176,18 @@ mod test {
     assert_eq!(node.text(), "a");
   }
 
+  #[test]
+  fn test_explain() {
+    let root = Tsx.ast_grep("let a = 1\nfoo(a, b, c)");
+    let find = root.root().find("$F($A, $$$REST)").expect("should find");
+    let explained = find.explain();
+    let expected = "matched `call_expression` at line 2: `foo(a, b, c)`
+  $$$REST = [`b`, `,`, `c`]
+  $A = `a`
+  $F = `foo`";
+    assert_eq!(explained, expected);
+  }
+
   #[test]
   fn test_replace_by() {
     let root = Tsx.ast_grep("var a = 1");
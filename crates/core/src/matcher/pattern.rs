@@ -1,7 +1,7 @@
-use crate::language::Language;
+use crate::language::{Language, TSLanguage};
 use crate::match_tree::{match_end_non_recursive, match_node_non_recursive, MatchStrictness};
 use crate::matcher::{kind_utils, KindMatcher, KindMatcherError, Matcher};
-use crate::meta_var::{MetaVarEnv, MetaVariable};
+use crate::meta_var::{is_valid_meta_var_char, MetaVarEnv, MetaVariable};
 use crate::source::TSParseError;
 use crate::{Doc, Node, Root, StrDoc};
 
@@ -9,21 +9,30 @@ use bit_set::BitSet;
 use thiserror::Error;
 
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
+use std::sync::RwLock;
 
 #[derive(Clone)]
 pub struct Pattern<L: Language> {
   pub node: PatternNode,
   root_kind: Option<u16>,
+  /// kind name of the selector of a contextual pattern
+  selector: Option<String>,
+  /// source text the pattern is parsed from, used for re-parsing in other languages
+  source: String,
   lang: PhantomData<L>,
   pub strictness: MatchStrictness,
+  /// named single meta variables in pattern order, see [`Pattern::named_captures`]
+  captures: Vec<String>,
 }
 
 #[derive(Clone)]
 pub enum PatternNode {
   MetaVar {
     meta_var: MetaVariable,
+    /// kind required by an inline annotation like `$A:identifier`
+    kind_id: Option<u16>,
   },
   /// Node without children.
   Terminal {
@@ -74,18 +83,26 @@ impl<'r, D: Doc> From<Node<'r, D>> for PatternNode {
 
 impl<'r, D: Doc> From<Node<'r, D>> for Pattern<D::Lang> {
   fn from(node: Node<'r, D>) -> Self {
+    let pattern_node = convert_node_to_pattern(node.clone());
+    let captures = named_captures(&pattern_node);
     Self {
-      node: convert_node_to_pattern(node),
+      source: node.text().to_string(),
+      node: pattern_node,
       root_kind: None,
+      selector: None,
       lang: PhantomData,
       strictness: MatchStrictness::Smart,
+      captures,
     }
   }
 }
 
 fn convert_node_to_pattern<D: Doc>(node: Node<D>) -> PatternNode {
   if let Some(meta_var) = extract_var_from_node(&node) {
-    PatternNode::MetaVar { meta_var }
+    PatternNode::MetaVar {
+      meta_var,
+      kind_id: None,
+    }
   } else if node.is_leaf() {
     PatternNode::Terminal {
       text: node.text().to_string(),
@@ -107,6 +124,127 @@ fn convert_node_to_pattern<D: Doc>(node: Node<D>) -> PatternNode {
   }
 }
 
+/// Meta variable annotations stripped from the pattern source.
+#[derive(Default)]
+struct MetaVarAnnotations {
+  /// annotated kind id of typed meta variables like `$A:identifier`
+  kinds: HashMap<String, u16>,
+  /// names of optional meta variables like `$?A`
+  optional: HashSet<String>,
+}
+
+impl MetaVarAnnotations {
+  fn is_empty(&self) -> bool {
+    self.kinds.is_empty() && self.optional.is_empty()
+  }
+}
+
+/// Strip inline kind annotations like `$A:identifier` and optional markers like `$?A`
+/// from the pattern source, so that the stripped source can be parsed by tree-sitter.
+/// The annotation must directly follow the variable name and name a valid node kind,
+/// otherwise the colon is kept as code, e.g. `{ $K: $V }`.
+/// The colon is also kept as code if the source parses without error that way,
+/// e.g. the TypeScript type annotation in `($A:string) => 1`.
+fn strip_typed_meta_vars<'s, L: Language>(
+  src: &'s str,
+  lang: &L,
+) -> (Cow<'s, str>, MetaVarAnnotations) {
+  let (stripped, annotations) = strip_annotations(src, lang, true);
+  if annotations.kinds.is_empty() {
+    return (stripped, annotations);
+  }
+  let (code, optional_only) = strip_annotations(src, lang, false);
+  let processed = lang.pre_process_pattern(&code);
+  match Root::<StrDoc<L>>::try_new(&processed, lang.clone()) {
+    Ok(root) if !root.root().inner.has_error() => (code, optional_only),
+    _ => (stripped, annotations),
+  }
+}
+
+fn strip_annotations<'s, L: Language>(
+  src: &'s str,
+  lang: &L,
+  strip_kinds: bool,
+) -> (Cow<'s, str>, MetaVarAnnotations) {
+  let meta_char = lang.meta_var_char();
+  let ts_lang = lang.get_ts_language();
+  let mut annotations = MetaVarAnnotations::default();
+  let mut stripped = String::with_capacity(src.len());
+  let mut rest = src;
+  while let Some(pos) = rest.find(meta_char) {
+    let (code, var) = rest.split_at(pos);
+    stripped.push_str(code);
+    let name_start = var.trim_start_matches(meta_char);
+    let prefix_len = var.len() - name_start.len();
+    stripped.push_str(&var[..prefix_len]);
+    // `$?A` is an optional capture, the question mark is not part of the parsed source
+    let (name_start, optional) = match name_start.strip_prefix('?') {
+      Some(n)
+        if prefix_len == meta_char.len_utf8()
+          && n.starts_with(|c: char| c.is_ascii_uppercase()) =>
+      {
+        (n, true)
+      }
+      _ => (name_start, false),
+    };
+    let name_len = name_start
+      .find(|c| !is_valid_meta_var_char(c))
+      .unwrap_or(name_start.len());
+    let name = &name_start[..name_len];
+    stripped.push_str(name);
+    rest = &name_start[name_len..];
+    if optional {
+      annotations.optional.insert(name.to_string());
+    }
+    // only single captures like $A or $$A can be typed
+    let is_capture = prefix_len <= 2 * meta_char.len_utf8() && name.starts_with(|c| c != '_');
+    let Some(annotation) = rest
+      .strip_prefix(':')
+      .filter(|_| strip_kinds && is_capture && !name.is_empty())
+    else {
+      continue;
+    };
+    let kind_len = annotation
+      .find(|c: char| !(c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'))
+      .unwrap_or(annotation.len());
+    let kind = &annotation[..kind_len];
+    let kind_id = ts_lang.id_for_node_kind(kind, /* named */ true);
+    if kind.is_empty() || kind_id == 0 {
+      continue;
+    }
+    annotations.kinds.insert(name.to_string(), kind_id);
+    rest = &annotation[kind_len..];
+  }
+  if annotations.is_empty() {
+    return (Cow::Borrowed(src), annotations);
+  }
+  stripped.push_str(rest);
+  (Cow::Owned(stripped), annotations)
+}
+
+fn annotate_meta_vars(node: &mut PatternNode, annotations: &MetaVarAnnotations) {
+  if annotations.is_empty() {
+    return;
+  }
+  match node {
+    PatternNode::MetaVar { meta_var, kind_id } => {
+      let MetaVariable::Capture(name, _) = meta_var else {
+        return;
+      };
+      *kind_id = annotations.kinds.get(name).copied();
+      if annotations.optional.contains(name) {
+        *meta_var = MetaVariable::Optional(name.clone());
+      }
+    }
+    PatternNode::Internal { children, .. } => {
+      for child in children {
+        annotate_meta_vars(child, annotations);
+      }
+    }
+    _ => {}
+  }
+}
+
 fn extract_var_from_node<D: Doc>(goal: &Node<D>) -> Option<MetaVariable> {
   let key = goal.text();
   goal.lang().extract_meta_var(&key)
@@ -167,6 +305,27 @@ impl<L: Language> Pattern<L> {
     collect_vars(&self.node, &mut vars);
     vars
   }
+
+  /// The text the pattern is parsed from. It is the context for contextual patterns.
+  pub fn source(&self) -> &str {
+    &self.source
+  }
+
+  /// Kind id of the selector of a contextual pattern.
+  pub fn root_kind(&self) -> Option<u16> {
+    self.root_kind
+  }
+
+  /// Kind name of the selector of a contextual pattern.
+  pub fn selector(&self) -> Option<&str> {
+    self.selector.as_deref()
+  }
+
+  /// Names of the meta variables capturing a single node, like `$A` or `$?A`,
+  /// in the order they first appear. `$_` and `$$$ARGS` are not included.
+  pub fn named_captures(&self) -> &[String] {
+    &self.captures
+  }
 }
 
 fn meta_var_name(meta_var: &MetaVariable) -> Option<&str> {
@@ -174,11 +333,38 @@ fn meta_var_name(meta_var: &MetaVariable) -> Option<&str> {
   match meta_var {
     MV::Capture(name, _) => Some(name),
     MV::MultiCapture(name) => Some(name),
+    MV::Optional(name) => Some(name),
     MV::Dropped(_) => None,
     MV::Multiple => None,
   }
 }
 
+fn named_captures(p: &PatternNode) -> Vec<String> {
+  let mut captures = vec![];
+  collect_captures(p, &mut captures);
+  captures
+}
+
+fn collect_captures(p: &PatternNode, captures: &mut Vec<String>) {
+  use MetaVariable as MV;
+  match p {
+    PatternNode::MetaVar { meta_var, .. } => {
+      let (MV::Capture(name, _) | MV::Optional(name)) = meta_var else {
+        return;
+      };
+      if !captures.contains(name) {
+        captures.push(name.clone());
+      }
+    }
+    PatternNode::Terminal { .. } => (),
+    PatternNode::Internal { children, .. } => {
+      for c in children {
+        collect_captures(c, captures);
+      }
+    }
+  }
+}
+
 fn collect_vars<'p>(p: &'p PatternNode, vars: &mut HashSet<&'p str>) {
   match p {
     PatternNode::MetaVar { meta_var, .. } => {
@@ -199,7 +385,8 @@ fn collect_vars<'p>(p: &'p PatternNode, vars: &mut HashSet<&'p str>) {
 
 impl<L: Language> Pattern<L> {
   pub fn try_new(src: &str, lang: L) -> Result<Self, PatternError> {
-    let processed = lang.pre_process_pattern(src);
+    let (stripped, annotations) = strip_typed_meta_vars(src, &lang);
+    let processed = lang.pre_process_pattern(&stripped);
     let root = Root::<StrDoc<L>>::try_new(&processed, lang)?;
     let goal = root.root();
     if goal.inner.child_count() == 0 {
@@ -209,20 +396,39 @@ impl<L: Language> Pattern<L> {
       return Err(PatternError::MultipleNode(src.into()));
     }
     let node = Self::single_matcher(&root);
-    Ok(Self::from(node))
+    let mut pattern = Self {
+      source: src.into(),
+      ..Self::from(node)
+    };
+    annotate_meta_vars(&mut pattern.node, &annotations);
+    Ok(pattern)
   }
 
   pub fn new(src: &str, lang: L) -> Self {
     Self::try_new(src, lang).unwrap()
   }
 
+  /// Same as [`Pattern::try_new`] but reuses the pattern compiled earlier in `cache`.
+  pub fn try_new_cached(src: &str, lang: L, cache: &PatternCache<L>) -> Result<Self, PatternError> {
+    cache.get_or_insert((src, None), lang, |lang| Self::try_new(src, lang))
+  }
+
+  pub fn new_cached(src: &str, lang: L, cache: &PatternCache<L>) -> Self {
+    Self::try_new_cached(src, lang, cache).unwrap()
+  }
+
   pub fn with_strictness(mut self, strictness: MatchStrictness) -> Self {
     self.strictness = strictness;
     self
   }
 
+  /// Create a pattern from a snippet that only parses correctly in a wider `context`,
+  /// e.g. a method `$M() {}` needs a surrounding class to be a `method_definition`.
+  /// The first node of kind `selector` in the parsed context becomes the pattern root.
+  /// Returns [`PatternError::NoSelectorInContext`] if no such node exists.
   pub fn contextual(context: &str, selector: &str, lang: L) -> Result<Self, PatternError> {
-    let processed = lang.pre_process_pattern(context);
+    let (stripped, annotations) = strip_typed_meta_vars(context, &lang);
+    let processed = lang.pre_process_pattern(&stripped);
     let root = Root::<StrDoc<L>>::try_new(&processed, lang.clone())?;
     let goal = root.root();
     let kind_matcher = KindMatcher::try_new(selector, lang)?;
@@ -232,17 +438,68 @@ impl<L: Language> Pattern<L> {
         selector: selector.into(),
       });
     };
+    let node_kind = node.kind_id();
+    let mut node = convert_node_to_pattern(node.get_node().clone());
+    annotate_meta_vars(&mut node, &annotations);
     Ok(Self {
-      root_kind: Some(node.kind_id()),
-      node: convert_node_to_pattern(node.get_node().clone()),
+      source: context.into(),
+      root_kind: Some(node_kind),
+      selector: Some(selector.into()),
+      captures: named_captures(&node),
+      node,
       lang: PhantomData,
       strictness: MatchStrictness::Smart,
     })
   }
+  /// Same as [`Pattern::contextual`] but reuses the pattern compiled earlier in `cache`.
+  pub fn contextual_cached(
+    context: &str,
+    selector: &str,
+    lang: L,
+    cache: &PatternCache<L>,
+  ) -> Result<Self, PatternError> {
+    cache.get_or_insert((context, Some(selector)), lang, |lang| {
+      Self::contextual(context, selector, lang)
+    })
+  }
+  /// Create a pattern spanning a sequence of statements.
+  ///
+  /// The source is wrapped in a synthetic block and the block becomes the pattern root,
+  /// so the pattern matches a block containing exactly the given statements.
+  /// The wrapper `function() { ... }` with selector `statement_block` targets JavaScript/TypeScript.
+  /// Other languages need their own wrapper with [`Pattern::contextual`], e.g.
+  /// `fn f() { ... }` with `block` for Rust, or `func f() { ... }` with `block` for Go.
+  pub fn new_multiline(src: &str, lang: L) -> Self {
+    Self::try_new_multiline(src, lang).unwrap()
+  }
+
+  pub fn try_new_multiline(src: &str, lang: L) -> Result<Self, PatternError> {
+    let context = format!("function() {{ {src} }}");
+    Self::contextual(&context, "statement_block", lang)
+  }
+
   pub fn doc(doc: StrDoc<L>) -> Self {
     let root = Root::doc(doc);
     Self::from(root.root())
   }
+
+  /// Build a pattern matching nodes structurally equal to `node`, e.g. to find duplicated code.
+  /// The node is converted directly instead of re-parsing its text,
+  /// so the pattern keeps the node's kind even if the text alone parses differently.
+  pub fn from_node<D: Doc<Lang = L>>(node: &Node<D>) -> Self {
+    Self::from(node.clone())
+  }
+
+  /// Check if the pattern source can be parsed by another language without syntax error.
+  /// e.g. `$A + $B` is compatible with both JavaScript and TypeScript but JSX is not valid TypeScript.
+  pub fn compatible_with<L2: Language>(&self, lang: &L2) -> bool {
+    let (stripped, _) = strip_typed_meta_vars(&self.source, lang);
+    let processed = lang.pre_process_pattern(&stripped);
+    match Root::<StrDoc<L2>>::try_new(&processed, lang.clone()) {
+      Ok(root) => !root.root().inner.has_error(),
+      Err(_) => false,
+    }
+  }
   fn single_matcher<D: Doc>(root: &Root<D>) -> Node<D> {
     // debug_assert!(matches!(self.style, PatternStyle::Single));
     let node = root.root();
@@ -254,6 +511,80 @@ impl<L: Language> Pattern<L> {
   }
 }
 
+type CacheKey = (String, Option<String>);
+/// grammar, meta variable char and expando char, which all change the compiled pattern
+type LangKey = (TSLanguage, char, char);
+type CacheEntries<L> = Vec<(LangKey, Pattern<L>)>;
+
+fn lang_key<L: Language>(lang: &L) -> LangKey {
+  (
+    lang.get_ts_language(),
+    lang.meta_var_char(),
+    lang.expando_char(),
+  )
+}
+
+/// Memoize compiled patterns keyed by pattern source, selector and language.
+/// Compiling a pattern requires a tree-sitter parse, so rules sharing a pattern string
+/// only pay it once. Cached patterns are cloned out, so later `with_strictness`
+/// or constraints on one rule do not affect others.
+pub struct PatternCache<L: Language> {
+  patterns: RwLock<HashMap<CacheKey, CacheEntries<L>>>,
+}
+
+impl<L: Language> Default for PatternCache<L> {
+  fn default() -> Self {
+    Self {
+      patterns: Default::default(),
+    }
+  }
+}
+
+impl<L: Language> PatternCache<L> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// number of cached patterns
+  pub fn len(&self) -> usize {
+    let patterns = self.patterns.read().expect("cache should not be poisoned");
+    patterns.values().map(Vec::len).sum()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  fn get_or_insert<F>(
+    &self,
+    (src, selector): (&str, Option<&str>),
+    lang: L,
+    compile: F,
+  ) -> Result<Pattern<L>, PatternError>
+  where
+    F: FnOnce(L) -> Result<Pattern<L>, PatternError>,
+  {
+    let key = (src.to_string(), selector.map(String::from));
+    let lang_key = lang_key(&lang);
+    {
+      let patterns = self.patterns.read().expect("cache should not be poisoned");
+      let cached = patterns
+        .get(&key)
+        .and_then(|v| v.iter().find(|(l, _)| *l == lang_key));
+      if let Some((_, pattern)) = cached {
+        return Ok(pattern.clone());
+      }
+    }
+    let pattern = compile(lang)?;
+    let mut patterns = self.patterns.write().expect("cache should not be poisoned");
+    let entry = patterns.entry(key).or_default();
+    if !entry.iter().any(|(l, _)| *l == lang_key) {
+      entry.push((lang_key, pattern.clone()));
+    }
+    Ok(pattern)
+  }
+}
+
 impl<L: Language> Matcher<L> for Pattern<L> {
   fn match_node_with_env<'tree, D: Doc<Lang = L>>(
     &self,
@@ -278,7 +609,7 @@ impl<L: Language> Matcher<L> for Pattern<L> {
   fn potential_kinds(&self) -> Option<bit_set::BitSet> {
     let kind = match self.node {
       PatternNode::Terminal { kind_id, .. } => kind_id,
-      PatternNode::MetaVar { .. } => self.root_kind?,
+      PatternNode::MetaVar { kind_id, .. } => self.root_kind.or(kind_id)?,
       PatternNode::Internal { kind_id, .. } => {
         if kind_utils::is_error_kind(kind_id) {
           // error can match any kind
@@ -318,7 +649,7 @@ impl<L: Language> std::fmt::Debug for Pattern<L> {
 #[cfg(test)]
 mod test {
   use super::*;
-  use crate::language::Tsx;
+  use crate::language::{TSLanguage, Tsx};
   use std::collections::HashMap;
 
   fn pattern_node(s: &str) -> Root<StrDoc<Tsx>> {
@@ -439,6 +770,22 @@ mod test {
     assert!(nm.is_none());
   }
 
+  #[test]
+  fn test_contextual_selector() {
+    let pattern =
+      Pattern::contextual("class A { $M() {} }", "method_definition", Tsx).expect("test");
+    assert_eq!(pattern.selector(), Some("method_definition"));
+    let cand = pattern_node("class B { foo() {} }");
+    let nm = pattern.find_node(cand.root()).expect("test");
+    assert_eq!(nm.kind(), "method_definition");
+    assert_eq!(nm.get_env().get_match("M").expect("test").text(), "foo");
+    // without the class context the snippet is a call followed by a block
+    let ret = Pattern::try_new("$M() {}", Tsx);
+    assert!(matches!(ret, Err(PatternError::MultipleNode(_))));
+    let ret = Pattern::contextual("let a = 1", "method_definition", Tsx);
+    assert!(matches!(ret, Err(PatternError::NoSelectorInContext { .. })));
+  }
+
   fn get_kind(kind_str: &str) -> usize {
     Tsx
       .get_ts_language()
@@ -490,6 +837,20 @@ mod test {
     assert!(kinds.contains(kind));
   }
 
+  #[test]
+  fn test_multiline_pattern() {
+    let pattern = Pattern::new_multiline("const $A = require($B); const { $C } = $A;", Tsx);
+    let cand = pattern_node("function f() { const a = require('a'); const { b } = a; }");
+    let nm = pattern.find_node(cand.root()).expect("should match");
+    let env = HashMap::from(nm.get_env().clone());
+    assert_eq!(env["A"], "a");
+    assert_eq!(env["C"], "b");
+    let cand = pattern_node("function f() { const a = require('a'); const { b } = c; }");
+    assert!(pattern.find_node(cand.root()).is_none());
+    let cand = pattern_node("const a = require('a')");
+    assert!(pattern.find_node(cand.root()).is_none());
+  }
+
   #[test]
   #[ignore]
   fn test_multi_node_pattern() {
@@ -530,6 +891,18 @@ mod test {
     assert!(ret.has_error());
   }
 
+  #[test]
+  fn test_compatible_with() {
+    let ts = tree_sitter_typescript::language_typescript();
+    let ts = TSLanguage::from(ts);
+    let pattern = Pattern::str("$A + $B", Tsx);
+    assert!(pattern.compatible_with(&Tsx));
+    assert!(pattern.compatible_with(&ts));
+    let pattern = Pattern::str("<div>{$A}</div>", Tsx);
+    assert!(pattern.compatible_with(&Tsx));
+    assert!(!pattern.compatible_with(&ts));
+  }
+
   #[test]
   fn test_bare_wildcard_in_context() {
     let pattern = Pattern::contextual("class A { $F }", "property_identifier", Tsx).expect("test");
@@ -592,6 +965,31 @@ mod test {
     assert_eq!(vars, ["A"]);
   }
 
+  #[test]
+  fn test_from_node() {
+    let grep = Tsx.ast_grep("foo(a, 1)\nbar(foo(a, 1))\nfoo(a, 2)");
+    let call = grep.root().find("foo($$$)").expect("should find");
+    let pattern = Pattern::from_node(call.get_node());
+    let found: Vec<_> = grep
+      .root()
+      .find_all(&pattern)
+      .map(|n| n.start_pos().line())
+      .collect();
+    assert_eq!(found, [0, 1]);
+    // the nested call is not a statement, so only the first line matches
+    let stmt = call.parent().expect("should have parent");
+    let pattern = Pattern::from_node(&stmt);
+    assert_eq!(grep.root().find_all(&pattern).count(), 1);
+  }
+
+  #[test]
+  fn test_named_captures() {
+    let pattern = Pattern::str("function $FUNC($$$ARGS, $_): $RET { $FUNC($A, $A) }", Tsx);
+    assert_eq!(pattern.named_captures(), ["FUNC", "RET", "A"]);
+    let pattern = Pattern::str("console.log(1)", Tsx);
+    assert!(pattern.named_captures().is_empty());
+  }
+
   #[test]
   fn test_contextual_pattern_vars() {
     let pattern = Pattern::contextual("<div ref={$A}/>", "jsx_attribute", Tsx).expect("correct");
@@ -602,4 +1000,96 @@ mod test {
   fn test_gh_1087() {
     test_match("($P) => $F($P)", "(x) => bar(x)");
   }
+
+  #[test]
+  fn test_pattern_cache() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+    let cache = PatternCache::new();
+    assert_send_sync(&cache);
+    let p1 = Pattern::new_cached("let $A = 1", Tsx, &cache);
+    let p2 = Pattern::new_cached("let $A = 1", Tsx, &cache);
+    assert_eq!(cache.len(), 1);
+    let strict = p2.with_strictness(MatchStrictness::Cst);
+    assert!(matches!(strict.strictness, MatchStrictness::Cst));
+    let p3 = Pattern::new_cached("let $A = 1", Tsx, &cache);
+    assert!(matches!(p3.strictness, MatchStrictness::Smart));
+    assert_eq!(p1.defined_vars(), p3.defined_vars());
+    let ctx = Pattern::contextual_cached("let $A = 1", "number", Tsx, &cache).expect("correct");
+    assert_eq!(cache.len(), 2);
+    assert!(ctx.defined_vars().is_empty());
+    assert!(Pattern::try_new_cached("", Tsx, &cache).is_err());
+    assert_eq!(cache.len(), 2);
+    // same source in different languages is cached separately
+    let cache = PatternCache::new();
+    let ts = TSLanguage::from(tree_sitter_typescript::language_typescript());
+    Pattern::new_cached("let $A = 1", ts, &cache);
+    Pattern::new_cached("let $A = 1", Tsx.get_ts_language(), &cache);
+    assert_eq!(cache.len(), 2);
+  }
+
+  #[test]
+  fn test_pattern_cache_meta_var_char() {
+    // same grammar with different meta variable chars
+    #[derive(Clone)]
+    enum Custom {
+      Dollar,
+      Hash,
+    }
+    impl Language for Custom {
+      fn get_ts_language(&self) -> TSLanguage {
+        Tsx.get_ts_language()
+      }
+      fn meta_var_char(&self) -> char {
+        match self {
+          Custom::Dollar => '$',
+          Custom::Hash => '#',
+        }
+      }
+    }
+    let cache = PatternCache::new();
+    let dollar = Pattern::new_cached("a($A)", Custom::Dollar, &cache);
+    let other = Pattern::new_cached("a($A)", Custom::Hash, &cache);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(dollar.defined_vars(), ["A"].into_iter().collect());
+    assert!(other.defined_vars().is_empty());
+  }
+
+  #[test]
+  fn test_typed_meta_var() {
+    test_match("foo($A:identifier)", "foo(a)");
+    test_non_match("foo($A:identifier)", "foo(1)");
+    test_match("foo($A:number)", "foo(1)");
+    test_match("a = $A:number", "a = 1");
+    test_non_match("a = $A:number", "a = b");
+    // not a valid kind, or not a type annotation
+    test_match("foo($A:not_a_kind)", "foo($A:not_a_kind)");
+    test_match("({ a: $A })", "({ a: 1 })");
+    let env = match_env("foo($A:identifier, $B)", "foo(a, 1)");
+    assert_eq!(env["A"], "a");
+    assert_eq!(env["B"], "1");
+    let pattern = Pattern::str("[$A:number]", Tsx);
+    assert_eq!(pattern.defined_vars(), ["A"].into_iter().collect());
+  }
+
+  #[test]
+  fn test_type_annotation_is_not_typed_meta_var() {
+    test_match("function f($A: number) {}", "function f(a: number) {}");
+    test_match("($A:string) => 1", "(a: string) => 1");
+    test_non_match("($A:string) => 1", "('a') => 1");
+    let env = match_env("($A:string) => 1", "(a:string) => 1");
+    assert_eq!(env["A"], "a");
+    // a labeled statement is valid code as well
+    test_match("$A:number", "a: number");
+    test_non_match("$A:number", "let a = 1");
+  }
+
+  #[test]
+  fn test_typed_meta_var_in_context() {
+    let pattern =
+      Pattern::contextual("obj[$K:string]", "subscript_expression", Tsx).expect("should parse");
+    let cand = pattern_node("obj['a']");
+    assert!(pattern.find_node(cand.root()).is_some());
+    let cand = pattern_node("obj[a]");
+    assert!(pattern.find_node(cand.root()).is_none());
+  }
 }
@@ -3,7 +3,7 @@ use crate::meta_var::MetaVarEnv;
 use crate::{Doc, Language, Node};
 
 use bit_set::BitSet;
-use regex::{Error as RegexError, Regex};
+use regex::{Error as RegexError, Regex, RegexBuilder};
 use thiserror::Error;
 
 use std::borrow::Cow;
@@ -13,21 +13,50 @@ use std::marker::PhantomData;
 pub enum RegexMatcherError {
   #[error("Parsing text matcher fails.")]
   Regex(#[from] RegexError),
+  #[error("Unsupported regex flag `{0}`, expected `i`, `m` or `s`.")]
+  InvalidFlag(char),
 }
 
 #[derive(Clone)]
 pub struct RegexMatcher<L: Language> {
   regex: Regex,
+  flags: String,
   lang: PhantomData<L>,
 }
 
 impl<L: Language> RegexMatcher<L> {
   pub fn try_new(text: &str) -> Result<Self, RegexMatcherError> {
+    Self::try_new_with_flags(text, "")
+  }
+
+  /// Supported flags are `i` (case-insensitive), `m` (multi-line)
+  /// and `s` (`.` matches new line).
+  pub fn try_new_with_flags(text: &str, flags: &str) -> Result<Self, RegexMatcherError> {
+    let mut builder = RegexBuilder::new(text);
+    for flag in flags.chars() {
+      match flag {
+        'i' => builder.case_insensitive(true),
+        'm' => builder.multi_line(true),
+        's' => builder.dot_matches_new_line(true),
+        c => return Err(RegexMatcherError::InvalidFlag(c)),
+      };
+    }
     Ok(RegexMatcher {
-      regex: Regex::new(text)?,
+      regex: builder.build()?,
+      flags: flags.to_string(),
       lang: PhantomData,
     })
   }
+
+  /// The source string of the compiled regex.
+  pub fn as_str(&self) -> &str {
+    self.regex.as_str()
+  }
+
+  /// The flags the regex is compiled with, empty if none.
+  pub fn flags(&self) -> &str {
+    &self.flags
+  }
 }
 
 impl<L: Language> Matcher<L> for RegexMatcher<L> {
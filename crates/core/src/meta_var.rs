@@ -1,14 +1,31 @@
-use crate::match_tree::does_node_match_exactly;
 use crate::matcher::Matcher;
 use crate::source::Content;
 use crate::{Doc, Language, Node, StrDoc};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
 
 use crate::replacer::formatted_slice;
 
 pub type MetaVariableID = String;
 
+/// A meta variable captured different code in two environments being merged.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("meta variable `{name}` is bound to `{existing_text}` but merged with `{incoming_text}`.")]
+pub struct MetaVarConflict {
+  pub name: String,
+  pub existing_text: String,
+  pub incoming_text: String,
+}
+
+/// A capture yielded by [`MetaVarEnv::iter`].
+pub enum MatchResult<'a, 'tree, D: Doc> {
+  /// A single node captured by `$A` or `$?A`.
+  Single(&'a Node<'tree, D>),
+  /// Nodes captured by `$$$A`, which may be empty.
+  Multi(&'a [Node<'tree, D>]),
+}
+
 type Underlying<D> = Vec<<<D as Doc>::Source as Content>::Underlying>;
 /// a dictionary that stores metavariable instantiation
 /// const a = 123 matched with const a = $A will produce env: $A => 123
@@ -16,6 +33,8 @@ type Underlying<D> = Vec<<<D as Doc>::Source as Content>::Underlying>;
 pub struct MetaVarEnv<'tree, D: Doc> {
   single_matched: HashMap<MetaVariableID, Node<'tree, D>>,
   multi_matched: HashMap<MetaVariableID, Vec<Node<'tree, D>>>,
+  /// optional meta variables like `$?A` that matched no node
+  absent_optional: HashSet<MetaVariableID>,
   transformed_var: HashMap<MetaVariableID, Underlying<D>>,
 }
 
@@ -24,6 +43,7 @@ impl<'tree, D: Doc> MetaVarEnv<'tree, D> {
     Self {
       single_matched: HashMap::new(),
       multi_matched: HashMap::new(),
+      absent_optional: HashSet::new(),
       transformed_var: HashMap::new(),
     }
   }
@@ -46,9 +66,18 @@ impl<'tree, D: Doc> MetaVarEnv<'tree, D> {
     }
   }
 
+  /// Records that the optional meta variable `$?A` matched no node.
+  pub fn insert_absent(&mut self, id: &str) -> Option<&mut Self> {
+    if self.single_matched.contains_key(id) {
+      return None;
+    }
+    self.absent_optional.insert(id.to_string());
+    Some(self)
+  }
+
   pub fn insert_transformation(&mut self, var: &MetaVariable, name: &str, slice: Underlying<D>) {
     let node = match var {
-      MetaVariable::Capture(v, _) => self.single_matched.get(v),
+      MetaVariable::Capture(v, _) | MetaVariable::Optional(v) => self.single_matched.get(v),
       MetaVariable::MultiCapture(vs) => self.multi_matched.get(vs).and_then(|vs| vs.first()),
       _ => None,
     };
@@ -64,6 +93,19 @@ impl<'tree, D: Doc> MetaVarEnv<'tree, D> {
     self.single_matched.get(var)
   }
 
+  /// Returns `None` if the variable is not captured at all,
+  /// `Some(None)` if the optional variable `$?A` matched no node,
+  /// and `Some(Some(node))` if it matched a node.
+  pub fn get_optional(&self, var: &str) -> Option<Option<&'_ Node<'tree, D>>> {
+    if let Some(node) = self.single_matched.get(var) {
+      Some(Some(node))
+    } else if self.absent_optional.contains(var) {
+      Some(None)
+    } else {
+      None
+    }
+  }
+
   pub fn get_multiple_matches(&self, var: &str) -> Vec<Node<'tree, D>> {
     self.multi_matched.get(var).cloned().unwrap_or_default()
   }
@@ -109,6 +151,99 @@ impl<'tree, D: Doc> MetaVarEnv<'tree, D> {
     single.chain(multi).chain(transformed)
   }
 
+  /// Number of single and multiple captures, in that order.
+  pub fn len(&self) -> (usize, usize) {
+    (self.single_matched.len(), self.multi_matched.len())
+  }
+
+  /// Returns true if no single or multiple capture is recorded.
+  pub fn is_empty(&self) -> bool {
+    self.single_matched.is_empty() && self.multi_matched.is_empty()
+  }
+
+  /// All single and multiple captures with their names, single captures first.
+  /// Transformed variables are not included since they have no node.
+  pub fn iter(&self) -> impl Iterator<Item = (&str, MatchResult<'_, 'tree, D>)> {
+    let single = self
+      .single_matched
+      .iter()
+      .map(|(id, node)| (id.as_str(), MatchResult::Single(node)));
+    let multi = self
+      .multi_matched
+      .iter()
+      .map(|(id, nodes)| (id.as_str(), MatchResult::Multi(nodes.as_slice())));
+    single.chain(multi)
+  }
+
+  /// Names of all single and multiple captures.
+  pub fn keys(&self) -> impl Iterator<Item = &str> {
+    let single = self.single_matched.keys();
+    let multi = self.multi_matched.keys();
+    single.chain(multi).map(String::as_str)
+  }
+
+  /// Merges captures of `other` into this env.
+  /// Shared variables must capture structurally equal nodes, otherwise the first
+  /// conflict is returned and `self` is left untouched.
+  pub fn merge(&mut self, other: MetaVarEnv<'tree, D>) -> Result<(), MetaVarConflict> {
+    self.check_conflict(&other)?;
+    let MetaVarEnv {
+      single_matched,
+      multi_matched,
+      absent_optional,
+      transformed_var,
+    } = other;
+    for (id, node) in single_matched {
+      self.single_matched.entry(id).or_insert(node);
+    }
+    for (id, nodes) in multi_matched {
+      self.multi_matched.entry(id).or_insert(nodes);
+    }
+    self.absent_optional.extend(absent_optional);
+    for (id, slice) in transformed_var {
+      self.transformed_var.entry(id).or_insert(slice);
+    }
+    Ok(())
+  }
+
+  /// Like [`MetaVarEnv::merge`] but returns the merged env without mutating `self`.
+  pub fn try_merge(&self, other: MetaVarEnv<'tree, D>) -> Result<Self, MetaVarConflict> {
+    let mut merged = self.clone();
+    merged.merge(other)?;
+    Ok(merged)
+  }
+
+  fn check_conflict(&self, other: &Self) -> Result<(), MetaVarConflict> {
+    let conflict = |name: &str, existing_text: String, incoming_text: String| MetaVarConflict {
+      name: name.to_string(),
+      existing_text,
+      incoming_text,
+    };
+    for (id, node) in &other.single_matched {
+      if self.absent_optional.contains(id) {
+        return Err(conflict(id, String::new(), node.text().into()));
+      }
+      if let Some(existing) = self.single_matched.get(id) {
+        if !self.match_variable(id, node) {
+          return Err(conflict(id, existing.text().into(), node.text().into()));
+        }
+      }
+    }
+    for id in &other.absent_optional {
+      if let Some(existing) = self.single_matched.get(id) {
+        return Err(conflict(id, existing.text().into(), String::new()));
+      }
+    }
+    for (id, nodes) in &other.multi_matched {
+      if let Some(existing) = self.multi_matched.get(id) {
+        if !self.match_multi_var(id, nodes) {
+          return Err(conflict(id, join_text(existing), join_text(nodes)));
+        }
+      }
+    }
+    Ok(())
+  }
+
   pub fn match_constraints<M: Matcher<D::Lang>>(
     &mut self,
     var_matchers: &HashMap<MetaVariableID, M>,
@@ -128,8 +263,11 @@ impl<'tree, D: Doc> MetaVarEnv<'tree, D> {
   }
 
   fn match_variable(&self, id: &str, candidate: &Node<D>) -> bool {
+    if self.absent_optional.contains(id) {
+      return false;
+    }
     if let Some(m) = self.single_matched.get(id) {
-      return does_node_match_exactly(m, candidate);
+      return m.structurally_eq(candidate);
     }
     true
   }
@@ -145,7 +283,7 @@ impl<'tree, D: Doc> MetaVarEnv<'tree, D> {
           // cand is done but node is not
           break false;
         };
-        if !does_node_match_exactly(node, cand) {
+        if !node.structurally_eq(cand) {
           break false;
         }
       } else if named_cands.next().is_some() {
@@ -175,6 +313,45 @@ impl<'tree, D: Doc> MetaVarEnv<'tree, D> {
   }
 }
 
+fn join_text<D: Doc>(nodes: &[Node<D>]) -> String {
+  let texts: Vec<_> = nodes.iter().map(|n| n.text()).collect();
+  texts.join(" ")
+}
+
+/// JSON serialization of captures, for tools consuming matches programmatically.
+#[cfg(feature = "serde_json")]
+impl<D: Doc> MetaVarEnv<'_, D> {
+  /// Returns an object keyed by capture name. A single capture is an object with
+  /// `text`, `kind` and zero-based `startLine`, `startCol`, `endLine`, `endCol`.
+  /// A multiple capture, like `$$$ARGS`, is an array of such objects.
+  pub fn to_json(&self) -> serde_json::Value {
+    let map = self
+      .iter()
+      .map(|(id, matched)| {
+        let value = match matched {
+          MatchResult::Single(node) => node_to_json(node),
+          MatchResult::Multi(nodes) => nodes.iter().map(node_to_json).collect(),
+        };
+        (id.to_string(), value)
+      })
+      .collect();
+    serde_json::Value::Object(map)
+  }
+}
+
+#[cfg(feature = "serde_json")]
+fn node_to_json<D: Doc>(node: &Node<D>) -> serde_json::Value {
+  let span = node.span();
+  serde_json::json!({
+    "text": node.text(),
+    "startLine": span.start_line,
+    "startCol": span.start_col,
+    "endLine": span.end_line,
+    "endCol": span.end_col,
+    "kind": node.kind(),
+  })
+}
+
 fn get_var_bytes_impl<'t, C, D>(
   env: &'t MetaVarEnv<'t, D>,
   var: &MetaVariable,
@@ -184,7 +361,7 @@ where
   C: Content + 't,
 {
   match var {
-    MetaVariable::Capture(n, _) => {
+    MetaVariable::Capture(n, _) | MetaVariable::Optional(n) => {
       if let Some(node) = env.get_match(n) {
         let bytes = node.root.doc.get_source().get_range(node.range());
         Some(bytes)
@@ -227,6 +404,8 @@ pub enum MetaVariable {
   Multiple,
   /// $$$A for captured ellipsis
   MultiCapture(MetaVariableID),
+  /// $?A for meta var matching zero or one node
+  Optional(MetaVariableID),
 }
 
 pub(crate) fn extract_meta_var(src: &str, meta_char: char) -> Option<MetaVariable> {
@@ -249,6 +428,13 @@ pub(crate) fn extract_meta_var(src: &str, meta_char: char) -> Option<MetaVariabl
     return None;
   }
   let trimmed = &src[meta_char.len_utf8()..];
+  if let Some(name) = trimmed.strip_prefix('?') {
+    let is_capture = name.starts_with(|c| is_valid_first_char(c) && c != '_');
+    if !is_capture || !name.chars().all(is_valid_meta_var_char) {
+      return None;
+    }
+    return Some(Optional(name.to_owned()));
+  }
   let (trimmed, named) = if let Some(t) = trimmed.strip_prefix(meta_char) {
     (t, false)
   } else {
@@ -318,6 +504,9 @@ mod test {
     assert_eq!(extract_var("$_"), Some(Dropped(true)));
     assert_eq!(extract_var("$_123"), Some(Dropped(true)));
     assert_eq!(extract_var("$$_"), Some(Dropped(false)));
+    assert_eq!(extract_var("$?A"), Some(Optional("A".into())));
+    assert_eq!(extract_var("$?_"), None);
+    assert_eq!(extract_var("$?"), None);
   }
 
   #[test]
@@ -385,4 +574,116 @@ mod test {
     let not_found = node.find("if (true) { $$$A } else { $$$A }");
     assert!(not_found.is_none());
   }
+
+  #[test]
+  fn test_env_len_keys() {
+    let grep = Tsx.ast_grep("foo(a, b, c)");
+    let node = grep.root().find("foo($A, $$$B)").expect("should match");
+    let env = node.get_env();
+    assert_eq!(env.len(), (1, 1));
+    assert!(!env.is_empty());
+    let mut keys: Vec<_> = env.keys().collect();
+    keys.sort();
+    assert_eq!(keys, ["A", "B"]);
+    let node = grep.root().find("foo($_, $$$)").expect("should match");
+    assert!(node.get_env().is_empty());
+    assert_eq!(node.get_env().keys().count(), 0);
+  }
+
+  #[test]
+  fn test_env_iter() {
+    let grep = Tsx.ast_grep("foo(a, b, c)");
+    let node = grep.root().find("foo($A, $$$B)").expect("should match");
+    let mut bindings: Vec<_> = node
+      .get_env()
+      .iter()
+      .map(|(name, matched)| match matched {
+        MatchResult::Single(n) => (name, n.text().to_string()),
+        MatchResult::Multi(ns) => {
+          let texts: Vec<_> = ns.iter().map(|n| n.text()).collect();
+          (name, texts.join("|"))
+        }
+      })
+      .collect();
+    bindings.sort();
+    assert_eq!(bindings, [("A", "a".into()), ("B", "b|,|c".into())]);
+  }
+
+  #[test]
+  #[cfg(feature = "serde_json")]
+  fn test_to_json() {
+    let grep = Tsx.ast_grep("foo(a,\n  b)");
+    let node = grep.root().find("foo($A, $$$B)").expect("should match");
+    let json = node.get_env().to_json();
+    let a = &json["A"];
+    assert_eq!(a["text"], "a");
+    assert_eq!(a["kind"], "identifier");
+    assert_eq!((&a["startLine"], &a["startCol"]), (&0.into(), &4.into()));
+    assert_eq!((&a["endLine"], &a["endCol"]), (&0.into(), &5.into()));
+    let b = json["B"].as_array().expect("should be array");
+    assert_eq!(b.len(), 1);
+    assert_eq!(b[0]["text"], "b");
+    assert_eq!(
+      (&b[0]["startLine"], &b[0]["startCol"]),
+      (&1.into(), &2.into())
+    );
+  }
+
+  fn env_of<'t>(
+    root: &'t crate::AstGrep<StrDoc<Tsx>>,
+    pattern: &str,
+  ) -> MetaVarEnv<'t, StrDoc<Tsx>> {
+    let node = root.root().find(pattern).expect("should match");
+    node.get_env().clone()
+  }
+
+  #[test]
+  fn test_merge_disjoint() {
+    let grep = Tsx.ast_grep("foo(a, b, c); bar(d)");
+    let mut env = env_of(&grep, "foo($A, $$$B)");
+    env.merge(env_of(&grep, "bar($C)")).expect("should merge");
+    assert_eq!(env.len(), (2, 1));
+    assert_eq!(env.get_match("A").map(|n| n.text()), Some("a".into()));
+    assert_eq!(env.get_match("C").map(|n| n.text()), Some("d".into()));
+  }
+
+  #[test]
+  fn test_merge_agreeing() {
+    let grep = Tsx.ast_grep("foo(a, b, c); bar(a, b, c)");
+    let env = env_of(&grep, "foo($A, $$$B)");
+    let merged = env
+      .try_merge(env_of(&grep, "bar($A, $$$B)"))
+      .expect("should merge");
+    assert_eq!(merged.len(), (1, 1));
+    let b: Vec<_> = merged.get_multiple_matches("B");
+    assert_eq!(b.len(), 3);
+  }
+
+  #[test]
+  fn test_merge_conflicting() {
+    let grep = Tsx.ast_grep("foo(a, b); bar(c, b); baz(a, d)");
+    let env = env_of(&grep, "foo($A, $$$B)");
+    let Err(err) = env.try_merge(env_of(&grep, "bar($A, $$$B)")) else {
+      panic!("should conflict");
+    };
+    assert_eq!(
+      err,
+      MetaVarConflict {
+        name: "A".into(),
+        existing_text: "a".into(),
+        incoming_text: "c".into(),
+      }
+    );
+    let mut env = env;
+    let err = env
+      .merge(env_of(&grep, "baz($A, $$$B)"))
+      .expect_err("should conflict");
+    assert_eq!(err.name, "B");
+    assert_eq!(
+      (err.existing_text.as_str(), err.incoming_text.as_str()),
+      ("b", "d")
+    );
+    // failed merge leaves env untouched
+    assert_eq!(env.len(), (1, 1));
+  }
 }
@@ -1,13 +1,17 @@
 use crate::language::Language;
-use crate::matcher::{FindAllNodes, Matcher, NodeMatch};
+use crate::matcher::{FindAllNodes, KindMatcher, Matcher, NodeMatch};
+use crate::meta_var::MetaVarEnv;
 use crate::replacer::Replacer;
-use crate::source::{perform_edit, Content, Edit as E, TSParseError};
-use crate::traversal::{Pre, Visitor};
+use crate::source::{perform_edit, Content, Edit as E, EditConflict, EditError, TSParseError};
+use crate::traversal::{DfsWithDepth, Level, LevelWithDepth, Pre, Visitor};
 use crate::{Doc, StrDoc};
 
 type Edit<D> = E<<D as Doc>::Source>;
 
 use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::ops::Range;
+use std::sync::RwLock;
 
 /// Represents a position in the source code.
 /// The line and column are zero-based, character offsets.
@@ -45,6 +49,48 @@ impl Position {
   }
 }
 
+/// Zero-based line and character column span of a node.
+/// Columns are counted in characters, see [`Position::column`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+  feature = "serde",
+  derive(serde::Serialize, serde::Deserialize),
+  serde(rename_all = "camelCase")
+)]
+pub struct Span {
+  pub start_line: usize,
+  pub start_col: usize,
+  pub end_line: usize,
+  pub end_col: usize,
+}
+
+/// One step from a parent node to its child, see [`Node::path_from_root`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ChildEdge {
+  /// Field name of the child in its parent, if the grammar assigns one.
+  pub field: Option<&'static str>,
+  /// Index of the child among all children, named or not.
+  pub child_index: usize,
+}
+
+/// Field names come from a fixed grammar table, so each distinct name is leaked once
+/// and shared by later lookups instead of allocating a new string every time.
+fn intern_field_name(name: Cow<str>) -> &'static str {
+  static NAMES: RwLock<BTreeSet<&'static str>> = RwLock::new(BTreeSet::new());
+  let names = NAMES.read().expect("field names should not be poisoned");
+  if let Some(interned) = names.get(&*name) {
+    return interned;
+  }
+  drop(names);
+  let mut names = NAMES.write().expect("field names should not be poisoned");
+  if let Some(interned) = names.get(&*name) {
+    return interned;
+  }
+  let interned: &'static str = Box::leak(name.into_owned().into_boxed_str());
+  names.insert(interned);
+  interned
+}
+
 /// Represents [`tree_sitter::Tree`] and owns source string
 /// Note: Root is generic against [`Language`](crate::language::Language)
 #[derive(Clone)]
@@ -92,15 +138,64 @@ impl<D: Doc> Root<D> {
     }
   }
 
+  /// The whole tree dumped by [`Node::to_pretty_ast`].
+  pub fn to_pretty_ast(&self) -> String {
+    self.root().to_pretty_ast()
+  }
+
+  /// Follow a path from [`Node::path_from_root`] back to the node.
+  /// Returns `None` if a child is missing or has a different field name.
+  pub fn node_at_path(&self, path: &[ChildEdge]) -> Option<Node<D>> {
+    path.iter().try_fold(self.root(), |node, edge| {
+      let child = node.child(edge.child_index)?;
+      (child.field_name() == edge.field).then_some(child)
+    })
+  }
+
   // extract non generic implementation to reduce code size
   pub fn do_edit(&mut self, edit: Edit<D>) -> Result<(), TSParseError> {
     let source = self.doc.get_source_mut();
-    let input_edit = perform_edit(&mut self.inner, source, &edit);
-    self.inner.edit(&input_edit);
+    // perform_edit already calls Tree::edit before the reparse below.
+    // Editing the old tree twice shifts its ranges twice and breaks incremental parsing.
+    perform_edit(&mut self.inner, source, &edit);
     self.inner = self.doc.parse(Some(&self.inner))?;
     Ok(())
   }
 
+  /// Apply a batch of edits, e.g. from [`Node::replace_all`], and reparse once.
+  /// Edits are sorted by position and rejected as a whole if any two of them overlap.
+  /// Otherwise they are applied from the highest offset so earlier offsets stay valid.
+  pub fn apply_edits(&mut self, mut edits: Vec<Edit<D>>) -> Result<&mut Self, EditError> {
+    // insertion sorts before deletion at the same position so they do not conflict
+    edits.sort_by_key(|e| (e.position, e.deleted_length));
+    let source = self.doc.get_source();
+    for pair in edits.windows(2) {
+      let first = pair[0].position..pair[0].position + pair[0].deleted_length;
+      let second = pair[1].position..pair[1].position + pair[1].deleted_length;
+      if first.end > second.start {
+        let get_text = |r: &Range<usize>| {
+          let bytes = source.get_range(r.clone());
+          D::Source::encode_bytes(bytes).into_owned()
+        };
+        return Err(EditError::Conflict(EditConflict {
+          first_text: get_text(&first),
+          second_text: get_text(&second),
+          first,
+          second,
+        }));
+      }
+    }
+    if edits.is_empty() {
+      return Ok(self);
+    }
+    let source = self.doc.get_source_mut();
+    for edit in edits.iter().rev() {
+      perform_edit(&mut self.inner, source, edit);
+    }
+    self.inner = self.doc.parse(Some(&self.inner))?;
+    Ok(self)
+  }
+
   /// Adopt the tree_sitter as the descendant of the root and return the wrapped sg Node.
   /// It assumes `inner` is the under the root and will panic at dev build if wrong node is used.
   pub fn adopt<'r>(&'r self, inner: tree_sitter::Node<'r>) -> Node<'r, D> {
@@ -151,12 +246,32 @@ pub struct Node<'r, D: Doc> {
   pub(crate) inner: tree_sitter::Node<'r>,
   pub(crate) root: &'r Root<D>,
 }
+
+/// Nodes are equal if they are the same tree-sitter node in the same tree.
+/// N.B. Comparing nodes from different Root instances is meaningless,
+/// even if the Roots are parsed from the same source.
+impl<D: Doc> PartialEq for Node<'_, D> {
+  fn eq(&self, other: &Self) -> bool {
+    std::ptr::eq(self.root, other.root) && self.inner == other.inner
+  }
+}
+
+impl<D: Doc> Eq for Node<'_, D> {}
+
+impl<D: Doc> std::hash::Hash for Node<'_, D> {
+  fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    self.inner.hash(state)
+  }
+}
+
 pub type KindId = u16;
 
 struct NodeWalker<'tree, D: Doc> {
   cursor: tree_sitter::TreeCursor<'tree>,
   root: &'tree Root<D>,
   count: usize,
+  // skip unnamed siblings, count must be the named child count
+  named: bool,
 }
 
 impl<'tree, D: Doc> Iterator for NodeWalker<'tree, D> {
@@ -165,6 +280,9 @@ impl<'tree, D: Doc> Iterator for NodeWalker<'tree, D> {
     if self.count == 0 {
       return None;
     }
+    while self.named && !self.cursor.node().is_named() {
+      self.cursor.goto_next_sibling();
+    }
     let ret = Some(Node {
       inner: self.cursor.node(),
       root: self.root,
@@ -198,6 +316,23 @@ impl<'r, D: Doc> Node<'r, D> {
   pub fn is_error(&self) -> bool {
     self.inner.is_error()
   }
+  /// if the node is inserted by tree-sitter's error recovery and absent in the source.
+  pub fn is_missing(&self) -> bool {
+    self.inner.is_missing()
+  }
+  /// if the node is an extra node like comments, which can appear anywhere in the tree.
+  /// See [`Language::extra_node_kinds`].
+  pub fn is_extra(&self) -> bool {
+    // tree-sitter-facade-sg 0.24.5 forwards is_extra to is_named by mistake, so check kinds
+    // configured by the language instead. See `Node::is_extra` in
+    // https://github.com/ast-grep/tree-sitter-facade/blob/main/src/node.rs
+    let kind = self.kind();
+    self.lang().extra_node_kinds().contains(&kind.as_ref())
+  }
+  /// if the node or any of its descendants is an `ERROR` node.
+  pub fn contains_error(&self) -> bool {
+    self.dfs().any(|n| n.is_error())
+  }
   pub fn kind(&self) -> Cow<str> {
     self.inner.kind()
   }
@@ -209,6 +344,42 @@ impl<'r, D: Doc> Node<'r, D> {
     self.inner.is_named()
   }
 
+  /// Check if two nodes have the same structure regardless of their positions.
+  /// Non-leaf nodes must have the same kind and structurally equal children.
+  /// If either node is a named leaf, only their texts are compared.
+  pub fn structurally_eq(&self, other: &Node<D>) -> bool {
+    self.structurally_eq_by(other, &|a, b| a == b)
+  }
+
+  /// Same as `structurally_eq` but leaf texts are compared with whitespace normalized,
+  /// e.g. `"a  b"` and `"a b"` are equal.
+  pub fn structurally_eq_ignoring_trivia(&self, other: &Node<D>) -> bool {
+    self.structurally_eq_by(other, &|a, b| a.split_whitespace().eq(b.split_whitespace()))
+  }
+
+  fn structurally_eq_by(&self, other: &Node<D>, text_eq: &impl Fn(&str, &str) -> bool) -> bool {
+    // return true if self and other are the same node
+    if self.node_id() == other.node_id() {
+      return true;
+    }
+    // gh issue #1087, we make pattern matching a little bit more permissive
+    // compare node text if at least one node is leaf
+    if self.is_named_leaf() || other.is_named_leaf() {
+      return text_eq(&self.text(), &other.text());
+    }
+    if self.kind_id() != other.kind_id() {
+      return false;
+    }
+    let children = self.children();
+    let other_children = other.children();
+    if children.len() != other_children.len() {
+      return false;
+    }
+    children
+      .zip(other_children)
+      .all(|(a, b)| a.structurally_eq_by(&b, text_eq))
+  }
+
   /// the underlying tree-sitter Node
   pub fn get_ts_node(&self) -> tree_sitter::Node<'r> {
     self.inner.clone()
@@ -233,6 +404,33 @@ impl<'r, D: Doc> Node<'r, D> {
     Position::new(pos.row(), pos.column(), byte)
   }
 
+  /// Nodes' start and end position in lines and character columns.
+  pub fn span(&self) -> Span {
+    let start = self.start_pos();
+    let end = self.end_pos();
+    Span {
+      start_line: start.line(),
+      start_col: start.column(self),
+      end_line: end.line(),
+      end_col: end.column(self),
+    }
+  }
+
+  /// Length of the node's text in bytes. Same as `self.range().len()`.
+  pub fn byte_length(&self) -> usize {
+    self.range().len()
+  }
+
+  /// Length of the node's text in unicode characters.
+  pub fn char_length(&self) -> usize {
+    self.text().chars().count()
+  }
+
+  /// Number of lines spanned by the node, counting both start and end lines.
+  pub fn line_count(&self) -> usize {
+    self.end_pos().line() - self.start_pos().line() + 1
+  }
+
   pub fn text(&self) -> Cow<'r, str> {
     let source = self.root.doc.get_source();
     source.get_text(&self.inner)
@@ -243,9 +441,68 @@ impl<'r, D: Doc> Node<'r, D> {
     self.inner.to_sexp()
   }
 
+  /// Like [`Node::to_sexp`], but one named node per line indented by nesting level.
+  /// Named leaf nodes are followed by their text. Useful for debugging rules.
+  pub fn to_pretty_ast(&self) -> String {
+    let mut ret = String::new();
+    self.write_pretty_ast(&mut ret, 0, None);
+    ret
+  }
+
+  fn write_pretty_ast(&self, out: &mut String, depth: usize, field: Option<&str>) {
+    for _ in 0..depth {
+      out.push_str("  ");
+    }
+    if let Some(field) = field {
+      out.push_str(field);
+      out.push_str(": ");
+    }
+    out.push('(');
+    out.push_str(&self.kind());
+    if self.is_named_leaf() {
+      out.push_str(&format!(" {:?}", self.text()));
+    }
+    let mut cursor = self.inner.walk();
+    if cursor.goto_first_child() {
+      loop {
+        let child = self.root.adopt(cursor.node());
+        if child.is_named() {
+          out.push('\n');
+          let field = cursor.field_name();
+          child.write_pretty_ast(out, depth + 1, field.as_deref());
+        }
+        if !cursor.goto_next_sibling() {
+          break;
+        }
+      }
+    }
+    out.push(')');
+  }
+
   pub fn lang(&self) -> &'r D::Lang {
     self.root.lang()
   }
+
+  /// Raw module paths imported in the subtree, in source order, with quotes stripped.
+  /// It uses [`Language::import_pattern`] and does not resolve paths to files.
+  pub fn resolve_imports(&self) -> Vec<String> {
+    let Some(pattern) = self.lang().import_pattern() else {
+      return vec![];
+    };
+    self
+      .find_all(pattern)
+      .filter_map(|nm| {
+        let path = nm.get_env().get_match("PATH")?.text();
+        let path = path.trim_matches(|c| c == '"' || c == '\'' || c == '`');
+        Some(path.to_string())
+      })
+      .collect()
+  }
+
+  /// Path of the file this node is parsed from, see [`Doc::file_path`].
+  pub fn source_file_path(&self) -> Option<&'r str> {
+    self.root.doc.file_path()
+  }
 }
 
 // TODO: figure out how to do this
@@ -297,6 +554,52 @@ impl<'r, L: Language> Node<'r, StrDoc<L>> {
   pub fn root(&self) -> &'r Root<StrDoc<L>> {
     self.root
   }
+
+  /// Render the node like a rustc diagnostic, see [`annotate_range`].
+  /// It is handy for asserting match positions in tests.
+  pub fn annotate(&self, label: &str) -> String {
+    let source = self.root.doc.get_source().as_str();
+    annotate_range(source, self.range(), label)
+  }
+}
+
+/// Render a byte range of `source` like a rustc diagnostic: the covered lines with
+/// one-based line numbers, `^` under the covered characters and `label` after the last line.
+/// ```text
+///   |
+/// 1 | let a = 123;
+///   |         ^^^ label
+/// ```
+pub fn annotate_range(source: &str, range: Range<usize>, label: &str) -> String {
+  let end = range.end.min(source.len());
+  let start = range.start.min(end);
+  let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+  let line_end = source[end..].find('\n').map_or(source.len(), |i| end + i);
+  let first_line = source[..line_start].matches('\n').count() + 1;
+  let lines: Vec<_> = source[line_start..line_end].split('\n').collect();
+  let width = (first_line + lines.len() - 1).to_string().len();
+  let mut annotated = format!("{:width$} |", "");
+  let mut offset = line_start;
+  for (i, line) in lines.iter().enumerate() {
+    let from = start.max(offset) - offset;
+    let to = end.min(offset + line.len()) - offset;
+    let padding = line[..from].chars().count();
+    // a zero-width range still gets one caret
+    let carets = line[from..to].chars().count().max(1);
+    annotated.push_str(&format!("\n{:>width$} | {line}", first_line + i));
+    annotated.push_str(&format!(
+      "\n{:width$} | {}{}",
+      "",
+      " ".repeat(padding),
+      "^".repeat(carets)
+    ));
+    offset += line.len() + 1;
+  }
+  if !label.is_empty() {
+    annotated.push(' ');
+    annotated.push_str(label);
+  }
+  annotated
 }
 
 /**
@@ -315,11 +618,27 @@ impl<D: Doc> Node<'_, D> {
     self.dfs().skip(1).find_map(|n| m.match_node(n)).is_some()
   }
 
+  /// Only named siblings are checked. Use `precedes_with_unnamed` to include punctuation.
   pub fn precedes<M: Matcher<D::Lang>>(&self, m: M) -> bool {
-    self.next_all().find_map(|n| m.match_node(n)).is_some()
+    self
+      .next_named_all()
+      .find_map(|n| m.match_node(n))
+      .is_some()
   }
 
+  /// Only named siblings are checked. Use `follows_with_unnamed` to include punctuation.
   pub fn follows<M: Matcher<D::Lang>>(&self, m: M) -> bool {
+    self
+      .prev_named_all()
+      .find_map(|n| m.match_node(n))
+      .is_some()
+  }
+
+  pub fn precedes_with_unnamed<M: Matcher<D::Lang>>(&self, m: M) -> bool {
+    self.next_all().find_map(|n| m.match_node(n)).is_some()
+  }
+
+  pub fn follows_with_unnamed<M: Matcher<D::Lang>>(&self, m: M) -> bool {
     self.prev_all().find_map(|n| m.match_node(n)).is_some()
   }
 }
@@ -335,6 +654,78 @@ pub struct DisplayContext<'r> {
   pub start_line: usize,
 }
 
+/// ANSI escape sequences used by [`DisplayContext::to_annotated_string`].
+/// An empty sequence leaves the text unstyled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorConfig {
+  /// style for the matched text
+  pub matched: String,
+  /// style for the context text before and after the match
+  pub context: String,
+  /// extra marker added to the matched text, e.g. underline `\x1b[4m`
+  pub marker: String,
+}
+
+const ANSI_RESET: &str = "\x1b[0m";
+
+impl Default for ColorConfig {
+  /// Bold red matches with plain context.
+  fn default() -> Self {
+    Self {
+      matched: "\x1b[1;31m".into(),
+      context: String::new(),
+      marker: String::new(),
+    }
+  }
+}
+
+impl ColorConfig {
+  /// No ANSI escape sequences at all, for non-TTY output.
+  pub fn plain() -> Self {
+    Self {
+      matched: String::new(),
+      context: String::new(),
+      marker: String::new(),
+    }
+  }
+
+  /// Style every line of the matched text separately so line prefixes stay unstyled.
+  pub fn paint_matched(&self, text: &str) -> String {
+    let style = format!("{}{}", self.matched, self.marker);
+    paint_lines(&style, text)
+  }
+
+  pub fn paint_context(&self, text: &str) -> String {
+    let mut ret = paint_lines(&self.context, text);
+    // keep the line break before the match, which `lines` drops
+    if text.ends_with('\n') {
+      ret.push('\n');
+    }
+    ret
+  }
+}
+
+fn paint_lines(style: &str, text: &str) -> String {
+  if style.is_empty() {
+    return text.lines().collect::<Vec<_>>().join("\n");
+  }
+  text
+    .lines()
+    .map(|line| format!("{style}{line}{ANSI_RESET}"))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+impl<'r> DisplayContext<'r> {
+  /// Render the context with the matched text highlighted by `config`.
+  pub fn to_annotated_string(&self, config: &ColorConfig) -> String {
+    let mut ret = config.paint_context(self.leading);
+    ret.push_str(&config.paint_matched(&self.matched));
+    ret.push_str(&config.paint_context(self.trailing));
+    ret
+  }
+}
+
 /// tree traversal API
 impl<'r, D: Doc> Node<'r, D> {
   #[must_use]
@@ -353,9 +744,32 @@ impl<'r, D: Doc> Node<'r, D> {
       cursor,
       root: self.root,
       count: self.inner.child_count() as usize,
+      named: false,
     }
   }
 
+  /// Children that are named nodes, skipping unnamed tokens like `(` or `,`.
+  pub fn named_children<'s>(&'s self) -> impl ExactSizeIterator<Item = Node<'r, D>> + 's {
+    let mut cursor = self.inner.walk();
+    cursor.goto_first_child();
+    NodeWalker {
+      cursor,
+      root: self.root,
+      count: self.named_child_count(),
+      named: true,
+    }
+  }
+
+  pub fn named_child_count(&self) -> usize {
+    self.inner.named_child_count() as usize
+  }
+
+  /// Children excluding extra nodes such as comments, see [`Node::is_extra`].
+  /// Unlike named children, tokens like `(` or `,` are kept.
+  pub fn children_without_extras<'s>(&'s self) -> impl Iterator<Item = Node<'r, D>> + 's {
+    self.children().filter(|n| !n.is_extra())
+  }
+
   #[must_use]
   pub fn child(&self, nth: usize) -> Option<Self> {
     // TODO: support usize
@@ -374,6 +788,58 @@ impl<'r, D: Doc> Node<'r, D> {
     })
   }
 
+  /// Follow a sequence of field names, e.g. `["body", "body"]`.
+  /// Returns `None` as soon as one step has no such field.
+  pub fn named_child_by_path(&self, path: &[&str]) -> Option<Self> {
+    path
+      .iter()
+      .try_fold(self.clone(), |node, segment| node.field(segment))
+  }
+
+  /// Follow a sequence of child indices, as [`Node::child`] does for one step.
+  pub fn child_by_index_path(&self, path: &[usize]) -> Option<Self> {
+    path
+      .iter()
+      .try_fold(self.clone(), |node, &nth| node.child(nth))
+  }
+
+  /// The step from the parent to `self`. `None` for the root node.
+  fn edge_from_parent(&self) -> Option<ChildEdge> {
+    let parent = self.inner.parent()?;
+    let mut cursor = parent.walk();
+    cursor.goto_first_child();
+    let mut child_index = 0;
+    while cursor.node() != self.inner {
+      if !cursor.goto_next_sibling() {
+        return None;
+      }
+      child_index += 1;
+    }
+    let field = cursor.field_name().map(intern_field_name);
+    Some(ChildEdge { field, child_index })
+  }
+
+  /// Index of `self` among all children of its parent. `None` for the root node.
+  pub fn sibling_index(&self) -> Option<usize> {
+    Some(self.edge_from_parent()?.child_index)
+  }
+
+  /// Field name of `self` in its parent, e.g. `body` for a function body.
+  pub fn field_name(&self) -> Option<&'static str> {
+    self.edge_from_parent()?.field
+  }
+
+  /// Steps from the root node to `self`, empty for the root node itself.
+  /// Replay them with [`Root::node_at_path`].
+  pub fn path_from_root(&self) -> Vec<ChildEdge> {
+    let mut path: Vec<_> = std::iter::once(self.clone())
+      .chain(self.ancestors())
+      .filter_map(|node| node.edge_from_parent())
+      .collect();
+    path.reverse();
+    path
+  }
+
   pub fn child_by_field_id(&self, field_id: u16) -> Option<Self> {
     let inner = self.inner.child_by_field_id(field_id)?;
     Some(Node {
@@ -465,6 +931,21 @@ impl<'r, D: Doc> Node<'r, D> {
     })
   }
 
+  /// Returns the next sibling that is a named node, skipping punctuation.
+  #[must_use]
+  pub fn next_named(&self) -> Option<Self> {
+    let inner = self.inner.next_named_sibling()?;
+    Some(Node {
+      inner,
+      root: self.root,
+    })
+  }
+
+  /// Returns all named sibling nodes next to `self`.
+  pub fn next_named_all(&self) -> impl Iterator<Item = Node<'r, D>> + '_ {
+    self.next_all().filter(|n| n.is_named())
+  }
+
   #[must_use]
   pub fn prev(&self) -> Option<Node<'r, D>> {
     let inner = self.inner.prev_sibling()?;
@@ -501,18 +982,130 @@ impl<'r, D: Doc> Node<'r, D> {
     })
   }
 
+  /// Returns the previous sibling that is a named node, skipping punctuation.
+  #[must_use]
+  pub fn prev_named(&self) -> Option<Self> {
+    let inner = self.inner.prev_named_sibling()?;
+    Some(Node {
+      inner,
+      root: self.root,
+    })
+  }
+
+  /// Returns all named sibling nodes before `self`, nearest first.
+  pub fn prev_named_all(&self) -> impl Iterator<Item = Node<'r, D>> + '_ {
+    self.prev_all().filter(|n| n.is_named())
+  }
+
+  /// Returns all children of the parent in document order, including `self`.
+  /// The root node has no siblings and yields nothing.
+  pub fn siblings(&self) -> impl Iterator<Item = Node<'r, D>> + '_ {
+    let parent = self.parent();
+    let count = parent
+      .as_ref()
+      .map_or(0, |p| p.inner.child_count() as usize);
+    // parent.children() borrows the parent, so walk its cursor directly
+    let node = parent.unwrap_or_else(|| self.clone());
+    let mut cursor = node.inner.walk();
+    cursor.goto_first_child();
+    NodeWalker {
+      cursor,
+      root: self.root,
+      count,
+      named: false,
+    }
+  }
+
+  /// Returns all named siblings in document order, including `self` if it is named.
+  pub fn named_siblings(&self) -> impl Iterator<Item = Node<'r, D>> + '_ {
+    self.siblings().filter(|n| n.is_named())
+  }
+
   pub fn dfs<'s>(&'s self) -> Pre<'r, D> {
     Pre::new(self)
   }
 
+  /// Pre-order traversal like [`Node::dfs`], skipping extra nodes and their descendants.
+  pub fn skip_extras(&self) -> impl Iterator<Item = Node<'r, D>> {
+    let mut stack = vec![self.clone()];
+    std::iter::from_fn(move || {
+      let node = stack.pop()?;
+      let children: Vec<_> = node.children_without_extras().collect();
+      stack.extend(children.into_iter().rev());
+      Some(node)
+    })
+  }
+
+  /// Pre-order traversal yielding each node with its depth, `self` being depth 0.
+  pub fn dfs_with_depth(&self) -> DfsWithDepth<'r, D> {
+    DfsWithDepth::new(self)
+  }
+
+  /// Breadth-first traversal starting from `self`. See [`Level`] for memory usage.
+  pub fn bfs(&self) -> Level<'r, D> {
+    Level::new(self)
+  }
+
+  /// Breadth-first traversal yielding each node with its depth, `self` being depth 0.
+  pub fn bfs_with_depth(&self) -> LevelWithDepth<'r, D> {
+    LevelWithDepth::new(self)
+  }
+
   #[must_use]
   pub fn find<M: Matcher<D::Lang>>(&self, pat: M) -> Option<NodeMatch<'r, D>> {
     pat.find_node(self.clone())
   }
 
-  pub fn find_all<M: Matcher<D::Lang>>(&self, pat: M) -> impl Iterator<Item = NodeMatch<'r, D>> {
+  pub fn find_all<M: Matcher<D::Lang>>(&self, pat: M) -> FindAllNodes<'r, D, M> {
     FindAllNodes::new(pat, self.clone())
   }
+
+  /// Like [`Node::find_all`] but yields each matched node with its owned env,
+  /// so callers that keep the captures need not clone them out of `get_env`.
+  pub fn find_all_with_env<M: Matcher<D::Lang>>(
+    &self,
+    pat: M,
+  ) -> impl Iterator<Item = (Node<'r, D>, MetaVarEnv<'r, D>)> {
+    self.find_all(pat).map(NodeMatch::into_parts)
+  }
+
+  /// Shorthand for [`Node::find`] with a [`KindMatcher`].
+  /// Returns None if `kind` is not a named kind of the language.
+  #[must_use]
+  pub fn find_by_kind(&self, kind: &str) -> Option<NodeMatch<'r, D>> {
+    let matcher = KindMatcher::try_new(kind, self.lang().clone()).ok()?;
+    self.find(matcher)
+  }
+
+  /// Shorthand for [`Node::find_all`] with a [`KindMatcher`].
+  /// Yields nothing if `kind` is not a named kind of the language.
+  pub fn find_all_by_kind(&self, kind: &str) -> impl Iterator<Item = NodeMatch<'r, D>> {
+    let matcher = KindMatcher::try_new(kind, self.lang().clone()).ok();
+    let node = self.clone();
+    matcher.into_iter().flat_map(move |m| node.find_all(m))
+  }
+}
+
+/// JSON dump of the syntax tree, for inspecting the raw AST when debugging patterns.
+#[cfg(feature = "serde_json")]
+impl<D: Doc> Node<'_, D> {
+  /// Returns the subtree as `{"kind", "named", "text", "startByte", "endByte", "children"}`.
+  /// Unnamed nodes are included. Leaf nodes have no `children` key.
+  pub fn to_json_ast(&self) -> serde_json::Value {
+    let range = self.range();
+    let mut json = serde_json::json!({
+      "kind": self.kind(),
+      "named": self.is_named(),
+      "text": self.text(),
+      "startByte": range.start,
+      "endByte": range.end,
+    });
+    let children: Vec<_> = self.children().map(|c| c.to_json_ast()).collect();
+    if !children.is_empty() {
+      json["children"] = serde_json::Value::Array(children);
+    }
+    json
+  }
 }
 
 /// Tree manipulation API
@@ -574,6 +1167,22 @@ impl<D: Doc> Node<'_, D> {
       inserted_text: Vec::new(),
     }
   }
+
+  /// Replace the node's text with `new_text`
+  pub fn replace_text(&self, new_text: &str) -> Edit<D> {
+    let range = self.range();
+    Edit::<D> {
+      position: range.start,
+      deleted_length: range.end - range.start,
+      inserted_text: D::Source::decode_str(new_text).into_owned(),
+    }
+  }
+
+  /// Surround the node's text with `prefix` and `suffix`
+  pub fn wrap_text(&self, prefix: &str, suffix: &str) -> Edit<D> {
+    let wrapped = format!("{prefix}{}{suffix}", self.text());
+    self.replace_text(&wrapped)
+  }
 }
 
 #[cfg(test)]
@@ -586,6 +1195,85 @@ mod test {
     assert!(!node.is_leaf());
   }
 
+  #[test]
+  fn test_source_info() {
+    let root = Tsx.ast_grep("let a = 123");
+    let node = root.root().find("123").expect("should exist");
+    assert!(node.source_file_path().is_none());
+    let doc = crate::StrDoc::new("let a = 123", Tsx).with_file_path("src/a.ts");
+    let root = crate::AstGrep::doc(doc);
+    let node = root.root().find("123").expect("should exist");
+    assert_eq!(node.source_file_path(), Some("src/a.ts"));
+  }
+
+  #[test]
+  fn test_node_length() {
+    let root = Tsx.ast_grep("let a = '你好'");
+    let node = root.root().find("'你好'").expect("should exist");
+    assert_eq!(node.byte_length(), 8);
+    assert_eq!(node.char_length(), 4);
+    assert_eq!(node.line_count(), 1);
+    let root = Tsx.ast_grep("function f() {\n  a()\n  b()\n}");
+    let node = root
+      .root()
+      .find("function f() { $$$ }")
+      .expect("should exist");
+    assert_eq!(node.line_count(), 4);
+  }
+
+  #[test]
+  fn test_children_without_extras() {
+    let kinds = |src: &str| {
+      let root = Tsx.ast_grep(src);
+      let call = root.root().find("f($$$)").expect("should exist");
+      let args = call.field("arguments").expect("should exist");
+      let kinds: Vec<_> = args
+        .children_without_extras()
+        .map(|n| n.kind().to_string())
+        .collect();
+      kinds
+    };
+    let with_comment = kinds("f(a, /* note */ b)");
+    assert_eq!(with_comment, kinds("f(a, b)"));
+    assert_eq!(with_comment, ["(", "identifier", ",", "identifier", ")"]);
+  }
+
+  #[test]
+  fn test_skip_extras() {
+    let root = Tsx.ast_grep("let a = /* one */ 1;\n// trailing");
+    let root = root.root();
+    let comments: Vec<_> = root.dfs().filter(|n| n.is_extra()).collect();
+    assert_eq!(comments.len(), 2);
+    assert!(comments.iter().all(|n| n.kind() == "comment"));
+    assert!(root.skip_extras().all(|n| n.kind() != "comment"));
+    assert_eq!(root.skip_extras().count(), root.dfs().count() - 2);
+    let texts: Vec<_> = root
+      .skip_extras()
+      .filter(|n| n.is_leaf())
+      .map(|n| n.text().to_string())
+      .collect();
+    assert_eq!(texts, ["let", "a", "=", "1", ";"]);
+  }
+
+  #[test]
+  fn test_node_hash_eq() {
+    use std::collections::HashSet;
+    let root = Tsx.ast_grep("let a = 1; let b = a");
+    let node = root.root();
+    let a1 = node.find("a").expect("should exist");
+    let a1_again = node.find("a").expect("should exist");
+    let a2 = node.find("let b = $A").expect("should exist");
+    let a2 = a2.get_env().get_match("A").expect("should exist").clone();
+    assert!(a1.get_node() == a1_again.get_node());
+    assert!(a1.get_node() != &a2);
+    let mut set = HashSet::new();
+    set.insert(a1.get_node().clone());
+    set.insert(a1_again.get_node().clone());
+    assert_eq!(set.len(), 1);
+    set.insert(a2);
+    assert_eq!(set.len(), 2);
+  }
+
   #[test]
   fn test_children() {
     let root = Tsx.ast_grep("let a = 123");
@@ -598,6 +1286,126 @@ mod test {
       .collect();
     assert_eq!(texts, vec!["let", "a = 123"]);
   }
+  #[test]
+  fn test_named_children() {
+    let root = Tsx.ast_grep("f(a, b, 123)");
+    let call = root.root().find("f($$$)").expect("should exist");
+    let args = call.field("arguments").expect("should exist");
+    assert_eq!(args.children().len(), 7);
+    assert_eq!(args.named_child_count(), 3);
+    let named = args.named_children();
+    assert_eq!(named.len(), 3);
+    let texts: Vec<_> = named.map(|c| c.text().to_string()).collect();
+    assert_eq!(texts, ["a", "b", "123"]);
+    let kinds: Vec<_> = call
+      .named_children()
+      .map(|c| c.kind().to_string())
+      .collect();
+    assert_eq!(kinds, ["identifier", "arguments"]);
+  }
+  #[test]
+  fn test_structurally_eq() {
+    let root = Tsx.ast_grep("a.b = a.b; c = a.b; d = a . b; e = 'x  y'; f = 'x y'");
+    let rhs: Vec<_> = root
+      .root()
+      .find_all("$A = $B")
+      .map(|n| n.field("right").expect("should exist"))
+      .collect();
+    let lhs = root.root().find("$A = $B").expect("should exist");
+    let lhs = lhs.field("left").expect("should exist");
+    assert!(lhs.structurally_eq(&rhs[0]));
+    assert!(lhs.structurally_eq(&rhs[1]));
+    // whitespace between tokens is not part of the tree
+    assert!(lhs.structurally_eq(&rhs[2]));
+    assert!(!lhs.structurally_eq(&rhs[3]));
+    assert!(!rhs[3].structurally_eq(&rhs[4]));
+    assert!(rhs[3].structurally_eq_ignoring_trivia(&rhs[4]));
+    // nodes from another tree
+    let other = Tsx.ast_grep("a.b");
+    let other = other.root().find("a.b").expect("should exist");
+    assert!(other.structurally_eq(&lhs));
+    assert!(!other.structurally_eq(&rhs[3]));
+  }
+
+  #[test]
+  fn test_structurally_eq_reflexive_symmetric() {
+    let srcs = [
+      "a.b",
+      "a . b",
+      "a.c",
+      "f(a, b)",
+      "f(a,b)",
+      "f(b, a)",
+      "'x  y'",
+      "'x y'",
+      "[1, 2, 3]",
+      "{ a: 1 }",
+      "x => x + 1",
+      "(x) => x + 1",
+    ];
+    let grep: Vec<_> = srcs.iter().map(|s| Tsx.ast_grep(s)).collect();
+    let nodes: Vec<_> = grep.iter().map(|g| g.root()).collect();
+    // parse again so that nodes are not compared with themselves by id
+    let reparsed: Vec<_> = srcs.iter().map(|s| Tsx.ast_grep(s)).collect();
+    for (a, same) in nodes.iter().zip(&reparsed) {
+      assert!(a.structurally_eq(&same.root()));
+      assert!(a.structurally_eq_ignoring_trivia(&same.root()));
+      for b in &nodes {
+        assert_eq!(a.structurally_eq(b), b.structurally_eq(a));
+        assert_eq!(
+          a.structurally_eq_ignoring_trivia(b),
+          b.structurally_eq_ignoring_trivia(a)
+        );
+        // ignoring trivia is more permissive
+        if a.structurally_eq(b) {
+          assert!(a.structurally_eq_ignoring_trivia(b));
+        }
+      }
+    }
+    assert!(nodes[0].structurally_eq(&nodes[1]));
+    assert!(nodes[3].structurally_eq(&nodes[4]));
+    assert!(!nodes[3].structurally_eq(&nodes[5]));
+    assert!(!nodes[6].structurally_eq(&nodes[7]));
+    assert!(nodes[6].structurally_eq_ignoring_trivia(&nodes[7]));
+    assert!(!nodes[10].structurally_eq(&nodes[11]));
+  }
+
+  fn arb_expr() -> impl proptest::strategy::Strategy<Value = String> {
+    use proptest::prelude::*;
+    let leaf = prop_oneof!["[a-c]", "[0-9]", "'[xy ]{0,3}'"];
+    leaf.prop_recursive(3, 16, 2, |inner| {
+      prop_oneof![
+        (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("{a} + {b}")),
+        (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("f({a},  {b})")),
+        inner.clone().prop_map(|a| format!("[{a}]")),
+        inner.prop_map(|a| format!("({a}).b")),
+      ]
+    })
+  }
+
+  proptest::proptest! {
+    #[test]
+    fn test_structurally_eq_reflexive(src in arb_expr()) {
+      // two separately parsed trees have different node ids
+      let a = Tsx.ast_grep(&src);
+      let b = Tsx.ast_grep(&src);
+      proptest::prop_assert!(a.root().structurally_eq(&b.root()));
+      proptest::prop_assert!(a.root().structurally_eq_ignoring_trivia(&b.root()));
+    }
+
+    #[test]
+    fn test_structurally_eq_symmetric(a in arb_expr(), b in arb_expr()) {
+      let a = Tsx.ast_grep(&a);
+      let b = Tsx.ast_grep(&b);
+      let (a, b) = (a.root(), b.root());
+      proptest::prop_assert_eq!(a.structurally_eq(&b), b.structurally_eq(&a));
+      proptest::prop_assert_eq!(
+        a.structurally_eq_ignoring_trivia(&b),
+        b.structurally_eq_ignoring_trivia(&a)
+      );
+    }
+  }
+
   #[test]
   fn test_empty() {
     let root = Tsx.ast_grep("let a = 123");
@@ -608,6 +1416,159 @@ mod test {
     assert_eq!(edit.position, 0);
   }
 
+  #[test]
+  fn test_error_node() {
+    let root = Tsx.ast_grep("let a = ;");
+    let node = root.root();
+    assert!(node.contains_error());
+    assert!(!node.is_error());
+    assert!(node.dfs().any(|n| n.is_error()));
+    let root = Tsx.ast_grep("let a = 1");
+    assert!(!root.root().contains_error());
+    assert!(!root.root().dfs().any(|n| n.is_missing()));
+    // tree-sitter inserts a missing `)` to recover
+    let root = Tsx.ast_grep("if (a { }");
+    let missing: Vec<_> = root.root().dfs().filter(|n| n.is_missing()).collect();
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].kind(), ")");
+    assert!(!missing[0].is_error());
+    assert!(!root.root().contains_error());
+  }
+
+  #[test]
+  fn test_filter_env() {
+    let root = Tsx.ast_grep("console.log(a); alert(b); console.warn(c)");
+    let texts: Vec<_> = root
+      .root()
+      .find_all("$FUNC($ARG)")
+      .filter_env("FUNC", |n| n.text().starts_with("console."))
+      .map(|m| m.text().to_string())
+      .collect();
+    assert_eq!(texts, ["console.log(a)", "console.warn(c)"]);
+    let missing = root
+      .root()
+      .find_all("$FUNC($ARG)")
+      .filter_env("NONE", |_| true);
+    assert_eq!(missing.count(), 0);
+  }
+
+  #[test]
+  fn test_find_all_with_env() {
+    let root = Tsx.ast_grep("console.log(a); alert(b)");
+    let found: Vec<_> = root
+      .root()
+      .find_all_with_env("$FUNC($ARG)")
+      .map(|(node, env)| {
+        let arg = env.get_match("ARG").expect("should capture").text();
+        (node.text().to_string(), arg.to_string())
+      })
+      .collect();
+    let expected = [("console.log(a)", "a"), ("alert(b)", "b")];
+    let expected: Vec<_> = expected
+      .iter()
+      .map(|(n, a)| (n.to_string(), a.to_string()))
+      .collect();
+    assert_eq!(found, expected);
+  }
+
+  #[test]
+  fn test_bfs() {
+    let root = Tsx.ast_grep("f(a)(b)");
+    let node = root.root().find("$F(b)").expect("should exist");
+    let dfs: Vec<_> = node.dfs().map(|n| n.text().to_string()).collect();
+    let bfs: Vec<_> = node.bfs().map(|n| n.text().to_string()).collect();
+    let expected = [
+      "f(a)(b)", "f(a)", "f", "(a)", "(", "a", ")", "(b)", "(", "b", ")",
+    ];
+    assert_eq!(dfs, expected);
+    let expected = [
+      "f(a)(b)", "f(a)", "(b)", "f", "(a)", "(", "b", ")", "(", "a", ")",
+    ];
+    assert_eq!(bfs, expected);
+    let depths: Vec<_> = node.bfs_with_depth().map(|(_, d)| d).collect();
+    assert_eq!(depths, [0, 1, 1, 2, 2, 2, 2, 2, 3, 3, 3]);
+    let shallow = node.bfs_with_depth().take_while(|(_, d)| *d < 2).count();
+    assert_eq!(shallow, 3);
+  }
+
+  #[test]
+  fn test_child_by_path() {
+    let root = Tsx.ast_grep("a.b.c.d");
+    let member = root.root().find("$A.d").expect("should exist");
+    let object = member
+      .named_child_by_path(&["object", "object", "object"])
+      .expect("should exist");
+    assert_eq!(object.text(), "a");
+    assert!(member.named_child_by_path(&["object", "body"]).is_none());
+    assert_eq!(member.named_child_by_path(&[]).unwrap().text(), "a.b.c.d");
+    let nested = root.root().child_by_index_path(&[0, 0, 0, 0]);
+    assert_eq!(nested.expect("should exist").text(), "a.b");
+    assert!(root.root().child_by_index_path(&[0, 10]).is_none());
+  }
+
+  #[test]
+  fn test_path_from_root() {
+    let root = Tsx.ast_grep("function f() { let a = 1; return a + 2 }");
+    let node = root.root().find("a + 2").expect("should exist");
+    let path = node.path_from_root();
+    let fields: Vec<_> = path.iter().map(|e| e.field).collect();
+    assert_eq!(fields, [None, Some("body"), None, None]);
+    assert_eq!(path[2].child_index, 2);
+    let found = root.node_at_path(&path).expect("should exist");
+    assert_eq!(found.range(), node.range());
+    assert!(found.field_name().is_none());
+    let body = found
+      .parent()
+      .and_then(|n| n.parent())
+      .expect("should exist");
+    assert_eq!(body.field_name(), Some("body"));
+    assert_eq!(body.sibling_index(), Some(3));
+  }
+
+  #[test]
+  fn test_field_name_binary_expression() {
+    let root = Tsx.ast_grep("a + b");
+    let expr = root.root().find("$A + $B").expect("should exist");
+    let fields: Vec<_> = expr.children().map(|n| n.field_name()).collect();
+    assert_eq!(fields, [Some("left"), Some("operator"), Some("right")]);
+    assert_eq!(expr.field_name(), None);
+  }
+
+  #[test]
+  fn test_to_pretty_ast() {
+    let root = Tsx.ast_grep("a + 'b'");
+    let expected = r#"(program
+  (expression_statement
+    (binary_expression
+      left: (identifier "a")
+      right: (string
+        (string_fragment "b")))))"#;
+    assert_eq!(root.to_pretty_ast(), expected);
+    let expr = root.root().find("$A + $B").expect("should exist");
+    assert_eq!(expr.to_pretty_ast().lines().count(), 4);
+  }
+
+  #[test]
+  fn test_path_from_root_edge_cases() {
+    let root = Tsx.ast_grep("a.b");
+    assert!(root.root().path_from_root().is_empty());
+    assert_eq!(root.root().sibling_index(), None);
+    assert_eq!(
+      root.node_at_path(&[]).expect("should exist").kind(),
+      "program"
+    );
+    let member = root.root().find("$A.$B").expect("should exist");
+    let property = member.get_env().get_match("B").expect("should exist");
+    let mut path = property.path_from_root();
+    assert_eq!(path.last().and_then(|e| e.field), Some("property"));
+    assert_eq!(root.node_at_path(&path).expect("should exist").text(), "b");
+    // a stale field name does not replay
+    path.last_mut().expect("should exist").field = Some("object");
+    assert!(root.node_at_path(&path).is_none());
+    path.last_mut().expect("should exist").child_index = 10;
+    assert!(root.node_at_path(&path).is_none());
+  }
+
   #[test]
   fn test_field_children() {
     let root = Tsx.ast_grep("let a = 123");
@@ -625,6 +1586,26 @@ if (a) {
 }
 ";
 
+  #[test]
+  fn test_find_by_kind() {
+    let root = Tsx.ast_grep("class A {}\nfunction f() { class B extends A {} }");
+    let node = root.root();
+    let class = node.find_by_kind("class_declaration").expect("should find");
+    assert_eq!(class.text(), "class A {}");
+    let names: Vec<_> = node
+      .find_all_by_kind("class_declaration")
+      .map(|n| {
+        n.field("name")
+          .expect("should have name")
+          .text()
+          .to_string()
+      })
+      .collect();
+    assert_eq!(names, ["A", "B"]);
+    assert!(node.find_by_kind("not_a_kind").is_none());
+    assert_eq!(node.find_all_by_kind("not_a_kind").count(), 0);
+  }
+
   #[test]
   fn test_display_context() {
     // src, matcher, lead, trail
@@ -643,6 +1624,27 @@ if (a) {
     }
   }
 
+  #[test]
+  fn test_annotated_string() {
+    let root = Tsx.ast_grep(MULTI_LINE);
+    let node = root.root().find("test").expect("should match");
+    let display = node.display_context(0, 0);
+    let plain = display.to_annotated_string(&super::ColorConfig::plain());
+    assert_eq!(plain, "  test(1)");
+    let colored = display.to_annotated_string(&super::ColorConfig::default());
+    assert_eq!(colored, "  \x1b[1;31mtest\x1b[0m(1)");
+    let config = super::ColorConfig {
+      context: "\x1b[2m".into(),
+      marker: "\x1b[4m".into(),
+      ..Default::default()
+    };
+    let display = node.display_context(1, 0);
+    let colored = display.to_annotated_string(&config);
+    let expected =
+      "\x1b[2mif (a) {\x1b[0m\n\x1b[2m  \x1b[0m\x1b[1;31m\x1b[4mtest\x1b[0m\x1b[2m(1)\x1b[0m";
+    assert_eq!(colored, expected);
+  }
+
   #[test]
   fn test_multi_line_context() {
     let cases = [
@@ -708,6 +1710,55 @@ if (a) {
     assert!(node.follows("Some(Some(1));"));
   }
 
+  #[test]
+  fn test_named_siblings() {
+    let root = Tsx.ast_grep("foo(a, b, c)");
+    let root = root.root();
+    let node = root.find("b").expect("should exist");
+    assert_eq!(node.next().expect("should exist").text(), ",");
+    assert_eq!(node.prev().expect("should exist").text(), ",");
+    assert_eq!(node.next_named().expect("should exist").text(), "c");
+    assert_eq!(node.prev_named().expect("should exist").text(), "a");
+    let a = node.prev_named().expect("should exist");
+    assert!(a.prev_named().is_none());
+    assert_eq!(a.next().expect("should exist").text(), ",");
+    assert_eq!(a.next_named().expect("should exist").text(), "b");
+    let next: Vec<_> = a.next_named_all().map(|n| n.text().to_string()).collect();
+    assert_eq!(next, ["b", "c"]);
+    let c = node.next_named().expect("should exist");
+    let prev: Vec<_> = c.prev_named_all().map(|n| n.text().to_string()).collect();
+    assert_eq!(prev, ["b", "a"]);
+  }
+
+  #[test]
+  fn test_siblings() {
+    let root = Tsx.ast_grep("foo(a, b, c)");
+    let root = root.root();
+    let node = root.find("b").expect("should exist");
+    let all: Vec<_> = node.siblings().map(|n| n.text().to_string()).collect();
+    assert_eq!(all, ["(", "a", ",", "b", ",", "c", ")"]);
+    let named: Vec<_> = node
+      .named_siblings()
+      .map(|n| n.text().to_string())
+      .collect();
+    assert_eq!(named, ["a", "b", "c"]);
+    assert_eq!(root.siblings().count(), 0);
+    assert_eq!(root.named_siblings().count(), 0);
+  }
+
+  #[test]
+  fn test_precedes_named_only() {
+    let root = Tsx.ast_grep("foo(a, b)");
+    let root = root.root();
+    let node = root.find("a").expect("should exist");
+    assert!(node.precedes("b"));
+    assert!(!node.precedes(","));
+    assert!(node.precedes_with_unnamed(","));
+    let node = root.find("b").expect("should exist");
+    assert!(!node.follows(","));
+    assert!(node.follows_with_unnamed(","));
+  }
+
   #[test]
   fn test_field() {
     let root = Tsx.ast_grep("class A{}");
@@ -736,6 +1787,21 @@ if (a) {
     assert_eq!(edit.deleted_length, 8);
   }
 
+  #[test]
+  fn test_replace_and_wrap_text() {
+    let mut grep = Tsx.ast_grep("let a = foo(1); bar()");
+    let root = grep.root();
+    let foo = root.find("foo($A)").expect("should exist");
+    let edit = foo.replace_text("baz");
+    assert_eq!((edit.position, edit.deleted_length), (8, 6));
+    assert_eq!(edit.inserted_text, b"baz");
+    let bar = root.find("bar()").expect("should exist");
+    let wrap = bar.wrap_text("await ", "!");
+    assert_eq!(wrap.inserted_text, b"await bar()!");
+    grep.apply_edits(vec![edit, wrap]).expect("should apply");
+    assert_eq!(grep.source(), "let a = baz; await bar()!");
+  }
+
   #[test]
   fn test_ascii_pos() {
     let root = Tsx.ast_grep("a");
@@ -764,4 +1830,70 @@ if (a) {
     assert_eq!(node.end_pos().line(), 1);
     assert_eq!(node.end_pos().column(&node), 4);
   }
+
+  #[test]
+  fn test_span() {
+    let root = Tsx.ast_grep("let a = 1\n  🦀🦀");
+    let root = root.root();
+    let node = root.find("let $A = 1").expect("should exist");
+    let expected = super::Span {
+      start_line: 0,
+      start_col: 0,
+      end_line: 0,
+      end_col: 9,
+    };
+    assert_eq!(node.span(), expected);
+    let node = root.find("🦀🦀").expect("should exist");
+    let span = node.span();
+    assert_eq!((span.start_line, span.start_col), (1, 2));
+    assert_eq!((span.end_line, span.end_col), (1, 4));
+  }
+
+  #[test]
+  #[cfg(feature = "serde_json")]
+  fn test_to_json_ast() {
+    let root = Tsx.ast_grep("a(1)");
+    let call = root.root().find("a(1)").expect("should exist");
+    let json = call.to_json_ast();
+    assert_eq!(json["kind"], "call_expression");
+    assert_eq!(json["named"], true);
+    assert_eq!(
+      (&json["startByte"], &json["endByte"]),
+      (&0.into(), &4.into())
+    );
+    let args = &json["children"][1];
+    assert_eq!(args["text"], "(1)");
+    let paren = &args["children"][0];
+    assert_eq!(paren["kind"], "(");
+    assert_eq!(paren["named"], false);
+    assert!(paren.get("children").is_none());
+    assert_eq!(args["children"][1]["startByte"], 2);
+  }
+
+  #[test]
+  fn test_annotate() {
+    let root = Tsx.ast_grep("let a = 123;\nlet b = a;");
+    let node = root.root().find("123").expect("should exist");
+    let expected = "  |\n1 | let a = 123;\n  |         ^^^ number";
+    assert_eq!(node.annotate("number"), expected);
+    let node = root.root().find("let b = $A").expect("should exist");
+    let expected = "  |\n2 | let b = a;\n  | ^^^^^^^^^^";
+    assert_eq!(node.annotate(""), expected);
+  }
+
+  #[test]
+  fn test_annotate_range() {
+    let source = "fn 世界() {\n  body\n}";
+    let expected =
+      "  |\n1 | fn 世界() {\n  |         ^\n2 |   body\n  | ^^^^^^\n3 | }\n  | ^ block";
+    assert_eq!(
+      super::annotate_range(source, 12..source.len(), "block"),
+      expected
+    );
+    let expected = "  |\n1 | fn 世界() {\n  |    ^ here";
+    assert_eq!(super::annotate_range(source, 3..3, "here"), expected);
+    let source = "a\n".repeat(9) + "bb";
+    let expected = "   |\n10 | bb\n   |  ^ end";
+    assert_eq!(super::annotate_range(&source, 19..20, "end"), expected);
+  }
 }
@@ -11,6 +11,16 @@ pub struct And<L: Language, P1: Matcher<L>, P2: Matcher<L>> {
   lang: PhantomData<L>,
 }
 
+impl<L: Language, P1: Matcher<L>, P2: Matcher<L>> And<L, P1, P2> {
+  pub fn new(pattern1: P1, pattern2: P2) -> Self {
+    Self {
+      pattern1,
+      pattern2,
+      lang: PhantomData,
+    }
+  }
+}
+
 impl<L: Language, P1, P2> Matcher<L> for And<L, P1, P2>
 where
   P1: Matcher<L>,
@@ -172,6 +182,16 @@ pub struct Or<L: Language, P1: Matcher<L>, P2: Matcher<L>> {
   lang: PhantomData<L>,
 }
 
+impl<L: Language, P1: Matcher<L>, P2: Matcher<L>> Or<L, P1, P2> {
+  pub fn new(pattern1: P1, pattern2: P2) -> Self {
+    Self {
+      pattern1,
+      pattern2,
+      lang: PhantomData,
+    }
+  }
+}
+
 impl<L, P1, P2> Matcher<L> for Or<L, P1, P2>
 where
   L: Language,
@@ -407,6 +427,22 @@ mod test {
     test_not_find(&matcher, "const b = 1");
   }
 
+  #[test]
+  fn test_matcher_ext_chain() {
+    use crate::matcher::{KindMatcher, MatcherExt};
+    let matcher = KindMatcher::new("lexical_declaration", Tsx)
+      .and("let $A = $B")
+      .and("let $A = 123".not());
+    test_find(&matcher, "let a = 1");
+    test_not_find(&matcher, "let a = 123");
+    test_not_find(&matcher, "const a = 1");
+    let either = "let a = 1".or("const b = 2");
+    let matcher = KindMatcher::new("lexical_declaration", Tsx).and(either.not());
+    test_find(&matcher, "const c = 3");
+    test_not_find(&matcher, "let a = 1");
+    test_not_find(&matcher, "const b = 2");
+  }
+
   #[test]
   fn test_not() {
     let matcher = Not {
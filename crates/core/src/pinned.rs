@@ -58,6 +58,15 @@ where
   }
 }
 
+impl<D: Doc + 'static> Root<D> {
+  /// Consume the Root and return its root node bundled with the tree that owns it.
+  /// The returned value has no lifetime parameter and can be stored in structs or
+  /// returned from the function that parsed the source.
+  pub fn into_root_node(self) -> PinnedNodeData<D, Node<'static, D>> {
+    PinnedNodeData::new(self, |r| r.root())
+  }
+}
+
 /// # Safety
 /// TODO: explain unsafe trait
 pub unsafe trait NodeData<D> {
@@ -135,6 +144,19 @@ mod test {
     assert!(matches!(b.lang(), Tsx));
   }
 
+  #[test]
+  fn test_into_root_node() {
+    fn parse() -> PinnedNodeData<StrDoc<Tsx>, Node<'static, StrDoc<Tsx>>> {
+      Root::new("let a = 123", Tsx).into_root_node()
+    }
+    let mut pinned = parse();
+    let root = pinned.get_data();
+    assert_eq!(root.kind(), "program");
+    assert_eq!(root.text(), "let a = 123");
+    let found = root.find("123").expect("should find");
+    assert_eq!(found.kind(), "number");
+  }
+
   #[test]
   #[ignore]
   fn test_node_match() {
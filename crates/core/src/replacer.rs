@@ -1,5 +1,5 @@
 use crate::matcher::{Matcher, NodeMatch};
-use crate::meta_var::{is_valid_meta_var_char, MetaVariableID};
+use crate::meta_var::{is_valid_meta_var_char, MetaVarEnv, MetaVariableID};
 use crate::source::Edit as E;
 use crate::{Doc, Node, Root};
 use std::ops::Range;
@@ -58,6 +58,55 @@ impl<D: Doc> Replacer<D> for Node<'_, D> {
   }
 }
 
+/// Replacer backed by a closure, created by [`from_fn`].
+/// The closure receives the matched meta variables and the language.
+pub struct FnReplacer<F>(F);
+
+/// Use a closure as a [`Replacer`] for replacement logic beyond templates,
+/// e.g. upper-casing a captured function name.
+pub fn from_fn<D, F>(f: F) -> FnReplacer<F>
+where
+  D: Doc,
+  F: Fn(&MetaVarEnv<D>, &D::Lang) -> Underlying<D::Source>,
+{
+  FnReplacer(f)
+}
+
+impl<D, F> Replacer<D> for FnReplacer<F>
+where
+  D: Doc,
+  F: Fn(&MetaVarEnv<D>, &D::Lang) -> Underlying<D::Source>,
+{
+  fn generate_replacement(&self, nm: &NodeMatch<D>) -> Underlying<D::Source> {
+    (self.0)(nm.get_env(), nm.lang())
+  }
+}
+
+/// Replacer backed by a closure returning a `String`, created by [`from_text_fn`].
+pub struct TextFnReplacer<F>(F);
+
+/// Same as [`from_fn`] but the closure returns text, which is converted to the
+/// source encoding. A blanket `Replacer` impl for closures would overlap with
+/// the impl for references, so closures need this wrapper.
+pub fn from_text_fn<D, F>(f: F) -> TextFnReplacer<F>
+where
+  D: Doc,
+  F: Fn(&MetaVarEnv<D>, &D::Lang) -> String,
+{
+  TextFnReplacer(f)
+}
+
+impl<D, F> Replacer<D> for TextFnReplacer<F>
+where
+  D: Doc,
+  F: Fn(&MetaVarEnv<D>, &D::Lang) -> String,
+{
+  fn generate_replacement(&self, nm: &NodeMatch<D>) -> Underlying<D::Source> {
+    let text = (self.0)(nm.get_env(), nm.lang());
+    D::Source::decode_str(&text).into_owned()
+  }
+}
+
 enum MetaVarExtract {
   /// $A for captured meta var
   Single(MetaVariableID),
@@ -112,3 +161,32 @@ fn split_first_meta_var(
   };
   Some((var, skipped + i))
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::language::{Language, Tsx};
+
+  #[test]
+  fn test_fn_replacer() {
+    let mut grep = Tsx.ast_grep("function foo() {}");
+    let replacer = from_fn(|env: &MetaVarEnv<_>, _: &Tsx| {
+      let name = env.get_match("F").expect("should capture").text();
+      format!("function {}() {{}}", name.to_uppercase()).into_bytes()
+    });
+    assert!(grep.replace("function $F() {}", replacer).unwrap());
+    assert_eq!(grep.root().text(), "function FOO() {}");
+  }
+
+  #[test]
+  fn test_text_fn_replacer() {
+    let mut grep = Tsx.ast_grep("let a = foo; let b = bar");
+    let replacer = from_text_fn(|env: &MetaVarEnv<_>, _: &Tsx| {
+      let name = env.get_match("A").expect("should capture").text();
+      format!("log({})", name.to_uppercase())
+    });
+    let edits = grep.root().replace_all("let $V = $A", &replacer);
+    grep.apply_edits(edits).expect("should not conflict");
+    assert_eq!(grep.root().text(), "log(FOO); log(BAR)");
+  }
+}
@@ -28,12 +28,28 @@ impl TemplateFix {
     create_template(tpl, lang.meta_var_char(), trans)
   }
 
-  pub fn used_vars(&self) -> HashSet<&str> {
-    let template = match self {
-      TemplateFix::WithMetaVar(t) => t,
-      TemplateFix::Textual(_) => return HashSet::new(),
+  /// Meta-variables in order of appearance, repeated if used more than once.
+  fn var_names(&self) -> impl Iterator<Item = &str> {
+    let vars = match self {
+      TemplateFix::WithMetaVar(t) => &t.vars[..],
+      TemplateFix::Textual(_) => &[],
     };
-    template.vars.iter().map(|v| v.0.used_var()).collect()
+    vars.iter().map(|v| v.0.used_var())
+  }
+
+  pub fn used_vars(&self) -> HashSet<&str> {
+    self.var_names().collect()
+  }
+
+  /// Names of all meta-variables referenced in the template,
+  /// in order of first appearance and without duplicates.
+  pub fn variables(&self) -> Vec<String> {
+    let mut unseen = self.used_vars();
+    self
+      .var_names()
+      .filter(|v| unseen.remove(v))
+      .map(String::from)
+      .collect()
   }
 }
 
@@ -190,6 +206,14 @@ if (true) {
     assert_eq!(src.root().text(), expect);
   }
 
+  #[test]
+  fn test_variables() {
+    let fix = TemplateFix::try_new("$B($A, $$$ARGS, $A)", &Tsx).expect("should work");
+    assert_eq!(fix.variables(), ["B", "A", "ARGS"]);
+    let fix = TemplateFix::try_new("return 123", &Tsx).expect("should work");
+    assert!(fix.variables().is_empty());
+  }
+
   fn test_str_replace(replacer: &str, vars: &[(&str, &str)], expected: &str) {
     let mut env = MetaVarEnv::new();
     let roots: Vec<_> = vars
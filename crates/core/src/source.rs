@@ -77,6 +77,27 @@ pub enum TSParseError {
   TreeUnavailable,
 }
 
+/// Two edits that modify overlapping ranges of the source.
+#[derive(Debug, Error)]
+#[error("edit at {first:?} `{first_text}` overlaps with edit at {second:?} `{second_text}`.")]
+pub struct EditConflict {
+  /// byte range deleted by the edit with the smaller position
+  pub first: Range<usize>,
+  pub first_text: String,
+  /// byte range deleted by the conflicting edit
+  pub second: Range<usize>,
+  pub second_text: String,
+}
+
+/// Error returned when a batch of edits cannot be applied.
+#[derive(Debug, Error)]
+pub enum EditError {
+  #[error(transparent)]
+  Conflict(#[from] EditConflict),
+  #[error(transparent)]
+  Parse(#[from] TSParseError),
+}
+
 pub trait Doc: Clone {
   type Source: Content;
   type Lang: Language;
@@ -89,6 +110,11 @@ pub trait Doc: Clone {
     parse_lang(|p| source.parse_tree_sitter(p, old_tree), lang)
   }
   fn clone_with_lang(&self, lang: Self::Lang) -> Self;
+  /// Path of the file the document is read from, if known.
+  /// Documents created from in-memory strings have no path.
+  fn file_path(&self) -> Option<&str> {
+    None
+  }
   /// TODO: are we paying too much to support str as Pattern/Replacer??
   /// this method converts string to Doc, so that we can support using
   /// string as replacer/searcher. Natively.
@@ -99,6 +125,7 @@ pub trait Doc: Clone {
 pub struct StrDoc<L: Language> {
   pub src: String,
   pub lang: L,
+  pub file_path: Option<String>,
 }
 
 impl<L: Language> StrDoc<L> {
@@ -106,6 +133,14 @@ impl<L: Language> StrDoc<L> {
     Self {
       src: src.into(),
       lang,
+      file_path: None,
+    }
+  }
+  /// Record the path the source is read from, see [`Doc::file_path`].
+  pub fn with_file_path(self, path: impl Into<String>) -> Self {
+    Self {
+      file_path: Some(path.into()),
+      ..self
     }
   }
 }
@@ -126,7 +161,14 @@ impl<L: Language> Doc for StrDoc<L> {
     Self::new(src, lang)
   }
   fn clone_with_lang(&self, lang: Self::Lang) -> Self {
-    Self::new(&self.src, lang)
+    Self {
+      src: self.src.clone(),
+      lang,
+      file_path: self.file_path.clone(),
+    }
+  }
+  fn file_path(&self) -> Option<&str> {
+    self.file_path.as_deref()
   }
 }
 
@@ -238,6 +238,30 @@ impl<'tree, D: Doc> Iterator for Pre<'tree, D> {
 }
 impl<D: Doc> FusedIterator for Pre<'_, D> {}
 
+/// Pre-order traversal yielding each node with its depth relative to the starting node.
+pub struct DfsWithDepth<'tree, D: Doc> {
+  pre: Pre<'tree, D>,
+}
+
+impl<'tree, D: Doc> DfsWithDepth<'tree, D> {
+  pub fn new(node: &Node<'tree, D>) -> Self {
+    Self {
+      pre: Pre::new(node),
+    }
+  }
+}
+
+impl<'tree, D: Doc> Iterator for DfsWithDepth<'tree, D> {
+  type Item = (Node<'tree, D>, usize);
+  fn next(&mut self) -> Option<Self::Item> {
+    // Pre moves its cursor after yielding, so read the depth first
+    let depth = self.pre.current_depth;
+    let node = self.pre.next()?;
+    Some((node, depth))
+  }
+}
+impl<D: Doc> FusedIterator for DfsWithDepth<'_, D> {}
+
 impl<'t, D: Doc> Traversal<'t, D> for Pre<'t, D> {
   fn calibrate_for_match(&mut self, depth: Option<usize>) {
     // not entering the node, ignore
@@ -388,6 +412,38 @@ impl<'tree, D: Doc> Iterator for Level<'tree, D> {
 }
 impl<D: Doc> FusedIterator for Level<'_, D> {}
 
+/// Level-order traversal yielding each node with its depth relative to the starting node.
+/// Depth is non-decreasing, so callers can stop at a maximum depth with `take_while`.
+pub struct LevelWithDepth<'tree, D: Doc> {
+  deque: VecDeque<(ts::Node<'tree>, usize)>,
+  cursor: ts::TreeCursor<'tree>,
+  root: &'tree Root<D>,
+}
+
+impl<'tree, D: Doc> LevelWithDepth<'tree, D> {
+  pub fn new(node: &Node<'tree, D>) -> Self {
+    let mut deque = VecDeque::new();
+    deque.push_back((node.inner.clone(), 0));
+    let cursor = node.inner.walk();
+    Self {
+      deque,
+      cursor,
+      root: node.root,
+    }
+  }
+}
+
+impl<'tree, D: Doc> Iterator for LevelWithDepth<'tree, D> {
+  type Item = (Node<'tree, D>, usize);
+  fn next(&mut self) -> Option<Self::Item> {
+    let (inner, depth) = self.deque.pop_front()?;
+    let children = inner.children(&mut self.cursor);
+    self.deque.extend(children.map(|c| (c, depth + 1)));
+    Some((self.root.adopt(inner), depth))
+  }
+}
+impl<D: Doc> FusedIterator for LevelWithDepth<'_, D> {}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -494,6 +550,62 @@ mod test {
     assert!(post.starts_with(&post2));
   }
 
+  #[test]
+  fn test_level_with_depth() {
+    // program > lexical_declaration > variable_declarator > identifier/number
+    let grep = Tsx.ast_grep("let a = 1; let b = 2;");
+    let node = grep.root();
+    let level: Vec<_> = Level::new(&node).map(|n| n.range()).collect();
+    let with_depth: Vec<_> = LevelWithDepth::new(&node).collect();
+    let ranges: Vec<_> = with_depth.iter().map(|(n, _)| n.range()).collect();
+    assert_eq!(level, ranges);
+    let depths: Vec<_> = with_depth.iter().map(|(_, d)| *d).collect();
+    assert!(depths.windows(2).all(|w| w[0] <= w[1]));
+    for (n, d) in &with_depth {
+      assert_eq!(n.ancestors().count(), *d);
+    }
+    let kinds: Vec<_> = LevelWithDepth::new(&node)
+      .take_while(|(_, d)| *d <= 1)
+      .map(|(n, _)| n.kind().to_string())
+      .collect();
+    assert_eq!(
+      kinds,
+      ["program", "lexical_declaration", "lexical_declaration"]
+    );
+  }
+
+  #[test]
+  fn test_dfs_with_depth() {
+    let grep = Tsx.ast_grep("function f() { if (a) { b() } }");
+    let node = grep.root();
+    let pre: Vec<_> = Pre::new(&node).map(|n| n.range()).collect();
+    let with_depth: Vec<_> = DfsWithDepth::new(&node).collect();
+    let ranges: Vec<_> = with_depth.iter().map(|(n, _)| n.range()).collect();
+    assert_eq!(pre, ranges);
+    for (n, d) in &with_depth {
+      assert_eq!(n.ancestors().count(), *d);
+    }
+    let body = node.find("{ if (a) { b() } }").expect("should find body");
+    let depths: Vec<_> = body
+      .dfs_with_depth()
+      .filter(|(n, _)| n.is_named())
+      .map(|(n, d)| (n.kind().to_string(), d))
+      .collect();
+    let expected = [
+      ("statement_block", 0),
+      ("if_statement", 1),
+      ("parenthesized_expression", 2),
+      ("identifier", 3),
+      ("statement_block", 2),
+      ("expression_statement", 3),
+      ("call_expression", 4),
+      ("identifier", 5),
+      ("arguments", 5),
+    ];
+    let expected: Vec<_> = expected.iter().map(|(k, d)| (k.to_string(), *d)).collect();
+    assert_eq!(depths, expected);
+  }
+
   fn pre_order_with_matcher(node: Node<StrDoc<Tsx>>, matcher: &str) -> Vec<Range<usize>> {
     if node.matches(matcher) {
       vec![node.range()]
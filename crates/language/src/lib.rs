@@ -31,7 +31,7 @@ pub use html::Html;
 
 use ast_grep_core::language::{TSLanguage, TSRange};
 use ast_grep_core::meta_var::MetaVariable;
-use ast_grep_core::{Doc, Node};
+use ast_grep_core::{Doc, Node, Pattern};
 use ignore::types::{Types, TypesBuilder};
 use serde::de::Visitor;
 use serde::{de, Deserialize, Deserializer, Serialize};
@@ -237,6 +237,68 @@ impl SupportLang {
   pub fn file_types(&self) -> Types {
     file_types(*self)
   }
+
+  /// Name of the `ignore` file type group registered by [`SupportLang::file_types`].
+  /// Callers building their own `TypesBuilder` can `select` this name.
+  /// It is the same as the `Display` name of the language.
+  pub fn file_type_name(&self) -> String {
+    self.to_string()
+  }
+
+  /// File extensions recognized for this language, without the leading dot.
+  pub fn extensions(&self) -> &'static [&'static str] {
+    extensions(*self)
+  }
+
+  /// Guess the language of a file from its extension.
+  /// Returns `None` if no built-in language claims the extension.
+  pub fn guess_from_path(path: &Path) -> Option<Self> {
+    from_extension(path)
+  }
+
+  /// Guess the language from file content, for files without a known extension.
+  /// Checks the shebang interpreter, e.g. `#!/usr/bin/env node`, then an HTML start tag.
+  pub fn guess_from_content(content: &str) -> Option<Self> {
+    let content = content.trim_start_matches('\u{feff}');
+    if let Some(shebang) = content.strip_prefix("#!") {
+      let line = shebang.lines().next().unwrap_or_default();
+      return from_interpreter(line);
+    }
+    let start = content.trim_start();
+    let start = start.get(..14).unwrap_or(start).to_ascii_lowercase();
+    if start.starts_with("<!doctype html") || start.starts_with("<html") {
+      return Some(SupportLang::Html);
+    }
+    None
+  }
+}
+
+/// Map the interpreter of a shebang line, like `/usr/bin/env python3 -u`, to a language.
+fn from_interpreter(line: &str) -> Option<SupportLang> {
+  use SupportLang::*;
+  let mut words = line.split_whitespace();
+  let mut program = words.next()?.rsplit('/').next()?;
+  if program == "env" {
+    // skip env flags like `-S` and variable assignments
+    program = words.find(|w| !w.starts_with('-') && !w.contains('='))?;
+  }
+  // python3.12 and python3 are python
+  let name = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+  let lang = match name {
+    "node" | "nodejs" | "bun" | "qjs" => JavaScript,
+    "deno" | "ts-node" | "tsx" => TypeScript,
+    "python" | "pypy" => Python,
+    "ruby" => Ruby,
+    "sh" | "bash" | "zsh" | "dash" | "ksh" => Bash,
+    "lua" | "luajit" => Lua,
+    "php" => Php,
+    "elixir" => Elixir,
+    "runghc" | "runhaskell" => Haskell,
+    "swift" => Swift,
+    "scala" => Scala,
+    _ => return None,
+  };
+  Some(lang)
 }
 
 impl fmt::Display for SupportLang {
@@ -412,6 +474,39 @@ impl Language for SupportLang {
   fn pre_process_pattern<'q>(&self, query: &'q str) -> Cow<'q, str> {
     execute_lang_method! { self, pre_process_pattern, query }
   }
+
+  fn import_pattern(&self) -> Option<Pattern<Self>> {
+    use SupportLang::*;
+    match self {
+      JavaScript | TypeScript | Tsx => Pattern::try_new("import $_ from $PATH", *self).ok(),
+      _ => None,
+    }
+  }
+
+  fn unwrap_expression(&self, src: &str) -> Option<String> {
+    use SupportLang::*;
+    let src = match self {
+      JavaScript | TypeScript | Tsx => {
+        let trimmed = src.trim_end();
+        trimmed.strip_suffix(';').unwrap_or(trimmed)
+      }
+      _ => src,
+    };
+    Some(src.to_string())
+  }
+
+  fn extra_node_kinds(&self) -> &'static [&'static str] {
+    use SupportLang::*;
+    match self {
+      Css => &["comment", "js_comment"],
+      Java | Rust => &["block_comment", "line_comment"],
+      Kotlin => &["line_comment", "multiline_comment"],
+      Scala => &["comment", "block_comment"],
+      Swift => &["comment", "multiline_comment"],
+      JavaScript | TypeScript | Tsx => &["comment", "html_comment"],
+      _ => &["comment"],
+    }
+  }
 }
 
 fn extensions(lang: SupportLang) -> &'static [&'static str] {
@@ -473,8 +568,7 @@ fn add_custom_file_type<'b>(
 fn file_types(lang: SupportLang) -> Types {
   let mut builder = TypesBuilder::new();
   let exts = extensions(lang);
-  let lang_name = lang.to_string();
-  add_custom_file_type(&mut builder, &lang_name, exts);
+  add_custom_file_type(&mut builder, &lang.to_string(), exts);
   builder.build().expect("file type must be valid")
 }
 
@@ -536,5 +630,80 @@ mod test {
     assert_eq!(from_extension(path), Some(SupportLang::Rust));
   }
 
-  // TODO: add test for file_types
+  #[test]
+  fn test_resolve_imports() {
+    let src = "import React from 'react'\nimport { a, b } from \"./utils\"\nconst c = 1";
+    let root = SupportLang::TypeScript.ast_grep(src);
+    assert_eq!(root.root().resolve_imports(), ["react", "./utils"]);
+    let root = SupportLang::Python.ast_grep("import os");
+    assert!(root.root().resolve_imports().is_empty());
+  }
+
+  #[test]
+  fn test_unwrap_expression() {
+    let unwrap = |lang: SupportLang, src: &str| lang.unwrap_expression(src);
+    assert_eq!(unwrap(SupportLang::Tsx, "a + b;").as_deref(), Some("a + b"));
+    assert_eq!(
+      unwrap(SupportLang::TypeScript, "a + b").as_deref(),
+      Some("a + b")
+    );
+    assert_eq!(
+      unwrap(SupportLang::JavaScript, "f(a);\n").as_deref(),
+      Some("f(a)")
+    );
+    assert_eq!(
+      unwrap(SupportLang::Python, "a + b;").as_deref(),
+      Some("a + b;")
+    );
+  }
+
+  #[test]
+  fn test_guess_from_path() {
+    let guess = |p: &str| SupportLang::guess_from_path(Path::new(p));
+    assert_eq!(guess("src/main.rs"), Some(SupportLang::Rust));
+    assert_eq!(guess("App.tsx"), Some(SupportLang::Tsx));
+    assert_eq!(guess("config.yml"), Some(SupportLang::Yaml));
+    assert_eq!(guess("README"), None);
+    assert_eq!(guess("notes.unknown"), None);
+    assert!(SupportLang::Python.extensions().contains(&"pyi"));
+  }
+
+  #[test]
+  fn test_guess_from_content() {
+    let guess = SupportLang::guess_from_content;
+    assert_eq!(
+      guess("#!/usr/bin/env node\nfoo()"),
+      Some(SupportLang::JavaScript)
+    );
+    assert_eq!(guess("#!/usr/bin/python3.12\n"), Some(SupportLang::Python));
+    assert_eq!(
+      guess("#!/usr/bin/env -S deno run"),
+      Some(SupportLang::TypeScript)
+    );
+    assert_eq!(guess("#! /bin/bash -e"), Some(SupportLang::Bash));
+    assert_eq!(guess("\u{feff}<!DOCTYPE html><p>"), Some(SupportLang::Html));
+    assert_eq!(guess("\n  <html lang=en>"), Some(SupportLang::Html));
+    assert_eq!(guess("#!/usr/bin/env perl"), None);
+    assert_eq!(guess("plain text"), None);
+  }
+
+  #[test]
+  fn test_file_types() {
+    for &lang in SupportLang::all_langs() {
+      let types = lang.file_types();
+      for ext in lang.extensions() {
+        let path = format!("file.{ext}");
+        assert!(types.matched(&path, false).is_whitelist(), "{lang} {ext}");
+      }
+    }
+    let mut builder = TypesBuilder::new();
+    for lang in [SupportLang::Rust, SupportLang::Python] {
+      add_custom_file_type(&mut builder, &lang.file_type_name(), lang.extensions());
+      builder.select(&lang.file_type_name());
+    }
+    let types = builder.build().unwrap();
+    assert!(types.matched("main.rs", false).is_whitelist());
+    assert!(types.matched("main.py", false).is_whitelist());
+    assert!(types.matched("main.go", false).is_ignore());
+  }
 }
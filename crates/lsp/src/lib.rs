@@ -1,6 +1,8 @@
 mod utils;
+mod watch;
 
 use dashmap::DashMap;
+use serde::Deserialize;
 use serde_json::Value;
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
@@ -11,8 +13,13 @@ use ast_grep_core::{language::Language, AstGrep, Doc, StrDoc};
 
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::task::JoinHandle;
 
-use utils::{convert_match_to_diagnostic, diagnostic_to_code_action, RewriteData};
+use utils::{
+  convert_match_to_diagnostic, diagnostic_to_code_action, diagnostic_to_doc_action, RewriteData,
+  DOCUMENTATION_AST_GREP,
+};
 
 pub use tower_lsp::{LspService, Server};
 
@@ -24,17 +31,39 @@ struct VersionedAst<D: Doc> {
   root: AstGrep<D>,
 }
 
+type RuleResult<L> = std::result::Result<RuleCollection<L>, String>;
+/// Reads rules again when config files in the base directory change.
+pub type RuleLoader<L> = dyn Fn() -> RuleResult<L> + Send + Sync;
+
 pub struct Backend<L: LSPLang> {
   client: Client,
-  map: DashMap<String, VersionedAst<StrDoc<L>>>,
+  map: Arc<DashMap<String, VersionedAst<StrDoc<L>>>>,
   base: PathBuf,
-  rules: std::result::Result<RuleCollection<L>, String>,
+  rules: Arc<RwLock<RuleResult<L>>>,
+  /// only rules having all the tags are active, set by the `ast-grep/setTags` notification
+  tags: Arc<RwLock<Vec<String>>>,
+  rule_loader: Option<Arc<RuleLoader<L>>>,
+  /// config files and rule directories watched to reload rules
+  rule_paths: Arc<[PathBuf]>,
+  /// background task reloading rules, aborted when the connection is closed
+  watcher: Mutex<Option<JoinHandle<()>>>,
 }
 
 const FALLBACK_CODE_ACTION_PROVIDER: Option<CodeActionProviderCapability> =
   Some(CodeActionProviderCapability::Simple(true));
 
+/// Custom notification to select active rules by tags. Register it with
+/// `LspService::build(..).custom_method(SET_TAGS, Backend::set_tags)`.
+pub const SET_TAGS: &str = "ast-grep/setTags";
+
+/// Params of the `ast-grep/setTags` notification. An empty list activates all rules.
+#[derive(Deserialize)]
+pub struct SetTagsParams {
+  pub tags: Vec<String>,
+}
+
 const APPLY_ALL_FIXES: &str = "ast-grep.applyAllFixes";
+const OPEN_DOCUMENTATION: &str = "ast-grep.openDocumentation";
 const QUICKFIX_AST_GREP: &str = "quickfix.ast-grep";
 const FIX_ALL_AST_GREP: &str = "source.fixAll.ast-grep";
 
@@ -55,6 +84,7 @@ fn code_action_provider(
     code_action_kinds: Some(vec![
       CodeActionKind::new(QUICKFIX_AST_GREP),
       CodeActionKind::new(FIX_ALL_AST_GREP),
+      CodeActionKind::new(DOCUMENTATION_AST_GREP),
     ]),
     work_done_progress_options: Default::default(),
     resolve_provider: Some(true),
@@ -75,7 +105,7 @@ impl<L: LSPLang> LanguageServer for Backend<L> {
         code_action_provider: code_action_provider(&params.capabilities)
           .or(FALLBACK_CODE_ACTION_PROVIDER),
         execute_command_provider: Some(ExecuteCommandOptions {
-          commands: vec![APPLY_ALL_FIXES.to_string()],
+          commands: vec![APPLY_ALL_FIXES.to_string(), OPEN_DOCUMENTATION.to_string()],
           work_done_progress_options: Default::default(),
         }),
         ..ServerCapabilities::default()
@@ -90,7 +120,12 @@ impl<L: LSPLang> LanguageServer for Backend<L> {
       .await;
 
     // Report errors loading config once, upon initialization
-    if let Err(error) = &self.rules {
+    let error = self
+      .rules
+      .read()
+      .ok()
+      .and_then(|r| r.as_ref().err().cloned());
+    if let Some(error) = error {
       // popup message
       self
         .client
@@ -108,9 +143,11 @@ impl<L: LSPLang> LanguageServer for Backend<L> {
         )
         .await;
     }
+    self.start_watching_rules();
   }
 
   async fn shutdown(&self) -> Result<()> {
+    self.stop_watching_rules();
     Ok(())
   }
 
@@ -171,28 +208,116 @@ impl<L: LSPLang> LanguageServer for Backend<L> {
 }
 
 impl<L: LSPLang> Backend<L> {
-  pub fn new(
-    client: Client,
-    base: PathBuf,
-    rules: std::result::Result<RuleCollection<L>, String>,
-  ) -> Self {
+  pub fn new(client: Client, base: PathBuf, rules: RuleResult<L>) -> Self {
     Self {
       client,
-      rules,
+      rules: Arc::new(RwLock::new(rules)),
+      tags: Arc::new(RwLock::new(vec![])),
       base,
-      map: DashMap::new(),
+      map: Arc::new(DashMap::new()),
+      rule_loader: None,
+      rule_paths: Arc::new([]),
+      watcher: Mutex::new(None),
     }
   }
 
-  fn get_rules(&self, uri: &Url) -> Option<Vec<&RuleConfig<L>>> {
-    let absolute_path = uri.to_file_path().ok()?;
-    let path = if let Ok(p) = absolute_path.strip_prefix(&self.base) {
-      p
-    } else {
-      &absolute_path
+  /// Watch `paths`, the config file and rule/util directories, after the client is initialized.
+  /// Rules are reloaded by `loader` on change, and open documents are diagnosed again.
+  pub fn with_rule_loader<F>(mut self, loader: F, paths: Vec<PathBuf>) -> Self
+  where
+    F: Fn() -> RuleResult<L> + Send + Sync + 'static,
+  {
+    self.rule_loader = Some(Arc::new(loader));
+    self.rule_paths = paths.into();
+    self
+  }
+
+  /// A handle sharing documents and rules with self, used by the watcher task.
+  fn share(&self) -> Self {
+    Self {
+      client: self.client.clone(),
+      map: self.map.clone(),
+      base: self.base.clone(),
+      rules: self.rules.clone(),
+      tags: self.tags.clone(),
+      rule_loader: None,
+      rule_paths: Arc::new([]),
+      watcher: Mutex::new(None),
+    }
+  }
+
+  fn start_watching_rules(&self) {
+    let Some(loader) = self.rule_loader.clone() else {
+      return;
     };
-    let rules = self.rules.as_ref().ok()?.for_path(path);
-    Some(rules)
+    let paths = self.rule_paths.clone();
+    let task = tokio::spawn(watch::watch_rules(self.share(), loader, paths));
+    if let Ok(mut watcher) = self.watcher.lock() {
+      if let Some(old) = watcher.replace(task) {
+        old.abort();
+      }
+    }
+  }
+
+  fn stop_watching_rules(&self) {
+    if let Ok(mut watcher) = self.watcher.lock() {
+      if let Some(task) = watcher.take() {
+        task.abort();
+      }
+    }
+  }
+
+  /// Replace rules and publish diagnostics of all open documents again.
+  async fn reload_rules(&self, rules: RuleResult<L>) {
+    match &rules {
+      Ok(_) => {
+        self
+          .client
+          .log_message(MessageType::INFO, "rules reloaded!")
+          .await
+      }
+      Err(error) => {
+        self
+          .client
+          .show_message(
+            MessageType::ERROR,
+            format!("Failed to load rules: {}", error),
+          )
+          .await
+      }
+    }
+    if let Ok(mut current) = self.rules.write() {
+      *current = rules;
+    }
+    self.republish_all_diagnostics().await;
+  }
+
+  /// Only run rules having all `tags`, compared case-insensitively.
+  pub async fn set_tags(&self, params: SetTagsParams) {
+    if let Ok(mut tags) = self.tags.write() {
+      *tags = params.tags;
+    }
+    self.republish_all_diagnostics().await;
+  }
+
+  async fn republish_all_diagnostics(&self) {
+    let uris: Vec<_> = self.map.iter().map(|e| e.key().clone()).collect();
+    for uri in uris {
+      let Ok(url) = Url::parse(&uri) else {
+        continue;
+      };
+      // compute diagnostics and release the map guard before awaiting the client
+      let Some((version, diagnostics)) = self.map.get(&uri).map(|versioned| {
+        let diagnostics = self.get_diagnostics(&url, &versioned);
+        (versioned.version, diagnostics.unwrap_or_default())
+      }) else {
+        continue;
+      };
+      self
+        .client
+        .publish_diagnostics(url, diagnostics, Some(version))
+        .await;
+    }
   }
 
   fn get_diagnostics(
@@ -200,7 +325,21 @@ impl<L: LSPLang> Backend<L> {
     uri: &Url,
     versioned: &VersionedAst<StrDoc<L>>,
   ) -> Option<Vec<Diagnostic>> {
-    let rules = self.get_rules(uri)?;
+    let absolute_path = uri.to_file_path().ok()?;
+    let path = if let Ok(p) = absolute_path.strip_prefix(&self.base) {
+      p
+    } else {
+      &absolute_path
+    };
+    let rules = self.rules.read().ok()?;
+    let tags = self.tags.read().ok()?;
+    let rules: Vec<&RuleConfig<L>> = rules
+      .as_ref()
+      .ok()?
+      .for_path(path)
+      .into_iter()
+      .filter(|r| tags.iter().all(|t| r.has_tag(t)))
+      .collect();
     if rules.is_empty() {
       return None;
     }
@@ -212,7 +351,7 @@ impl<L: LSPLang> Backend<L> {
     let matches = scan.scan(&versioned.root, pre_scan, false).matches;
     let mut diagnostics = vec![];
     for (rule, ms) in matches {
-      let to_diagnostic = |m| convert_match_to_diagnostic(m, rule);
+      let to_diagnostic = |m| convert_match_to_diagnostic(m, rule, uri);
       diagnostics.extend(ms.into_iter().map(to_diagnostic));
     }
     Some(diagnostics)
@@ -387,7 +526,13 @@ impl<L: LSPLang> Backend<L> {
           .map(|s| s.contains("ast-grep"))
           .unwrap_or(false)
       })
-      .filter_map(|d| diagnostic_to_code_action(&text_doc, d))
+      .filter_map(|d| {
+        if d.data.is_some() {
+          diagnostic_to_code_action(&text_doc, d)
+        } else {
+          diagnostic_to_doc_action(d, OPEN_DOCUMENTATION)
+        }
+      })
       .map(CodeActionOrCommand::from)
       .collect();
     Some(response)
@@ -411,6 +556,10 @@ impl<L: LSPLang> Backend<L> {
         self.on_apply_all_fix(command, arguments).await?;
         None
       }
+      OPEN_DOCUMENTATION => {
+        self.on_open_documentation(arguments).await?;
+        None
+      }
       _ => {
         self
           .client
@@ -461,6 +610,25 @@ impl<L: LSPLang> Backend<L> {
     None
   }
 
+  async fn on_open_documentation(&self, arguments: Vec<Value>) -> Option<()> {
+    let first = arguments.first()?.clone();
+    let uri: Url = match serde_json::from_value(first) {
+      Ok(uri) => uri,
+      Err(error) => {
+        self.report_error(LspError::JSONDecodeError(error)).await;
+        return None;
+      }
+    };
+    let params = ShowDocumentParams {
+      uri,
+      external: Some(true),
+      take_focus: None,
+      selection: None,
+    };
+    self.client.show_document(params).await.ok()?;
+    None
+  }
+
   async fn report_error(&self, error: LspError) {
     match error {
       LspError::JSONDecodeError(e) => {
@@ -488,6 +656,13 @@ impl<L: LSPLang> Backend<L> {
   }
 }
 
+impl<L: LSPLang> Drop for Backend<L> {
+  fn drop(&mut self) {
+    // the connection is closed
+    self.stop_watching_rules();
+  }
+}
+
 enum LspError {
   JSONDecodeError(serde_json::Error),
   UnsupportedFileType,
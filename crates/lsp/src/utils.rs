@@ -1,13 +1,16 @@
 //! Provides utility to convert ast-grep data types to lsp data types
 use ast_grep_config::RuleConfig;
 use ast_grep_config::Severity;
-use ast_grep_core::{language::Language, Doc, Node, NodeMatch, StrDoc};
+use ast_grep_core::{language::Language, Doc, Node, NodeMatch, Span, StrDoc};
 
 use serde::{Deserialize, Serialize};
 use tower_lsp::lsp_types::*;
 
 use std::collections::HashMap;
 
+/// Code action kind to open the documentation of rules without fix.
+pub const DOCUMENTATION_AST_GREP: &str = "source.documentation.ast-grep";
+
 #[derive(Serialize, Deserialize)]
 pub struct RewriteData {
   pub fixed: String,
@@ -44,7 +47,7 @@ pub fn diagnostic_to_code_action(
     return None;
   };
   let action = CodeAction {
-    title: format!("Fix `{id}` with ast-grep"),
+    title: format!("Apply fix: {id}"),
     command: None,
     diagnostics: None,
     edit: Some(edit),
@@ -56,17 +59,48 @@ pub fn diagnostic_to_code_action(
   Some(action)
 }
 
+/// For diagnostics without fix, offer to open the rule's documentation url.
+pub fn diagnostic_to_doc_action(diagnostic: Diagnostic, command: &str) -> Option<CodeAction> {
+  if diagnostic.data.is_some() {
+    return None;
+  }
+  let href = diagnostic.code_description.as_ref()?.href.clone();
+  let NumberOrString::String(id) = diagnostic.code.as_ref()? else {
+    return None;
+  };
+  let title = format!("Open documentation: {id}");
+  let command = Command::new(
+    title.clone(),
+    command.into(),
+    Some(vec![serde_json::Value::String(href.into())]),
+  );
+  let action = CodeAction {
+    title,
+    command: Some(command),
+    diagnostics: Some(vec![diagnostic]),
+    edit: None,
+    disabled: None,
+    kind: Some(CodeActionKind::new(DOCUMENTATION_AST_GREP)),
+    is_preferred: None,
+    data: None,
+  };
+  Some(action)
+}
+
 fn convert_node_to_range<D: Doc>(node_match: &Node<D>) -> Range {
-  let start = node_match.start_pos();
-  let end = node_match.end_pos();
+  convert_span_to_range(node_match.span())
+}
+
+// `From<Span> for Range` would be an orphan impl since neither type is defined here
+pub fn convert_span_to_range(span: Span) -> Range {
   Range {
     start: Position {
-      line: start.line() as u32,
-      character: start.column(node_match) as u32,
+      line: span.start_line as u32,
+      character: span.start_col as u32,
     },
     end: Position {
-      line: end.line() as u32,
-      character: end.column(node_match) as u32,
+      line: span.end_line as u32,
+      character: span.end_col as u32,
     },
   }
 }
@@ -74,12 +108,22 @@ fn convert_node_to_range<D: Doc>(node_match: &Node<D>) -> Range {
 pub fn convert_match_to_diagnostic<L: Language>(
   node_match: NodeMatch<StrDoc<L>>,
   rule: &RuleConfig<L>,
+  uri: &Url,
 ) -> Diagnostic {
   // TODO
   let rewrite_data =
     RewriteData::from_node_match(&node_match, rule).and_then(|r| serde_json::to_value(r).ok());
+  let range = convert_node_to_range(&node_match);
+  let note = rule.get_note(&node_match);
+  // the note is also a secondary hint at the same location, like rustc's `note:`
+  let related_information = note.clone().map(|note| {
+    vec![DiagnosticRelatedInformation {
+      location: Location::new(uri.clone(), range),
+      message: note,
+    }]
+  });
   Diagnostic {
-    range: convert_node_to_range(&node_match),
+    range,
     code: Some(NumberOrString::String(rule.id.clone())),
     code_description: url_to_code_description(&rule.url),
     severity: Some(match rule.severity {
@@ -89,15 +133,27 @@ pub fn convert_match_to_diagnostic<L: Language>(
       Severity::Hint => DiagnosticSeverity::HINT,
       Severity::Off => unreachable!("turned-off rule should not have match"),
     }),
-    message: get_non_empty_message(rule, &node_match),
-    source: Some(String::from("ast-grep")),
+    message: get_non_empty_message(rule, &node_match, note),
+    source: Some(diagnostic_source(rule)),
     tags: None,
-    related_information: None,
+    related_information,
     data: rewrite_data,
   }
 }
 
-fn get_non_empty_message<L: Language>(rule: &RuleConfig<L>, nm: &NodeMatch<StrDoc<L>>) -> String {
+/// Tags are shown next to the source so users can tell rule categories apart.
+fn diagnostic_source<L: Language>(rule: &RuleConfig<L>) -> String {
+  match &rule.tags {
+    Some(tags) if !tags.is_empty() => format!("ast-grep ({})", tags.join(", ")),
+    _ => String::from("ast-grep"),
+  }
+}
+
+fn get_non_empty_message<L: Language>(
+  rule: &RuleConfig<L>,
+  nm: &NodeMatch<StrDoc<L>>,
+  note: Option<String>,
+) -> String {
   // Note: The LSP client in vscode won't show any diagnostics at all if it receives one with an empty message
   let msg = if rule.message.is_empty() {
     rule.id.to_string()
@@ -105,7 +161,7 @@ fn get_non_empty_message<L: Language>(rule: &RuleConfig<L>, nm: &NodeMatch<StrDo
     rule.get_message(nm)
   };
   // append note to message ast-grep/ast-grep-vscode#352
-  if let Some(note) = &rule.note {
+  if let Some(note) = note {
     format!("{msg}\n\n{note}")
   } else {
     msg
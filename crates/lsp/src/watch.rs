@@ -0,0 +1,100 @@
+use crate::{Backend, LSPLang, RuleLoader};
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tower_lsp::lsp_types::MessageType;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Changes arriving within this window trigger only one reload.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Config files and rule/util directories whose changes reload rules.
+struct WatchedPaths(Vec<PathBuf>);
+
+impl WatchedPaths {
+  fn new(paths: &[PathBuf]) -> Self {
+    // notify may report resolved paths, e.g. FSEvents on macOS
+    let paths = paths.iter().filter_map(|p| p.canonicalize().ok());
+    Self(paths.collect())
+  }
+
+  fn watch(&self, watcher: &mut impl Watcher) -> notify::Result<()> {
+    for path in &self.0 {
+      if path.is_dir() {
+        watcher.watch(path, RecursiveMode::Recursive)?;
+      } else if let Some(parent) = path.parent() {
+        // editors may replace the file instead of writing it, so watch its directory
+        watcher.watch(parent, RecursiveMode::NonRecursive)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// sgconfig.yml, rule and util files are all YAML.
+  fn is_config_change(&self, event: &Event) -> bool {
+    if matches!(event.kind, EventKind::Access(_)) {
+      return false;
+    }
+    event.paths.iter().any(|path| {
+      let ext = path.extension().and_then(|e| e.to_str());
+      matches!(ext, Some("yml" | "yaml")) && self.0.iter().any(|p| path.starts_with(p))
+    })
+  }
+}
+
+/// Watch the config file and rule directories, and reload rules when they change.
+pub(crate) async fn watch_rules<L: LSPLang>(
+  backend: Backend<L>,
+  loader: Arc<RuleLoader<L>>,
+  paths: Arc<[PathBuf]>,
+) {
+  let (tx, mut rx) = mpsc::unbounded_channel();
+  let paths = Arc::new(WatchedPaths::new(&paths));
+  let watched = paths.clone();
+  let watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+    if event.map_or(false, |e| watched.is_config_change(&e)) {
+      _ = tx.send(());
+    }
+  });
+  let watched = watcher.and_then(|mut w| {
+    paths.watch(&mut w)?;
+    Ok(w)
+  });
+  // the watcher stops when dropped, so keep it until the task ends
+  let _watcher = match watched {
+    Ok(w) => w,
+    Err(error) => {
+      backend
+        .client
+        .log_message(
+          MessageType::ERROR,
+          format!("Failed to watch rules: {}", error),
+        )
+        .await;
+      return;
+    }
+  };
+  backend
+    .client
+    .log_message(MessageType::INFO, "watching rule changes!")
+    .await;
+  while rx.recv().await.is_some() {
+    // wait until no change happens within the debounce window
+    loop {
+      match timeout(DEBOUNCE, rx.recv()).await {
+        Ok(Some(())) => continue,
+        Ok(None) => return,
+        Err(_) => break,
+      }
+    }
+    let loader = loader.clone();
+    let Ok(rules) = tokio::task::spawn_blocking(move || loader()).await else {
+      return;
+    };
+    backend.reload_rules(rules).await;
+  }
+}
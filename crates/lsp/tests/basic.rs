@@ -211,6 +211,122 @@ fn test_basic() {
   });
 }
 
+/// Read messages until diagnostics are published, replying to the workspace folders request.
+async fn wait_for_diagnostics(
+  req_client: &mut DuplexStream,
+  resp_client: &mut DuplexStream,
+) -> Value {
+  let mut output = vec![];
+  let mut buf = vec![0; 1024];
+  tokio::time::timeout(std::time::Duration::from_secs(5), async {
+    loop {
+      let n = resp_client.read(&mut buf).await.unwrap();
+      output.extend_from_slice(&buf[..n]);
+      let messages = resp(&output);
+      // the server asks for workspace folders before checking the file
+      if let Some(folders) = messages
+        .iter()
+        .find(|v| v["method"] == "workspace/workspaceFolders")
+      {
+        let reply = serde_json::json!({"jsonrpc": "2.0", "id": folders["id"], "result": null});
+        let reply = req(&reply.to_string());
+        req_client.write_all(reply.as_bytes()).await.unwrap();
+        output.clear();
+        continue;
+      }
+      let published = messages
+        .into_iter()
+        .find(|v| v["method"] == "textDocument/publishDiagnostics");
+      if let Some(published) = published {
+        return published["params"]["diagnostics"].clone();
+      }
+    }
+  })
+  .await
+  .expect("should publish diagnostics")
+}
+
+#[test]
+fn test_note_as_related_information() {
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let (mut req_client, mut resp_client) = create_lsp();
+    initialize_lsp(&mut req_client, &mut resp_client).await;
+    let open = r#"{
+      "jsonrpc": "2.0",
+      "method": "textDocument/didOpen",
+      "params": {
+        "textDocument": {
+          "uri": "file:///Users/codes/test.ts",
+          "languageId": "typescript",
+          "version": 1,
+          "text": "console.log(123)"
+        }
+      }
+    }"#;
+    req_client.write_all(req(open).as_bytes()).await.unwrap();
+    let diagnostics = wait_for_diagnostics(&mut req_client, &mut resp_client).await;
+    let diagnostic = &diagnostics[0];
+    // clients without related information support still show the note
+    assert_eq!(diagnostic["message"], "No console.log\n\nno console.log");
+    let related = &diagnostic["relatedInformation"][0];
+    assert_eq!(related["message"], "no console.log");
+    assert_eq!(related["location"]["range"], diagnostic["range"]);
+  });
+}
+
+#[test]
+fn test_tags() {
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let rule = "
+id: no-debugger
+message: No debugger
+language: TypeScript
+tags: [security, js]
+rule: {kind: debugger_statement}
+";
+    let configs: Vec<RuleConfig<SupportLang>> =
+      from_yaml_string(rule, &GlobalRules::default()).unwrap();
+    let rules: std::result::Result<_, String> = Ok(RuleCollection::try_new(configs).unwrap());
+    let base = Path::new("./").to_path_buf();
+    let (service, socket) = LspService::build(|client| Backend::new(client, base, rules))
+      .custom_method(SET_TAGS, Backend::set_tags)
+      .finish();
+    let (mut req_client, req_server) = duplex(1024);
+    let (resp_server, mut resp_client) = duplex(1024);
+    tokio::spawn(Server::new(req_server, resp_server, socket).serve(service));
+    initialize_lsp(&mut req_client, &mut resp_client).await;
+    let open = r#"{
+      "jsonrpc": "2.0",
+      "method": "textDocument/didOpen",
+      "params": {
+        "textDocument": {
+          "uri": "file:///Users/codes/test.ts",
+          "languageId": "typescript",
+          "version": 1,
+          "text": "debugger"
+        }
+      }
+    }"#;
+    req_client.write_all(req(open).as_bytes()).await.unwrap();
+    let diagnostics = wait_for_diagnostics(&mut req_client, &mut resp_client).await;
+    assert_eq!(diagnostics[0]["source"], "ast-grep (security, js)");
+    let set_tags = r#"{"jsonrpc":"2.0","method":"ast-grep/setTags","params":{"tags":["style"]}}"#;
+    req_client
+      .write_all(req(set_tags).as_bytes())
+      .await
+      .unwrap();
+    let diagnostics = wait_for_diagnostics(&mut req_client, &mut resp_client).await;
+    assert_eq!(diagnostics, serde_json::json!([]));
+    let set_tags = r#"{"jsonrpc":"2.0","method":"ast-grep/setTags","params":{"tags":["JS"]}}"#;
+    req_client
+      .write_all(req(set_tags).as_bytes())
+      .await
+      .unwrap();
+    let diagnostics = wait_for_diagnostics(&mut req_client, &mut resp_client).await;
+    assert_eq!(diagnostics.as_array().map(Vec::len), Some(1));
+  });
+}
+
 #[test]
 #[ignore = "fixAll conflicts with quickfix"]
 fn test_code_action() {
@@ -258,3 +374,158 @@ fn test_execute_apply_all_fixes() {
     );
   });
 }
+
+async fn request_quickfix(
+  req_client: &mut DuplexStream,
+  resp_client: &mut DuplexStream,
+  diagnostic: Value,
+) -> Value {
+  let request = serde_json::json!({
+    "jsonrpc": "2.0",
+    "id": 2,
+    "method": "textDocument/codeAction",
+    "params": {
+      "range": {
+        "start": { "line": 0, "character": 0 },
+        "end": { "line": 0, "character": 16 }
+      },
+      "textDocument": { "uri": "file:///Users/codes/ast-grep-vscode/test.ts" },
+      "context": { "diagnostics": [diagnostic] }
+    }
+  });
+  req_client
+    .write_all(req(&request.to_string()).as_bytes())
+    .await
+    .unwrap();
+  let mut buf = vec![0; 4096];
+  let _ = resp_client.read(&mut buf).await.unwrap();
+  let end = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+  resp(&buf[..end])
+    .into_iter()
+    .find(|v| v["id"] == 2)
+    .expect("should respond to code action")
+}
+
+fn make_diagnostic(data: Option<Value>) -> Value {
+  serde_json::json!({
+    "range": {
+      "start": { "line": 0, "character": 0 },
+      "end": { "line": 0, "character": 16 }
+    },
+    "code": "no-console-rule",
+    "codeDescription": { "href": "https://ast-grep.github.io/rules/no-console" },
+    "source": "ast-grep",
+    "message": "No console.log",
+    "data": data
+  })
+}
+
+#[test]
+fn test_quickfix_code_action() {
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let (mut req_client, mut resp_client) = create_lsp();
+    initialize_lsp(&mut req_client, &mut resp_client).await;
+    let data = serde_json::json!({ "fixed": "alert(123)" });
+    let diagnostic = make_diagnostic(Some(data));
+    let response = request_quickfix(&mut req_client, &mut resp_client, diagnostic).await;
+    let action = &response["result"][0];
+    assert_eq!(action["title"], "Apply fix: no-console-rule");
+    assert_eq!(action["kind"], "quickfix");
+    let uri = "file:///Users/codes/ast-grep-vscode/test.ts";
+    let edit = &action["edit"]["changes"][uri][0];
+    assert_eq!(edit["newText"], "alert(123)");
+    assert_eq!(edit["range"]["end"]["character"], 16);
+  });
+}
+
+#[test]
+fn test_documentation_code_action() {
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let (mut req_client, mut resp_client) = create_lsp();
+    initialize_lsp(&mut req_client, &mut resp_client).await;
+    let diagnostic = make_diagnostic(None);
+    let response = request_quickfix(&mut req_client, &mut resp_client, diagnostic).await;
+    let action = &response["result"][0];
+    assert_eq!(action["title"], "Open documentation: no-console-rule");
+    assert_eq!(action["kind"], "source.documentation.ast-grep");
+    assert!(action.get("edit").is_none());
+    let command = &action["command"];
+    assert_eq!(command["command"], "ast-grep.openDocumentation");
+    assert_eq!(
+      command["arguments"][0],
+      "https://ast-grep.github.io/rules/no-console"
+    );
+  });
+}
+
+const RELOAD_RULE: &str = r"
+id: no-debugger
+message: No debugger
+language: TypeScript
+rule:
+  pattern: debugger
+";
+
+fn load_rule_file(path: &Path) -> std::result::Result<RuleCollection<SupportLang>, String> {
+  let yaml = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+  let globals = GlobalRules::default();
+  let configs = from_yaml_string(&yaml, &globals).map_err(|e| e.to_string())?;
+  RuleCollection::try_new(configs).map_err(|e| e.to_string())
+}
+
+/// Read responses until the server logs `message`, returns false on timeout.
+async fn wait_log_message(
+  resp_client: &mut DuplexStream,
+  output: &mut Vec<u8>,
+  message: &str,
+) -> bool {
+  let mut buf = vec![0; 1024];
+  let found = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+    loop {
+      let n = resp_client.read(&mut buf).await.unwrap();
+      output.extend_from_slice(&buf[..n]);
+      let found = resp(output)
+        .iter()
+        .any(|v| v["method"] == "window/logMessage" && v["params"]["message"] == message);
+      if found {
+        return;
+      }
+    }
+  })
+  .await;
+  found.is_ok()
+}
+
+#[test]
+fn test_reload_rules_on_change() {
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let dir = tempfile::tempdir().unwrap();
+    let rule_path = dir.path().join("rule.yml");
+    std::fs::write(&rule_path, RELOAD_RULE).unwrap();
+    let base = dir.path().to_path_buf();
+    let rules = load_rule_file(&rule_path);
+    let (service, socket) = LspService::build(|client| {
+      let watched = vec![rule_path.clone()];
+      Backend::new(client, base, rules)
+        .with_rule_loader(move || load_rule_file(&rule_path), watched)
+    })
+    .finish();
+    let (mut req_client, req_server) = duplex(1024);
+    let (resp_server, mut resp_client) = duplex(1024);
+    tokio::spawn(Server::new(req_server, resp_server, socket).serve(service));
+
+    initialize_lsp(&mut req_client, &mut resp_client).await;
+    let initialized = r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#;
+    req_client
+      .write_all(req(initialized).as_bytes())
+      .await
+      .unwrap();
+    let mut output = vec![];
+    let watching = wait_log_message(&mut resp_client, &mut output, "watching rule changes!").await;
+    assert!(watching, "watcher should start");
+    let changed = RELOAD_RULE.replace("No debugger", "Remove debugger");
+    std::fs::write(dir.path().join("rule.yml"), changed).unwrap();
+    let reloaded = wait_log_message(&mut resp_client, &mut output, "rules reloaded!").await;
+    assert!(reloaded, "rules should be reloaded after change");
+  });
+}
@@ -1,4 +1,7 @@
 import test from 'ava'
+import { chmodSync, mkdtempSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
 
 import {
   js,
@@ -6,7 +9,15 @@ import {
   tsx,
   html,
   parseFiles,
+  findInFilesStreaming,
+  findInFilesAsync,
+  isPatternCompatible,
+  validateConfig,
+  previewFix,
+  pattern,
+  Lang,
 } from '../index'
+import type { Capture, FindConfig, SgNode, SgRoot } from '../index'
 const { parse, kind } = js
 const parseMulti = countedPromise(parseFiles)
 
@@ -52,6 +63,69 @@ test('find multiple nodes', t => {
   t.deepEqual(end, { line: 0, column: 9, index: 9 })
 })
 
+test('get captures', t => {
+  const sg = parse('a(1, 2)')
+  const match = sg.root().find('$F($A, $$$B)')!
+  const captures = match.getCaptures()
+  t.deepEqual(captures['F'], {
+    text: 'a',
+    kind: 'identifier',
+    startLine: 0,
+    startCol: 0,
+    endLine: 0,
+    endCol: 1,
+  })
+  const multi = captures['B'] as Capture[]
+  t.deepEqual(multi.map(c => c.text), ['2'])
+})
+
+test('to json ast', t => {
+  const sg = parse('f("世界")')
+  const json = sg.root().find('f($A)')!.toJsonAst()
+  t.is(json.kind, 'call_expression')
+  t.true(json.named)
+  t.is(json.endByte, 7)
+  const args = json.children![1]
+  t.is(args.text, '("世界")')
+  t.is(args.startByte, 1)
+  const paren = args.children![0]
+  t.false(paren.named)
+  t.is(paren.children, undefined)
+})
+
+test('explain rule match', t => {
+  const sg = parse('console.log(123)')
+  const node = sg.root().find('console.log($A)')!
+  t.is(node.explain({ rule: { pattern: 'console.log($A)' } }), 'matched')
+  const explanation = node.explain({
+    rule: { pattern: 'console.log($A)' },
+    constraints: { A: { kind: 'string' } },
+  })
+  t.deepEqual(explanation, { failedConstraint: ['A', '123'] })
+  const inside = node.explain({
+    rule: { inside: { kind: 'function_declaration' } },
+  })
+  if (typeof inside === 'string' || !('failedRelational' in inside)) {
+    t.fail('should fail relational')
+    return
+  }
+  t.is(inside.failedRelational[0], 'inside')
+})
+
+test('pretty print ast', t => {
+  const sg = parse('a + 1')
+  t.is(
+    sg.prettyPrintAst(),
+    [
+      '(program',
+      '  (expression_statement',
+      '    (binary_expression',
+      '      left: (identifier "a")',
+      '      right: (number "1"))))',
+    ].join('\n'),
+  )
+})
+
 test('find unicode', t => {
   const str = `console.log("Hello, 世界")
   print("ザ・ワールド")`
@@ -293,6 +367,163 @@ test('find in files with filename', async t => {
   )
 })
 
+test('find in files streaming', async t => {
+  type Callback = (err: null | Error, n: SgNode[]) => void
+  const findStreaming = countedPromise((config: FindConfig, cb: Callback) =>
+    findInFilesStreaming(Lang.TypeScript, config, cb),
+  )
+  const dir = mkdtempSync(join(tmpdir(), 'sg-'))
+  writeFileSync(join(dir, 'a.ts'), 'parse(a)\nparse(b)\nparse(c)')
+  writeFileSync(join(dir, 'b.ts'), 'parse(d)')
+  const found: string[] = []
+  const roots = new Map<string, SgRoot>()
+  const count = await findStreaming(
+    {
+      paths: [dir],
+      matcher: {
+        rule: { pattern: 'parse($V)' },
+      },
+    },
+    (err, n) => {
+      t.is(err, null)
+      t.is(n.length, 1)
+      found.push(n[0].getMatch('V')!.text())
+      // matches of the same file share one root
+      const root = n[0].getRoot()
+      const shared = roots.get(root.filename()) ?? root
+      roots.set(root.filename(), root)
+      t.is(root, shared)
+    },
+  )
+  // the promise resolves to the number of callbacks
+  t.is(count, 4)
+  t.deepEqual(found.sort(), ['a', 'b', 'c', 'd'])
+  const capped = await findStreaming(
+    {
+      paths: [dir],
+      matcher: {
+        rule: { pattern: 'parse($V)' },
+      },
+      maxCallbacksPerFile: 2,
+    },
+    (err, n) => {
+      t.is(err, null)
+      t.is(n.length, 1)
+    },
+  )
+  t.is(capped, 3)
+})
+
+test('find in files async', async t => {
+  const files = findInFilesAsync(Lang.TypeScript, {
+    paths: ['./__test__/'],
+    matcher: {
+      rule: { pattern: 'parse($V)' },
+    },
+    queueSize: 1,
+  })
+  let count = 0
+  for await (const n of files) {
+    // ZZZ... sleep a while to mock slow consumer
+    await new Promise(resolve => setTimeout(resolve, 1))
+    t.assert(n.length > 0)
+    t.assert(n[0].text().startsWith('parse('))
+    t.assert(n[0].getRoot().filename().includes('.spec.ts'))
+    count += 1
+  }
+  t.assert(count > 0)
+  t.deepEqual(await files.next(), { done: true })
+})
+
+test('find in files reports progress', async t => {
+  const reports: number[][] = []
+  await ts.findInFiles(
+    {
+      paths: ['./__test__/'],
+      matcher: {
+        rule: { pattern: 'parse($V)' },
+      },
+      onProgress: (scanned, matched, total) => {
+        reports.push([scanned, matched, total])
+      },
+      progressInterval: 1,
+    },
+    () => {},
+  )
+  // all progress calls are made before the promise resolves
+  t.assert(reports.length > 0)
+  const [scanned, matched, total] = reports[reports.length - 1]
+  t.assert(total > 0)
+  t.is(scanned, total)
+  t.assert(matched > 0 && matched <= scanned)
+  // the final counts are reported only once
+  t.notDeepEqual(reports[reports.length - 2], reports[reports.length - 1])
+})
+
+test('find in files reports unreadable files', async t => {
+  // root can read files regardless of permission
+  if (process.platform === 'win32' || process.getuid?.() === 0) {
+    t.pass()
+    return
+  }
+  const dir = mkdtempSync(join(tmpdir(), 'sg-'))
+  const file = join(dir, 'secret.ts')
+  writeFileSync(file, 'parse(a)')
+  chmodSync(file, 0o000)
+  const errors: [Error, string][] = []
+  await ts.findInFiles(
+    {
+      paths: [dir],
+      matcher: {
+        rule: { pattern: 'parse($V)' },
+      },
+      onError: (err, path) => {
+        errors.push([err, path])
+      },
+    },
+    () => {
+      t.fail('unreadable file should not match')
+    },
+  )
+  // all error calls are made before the promise resolves
+  t.is(errors.length, 1)
+  const [err, path] = errors[0]
+  t.true(err instanceof Error)
+  t.is(path, file)
+})
+
+test('find in files async can stop early', async t => {
+  const files = findInFilesAsync(Lang.TypeScript, {
+    paths: ['./'],
+    matcher: {
+      rule: { kind: 'member_expression' },
+    },
+    queueSize: 1,
+  })
+  for await (const n of files) {
+    t.assert(n.length > 0)
+    break
+  }
+  t.deepEqual(await files.next(), { done: true })
+})
+
+test('find in files async can stop with a pending next', async t => {
+  const files = findInFilesAsync(Lang.TypeScript, {
+    paths: ['./'],
+    matcher: {
+      rule: { kind: 'member_expression' },
+    },
+    queueSize: 1,
+  })
+  const pending = files.next()
+  // return does not wait for the pending next
+  t.deepEqual(files.return!(), { done: true })
+  // the pending next may have received a file before return was called
+  const first = await pending
+  t.true(first.done || first.value.length > 0)
+  t.deepEqual(await files.next(), { done: true })
+})
+
 test('tsx should not find ts file', async t => {
   await tsx.findInFiles(
     {
@@ -355,6 +586,13 @@ test('fieldChildren returns all the fields of the given field', t => {
   t.is(fields[1].text(), 'className="bar"')
 })
 
+test('fieldPath follows nested fields', t => {
+  const sg = tsx.parse('a.b.c.d')
+  const member = sg.root().find('$A.d')!
+  t.is(member.fieldPath(['object', 'object', 'object'])?.text(), 'a')
+  t.is(member.fieldPath(['object', 'body']), null)
+})
+
 // biome-ignore lint/suspicious/noExplicitAny: do not complain any
 function countedPromise<F extends (t: any, cb: any) => Promise<number>>(
   func: F,
@@ -380,6 +618,35 @@ function countedPromise<F extends (t: any, cb: any) => Promise<number>>(
   }
 }
 
+test('check pattern compatibility across languages', t => {
+  t.true(isPatternCompatible(Lang.Tsx, Lang.TypeScript, '$A + $B'))
+  t.true(isPatternCompatible(Lang.Tsx, Lang.Tsx, '<div>{$A}</div>'))
+  t.false(isPatternCompatible(Lang.Tsx, Lang.TypeScript, '<div>{$A}</div>'))
+})
+
+test('validate rule config', t => {
+  t.is(validateConfig(ts.pattern('console.log($A)')), null)
+  const error = validateConfig({
+    rule: { kind: 'not_a_kind' },
+    language: 'TypeScript',
+  })
+  t.regex(error!, /Kind `not_a_kind` is invalid/)
+  t.truthy(validateConfig({ rule: { pattern: 'a' } }))
+})
+
+test('preview fix of the first match', t => {
+  const src = 'let a = 1\nlet b = 2'
+  const preview = previewFix(Lang.TypeScript, src, ts.pattern('let $A = $B'), 'const $A = $B')
+  t.deepEqual(preview, { original: src, fixed: 'const a = 1\nlet b = 2' })
+  t.is(previewFix(Lang.TypeScript, 'var c = 3', ts.pattern('let $A = $B'), 'const $A = $B'), null)
+})
+
+test('pattern lists named captures', t => {
+  const config = pattern(Lang.TypeScript, 'foo($A, $$$REST, $_, $A)')
+  t.deepEqual(config.captures, ['A'])
+  t.is(pattern(Lang.TypeScript, '').captures, undefined)
+})
+
 test('find node by range', t => {
   const sg = parse(
     'const message = `This is a multiline message with unicode characters\nÜbergang\nدرود\n🙌\n`;',
@@ -1,6 +1,18 @@
 //-----Type Only Export!-----//
-export type { Pos, Edit, Range } from './types/sgnode'
-export type { NapiConfig, FindConfig, FileOption } from './types/config'
+export type {
+  Pos,
+  Edit,
+  Range,
+  Capture,
+  JsonAstNode,
+  MatchExplanation,
+} from './types/sgnode'
+export type {
+  NapiConfig,
+  FindConfig,
+  FileOption,
+  FixPreview,
+} from './types/config'
 export type { DynamicLangRegistrations } from './types/registerDynamicLang'
 // Only Rule here. User can use Rule['pattern'], e.g., to get the type of subfield.
 export type { Rule } from './types/rule'
@@ -14,7 +26,12 @@ export {
   parseAsync,
   kind,
   pattern,
+  validateConfig,
+  previewFix,
+  isPatternCompatible,
   findInFiles,
+  findInFilesStreaming,
+  findInFilesAsync,
 } from './types/api'
 export { registerDynamicLanguage } from './types/registerDynamicLang'
 // deprecated
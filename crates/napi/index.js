@@ -310,7 +310,7 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { parseFiles, Lang, SgNode, SgRoot, parse, parseAsync, kind, pattern, findInFiles, registerDynamicLanguage, html, js, jsx, ts, tsx, css } = nativeBinding
+const { parseFiles, Lang, SgNode, SgRoot, parse, parseAsync, kind, pattern, validateConfig, previewFix, isPatternCompatible, findInFiles, findInFilesStreaming, findInFilesAsync, registerDynamicLanguage, html, js, jsx, ts, tsx, css } = nativeBinding
 
 module.exports.parseFiles = parseFiles
 module.exports.Lang = Lang
@@ -320,7 +320,12 @@ module.exports.parse = parse
 module.exports.parseAsync = parseAsync
 module.exports.kind = kind
 module.exports.pattern = pattern
+module.exports.validateConfig = validateConfig
+module.exports.previewFix = previewFix
+module.exports.isPatternCompatible = isPatternCompatible
 module.exports.findInFiles = findInFiles
+module.exports.findInFilesStreaming = findInFilesStreaming
+module.exports.findInFilesAsync = findInFilesAsync
 module.exports.registerDynamicLanguage = registerDynamicLanguage
 module.exports.html = html
 module.exports.js = js
@@ -26,16 +26,44 @@ pub struct NapiConfig {
   pub transform: Option<serde_json::Value>,
   /// https://ast-grep.github.io/guide/rule-config/utility-rule.html
   pub utils: Option<serde_json::Value>,
+  /// Meta variables captured by the pattern. Only set by `pattern()` and ignored in matching.
+  pub captures: Option<Vec<String>>,
 }
 
 impl NapiConfig {
+  /// Returns None if the config is valid, or the error message otherwise.
+  /// The config's `language` is used to parse patterns and kinds.
+  pub fn validate(self) -> Option<String> {
+    let Some(lang) = &self.language else {
+      return Some("`language` is required to validate a config.".into());
+    };
+    let lang: NapiLang = match lang.parse() {
+      Ok(lang) => lang,
+      Err(e) => return Some(e.to_string()),
+    };
+    self.parse_with(lang).err().map(|e| e.reason)
+  }
+
   pub fn parse_with(self, lang: NapiLang) -> NapiResult<RuleCore<NapiLang>> {
+    self.parse_with_fix(lang, None)
+  }
+
+  pub fn parse_with_fix(
+    self,
+    lang: NapiLang,
+    fix: Option<String>,
+  ) -> NapiResult<RuleCore<NapiLang>> {
     let rule = SerializableRuleCore {
       rule: serde_json::from_value(self.rule)?,
       constraints: self.constraints.map(serde_json::from_value).transpose()?,
       transform: self.transform.map(serde_json::from_value).transpose()?,
       utils: self.utils.map(serde_json::from_value).transpose()?,
-      fix: None,
+      fix: fix
+        .map(serde_json::Value::from)
+        .map(serde_json::from_value)
+        .transpose()?,
+      extends: None,
+      tags: None,
     };
     let env = DeserializeEnv::new(lang);
     rule.get_matcher(env).map_err(|e| {
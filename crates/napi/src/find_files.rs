@@ -5,10 +5,13 @@ use ignore::{WalkBuilder, WalkParallel, WalkState};
 use napi::anyhow::{anyhow, Context, Result as Ret};
 use napi::bindgen_prelude::*;
 use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
-use napi::{JsNumber, Task};
-use napi_derive::napi;
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicU32, Ordering};
+use napi::{CallContext, JsNumber, JsObject, JsSymbol, Task};
+use napi_derive::{js_function, napi};
+use std::collections::{hash_map, HashMap};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use tokio::sync::mpsc::{channel, Receiver, Sender};
 
 use crate::doc::{JsDoc, NapiConfig};
 use crate::napi_lang::{build_files, LangOption, NapiLang};
@@ -39,7 +42,217 @@ pub struct IterateFiles<D> {
   walk: WalkParallel,
   lang_option: LangOption,
   tsfn: D,
-  producer: fn(&D, Entry, &LangOption) -> Ret<bool>,
+  /// returns the number of results sent to JavaScript
+  producer: fn(&D, Entry, &LangOption) -> Ret<u32>,
+  /// walking quits once it returns true, e.g. the consumer is gone
+  is_stopped: fn(&D) -> bool,
+  progress: Option<Progress>,
+  on_error: Option<ErrorReporter>,
+}
+
+fn empty_walk() -> WalkParallel {
+  WalkBuilder::new(".").build_parallel()
+}
+
+fn is_file(entry: &Entry) -> bool {
+  entry
+    .as_ref()
+    .map_or(false, |e| e.file_type().map_or(false, |t| t.is_file()))
+}
+
+/// Calls queued to the JS thread but not taken by it yet.
+#[derive(Default)]
+struct Pending {
+  count: Mutex<usize>,
+  delivered: Condvar,
+}
+
+impl Pending {
+  fn lock(&self) -> MutexGuard<'_, usize> {
+    self.count.lock().expect("pending count is poisoned")
+  }
+
+  fn settle(&self) {
+    let mut count = self.lock();
+    *count -= 1;
+    if *count == 0 {
+      self.delivered.notify_all();
+    }
+  }
+}
+
+/// Travels with a queued call and settles it once the JS thread takes the value.
+/// The flag keeps it from settling twice if napi fails the call without dropping the value.
+struct PendingCall(Arc<Pending>, Arc<AtomicBool>);
+
+impl PendingCall {
+  fn settle(&self) {
+    if !self.1.swap(true, Ordering::AcqRel) {
+      self.0.settle();
+    }
+  }
+}
+
+impl Drop for PendingCall {
+  fn drop(&mut self) {
+    self.settle();
+  }
+}
+
+/// A JS callback whose queued calls are all delivered before the task resolves.
+struct Callback<T: 'static> {
+  tsfn: ThreadsafeFunction<(T, PendingCall), ErrorStrategy::Fatal>,
+  pending: Arc<Pending>,
+}
+
+impl<T: 'static> Callback<T> {
+  fn try_new<V, F>(callback: JsFunction, mut convert: F) -> Result<Self>
+  where
+    V: ToNapiValue,
+    F: 'static + Send + FnMut(Env, T) -> Result<Vec<V>>,
+  {
+    let tsfn = callback.create_threadsafe_function(0, move |ctx| {
+      // the call is settled right before JS function runs on the same tick
+      let (value, _call): (T, PendingCall) = ctx.value;
+      convert(ctx.env, value)
+    })?;
+    Ok(Self {
+      tsfn,
+      pending: Arc::default(),
+    })
+  }
+
+  fn call(&self, value: T) {
+    *self.pending.lock() += 1;
+    let flag = Arc::new(AtomicBool::new(false));
+    let call = PendingCall(self.pending.clone(), flag.clone());
+    let status = self
+      .tsfn
+      .call((value, call), ThreadsafeFunctionCallMode::NonBlocking);
+    if status != Status::Ok {
+      // the value may be leaked by a failed call, settle it here
+      PendingCall(self.pending.clone(), flag).settle();
+    }
+  }
+
+  /// blocks the worker thread until JS has taken every queued call
+  fn flush(&self) {
+    let mut count = self.pending.lock();
+    while *count > 0 {
+      count = self
+        .pending
+        .delivered
+        .wait(count)
+        .expect("pending count is poisoned");
+    }
+  }
+}
+
+const PROGRESS_INTERVAL: u32 = 100;
+
+/// Reports `(filesScanned, filesMatched, totalFiles)` to `FindConfig.onProgress`.
+pub struct Progress {
+  callback: Callback<(u32, u32, u32)>,
+  interval: u32,
+  /// walks the same files as the scan, only to count them beforehand
+  pre_walk: WalkParallel,
+  total: u32,
+}
+
+impl Progress {
+  fn try_new(lang: NapiLang, config: &mut FindConfig) -> Result<Option<Self>> {
+    let Some(callback) = config.on_progress.take() else {
+      return Ok(None);
+    };
+    let callback = Callback::try_new(callback, |env, (scanned, matched, total)| {
+      Ok(vec![
+        env.create_uint32(scanned)?,
+        env.create_uint32(matched)?,
+        env.create_uint32(total)?,
+      ])
+    })?;
+    let pre_walk = lang.find_files(config.paths.clone(), config.language_globs.clone())?;
+    Ok(Some(Self {
+      callback,
+      interval: config.progress_interval.unwrap_or(PROGRESS_INTERVAL).max(1),
+      pre_walk,
+      total: 0,
+    }))
+  }
+
+  fn count_files(&mut self) {
+    let total = AtomicU32::new(0);
+    let walker = std::mem::replace(&mut self.pre_walk, empty_walk());
+    walker.run(|| {
+      let total = &total;
+      Box::new(move |entry| {
+        if is_file(&entry) {
+          total.fetch_add(1, Ordering::AcqRel);
+        }
+        WalkState::Continue
+      })
+    });
+    self.total = total.load(Ordering::Acquire);
+  }
+
+  fn report(&self, scanned: u32, matched: u32) {
+    self.callback.call((scanned, matched, self.total));
+  }
+
+  fn finish(&self, scanned: u32, matched: u32) {
+    // the last interval report already has the final counts,
+    // since every file counts its match before it counts as scanned
+    if scanned == 0 || scanned % self.interval != 0 {
+      self.report(scanned, matched);
+    }
+    self.callback.flush();
+  }
+}
+
+/// Reports files that cannot be read or parsed to `FindConfig.onError`.
+pub struct ErrorReporter(Callback<(String, String)>);
+
+impl ErrorReporter {
+  fn try_new(config: &mut FindConfig) -> Result<Option<Self>> {
+    let Some(callback) = config.on_error.take() else {
+      return Ok(None);
+    };
+    let callback = Callback::try_new(callback, |env, (message, path): (String, String)| {
+      let error = env.create_error(Error::from_reason(message))?;
+      Ok(vec![
+        error.into_unknown(),
+        env.create_string(&path)?.into_unknown(),
+      ])
+    })?;
+    Ok(Some(Self(callback)))
+  }
+
+  fn report(&self, error: napi::anyhow::Error, path: String) {
+    self.0.call((format!("{error:#}"), path));
+  }
+
+  fn finish(&self) {
+    self.0.flush();
+  }
+}
+
+fn entry_path(entry: &Entry) -> String {
+  fn error_path(error: &ignore::Error) -> Option<&Path> {
+    match error {
+      ignore::Error::WithPath { path, .. } => Some(path),
+      ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+        error_path(err)
+      }
+      _ => None,
+    }
+  }
+  let path = match entry {
+    Ok(e) => Some(e.path()),
+    Err(e) => error_path(e),
+  };
+  path
+    .map(|p| p.to_string_lossy().into_owned())
+    .unwrap_or_default()
 }
 
 impl<T: 'static + Send + Sync> Task for IterateFiles<T> {
@@ -47,25 +260,63 @@ impl<T: 'static + Send + Sync> Task for IterateFiles<T> {
   type JsValue = JsNumber;
 
   fn compute(&mut self) -> Result<Self::Output> {
+    if let Some(progress) = &mut self.progress {
+      progress.count_files();
+    }
     let tsfn = &self.tsfn;
+    let result_count = AtomicU32::new(0);
     let file_count = AtomicU32::new(0);
+    let scanned_count = AtomicU32::new(0);
     let producer = self.producer;
-    let walker = std::mem::replace(&mut self.walk, WalkBuilder::new(".").build_parallel());
+    let is_stopped = self.is_stopped;
+    let walker = std::mem::replace(&mut self.walk, empty_walk());
     walker.run(|| {
+      let result_count = &result_count;
       let file_count = &file_count;
+      let scanned_count = &scanned_count;
       let lang_option = &self.lang_option;
-      Box::new(move |entry| match producer(tsfn, entry, lang_option) {
-        Ok(succeed) => {
-          if succeed {
-            // file is sent to JS thread, increment file count
-            file_count.fetch_add(1, Ordering::AcqRel);
+      let progress = self.progress.as_ref();
+      let on_error = self.on_error.as_ref();
+      Box::new(move |entry| {
+        if is_stopped(tsfn) {
+          return WalkState::Quit;
+        }
+        let scanned_file = is_file(&entry);
+        // the entry is consumed by producer, so keep its path for error report
+        let path = on_error.map(|_| entry_path(&entry));
+        let state = match producer(tsfn, entry, lang_option) {
+          Ok(sent) => {
+            if sent > 0 {
+              // results are sent to JS thread, increment counts
+              result_count.fetch_add(sent, Ordering::AcqRel);
+              file_count.fetch_add(1, Ordering::AcqRel);
+            }
+            WalkState::Continue
+          }
+          Err(error) => {
+            if let Some((on_error, path)) = on_error.zip(path) {
+              on_error.report(error, path);
+            }
+            WalkState::Skip
+          }
+        };
+        if let Some(progress) = progress.filter(|_| scanned_file) {
+          let scanned = scanned_count.fetch_add(1, Ordering::AcqRel) + 1;
+          if scanned % progress.interval == 0 {
+            progress.report(scanned, file_count.load(Ordering::Acquire));
           }
-          WalkState::Continue
         }
-        Err(_) => WalkState::Skip,
+        state
       })
     });
-    Ok(file_count.load(Ordering::Acquire))
+    if let Some(progress) = &self.progress {
+      let file_count = file_count.load(Ordering::Acquire);
+      progress.finish(scanned_count.load(Ordering::Acquire), file_count);
+    }
+    if let Some(on_error) = &self.on_error {
+      on_error.finish();
+    }
+    Ok(result_count.load(Ordering::Acquire))
   }
   fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
     env.create_uint32(output)
@@ -105,34 +356,37 @@ pub fn parse_files(
     tsfn,
     lang_option: LangOption::infer(&globs),
     producer: call_sg_root,
+    is_stopped: |_| false,
+    progress: None,
+    on_error: None,
   }))
 }
 
-// returns if the entry is a file and sent to JavaScript queue
+// returns 1 if the entry is a file and sent to JavaScript queue
 fn call_sg_root(
   tsfn: &ThreadsafeFunction<SgRoot, ErrorStrategy::CalleeHandled>,
   entry: std::result::Result<ignore::DirEntry, ignore::Error>,
   lang_option: &LangOption,
-) -> Ret<bool> {
+) -> Ret<u32> {
   let entry = entry?;
   if !entry
     .file_type()
     .context("could not use stdin as file")?
     .is_file()
   {
-    return Ok(false);
+    return Ok(0);
   }
   let (root, path) = get_root(entry, lang_option)?;
   let sg = SgRoot(root, path);
   tsfn.call(Ok(sg), ThreadsafeFunctionCallMode::Blocking);
-  Ok(true)
+  Ok(1)
 }
 
 fn get_root(entry: ignore::DirEntry, lang_option: &LangOption) -> Ret<(AstGrep<JsDoc>, String)> {
   let path = entry.into_path();
   let file_content = std::fs::read_to_string(&path)?;
   let lang = lang_option
-    .get_lang(&path)
+    .detect_lang(&path, &file_content)
     .context(anyhow!("file not recognized"))?;
   let doc = JsDoc::new(file_content, lang);
   Ok((AstGrep::doc(doc), path.to_string_lossy().into()))
@@ -160,20 +414,49 @@ pub struct FindConfig {
   /// eg. ['*.vue', '*.svelte'] for html.findFiles, or ['*.ts'] for tsx.findFiles.
   /// It is slightly different from https://ast-grep.github.io/reference/sgconfig.html#languageglobs
   pub language_globs: Option<Vec<String>>,
+  /// Only used by findInFilesStreaming.
+  /// The maximum number of matches reported per file. Extra matches are dropped.
+  pub max_callbacks_per_file: Option<u32>,
+  /// The maximum number of results waiting for JavaScript to consume.
+  /// File walking pauses when the queue is full. Defaults to 1000.
+  pub queue_size: Option<u32>,
+  /// Called with the numbers of scanned files, files with matches and all files to scan.
+  /// All files are counted by walking them once before scanning starts.
+  /// Every call is made before the returned promise resolves.
+  #[napi(ts_type = "(filesScanned: number, filesMatched: number, totalFiles: number) => void")]
+  pub on_progress: Option<JsFunction>,
+  /// Number of scanned files between two `onProgress` calls. Defaults to 100.
+  pub progress_interval: Option<u32>,
+  /// Called with the error and the file path when a file cannot be read or parsed.
+  /// Such files are skipped silently if it is not set.
+  /// Every call is made before the returned promise resolves.
+  #[napi(ts_type = "(err: Error, path: string) => void")]
+  pub on_error: Option<JsFunction>,
+}
+
+impl FindConfig {
+  fn queue_size(&self) -> usize {
+    self
+      .queue_size
+      .map_or(THREAD_FUNC_QUEUE_SIZE, |s| s.max(1) as usize)
+  }
 }
 
 pub fn find_in_files_impl(
   lang: NapiLang,
-  config: FindConfig,
+  mut config: FindConfig,
   callback: JsFunction,
 ) -> Result<AsyncTask<FindInFiles>> {
-  let tsfn = callback.create_threadsafe_function(THREAD_FUNC_QUEUE_SIZE, |ctx| {
+  let tsfn = callback.create_threadsafe_function(config.queue_size(), |ctx| {
     from_pinned_data(ctx.value, ctx.env)
   })?;
+  let progress = Progress::try_new(lang, &mut config)?;
+  let on_error = ErrorReporter::try_new(&mut config)?;
   let FindConfig {
     paths,
     matcher,
     language_globs,
+    ..
   } = config;
   let rule = matcher.parse_with(lang)?;
   let walk = lang.find_files(paths, language_globs)?;
@@ -182,11 +465,18 @@ pub fn find_in_files_impl(
     tsfn: (tsfn, rule),
     lang_option: LangOption::Specified(lang),
     producer: call_sg_node,
+    is_stopped: |_| false,
+    progress,
+    on_error,
   }))
 }
 
-// TODO: optimize
 fn from_pinned_data(pinned: PinnedNodes, env: napi::Env) -> Result<Vec<Vec<SgNode>>> {
+  Ok(vec![pinned_to_nodes(pinned, env)?])
+}
+
+// TODO: optimize
+fn pinned_to_nodes(pinned: PinnedNodes, env: napi::Env) -> Result<Vec<SgNode>> {
   let (root, nodes) = pinned.0.into_raw();
   let sg_root = SgRoot(AstGrep { inner: root }, pinned.1);
   let reference = SgRoot::into_reference(sg_root, env)?;
@@ -202,7 +492,7 @@ fn from_pinned_data(pinned: PinnedNodes, env: napi::Env) -> Result<Vec<Vec<SgNod
     };
     v.push(sg_node);
   }
-  Ok(vec![v])
+  Ok(v)
 }
 
 fn call_sg_node(
@@ -212,22 +502,314 @@ fn call_sg_node(
   ),
   entry: std::result::Result<ignore::DirEntry, ignore::Error>,
   lang_option: &LangOption,
-) -> Ret<bool> {
+) -> Ret<u32> {
+  let Some(pinned) = find_pinned_nodes(rule, entry, lang_option)? else {
+    return Ok(0);
+  };
+  tsfn.call(Ok(pinned), ThreadsafeFunctionCallMode::Blocking);
+  Ok(1)
+}
+
+// returns None if the entry is not a file or has no match
+fn find_pinned_nodes(
+  rule: &RuleCore<NapiLang>,
+  entry: std::result::Result<ignore::DirEntry, ignore::Error>,
+  lang_option: &LangOption,
+) -> Ret<Option<PinnedNodes>> {
   let entry = entry?;
   if !entry
     .file_type()
     .context("could not use stdin as file")?
     .is_file()
   {
-    return Ok(false);
+    return Ok(None);
   }
   let (root, path) = get_root(entry, lang_option)?;
   let mut pinned = PinnedNodeData::new(root.inner, |r| r.root().find_all(rule).collect());
   let hits: &Vec<_> = pinned.get_data();
   if hits.is_empty() {
-    return Ok(false);
+    return Ok(None);
   }
-  let pinned = PinnedNodes(pinned, path);
-  tsfn.call(Ok(pinned), ThreadsafeFunctionCallMode::Blocking);
-  Ok(true)
+  Ok(Some(PinnedNodes(pinned, path)))
+}
+
+/// Matches of a file, or the error that ends the walk, sent to the async iterator.
+type AsyncMatches = std::result::Result<PinnedNodes, String>;
+
+pub type FindInFilesAsync = IterateFiles<(Sender<AsyncMatches>, RuleCore<NapiLang>, StopFlag)>;
+
+/// Set by the iterator's `return`. It is shared without a lock,
+/// so stopping never waits for a pending `next`.
+type StopFlag = Arc<AtomicBool>;
+
+/// Walk files in a background thread and send matches of each file to a bounded channel.
+/// The walker waits when the channel is full, so a slow consumer never floods the JS queue.
+pub fn find_in_files_async_impl(
+  env: Env,
+  lang: NapiLang,
+  mut config: FindConfig,
+) -> Result<JsObject> {
+  let (tx, rx) = channel(config.queue_size());
+  let progress = Progress::try_new(lang, &mut config)?;
+  let on_error = ErrorReporter::try_new(&mut config)?;
+  let FindConfig {
+    paths,
+    matcher,
+    language_globs,
+    ..
+  } = config;
+  let rule = matcher.parse_with(lang)?;
+  let walk = lang.find_files(paths, language_globs)?;
+  let stopped = StopFlag::default();
+  let error_tx = tx.clone();
+  let mut task = FindInFilesAsync {
+    walk,
+    tsfn: (tx, rule, stopped.clone()),
+    lang_option: LangOption::Specified(lang),
+    producer: send_sg_node,
+    is_stopped: |(tx, _, stopped)| tx.is_closed() || stopped.load(Ordering::Acquire),
+    progress,
+    on_error,
+  };
+  std::thread::spawn(move || {
+    let result = task.compute();
+    // the task's sender is dropped here, the channel closes after error_tx is dropped too
+    drop(task);
+    if let Err(error) = result {
+      let _ = error_tx.blocking_send(Err(error.reason));
+    }
+  });
+  let iterator = FindInFilesIterator {
+    receiver: Arc::new(Mutex::new(rx)),
+    stopped,
+  };
+  let mut object = iterator.into_instance(env)?.as_object(env);
+  // make the iterator usable in `for await`
+  // Symbol is a function so it must be read unchecked as an object
+  let symbol: JsObject = env.get_global()?.get_named_property_unchecked("Symbol")?;
+  let async_iterator: JsSymbol = symbol.get_named_property("asyncIterator")?;
+  object.set_property(async_iterator, env.create_function("", return_this)?)?;
+  Ok(object)
+}
+
+#[js_function(1)]
+fn return_this(ctx: CallContext) -> Result<JsObject> {
+  ctx.this()
+}
+
+fn send_sg_node(
+  (tx, rule, _): &(Sender<AsyncMatches>, RuleCore<NapiLang>, StopFlag),
+  entry: std::result::Result<ignore::DirEntry, ignore::Error>,
+  lang_option: &LangOption,
+) -> Ret<u32> {
+  let Some(pinned) = find_pinned_nodes(rule, entry, lang_option)? else {
+    return Ok(0);
+  };
+  Ok(tx.blocking_send(Ok(pinned)).map_or(0, |_| 1))
+}
+
+type SharedReceiver = Arc<Mutex<Receiver<AsyncMatches>>>;
+
+/// An async iterator of matching nodes, one array per file.
+#[napi]
+pub struct FindInFilesIterator {
+  receiver: SharedReceiver,
+  stopped: StopFlag,
+}
+
+#[napi]
+impl FindInFilesIterator {
+  /// Wait for the matching nodes in the next file.
+  #[napi(ts_return_type = "Promise<IteratorResult<SgNode[], undefined>>")]
+  pub fn next(&self) -> AsyncTask<NextMatches> {
+    AsyncTask::new(NextMatches(self.receiver.clone(), self.stopped.clone()))
+  }
+
+  /// Stop finding files. Called by `for await` on `break`.
+  #[napi(
+    js_name = "return",
+    ts_return_type = "IteratorResult<SgNode[], undefined>"
+  )]
+  pub fn stop(&self, env: Env) -> Result<JsObject> {
+    self.stopped.store(true, Ordering::Release);
+    // a pending `next` holds the lock and closes the channel itself when it wakes up
+    if let Ok(mut rx) = self.receiver.try_lock() {
+      rx.close();
+    }
+    iterator_result(env, None)
+  }
+}
+
+pub struct NextMatches(SharedReceiver, StopFlag);
+
+impl Task for NextMatches {
+  type Output = Option<PinnedNodes>;
+  type JsValue = JsObject;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let mut rx = self
+      .0
+      .lock()
+      .map_err(|_| Error::from_reason("iterator is poisoned"))?;
+    let received = if self.1.load(Ordering::Acquire) {
+      None
+    } else {
+      rx.blocking_recv()
+    };
+    // close the channel so the walker blocked on a full channel can quit
+    if self.1.load(Ordering::Acquire) {
+      rx.close();
+      return Ok(None);
+    }
+    received.transpose().map_err(Error::from_reason)
+  }
+  fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    let nodes = output.map(|p| pinned_to_nodes(p, env)).transpose()?;
+    iterator_result(env, nodes)
+  }
+}
+
+fn iterator_result(env: Env, nodes: Option<Vec<SgNode>>) -> Result<JsObject> {
+  let mut result = env.create_object()?;
+  result.set("done", nodes.is_none())?;
+  if let Some(nodes) = nodes {
+    result.set("value", nodes)?;
+  }
+  Ok(result)
+}
+
+/// Matches of one file sent to JavaScript one by one.
+/// The first match to arrive in the JS thread moves the root into a shared `SgRoot`.
+pub struct StreamedFile {
+  id: u64,
+  pinned: Mutex<Option<PinnedNodes>>,
+  len: usize,
+}
+
+/// The `index`-th match of a file, sent as one callback of findInFilesStreaming.
+pub struct StreamedMatch(Arc<StreamedFile>, usize);
+
+/// The shared `SgRoot` of a file and its matches not yet sent to JavaScript.
+/// It is only created and used in the JS thread.
+struct StreamedRoot {
+  root: Reference<SgRoot>,
+  matches: Vec<Option<NodeMatch<'static, JsDoc>>>,
+  remaining: usize,
+}
+unsafe impl Send for StreamedRoot {}
+
+pub type FindInFilesStreaming = IterateFiles<(
+  ThreadsafeFunction<StreamedMatch, ErrorStrategy::CalleeHandled>,
+  RuleCore<NapiLang>,
+  usize,
+)>;
+
+pub fn find_in_files_streaming_impl(
+  lang: NapiLang,
+  mut config: FindConfig,
+  callback: JsFunction,
+) -> Result<AsyncTask<FindInFilesStreaming>> {
+  let mut roots = HashMap::new();
+  let tsfn = callback.create_threadsafe_function(config.queue_size(), move |ctx| {
+    let node = streamed_to_node(ctx.value, &mut roots, ctx.env)?;
+    Ok(vec![vec![node]])
+  })?;
+  let progress = Progress::try_new(lang, &mut config)?;
+  let on_error = ErrorReporter::try_new(&mut config)?;
+  let FindConfig {
+    paths,
+    matcher,
+    language_globs,
+    max_callbacks_per_file,
+    ..
+  } = config;
+  let rule = matcher.parse_with(lang)?;
+  let walk = lang.find_files(paths, language_globs)?;
+  let max = max_callbacks_per_file.map_or(usize::MAX, |m| m as usize);
+  Ok(AsyncTask::new(FindInFilesStreaming {
+    walk,
+    tsfn: (tsfn, rule, max),
+    lang_option: LangOption::Specified(lang),
+    producer: call_sg_node_per_match,
+    is_stopped: |_| false,
+    progress,
+    on_error,
+  }))
+}
+
+/// Matches of the same file share one `SgRoot`, looked up in `roots` by file id.
+fn streamed_to_node(
+  StreamedMatch(file, index): StreamedMatch,
+  roots: &mut HashMap<u64, StreamedRoot>,
+  env: Env,
+) -> Result<SgNode> {
+  if let hash_map::Entry::Vacant(entry) = roots.entry(file.id) {
+    let pinned = file
+      .pinned
+      .lock()
+      .map_err(|_| Error::from_reason("file is poisoned"))?
+      .take()
+      .ok_or_else(|| Error::from_reason("file is already sent"))?;
+    let (root, nodes) = pinned.0.into_raw();
+    let sg_root = SgRoot(AstGrep { inner: root }, pinned.1);
+    entry.insert(StreamedRoot {
+      root: SgRoot::into_reference(sg_root, env)?,
+      matches: nodes.into_iter().map(Some).collect(),
+      remaining: file.len,
+    });
+  }
+  let streamed = roots.get_mut(&file.id).expect("root must exist");
+  let mut node = streamed.matches[index]
+    .take()
+    .ok_or_else(|| Error::from_reason("match is already sent"))?;
+  let inner = streamed.root.clone(env)?.share_with(env, |root| {
+    let r = &root.0.inner;
+    node.visit_nodes(|n| unsafe { r.readopt(n) });
+    Ok(node)
+  })?;
+  streamed.remaining -= 1;
+  if streamed.remaining == 0 {
+    roots.remove(&file.id);
+  }
+  Ok(SgNode { inner })
+}
+
+static STREAMED_FILE_ID: AtomicU64 = AtomicU64::new(0);
+
+// Every match of a file is a separate callback, but all of them share the file's root.
+fn call_sg_node_per_match(
+  (tsfn, rule, max): &(
+    ThreadsafeFunction<StreamedMatch, ErrorStrategy::CalleeHandled>,
+    RuleCore<NapiLang>,
+    usize,
+  ),
+  entry: std::result::Result<ignore::DirEntry, ignore::Error>,
+  lang_option: &LangOption,
+) -> Ret<u32> {
+  let entry = entry?;
+  if !entry
+    .file_type()
+    .context("could not use stdin as file")?
+    .is_file()
+  {
+    return Ok(0);
+  }
+  let (root, path) = get_root(entry, lang_option)?;
+  let mut pinned = PinnedNodeData::new(root.inner, |r| {
+    r.root().find_all(rule).take(*max).collect::<Vec<_>>()
+  });
+  let len = pinned.get_data().len();
+  if len == 0 {
+    return Ok(0);
+  }
+  let file = Arc::new(StreamedFile {
+    id: STREAMED_FILE_ID.fetch_add(1, Ordering::Relaxed),
+    pinned: Mutex::new(Some(PinnedNodes(pinned, path))),
+    len,
+  });
+  for index in 0..len {
+    let streamed = StreamedMatch(file.clone(), index);
+    tsfn.call(Ok(streamed), ThreadsafeFunctionCallMode::Blocking);
+  }
+  Ok(len as u32)
 }
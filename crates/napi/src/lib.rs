@@ -5,14 +5,17 @@ mod find_files;
 mod napi_lang;
 mod sg_node;
 
-use ast_grep_core::{AstGrep, Language};
+use ast_grep_core::{AstGrep, Language, Pattern};
 use ast_grep_language::SupportLang;
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 use napi_lang::register_dynamic_language as register_dynamic_language_impl;
 
 use doc::{JsDoc, NapiConfig};
-use find_files::{find_in_files_impl, FindConfig, FindInFiles, ParseAsync};
+use find_files::{
+  find_in_files_async_impl, find_in_files_impl, find_in_files_streaming_impl, FindConfig,
+  FindInFiles, FindInFilesStreaming, ParseAsync,
+};
 use napi_lang::NapiLang;
 use sg_node::SgRoot;
 
@@ -93,8 +96,14 @@ pub fn kind(lang: String, kind_name: String) -> Result<u16> {
 }
 
 /// Compile a string to ast-grep Pattern.
+/// `captures` lists the pattern's named meta variables if it can be parsed in `lang`.
 #[napi]
 pub fn pattern(lang: String, pattern: String) -> NapiConfig {
+  let captures = lang
+    .parse::<NapiLang>()
+    .ok()
+    .and_then(|l| Pattern::try_new(&pattern, l).ok())
+    .map(|p| p.named_captures().to_vec());
   NapiConfig {
     rule: serde_json::json!({
       "pattern": pattern,
@@ -103,9 +112,50 @@ pub fn pattern(lang: String, pattern: String) -> NapiConfig {
     language: Some(lang),
     utils: None,
     transform: None,
+    captures,
   }
 }
 
+/// Validate a rule config using its `language` field.
+/// Returns null on success or the error message otherwise.
+#[napi]
+pub fn validate_config(config: NapiConfig) -> Option<String> {
+  config.validate()
+}
+
+/// The full source before and after a fix is applied.
+#[napi(object)]
+pub struct FixPreview {
+  pub original: String,
+  pub fixed: String,
+}
+
+/// Apply `fix` to the first node in `src` matching `config`.
+/// Returns null if no node matches.
+#[napi]
+pub fn preview_fix(
+  lang: String,
+  src: String,
+  config: NapiConfig,
+  fix: String,
+) -> Result<Option<FixPreview>> {
+  let lang: NapiLang = lang.parse()?;
+  let rule = config.parse_with_fix(lang, Some(fix))?;
+  let preview = rule.get_fix_preview(&src, lang);
+  Ok(preview.map(|(original, fixed)| FixPreview { original, fixed }))
+}
+
+/// Check if a pattern written in `lang` can also be parsed in `other_lang` without syntax error.
+/// e.g. JSX patterns are valid in tsx but not in ts.
+#[napi]
+pub fn is_pattern_compatible(lang: String, other_lang: String, pattern: String) -> Result<bool> {
+  let lang: NapiLang = lang.parse()?;
+  let other: NapiLang = other_lang.parse()?;
+  let pattern = Pattern::try_new(&pattern, lang)
+    .map_err(|e| napi::Error::new(napi::Status::InvalidArg, e.to_string()))?;
+  Ok(pattern.compatible_with(&other))
+}
+
 /// Discover and parse multiple files in Rust.
 /// `lang` specifies the language.
 /// `config` specifies the file path and matcher.
@@ -120,6 +170,29 @@ pub fn find_in_files(
   find_in_files_impl(lang, config, callback)
 }
 
+/// Same as `find_in_files` but `callback` is called once per match
+/// instead of once per file, so results can be processed as soon as they are found.
+/// Use `config.maxCallbacksPerFile` to cap the callbacks for a single file.
+/// The returned promise resolves to the number of callbacks.
+#[napi]
+pub fn find_in_files_streaming(
+  lang: String,
+  config: FindConfig,
+  callback: JsFunction,
+) -> Result<AsyncTask<FindInFilesStreaming>> {
+  let lang: NapiLang = lang.parse()?;
+  find_in_files_streaming_impl(lang, config, callback)
+}
+
+/// Same as `find_in_files` but returns an async iterator instead of calling a callback.
+/// Each iteration yields the matching nodes found in a file.
+/// File walking pauses when `config.queueSize` results are not consumed yet.
+#[napi(ts_return_type = "AsyncIterableIterator<SgNode[]>")]
+pub fn find_in_files_async(env: Env, lang: String, config: FindConfig) -> Result<Object> {
+  let lang: NapiLang = lang.parse()?;
+  find_in_files_async_impl(env, lang, config)
+}
+
 /// Register a dynamic language to ast-grep.
 /// `langs` is a Map of language name to its CustomLanguage registration.
 #[napi]
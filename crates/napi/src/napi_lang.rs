@@ -25,6 +25,19 @@ pub enum Lang {
   TypeScript,
 }
 
+impl From<Lang> for SupportLang {
+  fn from(lang: Lang) -> Self {
+    use SupportLang as S;
+    match lang {
+      Lang::Html => S::Html,
+      Lang::JavaScript => S::JavaScript,
+      Lang::Tsx => S::Tsx,
+      Lang::Css => S::Css,
+      Lang::TypeScript => S::TypeScript,
+    }
+  }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub enum NapiLang {
   // inlined support lang expando char
@@ -34,11 +47,10 @@ pub enum NapiLang {
 
 impl NapiLang {
   fn all_langs() -> Vec<Self> {
-    use SupportLang as S;
-    let builtin = [S::Html, S::JavaScript, S::Tsx, S::Css, S::TypeScript]
-      .iter()
-      .copied()
-      .map(Self::Builtin);
+    use Lang as L;
+    let builtin = [L::Html, L::JavaScript, L::Tsx, L::Css, L::TypeScript]
+      .into_iter()
+      .map(|lang| Self::Builtin(lang.into()));
     let customs = DynamicLang::all_langs().into_iter().map(Self::Custom);
     builtin.chain(customs).collect()
   }
@@ -142,6 +154,20 @@ impl Language for NapiLang {
       Custom(c) => c.expando_char(),
     }
   }
+
+  fn extra_node_kinds(&self) -> &'static [&'static str] {
+    match self {
+      Builtin(b) => b.extra_node_kinds(),
+      Custom(c) => c.extra_node_kinds(),
+    }
+  }
+
+  fn unwrap_expression(&self, src: &str) -> Option<String> {
+    match self {
+      Builtin(b) => b.unwrap_expression(src),
+      Custom(c) => c.unwrap_expression(src),
+    }
+  }
 }
 
 pub fn register_dynamic_language(langs: HashMap<String, CustomLang>) -> Result<()> {
@@ -171,6 +197,17 @@ impl LangOption {
         .find_map(|(lang, types)| types.matched(path, false).is_whitelist().then_some(*lang)),
     }
   }
+  /// Same as `get_lang`, but an inferred language falls back to the file content,
+  /// e.g. a shebang line. See [`SupportLang::guess_from_content`].
+  pub fn detect_lang(&self, path: &Path, content: &str) -> Option<NapiLang> {
+    self.get_lang(path).or_else(|| {
+      let LangOption::Inferred(pairs) = self else {
+        return None;
+      };
+      let lang = NapiLang::Builtin(SupportLang::guess_from_content(content)?);
+      pairs.iter().any(|(l, _)| *l == lang).then_some(lang)
+    })
+  }
   pub fn infer(language_globs: &LanguageGlobs) -> Self {
     let mut types = vec![];
     let empty = vec![];
@@ -193,6 +193,38 @@ impl SgNode {
     let bytes = self.inner.get_env().get_transformed(&m)?;
     Some(String::from_utf16_lossy(bytes))
   }
+  /// All captures of the match with text, kind and positions, keyed by meta variable name.
+  #[napi(ts_return_type = "Record<string, Capture | Capture[]>")]
+  pub fn get_captures(&self) -> serde_json::Value {
+    self.inner.get_env().to_json()
+  }
+  /// The subtree as nested JSON objects, including unnamed nodes. Useful for debugging patterns.
+  #[napi(ts_return_type = "JsonAstNode")]
+  pub fn to_json_ast(&self) -> serde_json::Value {
+    let mut json = self.inner.to_json_ast();
+    utf16_offsets(&mut json);
+    json
+  }
+  /// Why the rule does or does not match this node. Useful for debugging rules.
+  #[napi(ts_return_type = "MatchExplanation")]
+  pub fn explain(&self, rule: NapiConfig) -> Result<serde_json::Value> {
+    let lang = *self.inner.lang();
+    let rule = rule.parse_with(lang)?;
+    let explanation = rule.explain_match(self.inner.get_node().clone());
+    Ok(serde_json::to_value(explanation)?)
+  }
+}
+
+/// JsDoc offsets count UTF-16 bytes, convert them to string indices like `Pos.index`.
+fn utf16_offsets(json: &mut serde_json::Value) {
+  for key in ["startByte", "endByte"] {
+    if let Some(offset) = json[key].as_u64() {
+      json[key] = (offset / 2).into();
+    }
+  }
+  if let Some(serde_json::Value::Array(children)) = json.get_mut("children") {
+    children.iter_mut().for_each(utf16_offsets);
+  }
 }
 
 /// tree traversal API
@@ -316,6 +348,22 @@ impl SgNode {
     Self::transpose_option(reference, env, node)
   }
 
+  /// Follows the `field` names in `path` one level at a time
+  #[napi]
+  pub fn field_path(
+    &self,
+    reference: Reference<SgNode>,
+    env: Env,
+    path: Vec<String>,
+  ) -> Result<Option<SgNode>> {
+    let path: Vec<_> = path.iter().map(String::as_str).collect();
+    let node = reference
+      .inner
+      .named_child_by_path(&path)
+      .map(NodeMatch::from);
+    Self::transpose_option(reference, env, node)
+  }
+
   /// Finds all the children nodes in the `field`
   #[napi]
   pub fn field_children(
@@ -428,4 +476,10 @@ impl SgRoot {
   pub fn filename(&self) -> Result<String> {
     Ok(self.1.clone())
   }
+  /// Returns the tree with one named node per line, indented by nesting level.
+  /// Named leaf nodes are followed by their text. Useful for debugging rules.
+  #[napi]
+  pub fn pretty_print_ast(&self) -> String {
+    self.0.to_pretty_ast()
+  }
 }
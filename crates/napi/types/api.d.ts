@@ -1,5 +1,5 @@
 import type { SgNode, SgRoot } from './sgnode'
-import type { NapiConfig, FindConfig, FileOption } from './config'
+import type { NapiConfig, FindConfig, FileOption, FixPreview } from './config'
 import type { NapiLang } from './lang'
 import type { NamedKinds, TypesMap } from './staticTypes'
 
@@ -33,6 +33,32 @@ export declare function pattern<M extends TypesMap>(
   lang: NapiLang,
   pattern: string,
 ): NapiConfig<M>
+/**
+ * Validate a rule config using its `language` field.
+ * Returns null on success or the error message otherwise.
+ */
+export declare function validateConfig<M extends TypesMap>(
+  config: NapiConfig<M>,
+): string | null
+/**
+ * Apply `fix` to the first node in `src` matching `config`.
+ * Returns the full source before and after the fix, or null if no node matches.
+ */
+export declare function previewFix<M extends TypesMap>(
+  lang: NapiLang,
+  src: string,
+  config: NapiConfig<M>,
+  fix: string,
+): FixPreview | null
+/**
+ * Check if a pattern written in `lang` can also be parsed in `otherLang` without syntax error.
+ * e.g. JSX patterns are valid in tsx but not in ts.
+ */
+export declare function isPatternCompatible(
+  lang: NapiLang,
+  otherLang: NapiLang,
+  pattern: string,
+): boolean
 /**
  * Discover and parse multiple files in Rust.
  * `lang` specifies the language.
@@ -43,4 +69,24 @@ export declare function findInFiles<M extends TypesMap>(
   lang: NapiLang,
   config: FindConfig<M>,
   callback: (err: null | Error, result: SgNode<M>[]) => void,
-): Promise<number>
\ No newline at end of file
+): Promise<number>
+/**
+ * Same as `findInFiles` but `callback` is called once per match
+ * instead of once per file, so results can be processed as soon as they are found.
+ * Use `config.maxCallbacksPerFile` to cap the callbacks for a single file.
+ * The returned promise resolves to the number of callbacks.
+ */
+export declare function findInFilesStreaming<M extends TypesMap>(
+  lang: NapiLang,
+  config: FindConfig<M>,
+  callback: (err: null | Error, result: SgNode<M>[]) => void,
+): Promise<number>
+/**
+ * Same as `findInFiles` but returns an async iterator instead of calling a callback.
+ * Each iteration yields the matching nodes found in a file.
+ * File walking pauses when `config.queueSize` results are not consumed yet.
+ */
+export declare function findInFilesAsync<M extends TypesMap>(
+  lang: NapiLang,
+  config: FindConfig<M>,
+): AsyncIterableIterator<SgNode<M>[]>
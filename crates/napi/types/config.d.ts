@@ -20,6 +20,13 @@ export interface NapiConfig<M extends TypesMap = TypesMap> {
   transform?: unknown
   /** https://ast-grep.github.io/guide/rule-config/utility-rule.html */
   utils?: Record<string, Rule<M>>
+  /** Meta variables captured by the pattern. Only set by `pattern()` and ignored in matching. */
+  captures?: Array<string>
+}
+/** The full source before and after a fix is applied. */
+export interface FixPreview {
+  original: string
+  fixed: string
 }
 export interface FileOption {
   paths: Array<string>
@@ -36,4 +43,28 @@ export interface FindConfig<M extends TypesMap = TypesMap> {
    * It is slightly different from https://ast-grep.github.io/reference/sgconfig.html#languageglobs
    */
   languageGlobs?: Array<string>
+  /**
+   * Only used by findInFilesStreaming.
+   * The maximum number of matches reported per file. Extra matches are dropped.
+   */
+  maxCallbacksPerFile?: number
+  /**
+   * The maximum number of results waiting for JavaScript to consume.
+   * File walking pauses when the queue is full. Defaults to 1000.
+   */
+  queueSize?: number
+  /**
+   * Called with the numbers of scanned files, files with matches and all files to scan.
+   * All files are counted by walking them once before scanning starts.
+   * Every call is made before the returned promise resolves.
+   */
+  onProgress?: (filesScanned: number, filesMatched: number, totalFiles: number) => void
+  /** Number of scanned files between two `onProgress` calls. Defaults to 100. */
+  progressInterval?: number
+  /**
+   * Called with the error and the file path when a file cannot be read or parsed.
+   * Such files are skipped silently if it is not set.
+   * Every call is made before the returned promise resolves.
+   */
+  onError?: (err: Error, path: string) => void
 }
\ No newline at end of file
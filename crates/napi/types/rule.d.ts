@@ -15,10 +15,15 @@ export type PatternStyle<M extends TypesMap = TypesMap> =
 export interface Relation<M extends TypesMap = TypesMap> extends Rule<M> {
   /**
    * Specify how relational rule will stop relative to the target node.
+   * A positive number stops after that many nodes, `1` being the same as `neighbor`.
    */
-  stopBy?: 'neighbor' | 'end' | Rule<M>
+  stopBy?: 'neighbor' | 'end' | number | Rule<M>
   /** Specify the tree-sitter field in parent node. Only available in has/inside rule. */
   field?: string
+  /** Maximum number of levels to traverse. `depth: 1` is the same as `stopBy: neighbor`. */
+  depth?: number
+  /** Shorthand for `stopBy: neighbor`. It cannot be used with other `stopBy` values. */
+  immediate?: boolean
 }
 
 export interface NthChildObject<M extends TypesMap = TypesMap> {
@@ -51,6 +56,20 @@ export interface Range {
   end: Position
 }
 
+export interface Count {
+  /** Inclusive lower bound. Defaults to 0. */
+  min?: number
+  /** Inclusive upper bound. Defaults to unbounded. */
+  max?: number
+}
+
+export interface RegexWithFlags {
+  /** A Rust regular expression. */
+  pattern: string
+  /** Any of `i` (case-insensitive), `m` (multi-line) and `s` (`.` matches new line). */
+  flags: string
+}
+
 export interface Rule<M extends TypesMap = TypesMap> {
   /** A pattern string or a pattern object. */
   pattern?: PatternStyle<M>
@@ -58,8 +77,10 @@ export interface Rule<M extends TypesMap = TypesMap> {
   kind?: NamedKinds<M>
   /** The exact range of the node in the source code. */
   range?: Range
-  /** A Rust regular expression to match the node's text. https://docs.rs/regex/latest/regex/#syntax */
-  regex?: string
+  /**
+   * A Rust regular expression to match the node's text. https://docs.rs/regex/latest/regex/#syntax
+   * Or an object with field `pattern` and `flags`. */
+  regex?: string | RegexWithFlags
   /**
    * `nthChild` accepts number, string or object.
    * It specifies the position in nodes' sibling list. */
@@ -95,4 +116,9 @@ export interface Rule<M extends TypesMap = TypesMap> {
   not?: Rule<M>
   /** A utility rule id and matches a node if the utility rule matches. */
   matches?: string
+  // cardinality
+  /**
+   * `count` accepts an object with optional `min` and `max`.
+   * The target node must match the other fields of this rule and contain (itself included) between `min` and `max` nodes matching them. */
+  count?: Count
 }
@@ -35,6 +35,39 @@ export interface Range {
   end: Pos
 }
 
+/** A meta variable capture. Lines and columns start from 0 */
+export interface Capture {
+  text: string
+  kind: string
+  startLine: number
+  startCol: number
+  endLine: number
+  endCol: number
+}
+
+/** A syntax tree node dumped by `SgNode.toJsonAst`. Offsets are string indices like `Pos.index` */
+export interface JsonAstNode {
+  kind: string
+  named: boolean
+  text: string
+  startByte: number
+  endByte: number
+  /** absent for leaf nodes */
+  children?: JsonAstNode[]
+}
+
+/**
+ * Why a rule does or does not match a node, returned by `SgNode.explain`.
+ * A failed relational rule explains its inner rule against every node visited by `stopBy`.
+ */
+export type MatchExplanation =
+  | 'matched'
+  | { failedAtomicPattern: string }
+  /** meta variable name and its captured text */
+  | { failedConstraint: [string, string] }
+  | { failedRelational: [string, MatchExplanation] }
+  | { failedComposite: MatchExplanation[] }
+
 export declare class SgNode<
   M extends TypesMap = TypesMap,
   out T extends Kinds<M> = Kinds<M>,
@@ -51,6 +84,8 @@ export declare class SgNode<
   has(m: string | number | NapiConfig<M>): boolean
   precedes(m: string | number | NapiConfig<M>): boolean
   follows(m: string | number | NapiConfig<M>): boolean
+  /** Explains why the rule does or does not match the node */
+  explain(rule: NapiConfig<M>): MatchExplanation
   /** Returns the string name of the node kind */
   kind(): T
   readonly kindToRefine: T
@@ -62,6 +97,10 @@ export declare class SgNode<
   getMatch: NodeMethod<M, [mv: string]>
   getMultipleMatches(m: string): Array<SgNode<M>>
   getTransformed(m: string): string | null
+  /** Returns all captures keyed by meta variable name, multiple captures as arrays */
+  getCaptures(): Record<string, Capture | Capture[]>
+  /** Returns the subtree as JSON, including unnamed nodes */
+  toJsonAst(): JsonAstNode
   /** Returns the node's SgRoot */
   getRoot(): SgRoot<M>
   children(): Array<SgNode<M>>
@@ -71,6 +110,8 @@ export declare class SgNode<
   ): Array<RefineNode<M, K>>
   /** Finds the first child node in the `field` */
   field<F extends FieldNames<M[T]>>(name: F): FieldNode<M, T, F>
+  /** Follows the `field` names in `path` one level at a time */
+  fieldPath(path: string[]): SgNode<M> | null
   /** Finds all the children nodes in the `field` */
   fieldChildren<F extends FieldNames<M[T]>>(
     name: F,
@@ -95,6 +136,11 @@ export declare class SgRoot<M extends TypesMap = TypesMap> {
    * Returns `"anonymous"` if the instance is created by `lang.parse(source)`.
    */
   filename(): string
+  /**
+   * Returns the tree with one named node per line, indented by nesting level.
+   * Named leaf nodes are followed by their text. Useful for debugging rules.
+   */
+  prettyPrintAst(): string
 }
 
 interface NodeMethod<M extends TypesMap, Args extends unknown[] = []> {
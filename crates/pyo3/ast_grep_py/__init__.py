@@ -23,11 +23,19 @@ class RangeRule(TypedDict):
     start: PosRule
     end: PosRule
 
+class RegexWithFlags(TypedDict):
+    pattern: str
+    flags: str
+
+class CountRule(TypedDict, total=False):
+    min: int
+    max: int
+
 class RuleWithoutNot(TypedDict, total=False):
     # atomic rule
     pattern: str | Pattern
     kind: str
-    regex: str
+    regex: str | RegexWithFlags
     nthChild: int | str | NthChild
     range: RangeRule
 
@@ -44,19 +52,24 @@ class RuleWithoutNot(TypedDict, total=False):
     # not: Rule
     matches: str
 
+    # cardinality
+    count: CountRule
+
 # workaround
 # Python's keyword requires `not` be a special case
 class Rule(RuleWithoutNot, TypedDict("Not", {"not": "Rule"}, total=False)):
     pass
 
 # Relational Rule Related
-StopBy = Union[Literal["neighbor"], Literal["end"], Rule]
+StopBy = Union[Literal["neighbor"], Literal["end"], int, Rule]
 
 # Relation do NOT inherit from Rule due to pyright bug
 # see tests/test_rule.py
 class Relation(RuleWithoutNot, TypedDict("Not", {"not": "Rule"}, total=False), total=False):
     stopBy: StopBy
     field: str
+    depth: int
+    immediate: bool
 
 class Config(TypedDict, total=False):
     rule: Rule
@@ -117,4 +117,18 @@ impl Language for PyLang {
       Custom(c) => c.expando_char(),
     }
   }
+
+  fn extra_node_kinds(&self) -> &'static [&'static str] {
+    match self {
+      Builtin(b) => b.extra_node_kinds(),
+      Custom(c) => c.extra_node_kinds(),
+    }
+  }
+
+  fn unwrap_expression(&self, src: &str) -> Option<String> {
+    match self {
+      Builtin(b) => b.unwrap_expression(src),
+      Custom(c) => c.unwrap_expression(src),
+    }
+  }
 }
@@ -366,6 +366,8 @@ fn config_from_rule(dict: Bound<PyDict>) -> PyResult<SerializableRuleCore> {
     utils: None,
     transform: None,
     fix: None,
+    extends: None,
+    tags: None,
   })
 }
 
@@ -35,9 +35,41 @@ fn tweak_schema(schema: &mut RootSchema) -> Result<()> {
     bail!("rule's type is not object!");
   };
   rule.metadata().description = description;
+  require_rule_or_extends(schema);
+  add_inline_test(schema);
   Ok(())
 }
 
+/// Test cases of the rule, run by `ast-grep test` without a separate test file.
+#[derive(JsonSchema, Default)]
+#[allow(dead_code)]
+struct InlineTest {
+  /// Code snippets that should not match the rule.
+  #[schemars(default)]
+  valid: Vec<String>,
+  /// Code snippets that should match the rule.
+  #[schemars(default)]
+  invalid: Vec<String>,
+}
+
+// `test` is read by the cli test runner, not by the rule config
+fn add_inline_test(schema: &mut RootSchema) {
+  let test = InlineTest::json_schema(&mut SchemaGenerator::default());
+  let props = &mut schema.schema.object().properties;
+  props.insert("test".to_string(), test);
+}
+
+// `rule` can only be omitted if the rule `extends` another rule
+fn require_rule_or_extends(schema: &mut RootSchema) {
+  let required = |field: &str| {
+    let mut schema = SchemaObject::default();
+    schema.object().required.insert(field.to_string());
+    Schema::Object(schema)
+  };
+  let any_of = vec![required("rule"), required("extends")];
+  schema.schema.subschemas().any_of = Some(any_of);
+}
+
 fn remove_recursive_rule_relation_description(schema: &mut RootSchema) -> Result<Option<String>> {
   let definitions = &mut schema.definitions;
   let Schema::Object(relation) = definitions
@@ -78,6 +110,20 @@ fn simplify_stop_by(schema: &mut RootSchema) -> Result<()> {
     .remove("rule")
     .context("should have rule")?;
   one_ofs[1] = rule;
+  // count is a bare positive integer
+  let Schema::Object(count) = &mut one_ofs[2] else {
+    bail!("type is not object!");
+  };
+  let count = count
+    .object()
+    .properties
+    .remove("count")
+    .context("should have count")?;
+  let Schema::Object(mut count) = count else {
+    bail!("count's type is not object!");
+  };
+  count.number().minimum = Some(1.0);
+  one_ofs[2] = Schema::Object(count);
   Ok(())
 }
 